@@ -0,0 +1,18 @@
+use std::path::Path;
+use std::process::Command;
+
+/// Expose the short commit hash as `NBI_GIT_HASH` when building from git
+fn main() {
+  println!("cargo:rerun-if-changed=build.rs");
+  if !Path::new(".git").exists() {
+    return;
+  }
+  println!("cargo:rerun-if-changed=.git/HEAD");
+  println!("cargo:rerun-if-changed=.git/refs");
+
+  let output = Command::new("git").args(["rev-parse", "--short", "HEAD"]).output();
+  if let Some(output) = output.ok().filter(|o| o.status.success()) {
+    let hash = String::from_utf8_lossy(&output.stdout);
+    println!("cargo:rustc-env=NBI_GIT_HASH={}", hash.trim());
+  }
+}
@@ -0,0 +1,95 @@
+//! Request and response bodies of the HTTP API, shared by `nbi serve` and
+//! [`crate::client`] so the two can't drift apart
+
+use crate::config::RegistrySettings;
+use crate::registry::{Availability, AvailabilityResult};
+use serde::{Deserialize, Serialize};
+
+/// Body of `POST /api/check`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckRequest {
+  pub name: String,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub registries: Option<RegistrySelection>,
+  /// Registry identifiers to skip
+  #[serde(default, skip_serializing_if = "Vec::is_empty")]
+  pub exclude: Vec<String>,
+}
+
+/// Registries to check: a full settings object or a list of identifiers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum RegistrySelection {
+  Ids(Vec<String>),
+  Settings(RegistrySettings),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckResponse {
+  pub name: String,
+  pub results: Vec<Cached<AvailabilityResult>>,
+}
+
+/// A result and whether it came from the cache
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cached<T> {
+  #[serde(flatten)]
+  pub result: T,
+  pub cached: bool,
+}
+
+/// Body of `POST /api/domain`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainRequest {
+  pub name: String,
+  /// Falls back to `default_tlds` from config when omitted
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub tlds: Option<Vec<String>>,
+}
+
+/// Body of `POST /api/domain/full`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FullDomainRequest {
+  pub domains: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DomainResponse {
+  pub name: String,
+  pub results: Vec<DomainResult>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DomainResult {
+  pub domain: String,
+  /// Missing from servers older than it, which only sent `available`
+  #[serde(default)]
+  pub availability: Option<Availability>,
+  /// Kept alongside `availability` for older clients; deprecated since
+  /// 0.1.9 and removed in 0.2.0
+  pub available: Option<bool>,
+  pub error: Option<String>,
+}
+
+/// Domain checks name their result after the full domain
+impl From<AvailabilityResult> for DomainResult {
+  fn from(result: AvailabilityResult) -> Self {
+    Self {
+      availability: Some(result.availability),
+      available: result.available(),
+      domain: result.name,
+      error: result.error,
+    }
+  }
+}
+
+/// Body of every API error response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorBody {
+  pub error: String,
+  /// Machine-readable cause, e.g. "invalid_name" or "unauthorized"
+  pub kind: String,
+  /// Request field at fault, if any
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub field: Option<String>,
+}
@@ -1,36 +1,324 @@
-use crate::config::Config;
-use crate::registry::AvailabilityResult;
+use crate::config::{Config, ConfigSource};
+use crate::input::TextInput;
+use crate::registry::github::{
+  ManifestType, OnFailure, RepoOptions, Step, StepStatus, TokenInfo, CREATE_REPO_STEP,
+  PLACEHOLDER_DESCRIPTION,
+};
+use crate::registry::{
+  result_order, Availability, AvailabilityResult, RegistryType, ResultFilter, TimedResult,
+};
+use crate::tui::clipboard;
+use crate::tui::keys::KeyMap;
+use crate::tui::toast::{Severity, Toasts};
+use crate::tui::session::{self, SavedResult, Session};
+use crate::ui::theme::Theme;
+use ratatui::layout::{Margin, Position, Rect};
+use ratatui::widgets::ListState;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant, SystemTime};
 
 /// Current screen/view in the TUI
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Screen {
   Search,
   Register,
   Settings,
+  Domain,
+}
+
+impl Screen {
+  /// Screens in tab order; Tab, Shift+Tab and the number keys follow it
+  pub const ALL: [Screen; 4] = [Screen::Search, Screen::Register, Screen::Settings, Screen::Domain];
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      Screen::Search => "Search",
+      Screen::Register => "Register",
+      Screen::Settings => "Settings",
+      Screen::Domain => "Domain",
+    }
+  }
+
+  /// Position in [`Screen::ALL`]
+  pub fn index(&self) -> usize {
+    Self::ALL.iter().position(|s| s == self).expect("every screen is in ALL")
+  }
+
+  /// Title in the tab bar, with the number key that opens it
+  pub fn tab_title(&self) -> String {
+    format!("{} [{}]", self.label(), self.index() + 1)
+  }
+}
+
+/// Two clicks on the same cell within this long make a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(400);
+
+/// Where the last frame drew what the mouse can hit, recorded while
+/// rendering
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct HitAreas {
+  pub tabs: Rect,
+  /// The current screen's list, borders included; empty when it has none
+  pub list: Rect,
+}
+
+/// The screen whose tab title is at `column` in the tab bar drawn in `area`
+///
+/// Mirrors the `Tabs` widget: inside the border, each title is padded by a
+/// space on both sides and followed by a one-column divider.
+pub fn tab_at(area: Rect, column: u16, row: u16) -> Option<Screen> {
+  if !area.contains(Position::new(column, row)) {
+    return None;
+  }
+  let mut x = area.x + 1;
+  for screen in Screen::ALL {
+    let width = screen.tab_title().chars().count() as u16 + 2;
+    if (x..x + width).contains(&column) {
+      return Some(screen);
+    }
+    x += width + 1;
+  }
+  None
+}
+
+/// Index of the item at `row` in a bordered, single-line-item list drawn in
+/// `area` and scrolled down by `offset`
+pub fn row_at(area: Rect, offset: usize, column: u16, row: u16) -> Option<usize> {
+  let inner = area.inner(Margin::new(1, 1));
+  inner.contains(Position::new(column, row)).then(|| offset + (row - inner.y) as usize)
+}
+
+/// Which input on the Domain screen has focus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DomainField {
+  Name,
+  Tlds,
+}
+
+/// Repo option edited as text on the Register screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RepoTextField {
+  Description,
+  Homepage,
+  /// Comma-separated
+  Topics,
+  Gitignore,
+}
+
+impl RepoTextField {
+  pub fn label(&self) -> &'static str {
+    match self {
+      RepoTextField::Description => "Description",
+      RepoTextField::Homepage => "Homepage",
+      RepoTextField::Topics => "Topics, comma-separated",
+      RepoTextField::Gitignore => ".gitignore template",
+    }
+  }
+}
+
+/// Outcome of validating the GitHub token for the Register screen
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TokenCheck {
+  #[default]
+  Unchecked,
+  Checking,
+  Valid(TokenInfo),
+  Invalid(String),
+}
+
+/// A dialog that captures every key until it's answered
+#[derive(Debug, Clone)]
+pub enum Modal {
+  /// Confirm creating the repo for a registration
+  Register(Box<PendingRegistration>),
+  /// Quit anyway while a registration is running
+  Quit,
+  /// Cancel the running registration
+  CancelRegistration,
+}
+
+/// A registration waiting for confirmation in the Register screen dialog
+///
+/// All registries share one repo; each contributes its manifest.
+#[derive(Debug, Clone)]
+pub struct PendingRegistration {
+  pub name: String,
+  pub registries: Vec<RegistryType>,
+  pub options: RepoOptions,
+  /// The token's user if already validated, else `github_username`, to
+  /// skip a lookup
+  pub login: Option<String>,
+  /// What to do with the new repo if committing to it fails
+  pub on_failure: OnFailure,
+}
+
+impl PendingRegistration {
+  /// Manifests that will be committed; empty for a README-only repo
+  pub fn manifests(&self) -> Vec<ManifestType> {
+    self.registries.iter().filter_map(|r| ManifestType::for_registry(*r)).collect()
+  }
+
+  /// Repo owner as shown to the user
+  pub fn owner(&self) -> &str {
+    self.options.org.as_deref().unwrap_or("your account")
+  }
+
+  /// Options for the repo itself, with a placeholder description naming
+  /// the manifests when none was set
+  pub fn repo_options(&self) -> RepoOptions {
+    let manifests = self.manifests();
+    let placeholder = (!manifests.is_empty()).then(|| {
+      let filenames: Vec<_> = manifests.iter().map(|m| m.filename()).collect();
+      format!("{} {}", PLACEHOLDER_DESCRIPTION, filenames.join(", "))
+    });
+    RepoOptions {
+      description: self.options.description.clone().or(placeholder),
+      ..self.options.clone()
+    }
+  }
+
+  /// Contents of each manifest that will be committed
+  pub fn manifest_previews(&self) -> Vec<(ManifestType, String)> {
+    self
+      .manifests()
+      .into_iter()
+      .map(|m| {
+        let description = m.description(&self.options);
+        (m, m.generate_content(&self.name, &description, &self.options.manifest))
+      })
+      .collect()
+  }
+}
+
+/// Whether nbi can reserve a name on this registry through GitHub
+pub fn is_reservable(registry: RegistryType) -> bool {
+  registry == RegistryType::GitHub || ManifestType::for_registry(registry).is_some()
+}
+
+/// A row of the registry list on the settings screen
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RegistryRow {
+  Registry(RegistryType),
+  /// Every handle check at once
+  Handles,
 }
 
 /// Application state
 pub struct App {
   pub config: Config,
+  /// Where `config` came from and is saved to; its profile is shown in
+  /// the tab bar
+  pub source: ConfigSource,
   pub screen: Screen,
   pub should_quit: bool,
+  /// Printed once the terminal is restored, e.g. a repo a cancelled
+  /// registration left behind
+  pub quit_notice: Option<String>,
 
   // Search state
-  pub search_input: String,
+  pub search_input: TextInput,
   pub search_results: Vec<AvailabilityResult>,
+  /// How long each registry took to answer, in milliseconds
+  pub search_durations: HashMap<RegistryType, u64>,
+  /// When each result was checked, saved with the session
+  pub checked_at: HashMap<RegistryType, SystemTime>,
+  /// The results came from the last session rather than a search in this one
+  pub restored: bool,
+  /// The name the current results are for, set when a search starts
+  pub last_searched: Option<String>,
+  pub search_generation: u64,
   pub is_searching: bool,
+  /// Enabled registries whose result hasn't arrived yet
+  pub pending_registries: Vec<RegistryType>,
+  pub search_started: Option<Instant>,
+  pub search_task: Option<tokio::task::AbortHandle>,
+  /// The last search was cancelled before every registry answered
+  pub search_incomplete: bool,
+  /// Registries whose result is being checked again on its own
+  pub rechecking: Vec<RegistryType>,
+  pub result_filter: ResultFilter,
+  /// Highlighted row of the filtered results, plus its scroll offset
+  pub results_list_state: ListState,
+  pub show_result_detail: bool,
 
   // Register state
-  pub selected_registry: usize,
+  /// Highlighted registry; the first available one when unset or no
+  /// longer available, so it survives results being re-sorted
+  pub selected_registry: Option<RegistryType>,
+  /// Registries ticked for a combined registration, in no particular order
+  pub register_checked: Vec<RegistryType>,
   pub register_status: Option<String>,
   pub is_registering: bool,
+  pub registration_task: Option<tokio::task::AbortHandle>,
+  /// `owner/name` of the repo the running registration creates, or just
+  /// the name while the owner isn't known yet
+  pub registration_repo: Option<String>,
+  /// Step log of the current or last registration
+  pub register_steps: Vec<Step>,
+  /// Repo the last successful registration created or updated, for `y`
+  pub registered_repo_url: Option<String>,
+  /// Open confirmation dialog, if any
+  pub modal: Option<Modal>,
+  /// Visibility, description and owner for new repos, persisted in config
+  pub register_options: RepoOptions,
+  /// Orgs the token can create repos in; `None` until fetched
+  pub register_orgs: Option<Vec<String>>,
+  /// Text of the repo option being edited
+  pub option_input: TextInput,
+  pub editing_option: RepoTextField,
+  /// Cached token validation, fetched on entering the Register screen
+  pub token_check: TokenCheck,
 
   // Settings state
   pub selected_setting: usize,
+  /// The last save of a settings change failed, so the file is behind
+  pub settings_unsaved: bool,
+  pub tld_input: String,
+  pub username_input: String,
+
+  // Domain state
+  pub domain_input: TextInput,
+  /// Comma-separated TLDs and `@group`s, pre-filled from config
+  pub domain_tlds: TextInput,
+  pub domain_field: DomainField,
+  pub domain_results: Vec<AvailabilityResult>,
+  pub domain_list_state: ListState,
+  pub domain_generation: u64,
+  pub is_checking_domains: bool,
+  pub domain_error: Option<String>,
 
   // UI state
+  /// Resolved from `config.theme`
+  pub theme: Theme,
+  /// Resolved from `config.keys`
+  pub keys: KeyMap,
+  pub hit_areas: HitAreas,
+  /// Time and cell of the last click, to spot double-clicks
+  last_click: Option<(Instant, u16, u16)>,
   pub show_help: bool,
+  /// Notices in the bottom-right corner, and their history
+  pub toasts: Toasts,
+  pub show_messages: bool,
+  /// First history line shown in the messages popup
+  pub messages_scroll: u16,
   pub input_mode: InputMode,
+  /// Incremented on every idle poll, drives spinners
+  pub tick: u64,
+}
+
+/// The bindings from `config.keys`, falling back to the defaults when one
+/// doesn't parse; clashes are logged since the TUI can't print them
+fn key_map(config: &Config) -> KeyMap {
+  let keys = KeyMap::from_settings(&config.keys).unwrap_or_else(|e| {
+    tracing::warn!(error = %e, "invalid key binding, using the defaults");
+    KeyMap::default()
+  });
+  for conflict in keys.conflicts() {
+    tracing::warn!("{}", conflict);
+  }
+  keys
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -40,111 +328,1727 @@ pub enum InputMode {
 }
 
 impl App {
-  pub fn new() -> Self {
-    let config = Config::load().unwrap_or_default();
+  /// The TUI on `config`, loaded from `source`; a config that fails to load
+  /// stops the TUI before it starts, so autosave can't overwrite the file
+  /// with defaults
+  pub fn new(source: ConfigSource, config: Config) -> Self {
+    let restore = config.restore_session;
+    let mut app = Self::with_source(source, config);
+    let path = session::default_path().filter(|_| restore);
+    if let Some(session) = path.and_then(|path| Session::load(&path)) {
+      app.restore_session(session);
+    }
+    app
+  }
+
+  /// An app on `config` that saves to a scratch directory, so tests never
+  /// touch the real config
+  #[cfg(test)]
+  pub fn with_config(config: Config) -> Self {
+    let dir = std::env::temp_dir().join(format!("nbi-app-{}", std::process::id()));
+    Self::with_source(ConfigSource::resolve(Some(dir.join("config.toml")), None), config)
+  }
 
+  fn with_source(source: ConfigSource, config: Config) -> Self {
+    let domain_tlds = TextInput::from(config.default_tlds.join(",").as_str());
+    let register_options = config.with_repo_defaults(config.register.repo_options());
+    let theme = Theme::resolve(&config.theme).unwrap_or_default();
+    let keys = key_map(&config);
     Self {
       config,
+      source,
       screen: Screen::Search,
       should_quit: false,
+      quit_notice: None,
 
-      search_input: String::new(),
+      search_input: TextInput::default(),
       search_results: Vec::new(),
+      search_durations: HashMap::new(),
+      checked_at: HashMap::new(),
+      restored: false,
+      last_searched: None,
+      search_generation: 0,
       is_searching: false,
+      pending_registries: Vec::new(),
+      search_started: None,
+      search_task: None,
+      search_incomplete: false,
+      rechecking: Vec::new(),
+      result_filter: ResultFilter::All,
+      results_list_state: ListState::default(),
+      show_result_detail: false,
 
-      selected_registry: 0,
+      selected_registry: None,
+      register_checked: Vec::new(),
       register_status: None,
       is_registering: false,
+      registration_task: None,
+      registration_repo: None,
+      register_steps: Vec::new(),
+      registered_repo_url: None,
+      modal: None,
+      register_options,
+      register_orgs: None,
+      option_input: TextInput::default(),
+      editing_option: RepoTextField::Description,
+      token_check: TokenCheck::Unchecked,
 
       selected_setting: 0,
+      settings_unsaved: false,
+      tld_input: String::new(),
+      username_input: String::new(),
 
+      domain_input: TextInput::default(),
+      domain_tlds,
+      domain_field: DomainField::Name,
+      domain_results: Vec::new(),
+      domain_list_state: ListState::default(),
+      domain_generation: 0,
+      is_checking_domains: false,
+      domain_error: None,
+
+      theme,
+      keys,
+      hit_areas: HitAreas::default(),
+      last_click: None,
       show_help: false,
+      toasts: Toasts::default(),
+      show_messages: false,
+      messages_scroll: 0,
       input_mode: InputMode::Editing,
+      tick: 0,
+    }
+  }
+
+  /// Save config after a change made in the TUI, trying twice
+  ///
+  /// A failure is reported as a toast and marks the settings unsaved until
+  /// a later change saves.
+  fn autosave(&mut self) {
+    let saved = self.save_config().or_else(|e| {
+      tracing::debug!(error = %e, "failed to save config, retrying");
+      self.save_config()
+    });
+    match saved {
+      Ok(()) if self.settings_unsaved => {
+        self.settings_unsaved = false;
+        self.toasts.push("Settings saved", Severity::Success);
+      }
+      Ok(()) => {}
+      Err(e) => {
+        tracing::warn!(error = %e, "failed to save config");
+        self.settings_unsaved = true;
+        self.toasts.push(format!("Settings not saved: {:#}", e), Severity::Error);
+      }
     }
   }
 
   /// Save current config
   pub fn save_config(&self) -> anyhow::Result<()> {
-    self.config.save()
+    self.source.save(&self.config)
+  }
+
+  /// Switch to the next profile, or back to the base config after the last
+  pub fn cycle_profile(&mut self) -> anyhow::Result<()> {
+    let next = crate::config::next_profile(&self.source.profiles()?, self.source.profile());
+    let source = self.source.with_profile(next);
+    let config = source.load()?;
+    self.apply_config(source, config);
+    Ok(())
+  }
+
+  /// Use `config`, loaded from `source`, resetting what was derived from
+  /// the previous one
+  fn apply_config(&mut self, source: ConfigSource, config: Config) {
+    self.domain_tlds = TextInput::from(config.default_tlds.join(",").as_str());
+    self.register_options = config.with_repo_defaults(config.register.repo_options());
+    self.register_orgs = None;
+    self.token_check = TokenCheck::Unchecked;
+    self.settings_unsaved = false;
+    crate::registry::configure(&config);
+    self.theme = Theme::resolve(&config.theme).unwrap_or_default();
+    self.keys = key_map(&config);
+    self.config = config;
+    self.source = source;
   }
 
   /// Get available registries from search results
   pub fn get_available_registries(&self) -> Vec<&AvailabilityResult> {
     self.search_results
       .iter()
-      .filter(|r| r.available == Some(true))
+      .filter(|r| r.available() == Some(true))
+      .collect()
+  }
+
+  /// Advance the animation tick
+  pub fn on_tick(&mut self) {
+    self.tick = self.tick.wrapping_add(1);
+  }
+
+  /// Start a search for `name`, returning its generation
+  ///
+  /// Previous results are cleared and every enabled registry is pending
+  /// until its result streams in.
+  pub fn begin_search(&mut self, name: &str) -> u64 {
+    if let Some(task) = self.search_task.take() {
+      task.abort();
+    }
+    self.search_incomplete = false;
+    self.search_generation += 1;
+    self.is_searching = true;
+    self.set_search_results(Vec::new());
+    self.last_searched = Some(name.to_string());
+    self.pending_registries = RegistryType::ALL
+      .into_iter()
+      .filter(|r| self.config.registries.is_enabled(*r))
+      .collect();
+    self.search_started = Some(Instant::now());
+    self.search_generation
+  }
+
+  /// Add one streamed result from search `generation`, ignoring superseded searches
+  pub fn add_search_result(&mut self, generation: u64, timed: TimedResult) -> bool {
+    if generation != self.search_generation {
+      return false;
+    }
+    let TimedResult { result, duration_ms } = timed;
+    self.search_durations.insert(result.registry, duration_ms);
+    self.checked_at.insert(result.registry, SystemTime::now());
+    self.pending_registries.retain(|r| *r != result.registry);
+    let highlighted = self.selected_result().map(|r| r.registry);
+    // A registry answers once per search; a repeat replaces the earlier row
+    self.search_results.retain(|r| r.registry != result.registry);
+    let order = result_order(&result);
+    let index = self.search_results.partition_point(|r| result_order(r) <= order);
+    self.search_results.insert(index, result);
+    self.keep_highlight(highlighted);
+    true
+  }
+
+  /// Re-highlight `registry`'s result after rows shifted under it
+  fn keep_highlight(&mut self, registry: Option<RegistryType>) {
+    if let Some(registry) = registry {
+      let row = self.filtered_results().iter().position(|r| r.registry == registry);
+      self.results_list_state.select(row);
+    }
+  }
+
+  /// Start checking the highlighted result again, returning the search
+  /// generation, name and registry to check
+  ///
+  /// Nothing starts while a full search runs or the row is already being
+  /// re-checked.
+  pub fn begin_recheck(&mut self) -> Option<(u64, String, RegistryType)> {
+    if self.is_searching {
+      return None;
+    }
+    let name = self.last_searched.clone()?;
+    let registry = self.selected_result()?.registry;
+    if self.rechecking.contains(&registry) {
+      return None;
+    }
+    self.rechecking.push(registry);
+    Some((self.search_generation, name, registry))
+  }
+
+  /// Replace a result with its re-check from search `generation`, ignoring
+  /// re-checks a newer search has superseded
+  pub fn finish_recheck(&mut self, generation: u64, timed: TimedResult) -> bool {
+    if generation != self.search_generation {
+      return false;
+    }
+    let TimedResult { result, duration_ms } = timed;
+    self.rechecking.retain(|r| *r != result.registry);
+    let highlighted = self.selected_result().map(|r| r.registry);
+    let Some(slot) = self.search_results.iter_mut().find(|r| r.registry == result.registry) else {
+      return false;
+    };
+    self.search_durations.insert(result.registry, duration_ms);
+    self.checked_at.insert(result.registry, SystemTime::now());
+    *slot = result;
+    self.search_results.sort_by_cached_key(result_order);
+    self.keep_highlight(highlighted);
+    true
+  }
+
+  /// Mark search `generation` complete, ignoring superseded searches
+  pub fn finish_search(&mut self, generation: u64) -> bool {
+    if generation != self.search_generation {
+      return false;
+    }
+    self.is_searching = false;
+    self.pending_registries.clear();
+    self.search_task = None;
+    true
+  }
+
+  /// Abort the in-flight search, keeping partial results, returning whether one was running
+  pub fn cancel_search(&mut self) -> bool {
+    let Some(task) = self.search_task.take() else {
+      return false;
+    };
+    task.abort();
+    self.search_incomplete = !self.pending_registries.is_empty();
+    self.finish_search(self.search_generation);
+    true
+  }
+
+  /// How long the current search has been running
+  pub fn search_elapsed(&self) -> Option<Duration> {
+    self.search_started
+      .filter(|_| self.is_searching)
+      .map(|started| started.elapsed())
+  }
+
+  /// The last searched name, if the input has since been edited away from it
+  pub fn stale_search_term(&self) -> Option<&str> {
+    self.last_searched
+      .as_deref()
+      .filter(|term| self.search_input != **term)
+  }
+
+  /// Replace the search results, sorted by [`result_order`], resetting the
+  /// register selection
+  pub fn set_search_results(&mut self, mut results: Vec<AvailabilityResult>) {
+    crate::registry::dedupe(&mut results);
+    results.sort_by_cached_key(result_order);
+    self.search_results = results;
+    self.search_durations.clear();
+    self.checked_at.clear();
+    self.restored = false;
+    self.rechecking.clear();
+    self.selected_registry = None;
+    self.register_checked.clear();
+    self.results_list_state = ListState::default();
+    self.show_result_detail = false;
+  }
+
+  /// The Search screen as a session to save, if anything was searched
+  pub fn session(&self) -> Option<Session> {
+    let term = self.last_searched.clone()?;
+    let now = SystemTime::now();
+    let results = self
+      .search_results
+      .iter()
+      .map(|result| {
+        let checked_at = self.checked_at.get(&result.registry).copied().unwrap_or(now);
+        SavedResult { result: result.clone(), checked_at: session::unix_secs(checked_at) }
+      })
+      .collect();
+    Some(Session { term, screen: self.screen, filter: self.result_filter, results })
+  }
+
+  /// Show a saved session's results, marked as restored until the next search
+  pub fn restore_session(&mut self, session: Session) {
+    let (results, checked_at): (Vec<_>, HashMap<_, _>) = session
+      .results
+      .into_iter()
+      .map(|saved| {
+        let checked_at = (saved.result.registry, session::from_unix_secs(saved.checked_at));
+        (saved.result, checked_at)
+      })
+      .unzip();
+    self.set_search_results(results);
+    self.checked_at = checked_at;
+    self.restored = true;
+    self.search_input.set(session.term.as_str());
+    self.last_searched = Some(session.term);
+    self.screen = session.screen;
+    self.result_filter = session.filter;
+    self.input_mode = InputMode::Normal;
+  }
+
+  /// Save the session for the next launch, unless `restore_session` is off
+  pub fn save_session(&self) {
+    let path = session::default_path().filter(|_| self.config.restore_session);
+    let (Some(path), Some(session)) = (path, self.session()) else {
+      return;
+    };
+    if let Err(e) = session.save(&path) {
+      tracing::warn!(path = %path.display(), error = %e, "failed to save session");
+    }
+  }
+
+  /// Age of restored results, going by the oldest check
+  pub fn restored_age(&self) -> Option<Duration> {
+    let oldest = self.checked_at.values().min().filter(|_| self.restored)?;
+    Some(oldest.elapsed().unwrap_or_default())
+  }
+
+  /// Cycle the result filter; the highlight is cleared since rows shift
+  pub fn cycle_result_filter(&mut self) {
+    self.result_filter = self.result_filter.next();
+    self.results_list_state.select(None);
+  }
+
+  /// Move the results highlight down, starting from the first row
+  pub fn select_next_result(&mut self) {
+    let count = self.filtered_results().len();
+    if count == 0 {
+      return;
+    }
+    let next = self.results_list_state.selected().map_or(0, |i| (i + 1).min(count - 1));
+    self.results_list_state.select(Some(next));
+  }
+
+  /// Move the results highlight up
+  pub fn select_previous_result(&mut self) {
+    if let Some(i) = self.results_list_state.selected() {
+      self.results_list_state.select(Some(i.saturating_sub(1)));
+    }
+  }
+
+  /// The highlighted search result, if any
+  pub fn selected_result(&self) -> Option<&AvailabilityResult> {
+    let index = self.results_list_state.selected()?;
+    self.filtered_results().get(index).copied()
+  }
+
+  /// Open the highlighted result's registry page in the browser
+  pub fn open_selected_result(&self) {
+    let Some(result) = self.selected_result() else {
+      return;
+    };
+    let url = result.page_url();
+    if let Err(e) = open::that(&url) {
+      tracing::warn!(%url, error = %e, "failed to open browser");
+    }
+  }
+
+  /// Copy `text` to the clipboard, reporting the outcome as a toast
+  pub fn copy_to_clipboard(&mut self, text: &str) {
+    match clipboard::copy(text) {
+      Ok(()) => self.toasts.push(format!("Copied! {}", text), Severity::Success),
+      Err(e) => {
+        tracing::warn!(error = %e, "failed to copy to the clipboard");
+        self.toasts.push(format!("Couldn't copy: {}", e), Severity::Error);
+      }
+    }
+  }
+
+  /// Open the message history at the newest message
+  pub fn open_messages(&mut self) {
+    self.show_messages = true;
+    self.messages_scroll = 0;
+  }
+
+  /// Scroll the message history a line towards older messages, or back
+  pub fn scroll_messages(&mut self, older: bool) {
+    self.messages_scroll = if older {
+      self.messages_scroll.saturating_add(1)
+    } else {
+      self.messages_scroll.saturating_sub(1)
+    };
+  }
+
+  /// Open the detail popup for the highlighted result
+  pub fn open_result_detail(&mut self) -> bool {
+    self.show_result_detail = self.selected_result().is_some();
+    self.show_result_detail
+  }
+
+  /// Mark the token as being checked, returning whether a check should start
+  ///
+  /// Without `force` a cached result is kept.
+  pub fn begin_token_check(&mut self, force: bool) -> bool {
+    if !force && self.token_check != TokenCheck::Unchecked {
+      return false;
+    }
+    self.token_check = TokenCheck::Checking;
+    true
+  }
+
+  /// The token's user, once validated
+  pub fn known_login(&self) -> Option<&str> {
+    match &self.token_check {
+      TokenCheck::Valid(info) => Some(&info.login),
+      _ => None,
+    }
+  }
+
+  /// Owner for new repos: the validated login, else `github_username`
+  pub fn default_owner(&self) -> Option<&str> {
+    self.known_login().or(self.config.configured_username())
+  }
+
+  /// `github_username` when it names someone other than the token's user
+  pub fn username_mismatch(&self) -> Option<&str> {
+    let login = self.known_login()?;
+    self.config.configured_username().filter(|u| !u.eq_ignore_ascii_case(login))
+  }
+
+  /// Position of the highlighted registry among the available ones
+  pub fn selected_registry_index(&self) -> usize {
+    let available = self.get_available_registries();
+    self.selected_registry
+      .and_then(|selected| available.iter().position(|r| r.registry == selected))
+      .unwrap_or(0)
+  }
+
+  /// The highlighted registry's result on the Register screen
+  pub fn highlighted_registry(&self) -> Option<&AvailabilityResult> {
+    self.get_available_registries().get(self.selected_registry_index()).copied()
+  }
+
+  /// Highlight the available registry at `index`; false when out of range
+  fn select_available(&mut self, index: usize) -> bool {
+    let registry = self.get_available_registries().get(index).map(|r| r.registry);
+    if registry.is_some() {
+      self.selected_registry = registry;
+    }
+    registry.is_some()
+  }
+
+  /// Tick or untick the highlighted registry; false if it can't be reserved
+  pub fn toggle_register_check(&mut self) -> bool {
+    let Some(result) = self.highlighted_registry() else {
+      return false;
+    };
+    let registry = result.registry;
+    if !is_reservable(registry) {
+      return false;
+    }
+    match self.register_checked.iter().position(|r| *r == registry) {
+      Some(i) => {
+        self.register_checked.remove(i);
+      }
+      None => self.register_checked.push(registry),
+    }
+    true
+  }
+
+  /// Tick every reservable registry, or untick them all if they already are
+  pub fn toggle_all_register_checks(&mut self) {
+    let reservable: Vec<_> = self
+      .get_available_registries()
+      .iter()
+      .map(|r| r.registry)
+      .filter(|r| is_reservable(*r))
+      .collect();
+    if reservable.iter().all(|r| self.register_checked.contains(r)) {
+      self.register_checked.clear();
+    } else {
+      self.register_checked = reservable;
+    }
+  }
+
+  /// Results to register: the ticked ones, or else the highlighted one
+  pub fn registration_targets(&self) -> Vec<&AvailabilityResult> {
+    if self.register_checked.is_empty() {
+      return self.highlighted_registry().into_iter().collect();
+    }
+    self
+      .get_available_registries()
+      .into_iter()
+      .filter(|r| self.register_checked.contains(&r.registry))
+      .collect()
+  }
+
+  /// Drop a register selection that is no longer available
+  pub fn clamp_registry_selection(&mut self) {
+    let available = self.get_available_registries();
+    if !available.iter().any(|r| Some(r.registry) == self.selected_registry) {
+      self.selected_registry = None;
+    }
+  }
+
+  /// Record a registration step, updating it in place once it finishes
+  pub fn record_step(&mut self, step: Step) {
+    match self.register_steps.last_mut() {
+      Some(last) if last.label == step.label && last.status == StepStatus::Running => *last = step,
+      _ => self.register_steps.push(step),
+    }
+  }
+
+  /// Flip between public and private repos
+  pub fn toggle_register_private(&mut self) {
+    self.register_options.private = !self.register_options.private;
+  }
+
+  /// Move to the next owner: personal account, then each org
+  ///
+  /// Returns false when the orgs haven't been fetched yet.
+  pub fn cycle_register_owner(&mut self) -> bool {
+    let Some(orgs) = &self.register_orgs else {
+      return false;
+    };
+    let current = self
+      .register_options
+      .org
+      .as_ref()
+      .and_then(|org| orgs.iter().position(|o| o == org));
+    let next = match current {
+      Some(i) => orgs.get(i + 1),
+      None if self.register_options.org.is_some() => None,
+      None => orgs.first(),
+    };
+    self.register_options.org = next.cloned();
+    true
+  }
+
+  /// Turn issues on or off for new repos
+  pub fn toggle_register_issues(&mut self) {
+    let enabled = self.register_options.has_issues.unwrap_or(true);
+    self.register_options.has_issues = Some(!enabled);
+  }
+
+  /// Start editing a text option of new repos
+  pub fn start_option_edit(&mut self, field: RepoTextField) {
+    let options = &self.register_options;
+    let current = match field {
+      RepoTextField::Description => options.description.clone().unwrap_or_default(),
+      RepoTextField::Homepage => options.homepage.clone().unwrap_or_default(),
+      RepoTextField::Topics => options.topics.join(", "),
+      RepoTextField::Gitignore => options.gitignore_template.clone().unwrap_or_default(),
+    };
+    self.option_input.set(current);
+    self.editing_option = field;
+    self.input_mode = InputMode::Editing;
+  }
+
+  /// Apply the edited option; an empty description falls back to the
+  /// placeholder
+  pub fn commit_option_edit(&mut self) {
+    let text = self.option_input.as_str().trim();
+    let value = Some(text.to_string()).filter(|t| !t.is_empty());
+    let options = &mut self.register_options;
+    match self.editing_option {
+      RepoTextField::Description => options.description = value,
+      RepoTextField::Homepage => options.homepage = value,
+      RepoTextField::Topics => {
+        options.topics =
+          text.split(',').map(str::trim).filter(|t| !t.is_empty()).map(String::from).collect();
+      }
+      RepoTextField::Gitignore => options.gitignore_template = value,
+    }
+    self.input_mode = InputMode::Normal;
+  }
+
+  /// Remember the current register options in config
+  pub fn save_register_options(&mut self) {
+    let options = &self.register_options;
+    self.config.register = crate::config::RegisterDefaults {
+      on_failure: self.config.register.on_failure,
+      ..crate::config::RegisterDefaults::from_repo_options(options)
+    };
+    self.config.default_topics = options.topics.clone();
+    self.config.default_gitignore = options.gitignore_template.clone().unwrap_or_default();
+    self.autosave();
+  }
+
+  /// The registration the confirmation dialog is asking about
+  pub fn pending_registration(&self) -> Option<&PendingRegistration> {
+    match &self.modal {
+      Some(Modal::Register(pending)) => Some(pending),
+      _ => None,
+    }
+  }
+
+  /// Quit, asking first while a registration is running since quitting
+  /// aborts it halfway
+  pub fn request_quit(&mut self) {
+    if self.is_registering {
+      self.modal = Some(Modal::Quit);
+    } else {
+      self.should_quit = true;
+    }
+  }
+
+  /// Answer the quit dialog; staying leaves the registration running
+  ///
+  /// Quitting cancels it, and says which repo it may have left behind once
+  /// the terminal is back.
+  pub fn confirm_quit(&mut self, quit: bool) {
+    self.modal = None;
+    if quit {
+      if self.cancel_registration() && self.repo_may_be_left() {
+        self.quit_notice = self.register_status.clone();
+      }
+      self.should_quit = true;
+    }
+  }
+
+  /// Ask before cancelling a running registration, which may leave its repo
+  /// half set up
+  pub fn request_cancel_registration(&mut self) {
+    if self.is_registering {
+      self.modal = Some(Modal::CancelRegistration);
+    }
+  }
+
+  /// Answer the cancel dialog; staying leaves the registration running
+  pub fn confirm_cancel_registration(&mut self, cancel: bool) {
+    self.modal = None;
+    if cancel {
+      self.cancel_registration();
+    }
+  }
+
+  /// Abort an in-flight registration, returning whether one was running
+  pub fn cancel_registration(&mut self) -> bool {
+    let Some(task) = self.registration_task.take() else {
+      return false;
+    };
+    task.abort();
+    self.is_registering = false;
+    let status = match &self.registration_repo {
+      Some(repo) if self.repo_may_be_left() => {
+        format!("Registration cancelled; repo {} may be left half set up", repo)
+      }
+      _ => "Registration cancelled".to_string(),
+    };
+    self.register_status = Some(status);
+    true
+  }
+
+  /// Whether the registration got as far as creating its repo
+  fn repo_may_be_left(&self) -> bool {
+    self.register_steps.iter().any(|step| step.label == CREATE_REPO_STEP)
+  }
+
+  /// Search results passing the current result filter; failed checks
+  /// only show under "all" and "problems"
+  pub fn filtered_results(&self) -> Vec<&AvailabilityResult> {
+    let show_errors = self.result_filter == ResultFilter::All;
+    self.search_results
+      .iter()
+      .filter(|r| self.result_filter.matches(r, show_errors))
       .collect()
   }
 
-  /// Toggle screen between Search, Register, and Settings
-  pub fn toggle_screen(&mut self) {
-    self.screen = match self.screen {
-      Screen::Search => Screen::Register,
-      Screen::Register => Screen::Settings,
-      Screen::Settings => Screen::Search,
+  /// Go to the next screen in [`Screen::ALL`], wrapping to the first
+  pub fn next_screen(&mut self) {
+    self.screen = Screen::ALL[(self.screen.index() + 1) % Screen::ALL.len()];
+  }
+
+  /// Go to the previous screen in [`Screen::ALL`], wrapping to the last
+  pub fn previous_screen(&mut self) {
+    let len = Screen::ALL.len();
+    self.screen = Screen::ALL[(self.screen.index() + len - 1) % len];
+  }
+
+  /// Index of the current screen's list item at `column`, `row`, if any
+  pub fn list_row_at(&self, column: u16, row: u16) -> Option<usize> {
+    let offset = match self.screen {
+      Screen::Search => self.results_list_state.offset(),
+      Screen::Domain => self.domain_list_state.offset(),
+      Screen::Register | Screen::Settings => 0,
+    };
+    row_at(self.hit_areas.list, offset, column, row)
+  }
+
+  /// Highlight item `index` of the current screen's list; false when the
+  /// list is shorter, e.g. for a pending search row
+  pub fn select_row(&mut self, index: usize) -> bool {
+    let screen = self.screen;
+    match screen {
+      Screen::Search if index < self.filtered_results().len() => {
+        self.results_list_state.select(Some(index));
+      }
+      Screen::Register => return self.select_available(index),
+      Screen::Settings if index < self.registry_count() => self.selected_setting = index,
+      Screen::Domain if index < self.domain_results.len() => {
+        self.domain_list_state.select(Some(index));
+      }
+      _ => return false,
+    }
+    true
+  }
+
+  /// Record a click at `now`, returning whether it completes a double-click
+  pub fn click(&mut self, column: u16, row: u16, now: Instant) -> bool {
+    let double = self.last_click.is_some_and(|(at, c, r)| {
+      (c, r) == (column, row) && now.duration_since(at) < DOUBLE_CLICK
+    });
+    self.last_click = if double { None } else { Some((now, column, row)) };
+    double
+  }
+
+  /// The focused input on the Domain screen
+  pub fn domain_field_input(&mut self) -> &mut TextInput {
+    match self.domain_field {
+      DomainField::Name => &mut self.domain_input,
+      DomainField::Tlds => &mut self.domain_tlds,
+    }
+  }
+
+  /// Switch focus between the domain name and TLD inputs
+  pub fn toggle_domain_field(&mut self) {
+    self.domain_field = match self.domain_field {
+      DomainField::Name => DomainField::Tlds,
+      DomainField::Tlds => DomainField::Name,
+    };
+  }
+
+  /// Start a domain check, returning its generation, name and expanded TLDs
+  ///
+  /// An empty TLD input falls back to the configured defaults; an unknown
+  /// `@group` is reported in `domain_error`, in place of the last results,
+  /// and nothing starts.
+  pub fn begin_domain_check(&mut self) -> Option<(u64, String, Vec<String>)> {
+    let name = self.domain_input.as_str().trim().to_string();
+    if name.is_empty() {
+      return None;
+    }
+    let entries = crate::registry::domain::parse_tlds(self.domain_tlds.as_str());
+    let tlds = if entries.is_empty() {
+      self.config.default_tlds.clone()
+    } else {
+      match self.config.expand_tlds(&entries) {
+        Ok(tlds) => tlds,
+        Err(e) => {
+          self.domain_error = Some(e.to_string());
+          // The results panel only shows the error once it's empty
+          self.domain_results.clear();
+          self.domain_list_state = ListState::default();
+          return None;
+        }
+      }
+    };
+
+    self.domain_generation += 1;
+    self.is_checking_domains = true;
+    self.domain_error = None;
+    self.domain_results.clear();
+    self.domain_list_state = ListState::default();
+    Some((self.domain_generation, name, tlds))
+  }
+
+  /// Apply results from domain check `generation`, ignoring superseded checks
+  pub fn finish_domain_check(
+    &mut self,
+    generation: u64,
+    mut results: Vec<AvailabilityResult>,
+  ) -> bool {
+    if generation != self.domain_generation {
+      return false;
+    }
+    crate::registry::dedupe(&mut results);
+    self.domain_results = results;
+    self.is_checking_domains = false;
+    true
+  }
+
+  /// Move the domain results highlight down, starting from the first row
+  pub fn select_next_domain(&mut self) {
+    let count = self.domain_results.len();
+    if count == 0 {
+      return;
+    }
+    let next = self.domain_list_state.selected().map_or(0, |i| (i + 1).min(count - 1));
+    self.domain_list_state.select(Some(next));
+  }
+
+  /// Move the domain results highlight up
+  pub fn select_previous_domain(&mut self) {
+    if let Some(i) = self.domain_list_state.selected() {
+      self.domain_list_state.select(Some(i.saturating_sub(1)));
+    }
+  }
+
+  /// The highlighted domain result, if any
+  pub fn selected_domain(&self) -> Option<&AvailabilityResult> {
+    self.domain_results.get(self.domain_list_state.selected()?)
+  }
+
+  /// Open a registrar search for a free domain, or the site for a taken one
+  pub fn open_selected_domain(&self) {
+    let Some(result) = self.selected_domain() else {
+      return;
     };
+    let url = match result.available() {
+      Some(true) => crate::registry::domain::registrar_url(&result.name),
+      _ => result.page_url(),
+    };
+    if let Err(e) = open::that(&url) {
+      tracing::warn!(%url, error = %e, "failed to open browser");
+    }
   }
 
   /// Get number of registry settings
   pub fn registry_count(&self) -> usize {
-    8 // npm, crates, pypi, github, brew, flatpak, debian, dev_domain
+    self.registry_rows().count()
+  }
+
+  /// Registry rows on the settings screen, in display order, with the
+  /// handle checks folded into one row at the end; the trademark screening
+  /// is left to `--trademark`
+  ///
+  /// Both rendering and toggling index into this order.
+  pub fn registry_rows(&self) -> impl Iterator<Item = (RegistryRow, bool)> + '_ {
+    let settings = &self.config.registries;
+    RegistryType::ALL
+      .into_iter()
+      .filter(|registry| !registry.is_handle() && *registry != RegistryType::Trademark)
+      .map(|registry| (RegistryRow::Registry(registry), settings.is_enabled(registry)))
+      .chain([(RegistryRow::Handles, settings.handles())])
+  }
+
+  /// Number of rows on the settings screen: registries, default TLDs,
+  /// GitHub username and desktop notifications
+  pub fn settings_count(&self) -> usize {
+    self.registry_count() + 3
+  }
+
+  /// Whether the default TLDs row is selected
+  pub fn tld_setting_selected(&self) -> bool {
+    self.selected_setting == self.registry_count()
+  }
+
+  /// Whether the GitHub username row is selected
+  pub fn username_setting_selected(&self) -> bool {
+    self.selected_setting == self.registry_count() + 1
+  }
+
+  /// Whether the desktop notifications row is selected
+  pub fn desktop_setting_selected(&self) -> bool {
+    self.selected_setting == self.registry_count() + 2
+  }
+
+  /// Flip `notifications.desktop` and save it
+  ///
+  /// Builds without the `desktop-notifications` feature refuse to turn them
+  /// on and say why in a toast.
+  pub fn toggle_desktop_notifications(&mut self) {
+    if !cfg!(feature = "desktop-notifications") && !self.config.notifications.desktop {
+      let reason = "Desktop notifications need a build with the desktop-notifications feature";
+      self.toasts.push(reason, Severity::Error);
+      return;
+    }
+    self.config.notifications.desktop = !self.config.notifications.desktop;
+    self.autosave();
+  }
+
+  /// Show a desktop notification if they're turned on
+  pub fn notify_desktop(&self, summary: &str, body: &str) {
+    if self.config.notifications.desktop {
+      crate::notify::desktop(summary, body);
+    }
+  }
+
+  /// Notify that the finished search found the name free everywhere
+  ///
+  /// Searches with anything taken or unknown, or no results, stay quiet.
+  pub fn notify_search_verdict(&self) {
+    let results = &self.search_results;
+    if !results.is_empty() && results.iter().all(|r| r.available() == Some(true)) {
+      let body = format!("{} is available on all {} registries", results[0].name, results.len());
+      self.notify_desktop("nbi: name available", &body);
+    }
+  }
+
+  /// Start editing the default TLDs as a comma-separated list
+  pub fn start_tld_edit(&mut self) {
+    self.tld_input = self.config.default_tlds.join(",");
+    self.input_mode = InputMode::Editing;
+  }
+
+  /// Save the edited TLD list; an empty list keeps the previous value
+  pub fn commit_tld_edit(&mut self) {
+    let tlds = crate::registry::domain::parse_tlds(&self.tld_input);
+    if !tlds.is_empty() {
+      self.config.default_tlds = tlds;
+      self.autosave();
+    }
+    self.input_mode = InputMode::Normal;
+  }
+
+  /// Start editing `github_username`
+  pub fn start_username_edit(&mut self) {
+    self.username_input = self.config.github_username.clone();
+    self.input_mode = InputMode::Editing;
+  }
+
+  /// Save the edited username; an empty one falls back to the token's user
+  pub fn commit_username_edit(&mut self) {
+    self.config.github_username = self.username_input.trim().to_string();
+    self.autosave();
+    self.input_mode = InputMode::Normal;
   }
 
   /// Toggle registry at current selection
   pub fn toggle_selected_registry(&mut self) {
-    match self.selected_setting {
-      0 => self.config.registries.npm = !self.config.registries.npm,
-      1 => self.config.registries.crates = !self.config.registries.crates,
-      2 => self.config.registries.pypi = !self.config.registries.pypi,
-      3 => self.config.registries.github = !self.config.registries.github,
-      4 => self.config.registries.brew = !self.config.registries.brew,
-      5 => self.config.registries.flatpak = !self.config.registries.flatpak,
-      6 => self.config.registries.debian = !self.config.registries.debian,
-      7 => self.config.registries.dev_domain = !self.config.registries.dev_domain,
-      _ => {}
+    if self.toggle_registry_row(self.selected_setting).is_some() {
+      self.autosave();
     }
-    // Auto-save config
-    let _ = self.save_config();
+  }
+
+  /// Flip the registry shown at `row`, returning which one changed
+  fn toggle_registry_row(&mut self, row: usize) -> Option<RegistryRow> {
+    let (row, enabled) = self.registry_rows().nth(row)?;
+    match row {
+      RegistryRow::Registry(registry) => self.config.registries.set_enabled(registry, !enabled),
+      RegistryRow::Handles => self.config.registries.set_handles(!enabled),
+    }
+    Some(row)
   }
 
   /// Move selection up in register screen
   pub fn select_previous(&mut self) {
-    let available_count = self.get_available_registries().len();
-    if available_count > 0 && self.selected_registry > 0 {
-      self.selected_registry -= 1;
-    }
+    self.select_available(self.selected_registry_index().saturating_sub(1));
   }
 
   /// Move selection down in register screen
   pub fn select_next(&mut self) {
-    let available_count = self.get_available_registries().len();
-    if available_count > 0 && self.selected_registry < available_count - 1 {
-      self.selected_registry += 1;
-    }
+    self.select_available(self.selected_registry_index() + 1);
   }
 
   /// Get status text for a registry result
   pub fn get_status_symbol(result: &AvailabilityResult) -> &'static str {
-    match result.available {
-      Some(true) => "✓",
-      Some(false) => "✗",
-      None => "?",
+    match result.availability {
+      Availability::Available => "✓",
+      Availability::Taken => "✗",
+      Availability::Reserved => "⊘",
+      Availability::Unknown => "?",
     }
   }
 
-  /// Get status color for a registry result
-  pub fn get_status_color(result: &AvailabilityResult) -> ratatui::style::Color {
-    use ratatui::style::Color;
-    match result.available {
-      Some(true) => Color::Green,
-      Some(false) => Color::Red,
-      None => Color::Yellow,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::display_order;
+
+  #[test]
+  fn test_toggling_each_row_flips_only_that_registry() {
+    let mut app = App::with_config(Config::default());
+    let rows: Vec<_> = app.registry_rows().collect();
+    assert_eq!(rows.len(), app.registry_count());
+
+    for (row, (registry, _)) in rows.iter().enumerate() {
+      let before: Vec<_> = app.registry_rows().collect();
+      assert_eq!(app.toggle_registry_row(row), Some(*registry));
+      let after: Vec<_> = app.registry_rows().collect();
+
+      for (other, ((r, was), (_, now))) in before.iter().zip(&after).enumerate() {
+        assert_eq!(was != now, other == row, "row {} toggled {:?}", row, r);
+      }
     }
   }
-}
 
-impl Default for App {
-  fn default() -> Self {
-    Self::new()
+  #[test]
+  fn test_github_row_toggles_github_field() {
+    let mut app = App::with_config(Config::default());
+    let github = RegistryRow::Registry(RegistryType::GitHub);
+    let row = app.registry_rows().position(|(r, _)| r == github).unwrap();
+    app.toggle_registry_row(row);
+    assert!(!app.config.registries.github);
+    assert!(app.config.registries.npm && app.config.registries.brew);
+  }
+
+  #[test]
+  fn test_handles_row_toggles_every_handle() {
+    let mut app = App::with_config(Config::default());
+    app.config.registries.bluesky = true;
+    let row = app.registry_rows().position(|(r, _)| r == RegistryRow::Handles).unwrap();
+    assert_eq!(row, app.registry_count() - 1);
+    assert_eq!(app.registry_rows().nth(row), Some((RegistryRow::Handles, false)));
+
+    app.toggle_registry_row(row);
+    assert!(RegistryType::HANDLES.iter().all(|r| app.config.registries.is_enabled(*r)));
+    app.toggle_registry_row(row);
+    assert!(!RegistryType::HANDLES.iter().any(|r| app.config.registries.is_enabled(*r)));
+    assert!(app.config.registries.github);
+  }
+
+  #[test]
+  fn test_desktop_row_follows_tlds() {
+    let mut app = App::with_config(Config::default());
+    app.selected_setting = app.settings_count() - 1;
+    assert!(app.desktop_setting_selected());
+    assert!(!app.tld_setting_selected());
+    assert_eq!(app.toggle_registry_row(app.selected_setting), None);
+  }
+
+  #[test]
+  fn test_desktop_toggle_needs_the_feature() {
+    let mut app = App::with_config(Config::default());
+    app.toggle_desktop_notifications();
+    assert_eq!(app.config.notifications.desktop, cfg!(feature = "desktop-notifications"));
+    if !cfg!(feature = "desktop-notifications") {
+      let last = app.toasts.history().back().unwrap();
+      assert_eq!(last.severity, Severity::Error);
+      assert!(last.message.contains("desktop-notifications feature"));
+    }
+
+    // Turning them off always works
+    app.config.notifications.desktop = true;
+    app.toggle_desktop_notifications();
+    assert!(!app.config.notifications.desktop);
+  }
+
+  #[test]
+  fn test_failed_autosave_marks_settings_unsaved() {
+    // A config path under a regular file can't be written
+    let blocker = std::env::temp_dir().join(format!("nbi-blocker-{}", std::process::id()));
+    std::fs::write(&blocker, "").unwrap();
+    let failing = ConfigSource::resolve(Some(blocker.join("config.toml")), None);
+    let mut app = App::with_source(failing, Config::default());
+
+    app.toggle_selected_registry();
+    assert!(app.settings_unsaved);
+    let last = app.toasts.history().back().unwrap();
+    assert_eq!(last.severity, Severity::Error);
+    assert!(last.message.starts_with("Settings not saved"));
+
+    app.source = App::with_config(Config::default()).source;
+    app.toggle_selected_registry();
+    assert!(!app.settings_unsaved);
+    assert_eq!(app.toasts.history().back().unwrap().severity, Severity::Success);
+    std::fs::remove_file(blocker).unwrap();
+  }
+
+  #[test]
+  fn test_applying_a_profile_resets_derived_state() {
+    let mut app = App::with_config(Config::default());
+    app.token_check = TokenCheck::Invalid("bad".to_string());
+    app.settings_unsaved = true;
+    let mut work = Config::default();
+    work.register.org = "acme".to_string();
+    work.default_tlds = vec!["io".to_string()];
+
+    app.apply_config(app.source.with_profile(Some("work".to_string())), work);
+    assert_eq!(app.source.profile(), Some("work"));
+    assert_eq!(app.register_options.org.as_deref(), Some("acme"));
+    assert_eq!(app.domain_tlds.as_str(), "io");
+    assert_eq!(app.token_check, TokenCheck::Unchecked);
+    assert!(!app.settings_unsaved);
+  }
+
+  #[test]
+  fn test_username_row_sits_between_tlds_and_desktop() {
+    let mut app = App::with_config(Config::default());
+    app.selected_setting = app.registry_count() + 1;
+    assert!(app.username_setting_selected());
+    assert!(!app.tld_setting_selected() && !app.desktop_setting_selected());
+    assert_eq!(app.toggle_registry_row(app.selected_setting), None);
+  }
+
+  fn available(registry: RegistryType) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: "foo".to_string(),
+      availability: Availability::Available,
+      error: None,
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  fn timed(result: AvailabilityResult) -> TimedResult {
+    TimedResult { duration_ms: 40 * display_order(result.registry) as u64, result }
+  }
+
+  #[test]
+  fn test_new_results_reset_register_selection() {
+    let mut app = App::with_config(Config::default());
+    app.set_search_results(RegistryType::ALL[..4].iter().copied().map(available).collect());
+    for _ in 0..4 {
+      app.select_next();
+    }
+    // Sorted by name: crates.io, GitHub, npm, PyPI
+    assert_eq!(app.selected_registry, Some(RegistryType::PyPi));
+    assert_eq!(app.selected_registry_index(), 3);
+
+    app.set_search_results(RegistryType::ALL[..2].iter().copied().map(available).collect());
+    assert_eq!(app.selected_registry, None);
+    assert_eq!(app.highlighted_registry().map(|r| r.registry), Some(RegistryType::Crates));
+  }
+
+  #[test]
+  fn test_clamp_register_selection_after_shrink() {
+    let mut app = App::with_config(Config::default());
+    app.search_results = RegistryType::ALL[..4].iter().copied().map(available).collect();
+    app.selected_registry = Some(RegistryType::Crates);
+    app.clamp_registry_selection();
+    assert_eq!(app.selected_registry, Some(RegistryType::Crates));
+
+    app.search_results.remove(1);
+    app.clamp_registry_selection();
+    assert_eq!(app.selected_registry, None);
+    assert_eq!(app.highlighted_registry().map(|r| r.registry), Some(RegistryType::Npm));
+
+    app.search_results.clear();
+    app.clamp_registry_selection();
+    assert!(app.highlighted_registry().is_none());
+  }
+
+  #[tokio::test]
+  async fn test_stale_search_does_not_overwrite_newer_results() {
+    use std::sync::Arc;
+    use tokio::sync::{oneshot, Mutex};
+
+    let app = Arc::new(Mutex::new(App::with_config(Config::default())));
+    let mut tasks = Vec::new();
+    let mut releases = Vec::new();
+    for term in ["foo", "bar"] {
+      let generation = {
+        let mut app = app.lock().await;
+        app.search_input.set(term);
+        app.begin_search(term)
+      };
+      let (release, done) = oneshot::channel::<()>();
+      releases.push(release);
+      let app = Arc::clone(&app);
+      tasks.push(tokio::spawn(async move {
+        done.await.unwrap();
+        let mut result = available(RegistryType::Npm);
+        result.name = term.to_string();
+        let mut app = app.lock().await;
+        app.add_search_result(generation, timed(result)) && app.finish_search(generation)
+      }));
+    }
+
+    // "bar" finishes first, then the stale "foo" search
+    let foo = releases.remove(0);
+    releases.remove(0).send(()).unwrap();
+    assert!(tasks.pop().unwrap().await.unwrap());
+    foo.send(()).unwrap();
+    assert!(!tasks.pop().unwrap().await.unwrap());
+
+    let app = app.lock().await;
+    assert!(!app.is_searching);
+    assert_eq!(app.last_searched.as_deref(), Some("bar"));
+    assert_eq!(app.search_results[0].name, "bar");
+  }
+
+  #[test]
+  fn test_stale_search_keeps_searching_flag() {
+    let mut app = App::with_config(Config::default());
+    let first = app.begin_search("foo");
+    app.begin_search("foo");
+    assert!(!app.finish_search(first));
+    assert!(app.is_searching);
+  }
+
+  #[test]
+  fn test_streamed_results_sort_available_first() {
+    let mut app = App::with_config(Config::default());
+    app.search_input.set("foo");
+    let generation = app.begin_search("foo");
+    // Of the handle checks, only the GitHub user is on by default
+    assert_eq!(app.pending_registries, RegistryType::ALL[..9]);
+    assert!(app.search_elapsed().is_some());
+
+    let taken =
+      AvailabilityResult { availability: Availability::Taken, ..available(RegistryType::Brew) };
+    let failed =
+      AvailabilityResult { availability: Availability::Unknown, ..available(RegistryType::Crates) };
+    for result in [failed, taken, available(RegistryType::Npm)] {
+      assert!(app.add_search_result(generation, timed(result)));
+    }
+    // The highlight follows its result as later ones sort in above it
+    app.select_next_result();
+    app.select_next_result();
+    assert_eq!(app.selected_result().map(|r| r.registry), Some(RegistryType::Brew));
+    for registry in [RegistryType::GitHub, RegistryType::DevDomain] {
+      assert!(app.add_search_result(generation, timed(available(registry))));
+    }
+    assert_eq!(app.selected_result().map(|r| r.registry), Some(RegistryType::Brew));
+
+    let order: Vec<_> = app.search_results.iter().map(|r| r.registry).collect();
+    let expected = [
+      RegistryType::DevDomain,
+      RegistryType::GitHub,
+      RegistryType::Npm,
+      RegistryType::Brew,
+      RegistryType::Crates,
+    ];
+    assert_eq!(order, expected);
+    assert!(!app.pending_registries.contains(&RegistryType::Npm));
+    assert_eq!(app.pending_registries.len(), 9 - 5);
+    assert_eq!(app.search_durations.len(), 5);
+
+    assert!(app.finish_search(generation));
+    assert!(app.pending_registries.is_empty());
+    assert!(app.search_elapsed().is_none());
+  }
+
+  #[tokio::test]
+  async fn test_cancel_search_keeps_partial_results() {
+    let mut app = App::with_config(Config::default());
+    assert!(!app.cancel_search());
+
+    let generation = app.begin_search("foo");
+    let task = tokio::spawn(std::future::pending::<()>());
+    app.search_task = Some(task.abort_handle());
+    app.add_search_result(generation, timed(available(RegistryType::Npm)));
+
+    assert!(app.cancel_search());
+    assert!(!app.is_searching);
+    assert!(app.search_incomplete);
+    assert!(app.pending_registries.is_empty());
+    assert_eq!(app.search_results.len(), 1);
+    assert!(task.await.unwrap_err().is_cancelled());
+
+    // A late result from the cancelled search is ignored
+    assert!(!app.add_search_result(generation - 1, timed(available(RegistryType::Crates))));
+  }
+
+  #[tokio::test]
+  async fn test_new_search_aborts_previous_task() {
+    let mut app = App::with_config(Config::default());
+    app.begin_search("foo");
+    let task = tokio::spawn(std::future::pending::<()>());
+    app.search_task = Some(task.abort_handle());
+    app.search_incomplete = true;
+
+    app.begin_search("foo");
+    assert!(task.await.unwrap_err().is_cancelled());
+    assert!(!app.search_incomplete);
+  }
+
+  #[test]
+  fn test_stale_search_term_after_editing_input() {
+    let mut app = App::with_config(Config::default());
+    app.search_input.set("foo");
+    let generation = app.begin_search("foo");
+    assert!(app.finish_search(generation));
+    assert_eq!(app.stale_search_term(), None);
+
+    app.search_input.insert('d');
+    assert_eq!(app.stale_search_term(), Some("foo"));
+    assert_eq!(app.last_searched.as_deref(), Some("foo"));
+  }
+
+  #[test]
+  fn test_results_highlight_stays_in_filtered_range() {
+    let mut app = App::with_config(Config::default());
+    let mut results: Vec<_> = RegistryType::ALL[..3].iter().copied().map(available).collect();
+    results[1].availability = Availability::Taken;
+    app.set_search_results(results);
+
+    app.select_previous_result();
+    assert!(app.selected_result().is_none());
+    assert!(!app.open_result_detail());
+
+    // Taken results sort after available ones
+    for _ in 0..5 {
+      app.select_next_result();
+    }
+    assert_eq!(app.selected_result().map(|r| r.registry), Some(RegistryType::Crates));
+
+    // Available only: crates drops out and the highlight resets
+    app.cycle_result_filter();
+    assert!(app.selected_result().is_none());
+    app.select_next_result();
+    app.select_next_result();
+    assert_eq!(app.selected_result().map(|r| r.registry), Some(RegistryType::PyPi));
+    assert!(app.open_result_detail());
+
+    app.set_search_results(Vec::new());
+    assert!(!app.show_result_detail);
+    assert_eq!(app.results_list_state.selected(), None);
+  }
+
+  #[test]
+  fn test_tab_cycles_through_domain_screen() {
+    let mut app = App::with_config(Config::default());
+    let mut screens = Vec::new();
+    for _ in 0..4 {
+      app.next_screen();
+      screens.push(app.screen);
+    }
+    assert_eq!(screens, [Screen::Register, Screen::Settings, Screen::Domain, Screen::Search]);
+  }
+
+  #[test]
+  fn test_back_tab_cycles_in_reverse() {
+    let mut app = App::with_config(Config::default());
+    let mut screens = Vec::new();
+    for _ in 0..4 {
+      app.previous_screen();
+      screens.push(app.screen);
+    }
+    assert_eq!(screens, [Screen::Domain, Screen::Settings, Screen::Register, Screen::Search]);
+
+    for screen in Screen::ALL {
+      app.screen = screen;
+      app.next_screen();
+      app.previous_screen();
+      assert_eq!(app.screen, screen);
+    }
+  }
+
+  #[test]
+  fn test_session_restores_results_marked_with_their_age() {
+    let mut app = App::with_config(Config::default());
+    assert!(app.session().is_none());
+
+    let generation = app.begin_search("foo");
+    app.add_search_result(generation, timed(available(RegistryType::Npm)));
+    app.finish_search(generation);
+    app.screen = Screen::Register;
+    app.result_filter = ResultFilter::Available;
+    let mut session = app.session().unwrap();
+    assert!(app.restored_age().is_none());
+    session.results[0].checked_at -= 2 * 3600;
+
+    let mut restored = App::with_config(Config::default());
+    restored.restore_session(session);
+    assert_eq!(restored.search_results, app.search_results);
+    assert_eq!(restored.search_input.as_str(), "foo");
+    assert_eq!((restored.screen, restored.result_filter), (app.screen, app.result_filter));
+    let age = restored.restored_age().unwrap();
+    assert!((7200..7300).contains(&age.as_secs()), "{:?}", age);
+
+    // A new search clears the mark
+    restored.begin_search("foo");
+    assert!(restored.restored_age().is_none());
+  }
+
+  #[test]
+  fn test_bad_key_bindings_fall_back_to_the_defaults() {
+    let mut config = Config::default();
+    config.keys.select_down = vec!["j".to_string()];
+    let app = App::with_config(config.clone());
+    assert_eq!(app.keys.label(crate::tui::keys::Action::SelectDown), "j");
+
+    config.keys.quit = vec!["hyper+q".to_string()];
+    assert_eq!(App::with_config(config).keys, KeyMap::default());
+  }
+
+  #[test]
+  fn test_tab_hit_testing_follows_the_titles() {
+    let tabs = Rect::new(0, 0, 80, 3);
+    // "Search [1]" fills columns 2-11, padded to 1-12; 13 is the divider
+    assert_eq!(tab_at(tabs, 1, 1), Some(Screen::Search));
+    assert_eq!(tab_at(tabs, 12, 1), Some(Screen::Search));
+    assert_eq!(tab_at(tabs, 13, 1), None);
+    assert_eq!(tab_at(tabs, 14, 1), Some(Screen::Register));
+    assert_eq!(tab_at(tabs, 0, 1), None);
+    assert_eq!(tab_at(tabs, 14, 3), None);
+    assert_eq!(tab_at(tabs, 79, 1), None);
+  }
+
+  #[test]
+  fn test_row_hit_testing_skips_borders_and_adds_the_offset() {
+    let list = Rect::new(0, 10, 40, 6);
+    assert_eq!(row_at(list, 0, 5, 11), Some(0));
+    assert_eq!(row_at(list, 3, 5, 14), Some(6));
+    assert_eq!(row_at(list, 0, 5, 10), None);
+    assert_eq!(row_at(list, 0, 5, 15), None);
+    assert_eq!(row_at(list, 0, 0, 12), None);
+    assert_eq!(row_at(Rect::default(), 0, 0, 0), None);
+  }
+
+  #[test]
+  fn test_clicked_rows_select_within_the_list() {
+    let mut app = App::with_config(Config::default());
+    app.set_search_results(RegistryType::ALL[..3].iter().copied().map(available).collect());
+    app.hit_areas.list = Rect::new(0, 4, 40, 10);
+
+    assert_eq!(app.list_row_at(3, 7), Some(2));
+    assert!(app.select_row(2));
+    assert_eq!(app.selected_result().map(|r| r.registry), Some(RegistryType::PyPi));
+    assert!(!app.select_row(3));
+
+    app.screen = Screen::Settings;
+    assert!(app.select_row(1));
+    assert_eq!(app.selected_setting, 1);
+    assert!(!app.select_row(app.registry_count()));
+  }
+
+  #[test]
+  fn test_second_quick_click_on_the_same_cell_is_a_double_click() {
+    let mut app = App::with_config(Config::default());
+    let now = Instant::now();
+    assert!(!app.click(3, 7, now));
+    assert!(app.click(3, 7, now + Duration::from_millis(200)));
+    // A third click starts over
+    assert!(!app.click(3, 7, now + Duration::from_millis(300)));
+    assert!(!app.click(3, 8, now + Duration::from_millis(400)));
+    assert!(!app.click(3, 8, now + Duration::from_secs(1)));
+  }
+
+  #[test]
+  fn test_domain_check_expands_groups_and_defaults() {
+    let mut app = App::with_config(Config::default());
+    assert_eq!(app.domain_tlds.as_str(), "com,net,org,io,dev");
+    assert!(app.begin_domain_check().is_none());
+
+    app.domain_input.set("foo");
+    app.domain_tlds.set("@common, sh");
+    let (generation, name, tlds) = app.begin_domain_check().unwrap();
+    assert_eq!((generation, name.as_str()), (1, "foo"));
+    assert_eq!(tlds, ["com", "net", "org", "sh"]);
+
+    app.domain_tlds.set("");
+    let (_, _, tlds) = app.begin_domain_check().unwrap();
+    assert_eq!(tlds, app.config.default_tlds);
+  }
+
+  #[test]
+  fn test_domain_check_reports_unknown_group() {
+    let mut app = App::with_config(Config::default());
+    app.domain_input.set("foo");
+    app.domain_results = vec![available(RegistryType::DevDomain)];
+    app.domain_tlds.set("@nope");
+    assert!(app.begin_domain_check().is_none());
+    assert!(!app.is_checking_domains);
+    assert!(app.domain_error.as_deref().unwrap().contains("@nope"));
+    assert!(app.domain_results.is_empty(), "earlier results would hide the error");
+  }
+
+  #[test]
+  fn test_stale_domain_check_is_ignored() {
+    let mut app = App::with_config(Config::default());
+    app.domain_input.set("foo");
+    let (first, ..) = app.begin_domain_check().unwrap();
+    let (second, ..) = app.begin_domain_check().unwrap();
+
+    assert!(!app.finish_domain_check(first, vec![available(RegistryType::DevDomain)]));
+    assert!(app.is_checking_domains && app.domain_results.is_empty());
+    assert!(app.finish_domain_check(second, Vec::new()));
+    assert!(!app.is_checking_domains);
+  }
+
+  #[test]
+  fn test_pending_registration_describes_manifest() {
+    let pending = PendingRegistration {
+      name: "foo".to_string(),
+      registries: vec![RegistryType::Crates],
+      options: RepoOptions::default(),
+      login: None,
+      on_failure: OnFailure::Keep,
+    };
+    let filenames: Vec<_> = pending.manifests().iter().map(|m| m.filename()).collect();
+    assert_eq!(filenames, ["Cargo.toml"]);
+    assert_eq!(pending.owner(), "your account");
+    let previews = pending.manifest_previews();
+    assert!(previews[0].1.contains("name = \"foo\""));
+    assert!(previews[0].1.contains("Reserved package name for Cargo.toml"));
+
+    let github = PendingRegistration {
+      registries: vec![RegistryType::GitHub],
+      options: RepoOptions { org: Some("acme".to_string()), ..RepoOptions::default() },
+      ..pending
+    };
+    assert!(github.manifest_previews().is_empty());
+    assert_eq!(github.repo_options().description, None);
+    assert_eq!(github.owner(), "acme");
+  }
+
+  #[test]
+  fn test_combined_registration_shares_one_repo() {
+    let pending = PendingRegistration {
+      name: "foo".to_string(),
+      registries: vec![RegistryType::Npm, RegistryType::GitHub, RegistryType::Crates],
+      options: RepoOptions::default(),
+      login: None,
+      on_failure: OnFailure::Keep,
+    };
+    assert_eq!(pending.manifests(), [ManifestType::Npm, ManifestType::Crates]);
+    assert_eq!(
+      pending.repo_options().description.as_deref(),
+      Some("Reserved package name for package.json, Cargo.toml")
+    );
+
+    let described = PendingRegistration {
+      options: RepoOptions { description: Some("My tool".to_string()), ..RepoOptions::default() },
+      ..pending
+    };
+    assert_eq!(described.repo_options().description.as_deref(), Some("My tool"));
+  }
+
+  #[test]
+  fn test_token_check_is_cached() {
+    let mut app = App::with_config(Config::default());
+    assert_eq!(app.known_login(), None);
+
+    let info = TokenInfo { login: "me".to_string(), scopes: None };
+    app.token_check = TokenCheck::Valid(info);
+    assert_eq!(app.known_login(), Some("me"));
+    // A cached result isn't fetched again unless forced
+    assert!(!app.begin_token_check(false));
+    assert_eq!(app.known_login(), Some("me"));
+  }
+
+  #[test]
+  fn test_configured_username_is_the_fallback_owner() {
+    let mut app = App::with_config(Config::default());
+    app.config.github_username = " Me ".to_string();
+    assert_eq!(app.default_owner(), Some("Me"));
+    assert_eq!(app.username_mismatch(), None);
+
+    let info = TokenInfo { login: "me".to_string(), scopes: None };
+    app.token_check = TokenCheck::Valid(info);
+    assert_eq!(app.default_owner(), Some("me"));
+    assert_eq!(app.username_mismatch(), None);
+
+    app.config.github_username = "someone-else".to_string();
+    assert_eq!(app.username_mismatch(), Some("someone-else"));
+  }
+
+  #[test]
+  fn test_register_checks_only_reservable_registries() {
+    let mut app = App::with_config(Config::default());
+    let registries = [RegistryType::Npm, RegistryType::Brew, RegistryType::Crates];
+    app.set_search_results(registries.into_iter().map(available).collect());
+
+    // Nothing ticked registers the highlighted row
+    app.selected_registry = Some(RegistryType::Crates);
+    let targets: Vec<_> = app.registration_targets().iter().map(|r| r.registry).collect();
+    assert_eq!(targets, [RegistryType::Crates]);
+
+    assert!(app.toggle_register_check());
+    app.selected_registry = Some(RegistryType::Brew);
+    assert!(!app.toggle_register_check());
+    app.selected_registry = Some(RegistryType::Npm);
+    assert!(app.toggle_register_check());
+    let targets: Vec<_> = app.registration_targets().iter().map(|r| r.registry).collect();
+    assert_eq!(targets, [RegistryType::Crates, RegistryType::Npm]);
+
+    assert!(app.toggle_register_check());
+    assert_eq!(app.register_checked, [RegistryType::Crates]);
+  }
+
+  #[test]
+  fn test_select_all_register_checks_toggles() {
+    let mut app = App::with_config(Config::default());
+    let registries = [RegistryType::Npm, RegistryType::Brew, RegistryType::PyPi];
+    app.set_search_results(registries.into_iter().map(available).collect());
+
+    app.toggle_all_register_checks();
+    assert_eq!(app.register_checked, [RegistryType::Npm, RegistryType::PyPi]);
+    app.toggle_all_register_checks();
+    assert!(app.register_checked.is_empty());
+
+    app.toggle_all_register_checks();
+    app.set_search_results(vec![available(RegistryType::Npm)]);
+    assert!(app.register_checked.is_empty());
+  }
+
+  #[test]
+  fn test_register_options_start_from_config() {
+    let mut config = Config::default();
+    config.register.private = true;
+    config.register.org = "acme".to_string();
+    let app = App::with_config(config);
+    assert!(app.register_options.private);
+    assert_eq!(app.register_options.org.as_deref(), Some("acme"));
+  }
+
+  #[test]
+  fn test_cycle_register_owner() {
+    let mut app = App::with_config(Config::default());
+    assert!(!app.cycle_register_owner());
+
+    app.register_orgs = Some(vec!["acme".to_string(), "tools".to_string()]);
+    let mut owners = Vec::new();
+    for _ in 0..3 {
+      assert!(app.cycle_register_owner());
+      owners.push(app.register_options.org.clone());
+    }
+    assert_eq!(owners, [Some("acme".to_string()), Some("tools".to_string()), None]);
+
+    // A remembered org the token no longer sees falls back to personal
+    app.register_options.org = Some("gone".to_string());
+    app.cycle_register_owner();
+    assert_eq!(app.register_options.org, None);
+  }
+
+  #[test]
+  fn test_description_edit() {
+    let mut app = App::with_config(Config::default());
+    app.start_option_edit(RepoTextField::Description);
+    app.option_input.insert_str("My tool ");
+    app.commit_option_edit();
+    assert_eq!(app.register_options.description.as_deref(), Some("My tool"));
+
+    app.start_option_edit(RepoTextField::Description);
+    app.option_input.clear();
+    app.commit_option_edit();
+    assert_eq!(app.register_options.description, None);
+    assert_eq!(app.input_mode, InputMode::Normal);
+
+    app.start_option_edit(RepoTextField::Topics);
+    assert_eq!(app.option_input.as_str(), "reserved-name");
+    app.option_input.insert_str(", cli,,");
+    app.commit_option_edit();
+    assert_eq!(app.register_options.topics, ["reserved-name", "cli"]);
+
+    app.toggle_register_issues();
+    assert_eq!(app.register_options.has_issues, Some(false));
+  }
+
+  #[test]
+  fn test_record_step_updates_running_step() {
+    let mut app = App::with_config(Config::default());
+    let step = |label: &str, status| Step { label: label.to_string(), status };
+
+    app.record_step(step("Creating repository", StepStatus::Running));
+    app.record_step(step("Creating repository", StepStatus::Done));
+    app.record_step(step("Committing package.json", StepStatus::Running));
+    app.record_step(step("Committing package.json", StepStatus::Failed("boom".to_string())));
+
+    let statuses: Vec<_> = app.register_steps.iter().map(|s| s.status.clone()).collect();
+    assert_eq!(statuses, [StepStatus::Done, StepStatus::Failed("boom".to_string())]);
+  }
+
+  #[tokio::test]
+  async fn test_cancel_registration_aborts_task() {
+    let mut app = App::with_config(Config::default());
+    assert!(!app.cancel_registration());
+
+    let task = tokio::spawn(std::future::pending::<()>());
+    app.registration_task = Some(task.abort_handle());
+    app.is_registering = true;
+
+    assert!(app.cancel_registration());
+    assert!(!app.is_registering);
+    assert!(app.registration_task.is_none());
+    assert!(task.await.unwrap_err().is_cancelled());
+  }
+
+  #[tokio::test]
+  async fn test_quit_asks_while_registering() {
+    let mut app = App::with_config(Config::default());
+    let task = tokio::spawn(std::future::pending::<()>());
+    app.registration_task = Some(task.abort_handle());
+    app.is_registering = true;
+
+    app.request_quit();
+    assert!(matches!(app.modal, Some(Modal::Quit)) && !app.should_quit);
+    app.confirm_quit(false);
+    assert!(app.modal.is_none() && app.is_registering && !app.should_quit);
+
+    app.request_quit();
+    app.confirm_quit(true);
+    assert!(app.should_quit && !app.is_registering);
+    assert!(task.await.unwrap_err().is_cancelled());
+
+    let mut idle = App::with_config(Config::default());
+    idle.request_quit();
+    assert!(idle.should_quit && idle.modal.is_none());
+  }
+
+  #[tokio::test]
+  async fn test_quitting_names_the_repo_a_registration_left() {
+    let mut app = App::with_config(Config::default());
+    let task = tokio::spawn(std::future::pending::<()>());
+    app.registration_task = Some(task.abort_handle());
+    app.registration_repo = Some("octocat/foo".to_string());
+    app.is_registering = true;
+    app.record_step(Step { label: "Fetching username".to_string(), status: StepStatus::Done });
+
+    // Nothing exists yet, so there's nothing to mention
+    app.confirm_cancel_registration(true);
+    assert_eq!(app.register_status.as_deref(), Some("Registration cancelled"));
+
+    app.registration_task = Some(tokio::spawn(std::future::pending::<()>()).abort_handle());
+    app.is_registering = true;
+    app.record_step(Step { label: CREATE_REPO_STEP.to_string(), status: StepStatus::Running });
+    app.request_quit();
+    app.confirm_quit(true);
+    let left = "Registration cancelled; repo octocat/foo may be left half set up";
+    assert_eq!(app.quit_notice.as_deref(), Some(left));
+  }
+
+  #[test]
+  fn test_rows_past_the_end_do_nothing() {
+    let mut app = App::with_config(Config::default());
+    assert_eq!(app.toggle_registry_row(app.registry_count()), None);
   }
 }
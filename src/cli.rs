@@ -1,4 +1,32 @@
-use clap::{Parser, Subcommand};
+use crate::output::{ColorChoice, OutputFormat};
+use crate::publish::{NpmAccess, PublishOptions};
+use crate::registry::github::{ManifestType, OnFailure};
+use crate::registry::{RegistryType, ResultFilter};
+use crate::report::ReportFormat;
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{ArgAction, Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use std::ffi::OsString;
+use std::net::IpAddr;
+use std::path::PathBuf;
+
+const EXIT_CODE_HELP: &str = "\
+Exit codes (with --exit-code):
+  0  available on every checked registry
+  1  taken on at least one registry
+  2  at least one check failed, so availability is unknown";
+
+const REGISTER_EXIT_CODE_HELP: &str = "\
+Exit codes:
+  0  repository created
+  1  name is taken on the registry
+  2  availability check failed
+  3  GitHub authentication failed or no token was found
+  4  repository already exists
+  5  invalid repository name
+  6  rate limited by GitHub
+  7  other GitHub API error
+  8  network error";
 
 #[derive(Parser)]
 #[command(name = "nbi")]
@@ -6,6 +34,44 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
   #[command(subcommand)]
   pub command: Option<Commands>,
+
+  /// Increase log verbosity (-v debug, -vv trace); NBI_LOG overrides
+  #[arg(short, long, action = ArgAction::Count, global = true)]
+  pub verbose: u8,
+
+  /// Write logs to this file (TUI default: ~/.local/state/nbi/nbi.log)
+  #[arg(long, global = true, value_name = "PATH")]
+  pub log_file: Option<PathBuf>,
+
+  /// Config file to use instead of `config.toml` in NBI_CONFIG_DIR or the
+  /// platform config directory
+  #[arg(long, global = true, value_name = "PATH")]
+  pub config: Option<PathBuf>,
+
+  /// Config profile overlaid on the base config (`profiles/<NAME>.toml`)
+  #[arg(long, global = true, env = "NBI_PROFILE", value_name = "NAME")]
+  pub profile: Option<String>,
+
+  /// Registry checks in flight at once [default: concurrency from config, else 16]
+  #[arg(long, global = true, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+  pub concurrency: Option<u64>,
+
+  /// Answer checks from canned fixtures instead of the network, for demos and tests
+  #[arg(
+    long,
+    global = true,
+    env = "NBI_OFFLINE",
+    value_parser = clap::builder::BoolishValueParser::new()
+  )]
+  pub offline: bool,
+
+  /// Fixtures JSON for offline mode (implies --offline) [default: bundled table]
+  #[arg(long, global = true, env = "NBI_FIXTURES", value_name = "PATH")]
+  pub fixtures: Option<PathBuf>,
+
+  /// When to color output; NO_COLOR disables `auto`
+  #[arg(long, value_enum, global = true, default_value_t = ColorChoice::Auto, value_name = "WHEN")]
+  pub color: ColorChoice,
 }
 
 #[derive(Subcommand)]
@@ -14,67 +80,660 @@ pub enum Commands {
   Tui,
 
   /// Start web server for GUI
-  Serve {
-    /// Port to listen on
-    #[arg(short, long, default_value = "3000")]
-    port: u16,
-
-    /// Open browser automatically
-    #[arg(short, long)]
-    open: bool,
-  },
+  Serve(ServeArgs),
 
   /// Check name availability (CLI mode)
+  #[command(after_help = EXIT_CODE_HELP)]
   Check {
-    /// Package name to check
-    name: String,
+    /// Package names to check (`-` reads newline-separated names from stdin)
+    #[arg(required_unless_present = "file")]
+    names: Vec<String>,
 
-    /// Output as JSON
-    #[arg(short, long)]
+    /// Also check every name in this file, one per line (blank lines and
+    /// `#` comments are skipped); exits 0 only if some name is available
+    /// everywhere
+    #[arg(long, value_name = "PATH")]
+    file: Option<PathBuf>,
+
+    /// With --file, write the results here and list the best names on stdout
+    #[arg(short, long, value_name = "PATH", requires = "file")]
+    out: Option<PathBuf>,
+
+    /// How many names available everywhere to list with --out
+    #[arg(long, value_name = "N", default_value_t = 10, requires = "out")]
+    top: usize,
+
+    /// Only check these registries (comma-separated, e.g. npm,crates,pypi)
+    #[arg(short, long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    registries: Vec<RegistryType>,
+
+    /// Skip these registries (comma-separated, e.g. flatpak,debian)
+    #[arg(short = 'x', long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    exclude: Vec<RegistryType>,
+
+    /// Also check the name as a GitHub user, npm org, Bluesky and Mastodon handle
+    #[arg(long)]
+    handles: bool,
+
+    /// Also screen the name against live USPTO trademarks (see `trademark_classes`)
+    #[arg(long)]
+    trademark: bool,
+
+    /// Also check the repo name on GitHub, even with `registries.github` off or -r given
+    #[arg(long)]
+    github: bool,
+
+    /// Output format [default: from the --out extension, else plain]
+    #[arg(short, long, value_enum)]
+    format: Option<OutputFormat>,
+
+    /// Output as JSON (alias for --format json)
+    #[arg(short, long, hide = true)]
     json: bool,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// Exit with a status code reflecting availability (see below)
+    #[arg(long)]
+    exit_code: bool,
   },
 
   /// Check domain availability
+  #[command(after_help = EXIT_CODE_HELP)]
   Domain {
     /// Domain name (e.g., example.com)
-    name: String,
+    #[arg(required_unless_present = "list_groups")]
+    name: Option<String>,
 
-    /// TLDs to check (comma-separated, default: com,net,org,io,dev)
-    #[arg(short, long, default_value = "com,net,org,io,dev")]
-    tlds: String,
+    /// TLDs or @groups to check (e.g. @tech,me; defaults to `default_tlds` in config)
+    #[arg(short, long)]
+    tlds: Option<String>,
 
-    /// Output as JSON
+    /// List built-in and configured TLD groups
+    #[arg(long, exclusive = true)]
+    list_groups: bool,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Plain)]
+    format: OutputFormat,
+
+    /// Output as JSON (alias for --format json)
+    #[arg(short, long, hide = true)]
+    json: bool,
+
+    #[command(flatten)]
+    filter: FilterArgs,
+
+    /// Exit with a status code reflecting availability (see below)
+    #[arg(long)]
+    exit_code: bool,
+  },
+
+  /// Rank several candidate names by how many registries are free
+  Compare {
+    /// Candidate names
+    #[arg(required = true, num_args = 2..)]
+    names: Vec<String>,
+
+    /// Only check these registries (comma-separated, e.g. npm,crates,pypi)
+    #[arg(short, long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    registries: Vec<RegistryType>,
+
+    /// Skip these registries (comma-separated, e.g. flatpak,debian)
+    #[arg(short = 'x', long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    exclude: Vec<RegistryType>,
+
+    /// Also check domains, used to break ties
     #[arg(short, long)]
+    domains: bool,
+
+    /// TLDs or @groups to check with --domains (defaults to `default_tlds` in config)
+    #[arg(short, long, requires = "domains")]
+    tlds: Option<String>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Plain)]
+    format: OutputFormat,
+
+    /// Output as JSON (alias for --format json)
+    #[arg(short, long, hide = true)]
     json: bool,
   },
 
+  /// Write a shareable HTML or Markdown report comparing candidate names
+  Report {
+    /// Candidate names
+    #[arg(required = true)]
+    names: Vec<String>,
+
+    /// Only check these registries (comma-separated, e.g. npm,crates,pypi)
+    #[arg(short, long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    registries: Vec<RegistryType>,
+
+    /// Skip these registries (comma-separated, e.g. flatpak,debian)
+    #[arg(short = 'x', long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    exclude: Vec<RegistryType>,
+
+    /// Also check domains
+    #[arg(short, long)]
+    domains: bool,
+
+    /// TLDs or @groups to check with --domains (defaults to `default_tlds` in config)
+    #[arg(short, long, requires = "domains")]
+    tlds: Option<String>,
+
+    /// Report format [default: md for a .md --out, else html]
+    #[arg(short, long, value_enum)]
+    format: Option<ReportFormat>,
+
+    /// File to write the report to, instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    out: Option<PathBuf>,
+  },
+
+  /// Re-check names periodically and notify when one becomes available
+  ///
+  /// Notifications go to `notifications.webhook_url` and/or
+  /// `notifications.notify_command` in config.
+  Watch {
+    /// Package names to watch
+    #[arg(required_unless_present = "test_notification")]
+    names: Vec<String>,
+
+    /// Only check these registries (comma-separated, e.g. npm,crates,pypi)
+    #[arg(short, long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    registries: Vec<RegistryType>,
+
+    /// Skip these registries (comma-separated, e.g. flatpak,debian)
+    #[arg(short = 'x', long, value_delimiter = ',', value_name = "IDS", value_parser = registry_parser())]
+    exclude: Vec<RegistryType>,
+
+    /// Seconds between checks
+    #[arg(short, long, value_name = "SECS", default_value = "300",
+      value_parser = clap::value_parser!(u64).range(1..))]
+    interval: u64,
+
+    /// Send a sample notification to check the setup, then exit
+    #[arg(long)]
+    test_notification: bool,
+  },
+
+  /// Reserve a name by creating a GitHub repo with a package manifest
+  #[command(after_help = REGISTER_EXIT_CODE_HELP)]
+  Register {
+    /// Name to reserve
+    name: String,
+
+    /// Registry to reserve the name for
+    #[arg(short, long, value_enum)]
+    registry: RegisterRegistry,
+
+    /// Create a private repository
+    #[arg(long)]
+    private: bool,
+
+    /// Repository description
+    #[arg(short, long, value_name = "TEXT")]
+    description: Option<String>,
+
+    /// Create the repository under an organization
+    #[arg(long)]
+    org: Option<String>,
+
+    /// Repository homepage URL
+    #[arg(long, value_name = "URL")]
+    homepage: Option<String>,
+
+    /// Create the repository with issues turned off
+    #[arg(long)]
+    no_issues: bool,
+
+    /// GitHub .gitignore template, e.g. Rust or Node [default: default_gitignore from config]
+    #[arg(long, value_name = "TEMPLATE")]
+    gitignore: Option<String>,
+
+    /// Repository topic, repeatable [default: default_topics from config]; the
+    /// registry is always added
+    #[arg(long = "topic", value_name = "TOPIC")]
+    topics: Vec<String>,
+
+    /// What to do with the new repo if committing the manifest fails
+    /// [default: register.on_failure from config, else keep]
+    #[arg(long, value_enum, value_name = "ACTION")]
+    on_failure: Option<OnFailure>,
+
+    /// Skip the confirmation prompt
+    #[arg(short, long)]
+    yes: bool,
+  },
+
   /// Publish package to registry
   Publish {
     #[command(subcommand)]
     registry: PublishRegistry,
   },
+
+  /// View and edit settings
+  Config {
+    #[command(subcommand)]
+    action: ConfigAction,
+  },
+
+  /// List registries and choose which ones checks include
+  Registry {
+    #[command(subcommand)]
+    action: RegistryAction,
+  },
+
+  /// Manage the GitHub token stored in the OS keyring
+  #[cfg(feature = "keyring")]
+  Auth {
+    #[command(subcommand)]
+    action: AuthAction,
+  },
+
+  /// Print a shell completion script
+  Completions {
+    /// Shell to generate completions for
+    #[arg(value_enum)]
+    shell: Shell,
+  },
+
+  /// Print the man page
+  #[command(hide = true)]
+  Manpage,
+}
+
+/// Global options that take a separate value, skipped over by [`expand_shorthand`]
+const GLOBAL_VALUE_FLAGS: [&str; 6] =
+  ["--log-file", "--color", "--concurrency", "--fixtures", "--config", "--profile"];
+
+/// Rewrite `nbi NAME ...` to `nbi check NAME ...`
+///
+/// The first positional argument is treated as a name unless it's a known
+/// subcommand, so plain `nbi` still launches the TUI and a name that collides
+/// with a subcommand needs the explicit `nbi check serve` form.
+pub fn expand_shorthand(mut args: Vec<OsString>) -> Vec<OsString> {
+  let mut index = 1;
+  while let Some(arg) = args.get(index).map(|a| a.to_string_lossy()) {
+    if GLOBAL_VALUE_FLAGS.contains(&arg.as_ref()) {
+      index += 2;
+    } else if arg.starts_with('-') && arg != "-" {
+      index += 1;
+    } else {
+      break;
+    }
+  }
+
+  let Some(first) = args.get(index).map(|a| a.to_string_lossy().into_owned()) else {
+    return args;
+  };
+  if first == "help" || Cli::command().find_subcommand(&first).is_some() {
+    return args;
+  }
+  args.insert(index, "check".into());
+  args
+}
+
+/// Parser for registry identifiers that also exposes them to completions
+fn registry_parser() -> impl TypedValueParser<Value = RegistryType> {
+  PossibleValuesParser::new(RegistryType::ALL.map(|r| r.id()))
+    .map(|id| id.parse::<RegistryType>().expect("possible values are registry ids"))
+}
+
+#[derive(Subcommand)]
+pub enum RegistryAction {
+  /// Show each registry, whether checks include it, and its endpoint override
+  List {
+    /// Output as JSON
+    #[arg(short, long)]
+    json: bool,
+  },
+
+  /// Include these registries in checks (e.g. flatpak debian), saved to config
+  Enable {
+    #[arg(required = true, value_name = "ID", value_parser = registry_parser())]
+    registries: Vec<RegistryType>,
+  },
+
+  /// Leave these registries out of checks, saved to config
+  Disable {
+    #[arg(required = true, value_name = "ID", value_parser = registry_parser())]
+    registries: Vec<RegistryType>,
+  },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+  /// Print the config file path (the profile's with --profile)
+  Path,
+
+  /// List profiles; the active one is marked with *
+  Profiles,
+
+  /// Show the current config
+  Show {
+    /// Output as JSON instead of TOML
+    #[arg(short, long)]
+    json: bool,
+  },
+
+  /// Print a single value (e.g. registries.npm)
+  Get {
+    /// Dotted config key
+    key: String,
+  },
+
+  /// Set a value (e.g. registries.npm false); with --profile, only in
+  /// that profile, creating it if needed
+  Set {
+    /// Dotted config key
+    key: String,
+
+    /// New value, parsed as TOML (bare words are strings)
+    value: String,
+  },
+
+  /// Open the config file in $EDITOR
+  Edit,
+
+  /// Check the config (and profile) for unknown keys and invalid values,
+  /// exiting with 1 if there are any
+  Validate,
+}
+
+#[cfg(feature = "keyring")]
+#[derive(Subcommand)]
+pub enum AuthAction {
+  /// Prompt for a token, validate it and save it to the keyring
+  Login {
+    /// Authorize in the browser instead of pasting a token (needs
+    /// `github_client_id` in config)
+    #[arg(long)]
+    device: bool,
+  },
+
+  /// Show which account the current token authenticates as
+  Status,
+
+  /// Delete the token from the keyring
+  Logout,
+}
+
+/// Options for `nbi serve`
+#[derive(Args)]
+pub struct ServeArgs {
+  /// Address to listen on, e.g. 0.0.0.0 for every interface
+  /// [default: server.host from config, else 127.0.0.1]
+  #[arg(long, value_name = "IP")]
+  pub host: Option<IpAddr>,
+
+  /// Port to listen on; 0 picks a free port
+  #[arg(short, long, default_value = "3000")]
+  pub port: u16,
+
+  /// Requests per minute each client IP may make to the check, domain and
+  /// register APIs; 0 for no limit
+  #[arg(long, value_name = "N", default_value = "30")]
+  pub rate_limit: u32,
+
+  /// Seconds check results are reused across clients; 0 disables the cache
+  #[arg(long, value_name = "SECS", default_value = "300")]
+  pub cache_ttl: u64,
+
+  /// Largest request body accepted, in bytes
+  #[arg(long, value_name = "BYTES", default_value = "65536")]
+  pub max_body: usize,
+
+  /// Requests handled at once, open check streams included; more get 503.
+  /// 0 for no limit
+  #[arg(long, value_name = "N", default_value = "128")]
+  pub max_concurrent: usize,
+
+  /// Seconds a request may take before it's answered with 408; check
+  /// streams and registrations are exempt. 0 for no limit
+  #[arg(long, value_name = "SECS", default_value = "30")]
+  pub request_timeout: u64,
+
+  /// Seconds open requests get to finish on Ctrl+C or SIGTERM
+  #[arg(long, value_name = "SECS", default_value = "10")]
+  pub grace_period: u64,
+
+  /// Require `Authorization: Bearer <TOKEN>` on every /api request
+  #[arg(long, value_name = "TOKEN", env = "NBI_AUTH_TOKEN", hide_env_values = true)]
+  pub auth_token: Option<String>,
+
+  /// Let pages on this site call the API, e.g. https://example.com; may be
+  /// repeated [default: server.cors_origins from config, else none]
+  #[arg(long, value_name = "URL")]
+  pub cors_origin: Vec<String>,
+
+  /// Serve the web UI's files from this directory, falling back to the
+  /// built-in ones, to work on the frontend without rebuilding
+  #[arg(long, value_name = "DIR")]
+  pub static_dir: Option<PathBuf>,
+
+  /// Open browser automatically
+  #[arg(short, long)]
+  pub open: bool,
+}
+
+/// Result filter flags shared by `check` and `domain`
+#[derive(Args)]
+pub struct FilterArgs {
+  /// Only show available results
+  #[arg(long, conflicts_with = "taken")]
+  pub available: bool,
+
+  /// Only show taken results
+  #[arg(long)]
+  pub taken: bool,
+
+  /// Hide results whose check failed
+  #[arg(long)]
+  pub no_errors: bool,
+}
+
+impl FilterArgs {
+  pub fn result_filter(&self) -> ResultFilter {
+    if self.available {
+      ResultFilter::Available
+    } else if self.taken {
+      ResultFilter::Taken
+    } else {
+      ResultFilter::All
+    }
+  }
+}
+
+/// Registries `nbi register` can reserve a name on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum RegisterRegistry {
+  Npm,
+  Crates,
+  Pypi,
+  Github,
+}
+
+impl RegisterRegistry {
+  pub fn registry_type(&self) -> RegistryType {
+    match self {
+      RegisterRegistry::Npm => RegistryType::Npm,
+      RegisterRegistry::Crates => RegistryType::Crates,
+      RegisterRegistry::Pypi => RegistryType::PyPi,
+      RegisterRegistry::Github => RegistryType::GitHub,
+    }
+  }
+
+  /// Manifest committed to the new repo; GitHub needs none
+  pub fn manifest_type(&self) -> Option<ManifestType> {
+    match self {
+      RegisterRegistry::Npm => Some(ManifestType::Npm),
+      RegisterRegistry::Crates => Some(ManifestType::Crates),
+      RegisterRegistry::Pypi => Some(ManifestType::PyPi),
+      RegisterRegistry::Github => None,
+    }
+  }
 }
 
 #[derive(Subcommand)]
 pub enum PublishRegistry {
   /// Publish to npm
   Npm {
-    /// Package directory
-    #[arg(default_value = ".")]
-    path: String,
+    #[command(flatten)]
+    args: PublishArgs,
+
+    /// One-time password for accounts with 2FA
+    #[arg(long, value_name = "CODE")]
+    otp: Option<String>,
+
+    /// Publish under this dist-tag instead of `latest`
+    #[arg(long, value_name = "DIST_TAG")]
+    tag: Option<String>,
+
+    /// Package access level
+    #[arg(long, value_enum)]
+    access: Option<NpmAccess>,
   },
 
   /// Publish to crates.io
   Crates {
-    /// Package directory
-    #[arg(default_value = ".")]
-    path: String,
+    #[command(flatten)]
+    args: PublishArgs,
   },
 
   /// Publish to PyPI
   Pypi {
-    /// Package directory
-    #[arg(default_value = ".")]
-    path: String,
+    #[command(flatten)]
+    args: PublishArgs,
+
+    /// Upload to a repository from ~/.pypirc (e.g. testpypi)
+    #[arg(long, value_name = "NAME")]
+    repository: Option<String>,
   },
 }
+
+impl PublishRegistry {
+  pub fn manifest_type(&self) -> ManifestType {
+    match self {
+      PublishRegistry::Npm { .. } => ManifestType::Npm,
+      PublishRegistry::Crates { .. } => ManifestType::Crates,
+      PublishRegistry::Pypi { .. } => ManifestType::PyPi,
+    }
+  }
+
+  pub fn args(&self) -> &PublishArgs {
+    match self {
+      PublishRegistry::Npm { args, .. }
+      | PublishRegistry::Crates { args }
+      | PublishRegistry::Pypi { args, .. } => args,
+    }
+  }
+
+  /// Flags passed through to the publishing tool
+  pub fn options(&self) -> PublishOptions {
+    let args = self.args();
+    let mut options = PublishOptions {
+      dry_run: args.dry_run,
+      extra: args.extra.clone(),
+      ..Default::default()
+    };
+    match self {
+      PublishRegistry::Npm { otp, tag, access, .. } => {
+        options.otp = otp.clone();
+        options.tag = tag.clone();
+        options.access = *access;
+      }
+      PublishRegistry::Pypi { repository, .. } => options.repository = repository.clone(),
+      PublishRegistry::Crates { .. } => {}
+    }
+    options
+  }
+}
+
+/// Options shared by every publish target
+#[derive(Args)]
+pub struct PublishArgs {
+  /// Package directory
+  #[arg(default_value = ".")]
+  pub path: String,
+
+  /// Fail unless the manifest's package name is exactly this
+  #[arg(long)]
+  pub name: Option<String>,
+
+  /// Run the checks and the tool's dry run without publishing
+  #[arg(long)]
+  pub dry_run: bool,
+
+  /// Extra arguments forwarded to the publish command (after `--`)
+  #[arg(last = true, value_name = "ARGS")]
+  pub extra: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn expand(args: &[&str]) -> Vec<String> {
+    let args = args.iter().map(OsString::from).collect();
+    expand_shorthand(args)
+      .into_iter()
+      .map(|a| a.into_string().unwrap())
+      .collect()
+  }
+
+  #[test]
+  fn test_shorthand_inserts_check() {
+    assert_eq!(expand(&["nbi", "foo", "--json"]), ["nbi", "check", "foo", "--json"]);
+    assert_eq!(expand(&["nbi", "-v", "foo"]), ["nbi", "-v", "check", "foo"]);
+    assert_eq!(
+      expand(&["nbi", "--log-file", "x.log", "foo"]),
+      ["nbi", "--log-file", "x.log", "check", "foo"]
+    );
+    assert_eq!(expand(&["nbi", "-"]), ["nbi", "check", "-"]);
+    assert_eq!(
+      expand(&["nbi", "--concurrency", "4", "foo"]),
+      ["nbi", "--concurrency", "4", "check", "foo"]
+    );
+    assert_eq!(
+      expand(&["nbi", "--profile", "work", "foo"]),
+      ["nbi", "--profile", "work", "check", "foo"]
+    );
+  }
+
+  #[test]
+  fn test_profile_applies_after_subcommands() {
+    let cli =
+      Cli::try_parse_from(["nbi", "config", "set", "--profile", "work", "registries.debian", "false"])
+        .unwrap();
+    assert_eq!(cli.profile.as_deref(), Some("work"));
+    assert!(matches!(cli.command, Some(Commands::Config { action: ConfigAction::Set { .. } })));
+  }
+
+  #[test]
+  fn test_shorthand_keeps_subcommands_and_bare_invocation() {
+    assert_eq!(expand(&["nbi"]), ["nbi"]);
+    assert_eq!(expand(&["nbi", "-v"]), ["nbi", "-v"]);
+    assert_eq!(expand(&["nbi", "serve"]), ["nbi", "serve"]);
+    assert_eq!(expand(&["nbi", "help"]), ["nbi", "help"]);
+    assert_eq!(expand(&["nbi", "check", "serve"]), ["nbi", "check", "serve"]);
+  }
+
+  #[test]
+  fn test_publish_options_from_flags() {
+    let cli = Cli::try_parse_from([
+      "nbi", "publish", "npm", "pkg", "--otp", "123456", "--access", "restricted", "--", "--foo",
+    ])
+    .unwrap();
+    let Some(Commands::Publish { registry }) = cli.command else {
+      panic!("expected publish");
+    };
+    let options = registry.options();
+    assert_eq!(registry.args().path, "pkg");
+    assert_eq!(options.otp.as_deref(), Some("123456"));
+    assert_eq!(options.access, Some(NpmAccess::Restricted));
+    assert_eq!(options.extra, ["--foo"]);
+  }
+}
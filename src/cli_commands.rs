@@ -1,116 +1,1174 @@
-use anyhow::Result;
-use crate::cli::{PublishRegistry};
+use anyhow::{Context, Result};
+use crate::cli::{
+  Cli, ConfigAction, FilterArgs, PublishRegistry, RegisterRegistry, RegistryAction, ServeArgs,
+};
+use crate::compare::{self, Candidate};
+use crate::config::{Config, ConfigSource, RegistrySettings, ServerSettings};
+use crate::notify::{Event, Notifier};
+use crate::publish::{self, Severity};
+use crate::report::{self, ReportFormat};
+use crate::server::{AllowedOrigins, AuthToken, Limits, ServeOptions};
+use clap::{CommandFactory, ValueEnum};
+use clap_complete::Shell;
+use indicatif::{ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use std::net::SocketAddr;
+use std::path::Path;
+use std::time::Duration;
+use crate::output::{self, Colors, OutputFormat, ResultCounts};
+use futures::stream::{FuturesUnordered, StreamExt};
+use crate::registry::github::{self, GitHubError, OnFailure, RepoOptions};
+use crate::registry::{Availability, AvailabilityResult, NameResults, RegistryType, TimedResult};
 
-pub async fn run_check(name: &str, json: bool) -> Result<()> {
-  let config = crate::config::Config::load()?;
-  let results = crate::registry::check_all(name, &config.registries).await;
+/// Overall outcome of a check, mapped to the process exit code
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckSummary {
+  /// Available on every checked registry
+  AllAvailable,
+  /// Taken on at least one registry
+  Taken,
+  /// Nothing taken, but at least one check failed
+  Unknown,
+}
 
-  if json {
-    println!("{}", serde_json::to_string_pretty(&results)?);
-  } else {
-    println!("Checking availability for: {}\n", name);
-    for r in &results {
-      let status = match r.available {
-        Some(true) => "\x1b[32m✓ Available\x1b[0m",
-        Some(false) => "\x1b[31m✗ Taken\x1b[0m",
-        None => "\x1b[33m? Unknown\x1b[0m",
+impl CheckSummary {
+  /// Summarize results; a definite "taken" outranks failed checks
+  pub fn from_results<'a>(results: impl IntoIterator<Item = &'a AvailabilityResult>) -> Self {
+    let mut summary = CheckSummary::AllAvailable;
+    for result in results {
+      match result.available() {
+        Some(false) => return CheckSummary::Taken,
+        None => summary = CheckSummary::Unknown,
+        Some(true) => {}
+      }
+    }
+    summary
+  }
+
+  /// Summarize a batch by its best name: available if any name is free on
+  /// every registry, otherwise unknown if any name only had failed checks
+  pub fn from_batch(checks: &[NameResults]) -> Self {
+    let summaries = checks.iter().map(|c| Self::from_results(c.results.iter().map(|t| &t.result)));
+    summaries.min_by_key(|summary| match summary {
+      CheckSummary::AllAvailable => 0,
+      CheckSummary::Unknown => 1,
+      CheckSummary::Taken => 2,
+    })
+    .unwrap_or(CheckSummary::Unknown)
+  }
+
+  /// Status reported in the JSON Lines summary
+  pub fn label(&self) -> &'static str {
+    match self {
+      CheckSummary::AllAvailable => "available",
+      CheckSummary::Taken => "taken",
+      CheckSummary::Unknown => "unknown",
+    }
+  }
+
+  pub fn exit_code(&self) -> i32 {
+    match self {
+      CheckSummary::AllAvailable => 0,
+      CheckSummary::Taken => 1,
+      CheckSummary::Unknown => 2,
+    }
+  }
+}
+
+/// Exit the process with the summary's code when `--exit-code` is set
+pub fn exit_with_summary(summary: CheckSummary, exit_code: bool) -> Result<()> {
+  if exit_code && summary != CheckSummary::AllAvailable {
+    std::process::exit(summary.exit_code());
+  }
+  Ok(())
+}
+
+/// Registries picked on the command line, applied over the config's toggles
+pub struct Selection<'a> {
+  pub registries: &'a [RegistryType],
+  pub exclude: &'a [RegistryType],
+  /// Also check the handles, even if the config leaves them off
+  pub handles: bool,
+  /// Also screen USPTO trademarks, even if the config leaves it off
+  pub trademark: bool,
+  /// Also check the GitHub repo, even if the config leaves it off
+  pub github: bool,
+}
+
+impl Selection<'_> {
+  fn settings(&self, config: &RegistrySettings) -> RegistrySettings {
+    let (mut config, mut include) = (config.clone(), self.registries.to_vec());
+    if self.handles {
+      config.set_handles(true);
+      if !include.is_empty() {
+        include.extend(RegistryType::HANDLES);
+      }
+    }
+    for (flag, registry) in
+      [(self.trademark, RegistryType::Trademark), (self.github, RegistryType::GitHub)]
+    {
+      if flag {
+        config.set_enabled(registry, true);
+        if !include.is_empty() {
+          include.push(registry);
+        }
+      }
+    }
+    config.filtered(&include, self.exclude)
+  }
+}
+
+pub async fn run_check(
+  source: &ConfigSource,
+  names: Vec<String>,
+  selection: Selection<'_>,
+  format: OutputFormat,
+  filter: &FilterArgs,
+  colors: Colors,
+  batch: Option<Batch<'_>>,
+) -> Result<CheckSummary> {
+  let mut names = expand_stdin_names(names)?;
+  if let Some(batch) = &batch {
+    names.extend(read_names_file(batch.file)?);
+  }
+  if names.is_empty() {
+    anyhow::bail!("No names to check");
+  }
+
+  let config = source.load()?;
+  let settings = selection.settings(&config.registries);
+  let total = names.len() * RegistryType::ALL.iter().filter(|r| settings.is_enabled(**r)).count();
+  let out = batch.as_ref().and_then(|b| b.out);
+  // Results written to a file leave stdout to the progress bar
+  let shown = if out.is_some() { OutputFormat::Plain } else { format };
+  let progress = check_progress(total as u64, shown);
+  let tick = advance_progress(&progress);
+  let result_filter = filter.result_filter();
+  let on_result = |t: &TimedResult| {
+    tick(t);
+    let streamed = format == OutputFormat::Jsonl && out.is_none();
+    if streamed && result_filter.matches(&t.result, !filter.no_errors) {
+      emit_line(output::jsonl_result(&t.result, Some(t.duration_ms)));
+    }
+  };
+  let github = config.github_account(&settings).await;
+  let mut checks = crate::registry::check_many(&names, &settings, &github, &on_result).await;
+  progress.finish_and_clear();
+  let all_results = || checks.iter().flat_map(|c| &c.results).map(|t| &t.result);
+  let summary = match batch {
+    Some(_) => CheckSummary::from_batch(&checks),
+    None => CheckSummary::from_results(all_results()),
+  };
+  // Listed on stdout when the results go to a file
+  let best = out.and(batch).map(|b| output::format_available_everywhere(&checks, b.top, colors));
+
+  if format == OutputFormat::Jsonl {
+    let slowest = crate::registry::slowest(checks.iter().flat_map(|c| &c.results));
+    let summary_line =
+      output::jsonl_summary(&count_results(all_results()), summary.label(), slowest);
+    let (Some(out), Some(best)) = (out, best) else {
+      emit_line(summary_line);
+      return Ok(summary);
+    };
+    let mut lines = String::new();
+    for t in checks.iter().flat_map(|c| &c.results) {
+      if result_filter.matches(&t.result, !filter.no_errors) {
+        lines += &output::jsonl_result(&t.result, Some(t.duration_ms))?;
+      }
+    }
+    write_results(out, &(lines + &summary_line?))?;
+    print!("{}", best);
+    return Ok(summary);
+  }
+
+  for check in &mut checks {
+    check.results.retain(|t| result_filter.matches(&t.result, !filter.no_errors));
+  }
+  if checks.len() > 1 {
+    checks.retain(|c| !c.results.is_empty());
+  }
+
+  match (out, best) {
+    (Some(out), Some(best)) => {
+      // No escape codes in files
+      write_results(out, &output::format_checks(format, &checks, Colors::new(false))?)?;
+      print!("{}", best);
+    }
+    _ => print!("{}", output::format_checks(format, &checks, colors)?),
+  }
+  if format == OutputFormat::GhActions {
+    let results = checks.iter().flat_map(|c| &c.results).map(|t| &t.result);
+    write_step_summary(&output::step_summary(results, summary.label()))?;
+  }
+  Ok(summary)
+}
+
+/// `nbi check --file`: names from a file, and where the results go
+#[derive(Clone, Copy)]
+pub struct Batch<'a> {
+  pub file: &'a Path,
+  /// Write the results here and list the best names on stdout instead
+  pub out: Option<&'a Path>,
+  /// How many names available everywhere to list
+  pub top: usize,
+}
+
+/// Names from a `--file`, one per line, skipping blanks and `#` comments
+fn read_names_file(path: &Path) -> Result<Vec<String>> {
+  let text = std::fs::read_to_string(path)
+    .with_context(|| format!("Couldn't read names from {}", path.display()))?;
+  Ok(parse_names(&text))
+}
+
+fn parse_names(text: &str) -> Vec<String> {
+  text
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty() && !line.starts_with('#'))
+    .map(String::from)
+    .collect()
+}
+
+fn write_results(path: &Path, contents: &str) -> Result<()> {
+  std::fs::write(path, contents).with_context(|| format!("Couldn't write {}", path.display()))?;
+  eprintln!("Wrote {}", path.display());
+  Ok(())
+}
+
+/// Names to rank for `nbi compare` and `nbi report`, and where to check them
+pub struct Candidates<'a> {
+  pub names: &'a [String],
+  pub registries: &'a [RegistryType],
+  pub exclude: &'a [RegistryType],
+  /// Also check each name under `tlds` (or the configured default TLDs)
+  pub domains: bool,
+  pub tlds: Option<&'a str>,
+}
+
+/// Check every candidate and print them ranked best first
+pub async fn run_compare(
+  source: &ConfigSource,
+  candidates: Candidates<'_>,
+  format: OutputFormat,
+  colors: Colors,
+) -> Result<()> {
+  let candidates = check_candidates(source, candidates, format).await?;
+  print!("{}", output::format_comparison(format, &candidates, colors)?);
+  Ok(())
+}
+
+/// Check every name for `nbi compare` and `nbi report`, ranked best first
+async fn check_candidates(
+  source: &ConfigSource,
+  candidates: Candidates<'_>,
+  format: OutputFormat,
+) -> Result<Vec<Candidate>> {
+  let Candidates { names, registries, exclude, domains, tlds } = candidates;
+  let config = source.load()?;
+  let settings = config.registries.filtered(registries, exclude);
+  let tld_list = if domains { Some(resolve_tlds(tlds, &config)?) } else { None };
+
+  let total = names.len() * RegistryType::ALL.iter().filter(|r| settings.is_enabled(**r)).count();
+  let progress = check_progress(total as u64, format);
+  let on_result = advance_progress(&progress);
+  let github = config.github_account(&settings).await;
+  let checks = crate::registry::check_many(names, &settings, &github, &on_result);
+  let domains = futures::future::join_all(names.iter().map(|name| async {
+    match tld_list {
+      Some(ref tlds) => {
+        let tlds: Vec<&str> = tlds.iter().map(String::as_str).collect();
+        Some(crate::registry::domain::check_multiple_tlds(name, &tlds).await)
+      }
+      None => None,
+    }
+  }));
+  let (checks, domains) = tokio::join!(checks, domains);
+  progress.finish_and_clear();
+
+  let mut candidates: Vec<_> = checks
+    .into_iter()
+    .zip(domains)
+    .map(|(check, domains)| Candidate::new(check, domains))
+    .collect();
+  compare::rank(&mut candidates);
+  Ok(candidates)
+}
+
+/// Check `names` and write an HTML or Markdown report to `out`, or stdout
+pub async fn run_report(
+  source: &ConfigSource,
+  candidates: Candidates<'_>,
+  format: Option<ReportFormat>,
+  out: Option<&Path>,
+) -> Result<()> {
+  let format = format.unwrap_or_else(|| out.map_or(ReportFormat::Html, ReportFormat::for_path));
+  let candidates = check_candidates(source, candidates, OutputFormat::Plain).await?;
+  let checked_at = std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs());
+  let report = report::render(format, &candidates, checked_at);
+
+  match out {
+    Some(path) => {
+      std::fs::write(path, report).with_context(|| format!("Couldn't write {}", path.display()))?;
+      eprintln!("Wrote {}", path.display());
+    }
+    None => print!("{}", report),
+  }
+  Ok(())
+}
+
+/// Check `names` every `interval`, printing status changes and notifying
+/// when a taken name becomes available
+pub async fn run_watch(
+  source: &ConfigSource,
+  names: Vec<String>,
+  registries: &[RegistryType],
+  exclude: &[RegistryType],
+  interval: Duration,
+) -> Result<()> {
+  let names = expand_stdin_names(names)?;
+  if names.is_empty() {
+    anyhow::bail!("No names to watch");
+  }
+
+  let config = source.load()?;
+  let notifier = Notifier::new(config.notifications.clone())?;
+  if !notifier.is_configured() {
+    eprintln!(
+      "No notifications configured, so changes are only printed; set \
+       notifications.webhook_url or notifications.notify_command"
+    );
+  }
+  let settings = config.registries.filtered(registries, exclude);
+  let github = config.github_account(&settings).await;
+  let mut last_known = HashMap::new();
+  let mut ticks = tokio::time::interval(interval);
+  ticks.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+  loop {
+    ticks.tick().await;
+    let checks = crate::registry::check_many(&names, &settings, &github, &|_| {}).await;
+    for result in checks.iter().flat_map(|c| &c.results).map(|t| &t.result) {
+      let Some(previous) = record_status(&mut last_known, result) else {
+        continue;
       };
-      print!("  {:<12} {}", r.registry.to_string(), status);
-      if let Some(ref err) = r.error {
-        print!(" ({})", err);
+      let event = Event::new(&result.name, &result.registry.to_string(), result.availability);
+      if previous.is_none() {
+        println!("{} is {} on {}", event.name, event.status, event.registry);
+        continue;
+      }
+      println!("{}", event.message());
+      if result.available() == Some(true) && notifier.is_configured() {
+        if let Err(e) = notifier.send(&event).await {
+          eprintln!("Notification failed: {:#}", e);
+        }
       }
-      println!();
     }
   }
+}
+
+/// Remember `result`'s status, returning the one it replaced when it
+/// changed: `Some(None)` the first time a name and registry are seen
+///
+/// Failed checks aren't recorded, so taken, failed, available still counts
+/// as becoming available.
+fn record_status(
+  last_known: &mut HashMap<(String, RegistryType), bool>,
+  result: &AvailabilityResult,
+) -> Option<Option<bool>> {
+  let available = result.available()?;
+  let previous = last_known.insert((result.name.clone(), result.registry), available);
+  (previous != Some(available)).then_some(previous)
+}
+
+/// Send a sample event through the configured notifications
+pub async fn run_test_notification(source: &ConfigSource, name: Option<&str>) -> Result<()> {
+  let notifier = Notifier::new(source.load()?.notifications)?;
+  if !notifier.is_configured() {
+    anyhow::bail!(
+      "No notifications configured; set notifications.webhook_url or notifications.notify_command"
+    );
+  }
+  let registry = RegistryType::Npm.to_string();
+  let event = Event::new(name.unwrap_or("nbi-test"), &registry, Availability::Available);
+  notifier.send(&event).await?;
+  println!("Sent test notification: {}", event.message());
   Ok(())
 }
 
-pub async fn run_domain_check(name: &str, tlds: &str, json: bool) -> Result<()> {
-  // Check if input is a full domain (contains a dot)
-  let results = if name.contains('.') {
-    // Full domain check - also check additional TLDs if specified
-    let mut domains = vec![name.to_string()];
-    
-    // Parse the base name and add other TLDs
-    if let Some(dot_pos) = name.rfind('.') {
-      let base = &name[..dot_pos];
-      for tld in tlds.split(',').map(|s| s.trim()) {
-        let domain = format!("{}.{}", base, tld);
-        if domain != name {
+/// Write one JSON Lines record to stdout and flush it immediately
+fn emit_line(line: Result<String>) {
+  let mut stdout = io::stdout().lock();
+  let written = line.and_then(|line| Ok(stdout.write_all(line.as_bytes())?));
+  if let Err(e) = written.and_then(|_| Ok(stdout.flush()?)) {
+    tracing::warn!(error = %e, "failed to write result line");
+  }
+}
+
+fn count_results<'a>(results: impl IntoIterator<Item = &'a AvailabilityResult>) -> ResultCounts {
+  let mut counts = ResultCounts::default();
+  for result in results {
+    counts.add(result.availability);
+  }
+  counts
+}
+
+/// Progress bar on stderr, hidden unless stdout is a terminal showing
+/// human-readable output
+fn check_progress(total: u64, format: OutputFormat) -> ProgressBar {
+  if matches!(format, OutputFormat::Json | OutputFormat::Jsonl) || !io::stdout().is_terminal() {
+    return ProgressBar::hidden();
+  }
+  let style = ProgressStyle::with_template("{spinner} [{bar:30}] {pos}/{len} {wide_msg}")
+    .expect("valid progress template")
+    .progress_chars("=> ");
+  let progress = ProgressBar::new(total).with_style(style);
+  progress.enable_steady_tick(Duration::from_millis(100));
+  progress
+}
+
+/// Callback for `check_many` that ticks the bar once per finished registry
+fn advance_progress(progress: &ProgressBar) -> impl Fn(&TimedResult) + Sync + '_ {
+  move |t| {
+    progress.set_message(format!("{} {}", t.result.name, t.result.registry));
+    progress.inc(1);
+  }
+}
+
+/// Replace a `-` argument with newline-separated names read from stdin
+fn expand_stdin_names(names: Vec<String>) -> Result<Vec<String>> {
+  let mut expanded = Vec::new();
+  for name in names {
+    if name == "-" {
+      expanded.extend(parse_names(&std::io::read_to_string(std::io::stdin())?));
+    } else {
+      expanded.push(name);
+    }
+  }
+  Ok(expanded)
+}
+
+/// TLDs from `--tlds`, falling back to `default_tlds` in config, with
+/// `@group`s expanded
+fn resolve_tlds(tlds: Option<&str>, config: &Config) -> Result<Vec<String>> {
+  let entries = match tlds {
+    Some(list) => list.split(',').map(String::from).collect(),
+    None => config.default_tlds.clone(),
+  };
+  config.expand_tlds(&entries)
+}
+
+/// Print built-in TLD groups followed by groups from config
+pub fn run_list_tld_groups(source: &ConfigSource) -> Result<()> {
+  let config = source.load()?;
+  let builtin = crate::registry::domain::BUILTIN_TLD_GROUPS
+    .iter()
+    .filter(|(name, _)| !config.tld_groups.contains_key(*name))
+    .map(|(name, tlds)| (name.to_string(), tlds.join(", "), "built-in"));
+  let configured = config
+    .tld_groups
+    .iter()
+    .map(|(name, tlds)| (name.clone(), tlds.join(", "), "config"));
+
+  for (name, tlds, source) in builtin.chain(configured) {
+    println!("@{:<14} {:<9} {}", name, source, tlds);
+  }
+  Ok(())
+}
+
+/// Domains to check for `name`
+///
+/// A bare name is combined with every TLD. A full domain is checked as given,
+/// followed by its base name under each of the other TLDs.
+fn domain_list(name: &str, tlds: &[String]) -> Vec<String> {
+  let tlds = tlds.iter();
+  match name.rsplit_once('.') {
+    Some((base, _)) => {
+      let mut domains = vec![name.to_string()];
+      for domain in tlds.map(|tld| format!("{}.{}", base, tld)) {
+        if !domains.contains(&domain) {
           domains.push(domain);
         }
       }
+      domains
+    }
+    None => tlds.map(|tld| format!("{}.{}", name, tld)).collect(),
+  }
+}
+
+pub async fn run_domain_check(
+  source: &ConfigSource,
+  name: &str,
+  tlds: Option<&str>,
+  format: OutputFormat,
+  filter: &FilterArgs,
+  colors: Colors,
+) -> Result<CheckSummary> {
+  let result_filter = filter.result_filter();
+  let tlds = resolve_tlds(tlds, &source.load()?)?;
+  let domains = domain_list(name, &tlds);
+
+  // Check concurrently, emitting JSON Lines as each lookup finishes
+  let mut pending: FuturesUnordered<_> = domains
+    .iter()
+    .enumerate()
+    .map(|(index, domain)| async move {
+      (index, crate::registry::domain::check_full_domain(domain).await)
+    })
+    .collect();
+  let mut indexed = Vec::with_capacity(domains.len());
+  while let Some((index, result)) = pending.next().await {
+    if format == OutputFormat::Jsonl && result_filter.matches(&result, !filter.no_errors) {
+      emit_line(output::jsonl_result(&result, None));
+    }
+    indexed.push((index, result));
+  }
+  indexed.sort_by_key(|(index, _)| *index);
+  let results: Vec<_> = indexed.into_iter().map(|(_, result)| result).collect();
+
+  let summary = CheckSummary::from_results(&results);
+  if format == OutputFormat::Jsonl {
+    emit_line(output::jsonl_summary(&count_results(&results), summary.label(), None));
+    return Ok(summary);
+  }
+
+  let results: Vec<_> = results
+    .into_iter()
+    .filter(|r| result_filter.matches(r, !filter.no_errors))
+    .collect();
+
+  print!("{}", output::format_domains(format, name, &results, colors)?);
+  if format == OutputFormat::GhActions {
+    write_step_summary(&output::step_summary(&results, summary.label()))?;
+  }
+  Ok(summary)
+}
+
+/// Append `markdown` to the job summary when running in GitHub Actions
+fn write_step_summary(markdown: &str) -> Result<()> {
+  let Some(path) = std::env::var_os("GITHUB_STEP_SUMMARY") else {
+    return Ok(());
+  };
+  let mut file = std::fs::OpenOptions::new()
+    .create(true)
+    .append(true)
+    .open(&path)
+    .with_context(|| format!("Couldn't open GITHUB_STEP_SUMMARY {}", Path::new(&path).display()))?;
+  file.write_all(markdown.as_bytes())?;
+  Ok(())
+}
+
+/// Exit code for a failed registration, one per [`GitHubError`] variant
+pub fn github_exit_code(error: &GitHubError) -> i32 {
+  match error {
+    GitHubError::AuthRequired => 3,
+    GitHubError::RepoExists => 4,
+    GitHubError::InvalidName => 5,
+    GitHubError::RateLimited { .. } => 6,
+    GitHubError::ApiError(_) | GitHubError::RepoNotReady { .. } => 7,
+    GitHubError::NetworkError(_) => 8,
+    GitHubError::ManifestFailed { source, .. } => github_exit_code(source),
+  }
+}
+
+fn exit_with_github_error(error: GitHubError) -> ! {
+  eprintln!("Error: {}", error);
+  std::process::exit(github_exit_code(&error));
+}
+
+pub async fn run_register(
+  source: &ConfigSource,
+  name: &str,
+  registry: RegisterRegistry,
+  options: &RepoOptions,
+  on_failure: Option<OnFailure>,
+  yes: bool,
+) -> Result<()> {
+  let config = source.load()?;
+  let on_failure = on_failure.unwrap_or(config.register.on_failure);
+  let Some(token) = config.get_github_token() else {
+    exit_with_github_error(GitHubError::AuthRequired);
+  };
+  let options = &config.with_repo_defaults(options.clone());
+
+  let registry_type = registry.registry_type();
+  // The repo will be created under the org when one is given
+  let settings = config.registries.filtered(&[registry_type], &[]);
+  let mut github = config.github_account(&settings).await;
+  github.owner = options.org.clone().or(github.owner);
+  let check = crate::registry::check_one(registry_type, name, &github).await;
+  match check.available() {
+    Some(true) => {}
+    Some(false) => {
+      eprintln!("'{}' is already taken on {}", name, registry_type);
+      std::process::exit(CheckSummary::Taken.exit_code());
+    }
+    None => {
+      eprintln!(
+        "Could not check '{}' on {}: {}",
+        name,
+        registry_type,
+        check.error.as_deref().unwrap_or("unknown error")
+      );
+      std::process::exit(CheckSummary::Unknown.exit_code());
     }
-    
-    let mut results = Vec::new();
-    for domain in &domains {
-      results.push(crate::registry::domain::check_full_domain(domain).await);
+  }
+
+  let owner = options.org.as_deref().unwrap_or("your GitHub account");
+  let visibility = if options.private { "private" } else { "public" };
+  if !yes && !confirm(&format!("Create {} repo '{}' under {}?", visibility, name, owner))? {
+    println!("Aborted");
+    return Ok(());
+  }
+
+  let progress = github::Progress::default();
+  let created = github::reserve(name, registry.manifest_type(), options, &token, &progress).await;
+  let html_url = match created {
+    Ok(html_url) => html_url,
+    Err(GitHubError::ManifestFailed { full_name, repo_url, source }) => {
+      eprintln!("Error: Created {}, but committing the manifest failed: {}", repo_url, source);
+      let code = github_exit_code(&source);
+      match on_failure {
+        OnFailure::Delete => match github::delete_repo(&full_name, &token).await {
+          Ok(()) => eprintln!("Deleted {} again", full_name),
+          Err(e) => {
+            eprintln!("Deleting {} failed too: {}", full_name, e);
+            eprintln!("To retry: {}", recovery_command(&full_name, name, registry));
+          }
+        },
+        OnFailure::Keep => {
+          let retry = recovery_command(&full_name, name, registry);
+          eprintln!("Kept {}. To retry: {}", full_name, retry);
+        }
+      }
+      std::process::exit(code);
     }
-    results
+    Err(e) => exit_with_github_error(e),
+  };
+
+  println!("Created {}", html_url);
+  if let Some(manifest) = registry.manifest_type() {
+    println!("Run '{}' from a clone of the repo to claim the name", manifest.publish_command());
+  }
+  Ok(())
+}
+
+/// Shell command that deletes a half-registered repo and tries again
+fn recovery_command(full_name: &str, name: &str, registry: RegisterRegistry) -> String {
+  let registry = registry.to_possible_value().map(|v| v.get_name().to_string()).unwrap_or_default();
+  format!("gh repo delete {} --yes && nbi register {} --registry {}", full_name, name, registry)
+}
+
+/// Ask a yes/no question on stderr; anything but y/yes declines
+fn confirm(question: &str) -> Result<bool> {
+  if !io::stdin().is_terminal() {
+    anyhow::bail!("Refusing to prompt without a terminal; pass --yes to confirm");
+  }
+  eprint!("{} [y/N] ", question);
+  io::stderr().flush()?;
+  let mut answer = String::new();
+  io::stdin().read_line(&mut answer)?;
+  Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+pub async fn run_serve(source: &ConfigSource, args: ServeArgs) -> Result<()> {
+  // Config is only needed for what the flags leave out
+  let settings = match (args.host, args.cors_origin.is_empty()) {
+    (Some(_), false) => ServerSettings::default(),
+    _ => source.load()?.server,
+  };
+  let host = args.host.unwrap_or(settings.host);
+  let cors_origins = if args.cors_origin.is_empty() {
+    settings.cors_origins
   } else {
-    // Name + TLDs check
-    let tld_list: Vec<&str> = tlds.split(',').map(|s| s.trim()).collect();
-    crate::registry::domain::check_multiple_tlds(name, &tld_list).await
+    args.cors_origin
   };
+  let auth = args.auth_token.as_deref().map(str::trim).filter(|t| !t.is_empty());
+  crate::server::start(ServeOptions {
+    config: source.clone(),
+    addr: SocketAddr::new(host, args.port),
+    open_browser: args.open,
+    auth: auth.map(AuthToken::new),
+    rate_limit: args.rate_limit,
+    grace_period: Duration::from_secs(args.grace_period),
+    cache_ttl: Duration::from_secs(args.cache_ttl),
+    static_dir: args.static_dir,
+    cors_origins: AllowedOrigins::parse(&cors_origins)?,
+    limits: Limits {
+      max_body: args.max_body,
+      max_concurrent: args.max_concurrent,
+      request_timeout: Duration::from_secs(args.request_timeout),
+    },
+  })
+  .await
+}
 
-  if json {
-    println!("{}", serde_json::to_string_pretty(&results)?);
+pub async fn run_publish(registry: PublishRegistry) -> Result<()> {
+  let manifest = registry.manifest_type();
+  let args = registry.args();
+  let dir = Path::new(&args.path);
+
+  println!("Checking {} publish from: {}", manifest.registry(), args.path);
+  let findings = publish::preflight(manifest, dir, args.name.as_deref());
+  for finding in &findings {
+    println!("{}", finding);
+  }
+  if findings.iter().any(|f| f.severity == Severity::Error) {
+    anyhow::bail!("Preflight checks failed");
+  }
+
+  for argv in publish::publish_commands(manifest, &registry.options()) {
+    println!("$ {}", argv.join(" "));
+    let status = std::process::Command::new(&argv[0])
+      .args(&argv[1..])
+      .current_dir(dir)
+      .status()?;
+    if !status.success() {
+      anyhow::bail!("{} failed", argv.join(" "));
+    }
+  }
+
+  if args.dry_run {
+    println!("✓ Dry run complete; nothing was published");
   } else {
-    println!("Checking domain availability for: {}\n", name);
-    for r in &results {
-      let status = match r.available {
-        Some(true) => "\x1b[32m✓ Available\x1b[0m",
-        Some(false) => "\x1b[31m✗ Taken\x1b[0m",
-        None => "\x1b[33m? Unknown\x1b[0m",
+    println!("✓ Published successfully!");
+  }
+  Ok(())
+}
+
+pub fn run_config(source: &ConfigSource, action: ConfigAction) -> Result<()> {
+  match action {
+    ConfigAction::Path => println!("{}", source.active_path()?.display()),
+    ConfigAction::Profiles => {
+      for name in source.profiles()? {
+        let marker = if source.profile() == Some(name.as_str()) { "*" } else { " " };
+        println!("{} {}", marker, name);
+      }
+    }
+    ConfigAction::Show { json } => {
+      let mut config = source.load()?;
+      // What checks actually use, --concurrency included
+      config.concurrency = crate::registry::concurrency();
+      if json {
+        println!("{}", serde_json::to_string_pretty(&config)?);
+      } else {
+        print!("{}", toml::to_string_pretty(&config)?);
+      }
+    }
+    ConfigAction::Get { key } => match source.load()?.get(&key)? {
+      toml::Value::String(s) => println!("{}", s),
+      value => println!("{}", value),
+    },
+    ConfigAction::Set { key, value } => {
+      // Setting a value in a profile is how it gets created
+      let mut config = source.load_for_edit()?;
+      config.set(&key, &value)?;
+      source.save(&config)?;
+      println!("{} = {}", key, config.get(&key)?);
+    }
+    ConfigAction::Edit => edit_config(source)?,
+    ConfigAction::Validate => {
+      let problems = source.validate()?;
+      for problem in &problems {
+        eprintln!("{}", problem);
+      }
+      if !problems.is_empty() {
+        std::process::exit(1);
+      }
+      let keys = crate::tui::keys::KeyMap::from_settings(&source.load()?.keys)?;
+      for conflict in keys.conflicts() {
+        eprintln!("warning: {}", conflict);
+      }
+      println!("{} is valid", source.active_path()?.display());
+    }
+  }
+  Ok(())
+}
+
+/// A row of `nbi registry list`
+#[derive(Debug, serde::Serialize)]
+struct RegistryStatus {
+  id: &'static str,
+  name: String,
+  enabled: bool,
+  /// `endpoints.<id>` when set
+  endpoint: Option<String>,
+}
+
+/// Every registry in display order, with its state in `config`
+fn registry_statuses(config: &Config) -> Vec<RegistryStatus> {
+  RegistryType::ALL
+    .into_iter()
+    .map(|registry| RegistryStatus {
+      id: registry.id(),
+      name: registry.to_string(),
+      enabled: config.registries.is_enabled(registry),
+      endpoint: config.endpoints.get(registry).map(String::from),
+    })
+    .collect()
+}
+
+pub fn run_registry(source: &ConfigSource, action: RegistryAction) -> Result<()> {
+  let (registries, enabled) = match action {
+    RegistryAction::List { json } => {
+      let statuses = registry_statuses(&source.load()?);
+      if json {
+        println!("{}", serde_json::to_string_pretty(&statuses)?);
+        return Ok(());
+      }
+      for status in statuses {
+        let state = if status.enabled { "on" } else { "off" };
+        let endpoint = status.endpoint.unwrap_or_default();
+        let line = format!("{:<12} {:<12} {:<4} {}", status.id, status.name, state, endpoint);
+        println!("{}", line.trim_end());
+      }
+      return Ok(());
+    }
+    RegistryAction::Enable { registries } => (registries, true),
+    RegistryAction::Disable { registries } => (registries, false),
+  };
+  let mut config = source.load_for_edit()?;
+  for registry in &registries {
+    config.registries.set_enabled(*registry, enabled);
+  }
+  source.save(&config)?;
+  let state = if enabled { "enabled" } else { "disabled" };
+  for registry in registries {
+    println!("{} {}", registry.id(), state);
+  }
+  Ok(())
+}
+
+#[cfg(feature = "keyring")]
+pub async fn run_auth(source: &ConfigSource, action: crate::cli::AuthAction) -> Result<()> {
+  use crate::cli::AuthAction;
+  use crate::config::{self, TOKEN_SOURCES};
+
+  match action {
+    AuthAction::Login { device } => {
+      let token = if device { device_login(source).await? } else { read_token()? };
+      let info = github::token_info(&token).await.unwrap_or_else(|e| exit_with_github_error(e));
+      config::store_keyring_token(&token)?;
+      println!("Logged in as {} ({})", info.login, info.scopes_label());
+      if let Some(warning) = info.scope_warning(false) {
+        eprintln!("Warning: {}; registering will fail", warning);
+      }
+    }
+    AuthAction::Status => {
+      let config = source.load()?;
+      let (Some(token), Some(source)) = (config.get_github_token(), config.github_token_source())
+      else {
+        eprintln!("Not logged in (tried {})", TOKEN_SOURCES);
+        std::process::exit(github_exit_code(&GitHubError::AuthRequired));
       };
-      println!("  {:<25} {}", r.name, status);
+      let info = github::token_info(&token).await.unwrap_or_else(|e| exit_with_github_error(e));
+      println!("Logged in as {} via {} ({})", info.login, source.label(), info.scopes_label());
+    }
+    AuthAction::Logout => {
+      if config::delete_keyring_token()? {
+        println!("Removed the GitHub token from the keyring");
+      } else {
+        println!("No GitHub token was stored in the keyring");
+      }
+      // Env vars and the token command are resolved independently
+      if let Some(token_source) = source.load()?.github_token_source() {
+        eprintln!("Note: a token is still provided by {}", token_source.label());
+      }
     }
   }
   Ok(())
 }
 
-pub async fn run_publish(registry: PublishRegistry) -> Result<()> {
-  match registry {
-    PublishRegistry::Npm { path } => {
-      println!("Publishing to npm from: {}", path);
-      let status = std::process::Command::new("npm")
-        .args(["publish"])
-        .current_dir(&path)
-        .status()?;
-      if !status.success() {
-        anyhow::bail!("npm publish failed");
-      }
-    }
-    PublishRegistry::Crates { path } => {
-      println!("Publishing to crates.io from: {}", path);
-      let status = std::process::Command::new("cargo")
-        .args(["publish"])
-        .current_dir(&path)
-        .status()?;
-      if !status.success() {
-        anyhow::bail!("cargo publish failed");
-      }
-    }
-    PublishRegistry::Pypi { path } => {
-      println!("Publishing to PyPI from: {}", path);
-      // Build
-      let build = std::process::Command::new("python")
-        .args(["-m", "build"])
-        .current_dir(&path)
-        .status()?;
-      if !build.success() {
-        anyhow::bail!("python build failed");
-      }
-      // Upload
-      let upload = std::process::Command::new("python")
-        .args(["-m", "twine", "upload", "dist/*"])
-        .current_dir(&path)
-        .status()?;
-      if !upload.success() {
-        anyhow::bail!("twine upload failed");
-      }
-    }
-  }
-  println!("✓ Published successfully!");
+/// Get a token through GitHub's device authorization flow
+#[cfg(feature = "keyring")]
+async fn device_login(source: &ConfigSource) -> Result<String> {
+  let client_id = source.load()?.github_client_id;
+  if client_id.trim().is_empty() {
+    anyhow::bail!(
+      "Set github_client_id to an OAuth app's client id (with device flow enabled) first:\n  \
+       nbi config set github_client_id <id>"
+    );
+  }
+
+  let device = github::device::request_device_code(&client_id)
+    .await
+    .unwrap_or_else(|e| exit_with_github_error(e));
+  eprintln!("Open {} and enter the code: {}", device.verification_uri, device.user_code);
+  if let Err(e) = open::that(&device.verification_uri) {
+    tracing::debug!(error = %e, "failed to open browser");
+  }
+
+  let spinner = ProgressBar::new_spinner().with_message("Waiting for authorization...");
+  spinner.enable_steady_tick(Duration::from_millis(100));
+  let token = github::device::poll_device_token(&client_id, &device).await;
+  spinner.finish_and_clear();
+  Ok(token.unwrap_or_else(|e| exit_with_github_error(e)))
+}
+
+/// Read a token without echoing it, or from piped stdin
+#[cfg(feature = "keyring")]
+fn read_token() -> Result<String> {
+  let token = if io::stdin().is_terminal() {
+    eprintln!("Create a token at https://github.com/settings/tokens (scope: public_repo or repo)");
+    rpassword::prompt_password("GitHub token: ")?
+  } else {
+    let mut line = String::new();
+    io::stdin().read_line(&mut line)?;
+    line
+  };
+  let token = token.trim().to_string();
+  if token.is_empty() {
+    anyhow::bail!("No token entered");
+  }
+  Ok(token)
+}
+
+/// Open the config file (or the active profile) in the user's editor,
+/// then validate it
+fn edit_config(source: &ConfigSource) -> Result<()> {
+  let path = source.active_path()?;
+  if !path.exists() {
+    source.save(&source.load_for_edit()?)?;
+  }
+
+  let editor = std::env::var("VISUAL")
+    .or_else(|_| std::env::var("EDITOR"))
+    .unwrap_or_else(|_| if cfg!(windows) { "notepad" } else { "vi" }.to_string());
+
+  // Editors like "code --wait" carry their own arguments
+  let mut parts = editor.split_whitespace();
+  let program = parts.next().unwrap_or("vi");
+  let status = std::process::Command::new(program)
+    .args(parts)
+    .arg(&path)
+    .status()?;
+  if !status.success() {
+    anyhow::bail!("{} exited with {}", editor, status);
+  }
+
+  if let Err(e) = source.load() {
+    anyhow::bail!("Config saved but is invalid: {}", e);
+  }
+  // Typos still load, just to no effect
+  for problem in source.validate()? {
+    eprintln!("Warning: {}", problem);
+  }
+  Ok(())
+}
+
+pub fn run_completions(shell: Shell) -> Result<()> {
+  write_completions(shell, &mut io::stdout());
   Ok(())
 }
+
+pub fn run_manpage() -> Result<()> {
+  write_manpage(&mut io::stdout())
+}
+
+fn write_completions(shell: Shell, out: &mut impl Write) {
+  clap_complete::generate(shell, &mut Cli::command(), "nbi", out);
+}
+
+fn write_manpage(out: &mut impl Write) -> Result<()> {
+  clap_mangen::Man::new(Cli::command()).render(out)?;
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn result(available: Option<bool>) -> AvailabilityResult {
+    AvailabilityResult {
+      registry: RegistryType::Npm,
+      name: "foo".to_string(),
+      availability: available.into(),
+      error: None,
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  #[test]
+  fn test_summary_all_available() {
+    let results = [result(Some(true)), result(Some(true))];
+    assert_eq!(CheckSummary::from_results(&results), CheckSummary::AllAvailable);
+    assert_eq!(CheckSummary::AllAvailable.exit_code(), 0);
+  }
+
+  #[test]
+  fn test_summary_taken_outranks_unknown() {
+    let results = [result(None), result(Some(false)), result(Some(true))];
+    assert_eq!(CheckSummary::from_results(&results), CheckSummary::Taken);
+    assert_eq!(CheckSummary::Taken.exit_code(), 1);
+  }
+
+  #[test]
+  fn test_batch_summary_goes_by_the_best_name() {
+    let check = |results: Vec<AvailabilityResult>| NameResults {
+      name: "foo".to_string(),
+      results: results.into_iter().map(|result| TimedResult { result, duration_ms: 1 }).collect(),
+    };
+    let taken = check(vec![result(Some(true)), result(Some(false))]);
+    let unknown = check(vec![result(Some(true)), result(None)]);
+    let free = check(vec![result(Some(true))]);
+    let summary = |checks: &[NameResults]| CheckSummary::from_batch(checks);
+    assert_eq!(summary(&[taken.clone(), unknown.clone()]), CheckSummary::Unknown);
+    assert_eq!(summary(&[taken.clone(), unknown, free]), CheckSummary::AllAvailable);
+    assert_eq!(summary(&[taken]), CheckSummary::Taken);
+  }
+
+  #[test]
+  fn test_names_file_skips_blanks_and_comments() {
+    let text = "# candidates\nfoo\n\n  bar  \n  # maybe later\nbaz\n";
+    assert_eq!(parse_names(text), ["foo", "bar", "baz"]);
+  }
+
+  #[test]
+  fn test_registry_statuses_show_state_and_endpoint() {
+    let mut config = Config::default();
+    config.registries.brew = false;
+    config.endpoints.crates = "https://crates.internal/api/v1/crates".to_string();
+    let statuses = registry_statuses(&config);
+    assert_eq!(statuses.len(), RegistryType::ALL.len());
+    let brew = statuses.iter().find(|s| s.id == "brew").unwrap();
+    assert!(!brew.enabled && brew.endpoint.is_none());
+    let crates = statuses.iter().find(|s| s.id == "crates").unwrap();
+    assert!(crates.enabled);
+    assert_eq!(crates.endpoint.as_deref(), Some("https://crates.internal/api/v1/crates"));
+  }
+
+  #[test]
+  fn test_registry_typos_get_a_suggestion() {
+    use clap::Parser;
+    let err = Cli::try_parse_from(["nbi", "registry", "enable", "flatpack"]).err().unwrap();
+    let message = err.to_string();
+    assert!(message.contains("invalid value 'flatpack'"), "{}", message);
+    assert!(message.contains("'flatpak'"), "{}", message);
+  }
+
+  #[test]
+  fn test_handles_flag_adds_handles_to_any_selection() {
+    let enabled = |registries, exclude, handles| {
+      let selection = Selection { registries, exclude, handles, trademark: false, github: false };
+      let settings = selection.settings(&RegistrySettings::default());
+      RegistryType::ALL.into_iter().filter(|r| settings.is_enabled(*r)).collect::<Vec<_>>()
+    };
+    let none: &[RegistryType] = &[];
+
+    let config = enabled(none, none, false);
+    let handles: Vec<_> = config.into_iter().filter(|r| r.is_handle()).collect();
+    assert_eq!(handles, [RegistryType::GitHubUser]);
+    let all = enabled(none, none, true);
+    assert_eq!(all, RegistryType::ALL[..RegistryType::ALL.len() - 1]);
+    let npm = enabled(&[RegistryType::Npm], &[RegistryType::Mastodon], true);
+    let handles = [RegistryType::GitHubUser, RegistryType::NpmOrg, RegistryType::Bluesky];
+    assert_eq!(npm[0], RegistryType::Npm);
+    assert_eq!(npm[1..], handles);
+  }
+
+  #[test]
+  fn test_trademark_flag_adds_the_screening() {
+    let only = [RegistryType::Npm];
+    let selection =
+      Selection { registries: &only, exclude: &[], handles: false, trademark: true, github: false };
+    let settings = selection.settings(&RegistrySettings::default());
+    assert!(settings.is_enabled(RegistryType::Trademark) && settings.is_enabled(RegistryType::Npm));
+    assert!(!RegistrySettings::default().is_enabled(RegistryType::Trademark));
+  }
+
+  #[test]
+  fn test_github_flag_overrides_the_config() {
+    let only = [RegistryType::Npm];
+    let selection =
+      Selection { registries: &only, exclude: &[], handles: false, trademark: false, github: true };
+    let config = RegistrySettings { github: false, ..RegistrySettings::default() };
+    let settings = selection.settings(&config);
+    assert!(settings.is_enabled(RegistryType::GitHub) && settings.is_enabled(RegistryType::Npm));
+    assert!(!settings.is_enabled(RegistryType::Crates));
+  }
+
+  #[test]
+  fn test_record_status_reports_changes_across_failures() {
+    let mut last_known = HashMap::new();
+    assert_eq!(record_status(&mut last_known, &result(Some(false))), Some(None));
+    assert_eq!(record_status(&mut last_known, &result(Some(false))), None);
+    assert_eq!(record_status(&mut last_known, &result(None)), None);
+    assert_eq!(record_status(&mut last_known, &result(Some(true))), Some(Some(false)));
+  }
+
+  #[test]
+  fn test_recovery_command() {
+    assert_eq!(
+      recovery_command("acme/foo", "foo", RegisterRegistry::Crates),
+      "gh repo delete acme/foo --yes && nbi register foo --registry crates"
+    );
+  }
+
+  #[test]
+  fn test_domain_list() {
+    let tlds = ["com".to_string(), "dev".to_string()];
+    assert_eq!(domain_list("foo", &tlds), ["foo.com", "foo.dev"]);
+    assert_eq!(domain_list("foo.dev", &tlds), ["foo.dev", "foo.com"]);
+  }
+
+  #[test]
+  fn test_github_errors_have_distinct_exit_codes() {
+    let codes = [
+      github_exit_code(&GitHubError::AuthRequired),
+      github_exit_code(&GitHubError::RepoExists),
+      github_exit_code(&GitHubError::InvalidName),
+      github_exit_code(&GitHubError::RateLimited { reset_at: None }),
+      github_exit_code(&GitHubError::ApiError(String::new())),
+    ];
+    let unique: std::collections::HashSet<_> = codes.iter().collect();
+    assert_eq!(unique.len(), codes.len());
+    // 0-2 are reserved for availability outcomes
+    assert!(codes.iter().all(|code| *code > 2));
+  }
+
+  fn completions(shell: Shell) -> String {
+    let mut out = Vec::new();
+    write_completions(shell, &mut out);
+    String::from_utf8(out).unwrap()
+  }
+
+  #[test]
+  fn test_completions_for_every_shell() {
+    for shell in [Shell::Bash, Shell::Zsh, Shell::Fish, Shell::PowerShell] {
+      let script = completions(shell);
+      assert!(script.contains("completions"), "{shell} script lists subcommands");
+    }
+  }
+
+  #[test]
+  fn test_fish_completions_for_check() {
+    // Value lists span several lines, so keep the whole block for `check`
+    let script = completions(Shell::Fish);
+    let lines: Vec<&str> = script.lines().collect();
+    let is_check = |line: &&str| line.contains("__fish_nbi_using_subcommand check");
+    let first = lines.iter().position(is_check).unwrap();
+    let last = lines.iter().rposition(is_check).unwrap();
+    insta::assert_snapshot!(lines[first..=last].join("\n"));
+  }
+
+  #[test]
+  fn test_manpage() {
+    let mut out = Vec::new();
+    write_manpage(&mut out).unwrap();
+    // `nbi auth` only exists with the keyring feature
+    let suffix = if cfg!(feature = "keyring") { "keyring" } else { "" };
+    insta::with_settings!({ snapshot_suffix => suffix }, {
+      insta::assert_snapshot!(String::from_utf8(out).unwrap());
+    });
+  }
+
+  #[test]
+  fn test_summary_unknown() {
+    let results = [result(Some(true)), result(None)];
+    assert_eq!(CheckSummary::from_results(&results), CheckSummary::Unknown);
+    assert_eq!(CheckSummary::Unknown.exit_code(), 2);
+  }
+}
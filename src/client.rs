@@ -0,0 +1,114 @@
+//! Typed client for the HTTP API of a running `nbi serve`
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), nbi::client::ClientError> {
+//! use nbi::client::NbiClient;
+//! use nbi::registry::{Availability, RegistryType};
+//!
+//! let client = NbiClient::new("http://127.0.0.1:3000", None);
+//! let checked = client.check("ferrite", &[RegistryType::Crates]).await?;
+//! let free = checked.results.iter().all(|r| r.result.availability == Availability::Available);
+//! # Ok(()) }
+//! ```
+
+use crate::api_types::{
+  CheckRequest, CheckResponse, DomainRequest, DomainResponse, ErrorBody, RegistrySelection,
+};
+use crate::config::Config;
+use crate::registry::RegistryType;
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
+
+/// A failed API call
+#[derive(Debug, Error)]
+pub enum ClientError {
+  /// The server couldn't be reached, or its answer wasn't the expected JSON
+  #[error("Request failed: {0}")]
+  Http(#[from] reqwest::Error),
+  /// The server refused the request
+  #[error("Server answered {status}: {}", body.error)]
+  Api { status: u16, body: ErrorBody },
+}
+
+/// Talks to one `nbi serve`
+#[derive(Debug, Clone)]
+pub struct NbiClient {
+  base_url: String,
+  token: Option<String>,
+  http: reqwest::Client,
+}
+
+impl NbiClient {
+  /// `base_url` is where the server listens, e.g. `http://127.0.0.1:3000`;
+  /// `token` is its `--auth-token`, if it has one
+  pub fn new(base_url: impl Into<String>, token: Option<String>) -> Self {
+    let base_url = base_url.into().trim_end_matches('/').to_string();
+    Self { base_url, token, http: reqwest::Client::new() }
+  }
+
+  /// Check `name` on `registries`, or on the server's defaults when empty
+  ///
+  /// API: POST /api/check
+  pub async fn check(
+    &self,
+    name: &str,
+    registries: &[RegistryType],
+  ) -> Result<CheckResponse, ClientError> {
+    let ids: Vec<_> = registries.iter().map(|r| r.id().to_string()).collect();
+    let request = CheckRequest {
+      name: name.to_string(),
+      registries: (!ids.is_empty()).then_some(RegistrySelection::Ids(ids)),
+      exclude: Vec::new(),
+    };
+    self.send(self.request(Method::POST, "/api/check").json(&request)).await
+  }
+
+  /// Check `name` under `tlds` (TLDs or `@group`s), or the server's
+  /// `default_tlds` when empty
+  ///
+  /// API: POST /api/domain
+  pub async fn check_domains(
+    &self,
+    name: &str,
+    tlds: &[&str],
+  ) -> Result<DomainResponse, ClientError> {
+    let request = DomainRequest {
+      name: name.to_string(),
+      tlds: (!tlds.is_empty()).then(|| tlds.iter().map(|t| t.to_string()).collect()),
+    };
+    self.send(self.request(Method::POST, "/api/domain").json(&request)).await
+  }
+
+  /// The server's config
+  ///
+  /// API: GET /api/config
+  pub async fn get_config(&self) -> Result<Config, ClientError> {
+    self.send(self.request(Method::GET, "/api/config")).await
+  }
+
+  fn request(&self, method: Method, path: &str) -> RequestBuilder {
+    let request = self.http.request(method, format!("{}{}", self.base_url, path));
+    match &self.token {
+      Some(token) => request.bearer_auth(token),
+      None => request,
+    }
+  }
+
+  /// Send `request`, parsing an error status into [`ClientError::Api`]
+  async fn send<T: DeserializeOwned>(&self, request: RequestBuilder) -> Result<T, ClientError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+      return Ok(response.json().await?);
+    }
+    // Proxies and body limits may answer in plain text
+    let text = response.text().await?;
+    let body = serde_json::from_str(&text).unwrap_or(ErrorBody {
+      error: text,
+      kind: "http_error".to_string(),
+      field: None,
+    });
+    Err(ClientError::Api { status: status.as_u16(), body })
+  }
+}
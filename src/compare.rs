@@ -0,0 +1,142 @@
+//! Ranking several candidate names against each other
+
+use crate::registry::{AvailabilityResult, NameResults, TimedResult};
+use serde::Serialize;
+
+/// Per-name counts used to rank candidates
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct CandidateSummary {
+  pub available: usize,
+  pub taken: usize,
+  pub unknown: usize,
+  /// Free domains, only present when domains were checked
+  #[serde(skip_serializing_if = "Option::is_none")]
+  pub domains_available: Option<usize>,
+}
+
+/// One name's results and summary
+#[derive(Debug, Clone, Serialize)]
+pub struct Candidate {
+  pub name: String,
+  pub summary: CandidateSummary,
+  pub results: Vec<TimedResult>,
+  #[serde(skip_serializing_if = "Vec::is_empty")]
+  pub domains: Vec<AvailabilityResult>,
+}
+
+impl Candidate {
+  /// Build a candidate; `domains` is `None` when domains weren't checked
+  pub fn new(check: NameResults, domains: Option<Vec<AvailabilityResult>>) -> Self {
+    let mut summary = CandidateSummary::default();
+    for t in &check.results {
+      match t.result.available() {
+        Some(true) => summary.available += 1,
+        Some(false) => summary.taken += 1,
+        None => summary.unknown += 1,
+      }
+    }
+    summary.domains_available = domains
+      .as_ref()
+      .map(|d| d.iter().filter(|r| r.available() == Some(true)).count());
+
+    Self {
+      name: check.name,
+      summary,
+      results: check.results,
+      domains: domains.unwrap_or_default(),
+    }
+  }
+}
+
+/// Sort candidates best first: most free registries, then most free domains
+///
+/// The sort is stable, so fully tied candidates keep their input order.
+pub fn rank(candidates: &mut [Candidate]) {
+  candidates.sort_by(|a, b| {
+    b.summary
+      .available
+      .cmp(&a.summary.available)
+      .then(b.summary.domains_available.cmp(&a.summary.domains_available))
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::RegistryType;
+
+  fn result(registry: RegistryType, name: &str, available: Option<bool>) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: name.to_string(),
+      availability: available.into(),
+      error: None,
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  fn candidate(name: &str, registries: &[Option<bool>], domains: Option<&[bool]>) -> Candidate {
+    let results = registries
+      .iter()
+      .zip(RegistryType::ALL)
+      .map(|(available, registry)| TimedResult {
+        result: result(registry, name, *available),
+        duration_ms: 0,
+      })
+      .collect();
+    let domains = domains.map(|d| {
+      d.iter()
+        .map(|available| result(RegistryType::DevDomain, name, Some(*available)))
+        .collect()
+    });
+    Candidate::new(NameResults { name: name.to_string(), results }, domains)
+  }
+
+  fn names(candidates: &[Candidate]) -> Vec<&str> {
+    candidates.iter().map(|c| c.name.as_str()).collect()
+  }
+
+  #[test]
+  fn test_summary_counts() {
+    let c = candidate("foo", &[Some(true), Some(false), None, Some(true)], Some(&[true, false]));
+    assert_eq!(
+      c.summary,
+      CandidateSummary {
+        available: 2,
+        taken: 1,
+        unknown: 1,
+        domains_available: Some(1),
+      }
+    );
+  }
+
+  #[test]
+  fn test_rank_by_free_registries_then_domains() {
+    let mut candidates = vec![
+      candidate("ferrite", &[Some(true), Some(false)], Some(&[true, false])),
+      candidate("oxidize", &[Some(true), Some(true)], Some(&[false, false])),
+      candidate("rustle", &[Some(true), Some(false)], Some(&[true, true])),
+    ];
+    rank(&mut candidates);
+    assert_eq!(names(&candidates), ["oxidize", "rustle", "ferrite"]);
+  }
+
+  #[test]
+  fn test_rank_keeps_input_order_for_ties() {
+    let mut candidates = vec![
+      candidate("b", &[Some(true)], None),
+      candidate("a", &[Some(true)], None),
+    ];
+    rank(&mut candidates);
+    assert_eq!(names(&candidates), ["b", "a"]);
+  }
+
+  #[test]
+  fn test_summary_omits_domains_when_unchecked() {
+    let c = candidate("foo", &[Some(true)], None);
+    let value = serde_json::to_value(&c).unwrap();
+    assert!(value["summary"].get("domains_available").is_none());
+    assert!(value.get("domains").is_none());
+  }
+}
@@ -1,11 +1,215 @@
-use anyhow::Result;
+use crate::registry::github::{self, GitHubAccount, ManifestFields, OnFailure, RepoOptions};
+use crate::registry::RegistryType;
+use crate::tui::keys::{Action, KeyMap};
+use anyhow::{Context, Result};
 use directories::ProjectDirs;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::PathBuf;
+use std::net::{IpAddr, Ipv4Addr};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
 
 const APP_NAME: &str = "nbi";
 
+/// Format of the config file, stored as `version`; files written before it
+/// existed have none and count as version 0
+pub const CONFIG_VERSION: u32 = MIGRATIONS.len() as u32;
+
+/// Upgrades of a config table, `MIGRATIONS[n]` taking version `n` to `n + 1`
+///
+/// Profiles hold only differences and carry no version, so every step runs
+/// on them; steps must leave an already upgraded table alone.
+const MIGRATIONS: [fn(&mut toml::Table); 1] = [migrate_v0];
+
+/// Version 0 could hold a plaintext `github_token`, which is never read;
+/// drop it rather than keep a secret in the file
+fn migrate_v0(table: &mut toml::Table) {
+  if table.remove("github_token").is_some() {
+    tracing::warn!("dropped github_token from the config; use GITHUB_TOKEN or `nbi auth login`");
+  }
+}
+
+/// The profile after `current` in `profiles`, cycling back to the base
+/// config (`None`) after the last one
+pub fn next_profile(profiles: &[String], current: Option<&str>) -> Option<String> {
+  let next = match current {
+    None => 0,
+    Some(current) => profiles.iter().position(|p| p == current)? + 1,
+  };
+  profiles.get(next).cloned()
+}
+
+/// Where config is loaded from and saved to
+///
+/// `file` is the base config; its directory also holds `profiles/` and
+/// `templates/`. `profile` names a `profiles/<name>.toml` overlaid on it.
+/// Resolved once from the command line and handed to whatever loads or
+/// saves config, so tests and containers can point it anywhere.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigSource {
+  file: PathBuf,
+  profile: Option<String>,
+}
+
+impl Default for ConfigSource {
+  fn default() -> Self {
+    Self::resolve(None, None)
+  }
+}
+
+impl ConfigSource {
+  /// `--config`, else `config.toml` in `NBI_CONFIG_DIR`, else in the
+  /// platform config directory
+  pub fn resolve(config: Option<PathBuf>, profile: Option<String>) -> Self {
+    let dir = std::env::var_os("NBI_CONFIG_DIR").map(PathBuf::from);
+    Self { file: locate(config, dir), profile }
+  }
+
+  /// The base config file
+  pub fn file(&self) -> &Path {
+    &self.file
+  }
+
+  fn dir(&self) -> &Path {
+    self.file.parent().unwrap_or(Path::new(""))
+  }
+
+  /// The overlaid profile's name, if any
+  pub fn profile(&self) -> Option<&str> {
+    self.profile.as_deref()
+  }
+
+  /// The same base config with `profile` overlaid instead
+  pub fn with_profile(&self, profile: Option<String>) -> Self {
+    Self { file: self.file.clone(), profile }
+  }
+
+  /// File of the profile `name`
+  ///
+  /// Names are limited to letters, digits, `-` and `_` so they can't
+  /// point outside the profiles directory.
+  pub fn profile_path(&self, name: &str) -> Result<PathBuf> {
+    let valid = |c: char| c.is_ascii_alphanumeric() || c == '-' || c == '_';
+    if name.is_empty() || !name.chars().all(valid) {
+      anyhow::bail!("Invalid profile name '{}': use letters, digits, '-' and '_'", name);
+    }
+    Ok(self.dir().join("profiles").join(format!("{}.toml", name)))
+  }
+
+  /// Names of the profiles in the profiles directory, sorted
+  pub fn profiles(&self) -> Result<Vec<String>> {
+    let dir = self.dir().join("profiles");
+    if !dir.exists() {
+      return Ok(Vec::new());
+    }
+    let mut names: Vec<String> = fs::read_dir(&dir)
+      .with_context(|| format!("Failed to read {}", dir.display()))?
+      .filter_map(|entry| {
+        let path = entry.ok()?.path();
+        if path.extension()? != "toml" {
+          return None;
+        }
+        Some(path.file_stem()?.to_str()?.to_string())
+      })
+      .collect();
+    names.sort();
+    Ok(names)
+  }
+
+  /// The file edits go to: the profile's, or the base config
+  pub fn active_path(&self) -> Result<PathBuf> {
+    match &self.profile {
+      Some(profile) => self.profile_path(profile),
+      None => Ok(self.file.clone()),
+    }
+  }
+
+  /// Directory of user manifest templates, e.g. `templates/Cargo.toml`
+  pub fn templates_dir(&self) -> PathBuf {
+    self.dir().join("templates")
+  }
+
+  /// Load the base config with the profile overlaid
+  ///
+  /// Fails when the profile doesn't exist, so a mistyped `--profile` isn't
+  /// silently ignored.
+  pub fn load(&self) -> Result<Config> {
+    if let Some(name) = &self.profile {
+      let path = self.profile_path(name)?;
+      if !path.exists() {
+        anyhow::bail!(
+          "Profile '{}' not found at {}; create it with `nbi config set --profile {} <key> <value>`",
+          name,
+          path.display(),
+          name
+        );
+      }
+    }
+    self.load_for_edit()
+  }
+
+  /// Like [`ConfigSource::load`], but a missing profile counts as empty so
+  /// that saving creates it
+  pub fn load_for_edit(&self) -> Result<Config> {
+    let overlay = self.profile.as_deref().map(|p| self.profile_path(p)).transpose()?;
+    let mut config = Config::load_layered(&self.file, overlay.as_deref())?;
+    config.templates_dir = Some(self.templates_dir());
+    Ok(config)
+  }
+
+  /// Save to the base config, or only what differs from it to the profile
+  pub fn save(&self, config: &Config) -> Result<()> {
+    let overlay = self.profile.as_deref().map(|p| self.profile_path(p)).transpose()?;
+    config.save_layered(&self.file, overlay.as_deref())
+  }
+
+  /// Problems in the base config and the profile, each prefixed with its file
+  ///
+  /// Loading ignores keys it doesn't know; here every key must be one nbi
+  /// reads, and misspelled ones come with a suggestion. Nothing is migrated
+  /// or written.
+  pub fn validate(&self) -> Result<Vec<String>> {
+    let mut problems = Vec::new();
+    let mut files = vec![self.file.clone()];
+    if let Some(profile) = &self.profile {
+      files.push(self.profile_path(profile)?);
+    }
+    for path in files {
+      if !path.exists() {
+        if path != self.file {
+          problems.push(format!("{}: profile not found", path.display()));
+        }
+        continue;
+      }
+      let mut table = read_table(&path)?;
+      let version = migrate(&mut table)?;
+      if version > CONFIG_VERSION {
+        problems.push(format!(
+          "{}: written by a newer nbi (config version {}); upgrade nbi to check it",
+          path.display(),
+          version
+        ));
+        continue;
+      }
+      table.remove("version");
+      let file = path.display();
+      problems.extend(check_table(&table).into_iter().map(|p| format!("{}: {}", file, p)));
+    }
+    Ok(problems)
+  }
+}
+
+/// The base config file: `config`, else in `config_dir`, else in the
+/// platform config directory (or the working directory without one)
+fn locate(config: Option<PathBuf>, config_dir: Option<PathBuf>) -> PathBuf {
+  config.unwrap_or_else(|| {
+    let platform = || ProjectDirs::from("", "", APP_NAME).map(|d| d.config_dir().to_path_buf());
+    let dir = config_dir.filter(|d| !d.as_os_str().is_empty()).or_else(platform);
+    dir.unwrap_or_default().join("config.toml")
+  })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RegistrySettings {
   #[serde(default = "default_true")]
@@ -24,6 +228,21 @@ pub struct RegistrySettings {
   pub dev_domain: bool,
   #[serde(default = "default_true")]
   pub github: bool,
+  /// Whether the name is free as a GitHub user or org, on by default as
+  /// nbi always checked it before the repo check existed
+  #[serde(default = "default_true")]
+  pub github_user: bool,
+  /// The other handle checks, off by default; `--handles` and the
+  /// settings screen turn them on together with `github_user`
+  #[serde(default)]
+  pub npm_org: bool,
+  #[serde(default)]
+  pub bluesky: bool,
+  #[serde(default)]
+  pub mastodon: bool,
+  /// USPTO screening, off by default and turned on by `--trademark`
+  #[serde(default)]
+  pub trademark: bool,
 }
 
 fn default_true() -> bool {
@@ -41,50 +260,699 @@ impl Default for RegistrySettings {
       debian: true,
       dev_domain: true,
       github: true,
+      github_user: true,
+      npm_org: false,
+      bluesky: false,
+      mastodon: false,
+      trademark: false,
     }
   }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+impl RegistrySettings {
+  /// Settings with every registry disabled
+  pub fn none() -> Self {
+    Self {
+      npm: false,
+      crates: false,
+      pypi: false,
+      brew: false,
+      flatpak: false,
+      debian: false,
+      dev_domain: false,
+      github: false,
+      github_user: false,
+      npm_org: false,
+      bluesky: false,
+      mastodon: false,
+      trademark: false,
+    }
+  }
+
+  fn field_mut(&mut self, registry: RegistryType) -> &mut bool {
+    match registry {
+      RegistryType::Npm => &mut self.npm,
+      RegistryType::Crates => &mut self.crates,
+      RegistryType::PyPi => &mut self.pypi,
+      RegistryType::Brew => &mut self.brew,
+      RegistryType::Flatpak => &mut self.flatpak,
+      RegistryType::Debian => &mut self.debian,
+      RegistryType::DevDomain => &mut self.dev_domain,
+      RegistryType::GitHub => &mut self.github,
+      RegistryType::GitHubUser => &mut self.github_user,
+      RegistryType::NpmOrg => &mut self.npm_org,
+      RegistryType::Bluesky => &mut self.bluesky,
+      RegistryType::Mastodon => &mut self.mastodon,
+      RegistryType::Trademark => &mut self.trademark,
+    }
+  }
+
+  /// Whether a registry is enabled
+  pub fn is_enabled(&self, registry: RegistryType) -> bool {
+    match registry {
+      RegistryType::Npm => self.npm,
+      RegistryType::Crates => self.crates,
+      RegistryType::PyPi => self.pypi,
+      RegistryType::Brew => self.brew,
+      RegistryType::Flatpak => self.flatpak,
+      RegistryType::Debian => self.debian,
+      RegistryType::DevDomain => self.dev_domain,
+      RegistryType::GitHub => self.github,
+      RegistryType::GitHubUser => self.github_user,
+      RegistryType::NpmOrg => self.npm_org,
+      RegistryType::Bluesky => self.bluesky,
+      RegistryType::Mastodon => self.mastodon,
+      RegistryType::Trademark => self.trademark,
+    }
+  }
+
+  /// Enable or disable a registry
+  pub fn set_enabled(&mut self, registry: RegistryType, enabled: bool) {
+    *self.field_mut(registry) = enabled;
+  }
+
+  /// Whether every handle check is enabled
+  pub fn handles(&self) -> bool {
+    RegistryType::HANDLES.iter().all(|r| self.is_enabled(*r))
+  }
+
+  /// Enable or disable every handle check
+  pub fn set_handles(&mut self, enabled: bool) {
+    for registry in RegistryType::HANDLES {
+      self.set_enabled(registry, enabled);
+    }
+  }
+
+  /// Narrow these settings to an explicit selection
+  ///
+  /// A non-empty `include` list replaces the toggles entirely; `exclude`
+  /// then disables registries on top of that.
+  pub fn filtered(&self, include: &[RegistryType], exclude: &[RegistryType]) -> Self {
+    let mut settings = if include.is_empty() {
+      self.clone()
+    } else {
+      let mut only = Self::none();
+      for &registry in include {
+        only.set_enabled(registry, true);
+      }
+      only
+    };
+    for &registry in exclude {
+      settings.set_enabled(registry, false);
+    }
+    settings
+  }
+}
+
+/// Last-used repository options on the TUI Register screen
+///
+/// Empty strings mean "no description" and "personal account".
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegisterDefaults {
+  #[serde(default)]
+  pub private: bool,
+  #[serde(default)]
+  pub description: String,
+  #[serde(default)]
+  pub org: String,
+  /// What to do with a new repo when committing the manifest fails
+  #[serde(default)]
+  pub on_failure: OnFailure,
+  #[serde(default)]
+  pub homepage: String,
+  /// Create repos with issues turned off
+  #[serde(default)]
+  pub disable_issues: bool,
+}
+
+impl RegisterDefaults {
+  pub fn repo_options(&self) -> RepoOptions {
+    let non_empty = |s: &str| Some(s.trim().to_string()).filter(|s| !s.is_empty());
+    RepoOptions {
+      description: non_empty(&self.description),
+      private: self.private,
+      org: non_empty(&self.org),
+      homepage: non_empty(&self.homepage),
+      has_issues: self.disable_issues.then_some(false),
+      ..RepoOptions::default()
+    }
+  }
+
+  pub fn from_repo_options(options: &RepoOptions) -> Self {
+    Self {
+      private: options.private,
+      description: options.description.clone().unwrap_or_default(),
+      org: options.org.clone().unwrap_or_default(),
+      homepage: options.homepage.clone().unwrap_or_default(),
+      disable_issues: options.has_issues == Some(false),
+      ..Self::default()
+    }
+  }
+}
+
+/// Settings for `nbi serve`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerSettings {
+  /// Address to listen on; anything but loopback exposes the API
+  #[serde(default = "default_host")]
+  pub host: IpAddr,
+  /// Other sites whose pages may call the API, e.g. "https://example.com"
+  #[serde(default)]
+  pub cors_origins: Vec<String>,
+}
+
+fn default_host() -> IpAddr {
+  IpAddr::V4(Ipv4Addr::LOCALHOST)
+}
+
+impl Default for ServerSettings {
+  fn default() -> Self {
+    Self { host: default_host(), cors_origins: Vec::new() }
+  }
+}
+
+/// TUI colors: a built-in preset with per-role overrides
+///
+/// Also read from the shorthand `theme = "light"`. Colors are ANSI names
+/// (e.g. `blue`, `light red`), `#rrggbb`, or 256-color indexes; empty keeps
+/// the preset's.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ThemeSettings {
+  /// `dark` (the default) or `light`
+  #[serde(default)]
+  pub preset: String,
+  #[serde(default)]
+  pub accent: String,
+  #[serde(default)]
+  pub success: String,
+  #[serde(default)]
+  pub error: String,
+  #[serde(default)]
+  pub warning: String,
+  #[serde(default)]
+  pub reserved: String,
+  #[serde(default)]
+  pub muted: String,
+}
+
+/// Keys for the TUI's remappable actions, as specs such as "ctrl+p", "j"
+/// or "F2"; an empty list leaves the action unbound
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeySettings {
+  pub quit: Vec<String>,
+  pub help: Vec<String>,
+  /// Focus the Search or Domain input
+  pub edit: Vec<String>,
+  /// Run the search being typed
+  pub search: Vec<String>,
+  pub next_screen: Vec<String>,
+  pub previous_screen: Vec<String>,
+  pub select_up: Vec<String>,
+  pub select_down: Vec<String>,
+  pub register: Vec<String>,
+  /// Tick a registry on the Register screen or flip a setting
+  pub toggle: Vec<String>,
+}
+
+impl Default for KeySettings {
+  fn default() -> Self {
+    let keys = |keys: &[&str]| keys.iter().map(|k| k.to_string()).collect();
+    Self {
+      quit: keys(&["q"]),
+      help: keys(&["?"]),
+      edit: keys(&["i", "e"]),
+      search: keys(&["enter"]),
+      next_screen: keys(&["tab"]),
+      previous_screen: keys(&["shift+tab"]),
+      select_up: keys(&["up"]),
+      select_down: keys(&["down"]),
+      register: keys(&["enter"]),
+      toggle: keys(&["space"]),
+    }
+  }
+}
+
+impl KeySettings {
+  /// The specs bound to `action`
+  pub fn get(&self, action: Action) -> &[String] {
+    match action {
+      Action::Quit => &self.quit,
+      Action::Help => &self.help,
+      Action::Edit => &self.edit,
+      Action::Search => &self.search,
+      Action::NextScreen => &self.next_screen,
+      Action::PreviousScreen => &self.previous_screen,
+      Action::SelectUp => &self.select_up,
+      Action::SelectDown => &self.select_down,
+      Action::Register => &self.register,
+      Action::Toggle => &self.toggle,
+    }
+  }
+}
+
+/// A `[theme]` table, or just a preset name
+fn preset_or_theme<'de, D: serde::Deserializer<'de>>(d: D) -> Result<ThemeSettings, D::Error> {
+  #[derive(Deserialize)]
+  #[serde(untagged)]
+  enum Theme {
+    Preset(String),
+    Settings(ThemeSettings),
+  }
+  Ok(match Theme::deserialize(d)? {
+    Theme::Preset(preset) => ThemeSettings { preset, ..ThemeSettings::default() },
+    Theme::Settings(settings) => settings,
+  })
+}
+
+/// Where `nbi watch` reports names that became available
+///
+/// Either or both of the webhook and the command may be set.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NotificationSettings {
+  /// Slack, Discord or other webhook URL the event is POSTed to
+  #[serde(default)]
+  pub webhook_url: String,
+  /// Webhook body with `{name}`, `{registry}` and `{status}` placeholders;
+  /// empty for one Slack and Discord both understand
+  #[serde(default)]
+  pub template: String,
+  /// Command run through the shell with the event as JSON on stdin
+  #[serde(default)]
+  pub notify_command: String,
+  /// Desktop notifications when a TUI search or registration finishes;
+  /// needs the `desktop-notifications` build feature
+  #[serde(default)]
+  pub desktop: bool,
+}
+
+/// Base URLs checked instead of the public registries', e.g. an internal
+/// mirror; empty keeps the default
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EndpointSettings {
+  /// Default `https://registry.npmjs.org`
+  #[serde(default)]
+  pub npm: String,
+  /// Default `https://crates.io/api/v1/crates`
+  #[serde(default)]
+  pub crates: String,
+  /// Simple index; default `https://pypi.org/simple`
+  #[serde(default)]
+  pub pypi: String,
+  /// Default `https://formulae.brew.sh/api/formula`
+  #[serde(default)]
+  pub brew: String,
+  /// Default `https://flathub.org/api/v1/apps`
+  #[serde(default)]
+  pub flatpak: String,
+  /// Default `https://sources.debian.org/api/src`
+  #[serde(default)]
+  pub debian: String,
+  /// API base, e.g. `https://host/api/v3` for GitHub Enterprise; default
+  /// `https://api.github.com`, and `NBI_GITHUB_API_URL` wins over both
+  #[serde(default)]
+  pub github: String,
+  /// PDS resolving `*.bsky.social` handles; default `https://bsky.social`
+  #[serde(default)]
+  pub bluesky: String,
+  /// Instance for the Mastodon handle check; default `https://mastodon.social`
+  #[serde(default)]
+  pub mastodon: String,
+  /// Trademark search API; default `https://tmsearch.uspto.gov/api-v1-0-0/tmsearch`
+  #[serde(default)]
+  pub trademark: String,
+}
+
+impl EndpointSettings {
+  /// The configured base URL for `registry`, without a trailing slash
+  ///
+  /// The npm org and GitHub user checks share the npm and GitHub endpoints.
+  pub fn get(&self, registry: RegistryType) -> Option<&str> {
+    let url = match registry {
+      RegistryType::Npm | RegistryType::NpmOrg => &self.npm,
+      RegistryType::Crates => &self.crates,
+      RegistryType::PyPi => &self.pypi,
+      RegistryType::Brew => &self.brew,
+      RegistryType::Flatpak => &self.flatpak,
+      RegistryType::Debian => &self.debian,
+      RegistryType::GitHub | RegistryType::GitHubUser => &self.github,
+      RegistryType::Bluesky => &self.bluesky,
+      RegistryType::Mastodon => &self.mastodon,
+      RegistryType::Trademark => &self.trademark,
+      RegistryType::DevDomain => return None,
+    };
+    Some(url.trim().trim_end_matches('/')).filter(|url| !url.is_empty())
+  }
+
+  /// Fails on the first endpoint that isn't an http(s) URL, naming its key
+  pub fn validate(&self) -> Result<()> {
+    let shared = [RegistryType::NpmOrg, RegistryType::GitHubUser];
+    for registry in RegistryType::ALL.into_iter().filter(|r| !shared.contains(r)) {
+      let Some(url) = self.get(registry) else {
+        continue;
+      };
+      let valid = reqwest::Url::parse(url)
+        .is_ok_and(|u| matches!(u.scheme(), "http" | "https") && u.has_host());
+      if !valid {
+        anyhow::bail!("Invalid URL for endpoints.{}: '{}'", registry.id(), url);
+      }
+    }
+    Ok(())
+  }
+}
+
+/// Where the GitHub token was found, in the order sources are tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenSource {
+  GithubToken,
+  GhToken,
+  Command,
+  Keyring,
+}
+
+impl TokenSource {
+  pub fn label(&self) -> &'static str {
+    match self {
+      TokenSource::GithubToken => "GITHUB_TOKEN",
+      TokenSource::GhToken => "GH_TOKEN",
+      TokenSource::Command => "github_token_command",
+      TokenSource::Keyring => "keyring",
+    }
+  }
+}
+
+/// Token sources in resolution order, for messages like "tried ..."
+pub const TOKEN_SOURCES: &str = "GITHUB_TOKEN, GH_TOKEN, github_token_command, keyring";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
+  /// Format of the file this was read from, see [`CONFIG_VERSION`]
+  #[serde(default = "current_version")]
+  version: u32,
+  /// Resolved once, since the command or keyring may be slow
   #[serde(skip)]
-  #[allow(dead_code)]
-  github_token: Option<String>,
+  github_token: OnceLock<Option<(String, TokenSource)>>,
+  /// Command whose stdout is the GitHub token, e.g. `gh auth token`
+  #[serde(default)]
+  pub github_token_command: String,
+  /// OAuth app client id for `nbi auth login --device`
+  #[serde(default)]
+  pub github_client_id: String,
+  /// Owner whose repos the GitHub check looks at; defaults to the token's user
+  #[serde(default)]
+  pub github_username: String,
+  /// The token's user, looked up once and shared between clones
+  #[serde(skip)]
+  github_login: Arc<tokio::sync::OnceCell<Option<String>>>,
+  /// Set by [`ConfigSource::load`]; without it the built-in templates are used
+  #[serde(skip)]
+  templates_dir: Option<PathBuf>,
+  /// Registry checks in flight at once, across every name; `--concurrency`
+  /// overrides it
+  #[serde(default = "default_concurrency")]
+  pub concurrency: usize,
+  /// TLDs checked by `nbi domain` and `/api/domain` when none are given
+  #[serde(default = "default_tlds")]
+  pub default_tlds: Vec<String>,
   #[serde(default)]
   pub registries: RegistrySettings,
+  /// User-defined TLD groups, used as `@name` in TLD lists
+  #[serde(default)]
+  pub tld_groups: BTreeMap<String, Vec<String>>,
+  #[serde(default)]
+  pub register: RegisterDefaults,
+  #[serde(default)]
+  pub server: ServerSettings,
+  #[serde(default)]
+  pub notifications: NotificationSettings,
+  #[serde(default)]
+  pub endpoints: EndpointSettings,
+  #[serde(default, deserialize_with = "preset_or_theme")]
+  pub theme: ThemeSettings,
+  #[serde(default)]
+  pub keys: KeySettings,
+  /// Nice classes the trademark check screens, e.g. 9 for software; empty
+  /// screens every class
+  #[serde(default = "default_trademark_classes")]
+  pub trademark_classes: Vec<u32>,
+  /// Third-party Homebrew taps the brew check also looks in, e.g.
+  /// "hashicorp/tap"; each costs a GitHub API request per name
+  #[serde(default)]
+  pub brew_taps: Vec<String>,
+  /// Save the TUI's last search on quit and show it again on launch
+  #[serde(default = "default_true")]
+  pub restore_session: bool,
+  /// License for generated manifests
+  #[serde(default = "default_license")]
+  pub default_license: String,
+  /// Author for generated manifests, e.g. "Jane Doe <jane@example.com>"
+  #[serde(default)]
+  pub author: String,
+  /// Commit a README.md explaining the reservation to new repos
+  #[serde(default = "default_true")]
+  pub add_readme: bool,
+  /// Commit a LICENSE file matching `default_license` to new repos
+  #[serde(default = "default_true")]
+  pub add_license: bool,
+  /// Topics for new repos, on top of the registry of each manifest
+  #[serde(default = "default_topics")]
+  pub default_topics: Vec<String>,
+  /// GitHub .gitignore template for new repos, e.g. "Rust"; empty for none
+  #[serde(default)]
+  pub default_gitignore: String,
+}
+
+fn current_version() -> u32 {
+  CONFIG_VERSION
+}
+
+fn default_topics() -> Vec<String> {
+  vec!["reserved-name".to_string()]
+}
+
+fn default_license() -> String {
+  "MIT".to_string()
+}
+
+fn default_concurrency() -> usize {
+  crate::registry::DEFAULT_CONCURRENCY
+}
+
+fn default_tlds() -> Vec<String> {
+  ["com", "net", "org", "io", "dev"].map(String::from).to_vec()
+}
+
+fn default_trademark_classes() -> Vec<u32> {
+  crate::registry::trademark::DEFAULT_CLASSES.to_vec()
+}
+
+impl Default for Config {
+  fn default() -> Self {
+    Self {
+      version: CONFIG_VERSION,
+      github_token: OnceLock::new(),
+      github_token_command: String::new(),
+      github_client_id: String::new(),
+      github_username: String::new(),
+      github_login: Arc::default(),
+      templates_dir: None,
+      concurrency: default_concurrency(),
+      default_tlds: default_tlds(),
+      registries: RegistrySettings::default(),
+      tld_groups: BTreeMap::new(),
+      register: RegisterDefaults::default(),
+      server: ServerSettings::default(),
+      notifications: NotificationSettings::default(),
+      endpoints: EndpointSettings::default(),
+      theme: ThemeSettings::default(),
+      keys: KeySettings::default(),
+      trademark_classes: default_trademark_classes(),
+      brew_taps: Vec::new(),
+      restore_session: true,
+      default_license: default_license(),
+      author: String::new(),
+      add_readme: true,
+      add_license: true,
+      default_topics: default_topics(),
+      default_gitignore: String::new(),
+    }
+  }
 }
 
 impl Config {
-  /// Get the config file path
-  fn config_path() -> Option<PathBuf> {
-    ProjectDirs::from("", "", APP_NAME).map(|dirs| dirs.config_dir().join("config.toml"))
+  /// Checks what the types alone don't: endpoint URLs, theme colors and
+  /// key specs
+  pub fn validate(&self) -> Result<()> {
+    self.endpoints.validate()?;
+    crate::ui::theme::Theme::resolve(&self.theme)?;
+    KeyMap::from_settings(&self.keys)?;
+    Ok(())
+  }
+
+  /// Author, license and templates for generated manifests
+  pub fn manifest_fields(&self) -> ManifestFields {
+    ManifestFields {
+      author: self.author.trim().to_string(),
+      license: self.default_license.trim().to_string(),
+      templates_dir: self.templates_dir.clone(),
+      readme: self.add_readme,
+      license_file: self.add_license,
+    }
+  }
+
+  /// `options` with the manifest settings, plus the default topics and
+  /// .gitignore template where none were given
+  pub fn with_repo_defaults(&self, options: RepoOptions) -> RepoOptions {
+    let topics = if options.topics.is_empty() {
+      self.default_topics.clone()
+    } else {
+      options.topics.clone()
+    };
+    let gitignore = Some(self.default_gitignore.trim().to_string()).filter(|g| !g.is_empty());
+    RepoOptions {
+      manifest: self.manifest_fields(),
+      topics,
+      gitignore_template: options.gitignore_template.clone().or(gitignore),
+      ..options
+    }
   }
 
-  /// Load config from file
-  pub fn load() -> Result<Self> {
-    let path =
-      Self::config_path().ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
+  /// Expand a TLD list (literal TLDs and `@group`s) using configured groups
+  pub fn expand_tlds(&self, entries: &[String]) -> Result<Vec<String>> {
+    Ok(crate::registry::domain::expand_tlds(entries, &self.tld_groups)?)
+  }
+
+  /// Every settable dotted key, e.g. `registries.npm`
+  pub fn keys() -> Vec<String> {
+    let mut keys = Vec::new();
+    if let Ok(table) = schema() {
+      collect_keys(&table, "", &mut keys);
+    }
+    keys
+  }
+
+  /// Get a value by dotted key
+  pub fn get(&self, key: &str) -> Result<toml::Value> {
+    let root = toml::Value::try_from(self)?;
+    key
+      .split('.')
+      .try_fold(&root, |value, part| value.get(part))
+      .cloned()
+      .ok_or_else(|| unknown_key(key))
+  }
+
+  /// Set a value by dotted key, parsing `raw` as a TOML value
+  ///
+  /// Bare words that aren't valid TOML (e.g. `foo`) are treated as strings.
+  /// The updated config is re-validated against the schema before applying.
+  pub fn set(&mut self, key: &str, raw: &str) -> Result<()> {
+    if !Self::keys().iter().any(|k| k == key) {
+      return Err(unknown_key(key));
+    }
+
+    let mut root = toml::Value::try_from(&*self)?;
+    let mut slot = &mut root;
+    for part in key.split('.') {
+      let table = slot.as_table_mut().ok_or_else(|| unknown_key(key))?;
+      slot = table
+        .entry(part)
+        .or_insert_with(|| toml::Value::Table(Default::default()));
+    }
+    *slot = parse_value(raw);
+
+    let mut config: Config = root
+      .try_into()
+      .map_err(|e| anyhow::anyhow!("Invalid value for {}: {}", key, e))?;
+    config.validate()?;
+    config.templates_dir = self.templates_dir.take();
+    *self = config;
+    Ok(())
+  }
 
-    if !path.exists() {
-      return Ok(Self::default());
+  /// Every field with its default value, with free-form maps such as
+  /// `tld_groups` listed as a single table
+  pub fn fields() -> Vec<(String, toml::Value)> {
+    let mut fields = Vec::new();
+    if let Ok(table) = schema() {
+      collect_fields(&table, "", &mut fields);
     }
+    fields
+  }
+
+  /// Update only the fields present in `patch`, leaving the rest alone
+  ///
+  /// Nested tables are merged key by key, except free-form maps, which are
+  /// replaced whole. Unknown keys and wrongly typed values are rejected.
+  pub fn merge(&mut self, patch: toml::Table) -> Result<()> {
+    let schema = schema()?;
+    let toml::Value::Table(mut root) = toml::Value::try_from(&*self)? else {
+      anyhow::bail!("Config isn't a table");
+    };
+    merge_table(&mut root, &schema, patch, "")?;
+
+    let mut config: Config = toml::Value::Table(root)
+      .try_into()
+      .map_err(|e| anyhow::anyhow!("Invalid config: {}", e))?;
+    config.validate()?;
+    config.templates_dir = self.templates_dir.take();
+    *self = config;
+    Ok(())
+  }
+
+  fn load_layered(path: &Path, profile: Option<&Path>) -> Result<Self> {
+    let Some(profile) = profile else {
+      return Self::load_from(path);
+    };
+    let mut table = read_current(path)?;
+    let mut overlay = read_table(profile)?;
+    migrate(&mut overlay)?;
+    overlay_table(&mut table, overlay);
+    let config: Self = toml::Value::Table(table)
+      .try_into()
+      .with_context(|| format!("Failed to parse {} over {}", profile.display(), path.display()))?;
+    config.validate().with_context(|| format!("In {}", profile.display()))?;
+    Ok(config)
+  }
 
-    let content = fs::read_to_string(&path)?;
-    let config: Config = toml::from_str(&content)?;
+  fn load_from(path: &Path) -> Result<Self> {
+    let config: Self = toml::Value::Table(read_current(path)?)
+      .try_into()
+      .with_context(|| format!("Failed to parse {}", path.display()))?;
+    config.validate().with_context(|| format!("In {}", path.display()))?;
     Ok(config)
   }
 
-  /// Save config to file
-  pub fn save(&self) -> Result<()> {
-    let path =
-      Self::config_path().ok_or_else(|| anyhow::anyhow!("Could not find config directory"))?;
+  fn save_layered(&self, path: &Path, profile: Option<&Path>) -> Result<()> {
+    // Saving would drop whatever settings the newer version added
+    if self.version > CONFIG_VERSION {
+      anyhow::bail!(
+        "{} was written by a newer nbi (config version {}); upgrade nbi to change it",
+        path.display(),
+        self.version
+      );
+    }
+    let Some(profile) = profile else {
+      return self.save_to(path);
+    };
+    let (toml::Value::Table(base), toml::Value::Table(current)) =
+      (toml::Value::try_from(Self::load_from(path)?)?, toml::Value::try_from(self)?)
+    else {
+      anyhow::bail!("Config isn't a table");
+    };
+    if let Some(parent) = profile.parent() {
+      fs::create_dir_all(parent)?;
+    }
+    fs::write(profile, toml::to_string_pretty(&diff_table(&base, current))?)?;
+    Ok(())
+  }
 
+  fn save_to(&self, path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
       fs::create_dir_all(parent)?;
     }
 
     let content = toml::to_string_pretty(self)?;
-    fs::write(&path, content)?;
+    fs::write(path, content)?;
     Ok(())
   }
 
@@ -92,11 +960,912 @@ impl Config {
   #[allow(dead_code)]
   pub fn set_github_token(&mut self, _token: String) -> Result<()> {
     // Deprecated: tokens should only be provided via environment variables
-    anyhow::bail!("GitHub tokens should only be set via GITHUB_TOKEN environment variable for security")
+    anyhow::bail!("GitHub tokens aren't stored in the config for security; use one of {}", TOKEN_SOURCES)
   }
 
-  /// Get GitHub token from environment only (not stored in config)
+  /// Account for the GitHub repo check, if `settings` enables it
+  ///
+  /// The owner is `github_username`, or else the token's user, which is
+  /// looked up once.
+  pub async fn github_account(&self, settings: &RegistrySettings) -> GitHubAccount {
+    if !settings.github || crate::registry::offline::fixtures().is_some() {
+      return GitHubAccount::default();
+    }
+    let token = self.get_github_token();
+    let owner = match (self.configured_username(), &token) {
+      (Some(username), _) => Some(username.to_string()),
+      (None, Some(token)) => {
+        let login = self.github_login.get_or_init(|| async {
+          github::get_username(token).await.ok()
+        });
+        login.await.clone()
+      }
+      (None, None) => None,
+    };
+    GitHubAccount { owner, token }
+  }
+
+  /// `github_username`, unless it's blank
+  pub fn configured_username(&self) -> Option<&str> {
+    Some(self.github_username.trim()).filter(|u| !u.is_empty())
+  }
+
+  /// GitHub token from the first source that has one (see [`TOKEN_SOURCES`])
   pub fn get_github_token(&self) -> Option<String> {
-    std::env::var("GITHUB_TOKEN").ok()
+    self.github_token_with_source().map(|(token, _)| token)
+  }
+
+  /// Where [`Config::get_github_token`] found the token
+  pub fn github_token_source(&self) -> Option<TokenSource> {
+    self.github_token_with_source().map(|(_, source)| source)
+  }
+
+  /// Where the token was found, once it has been looked up
+  ///
+  /// Unlike [`Config::github_token_source`] this never runs the command or
+  /// reads the keyring, so it's safe to call while drawing.
+  pub fn resolved_token_source(&self) -> Option<Option<TokenSource>> {
+    self.github_token.get().map(|found| found.as_ref().map(|(_, source)| *source))
+  }
+
+  /// Keep a token looked up on a clone of this config, e.g. off the UI thread
+  pub fn remember_github_token(&self, found: Option<(String, TokenSource)>) {
+    let _ = self.github_token.set(found);
+  }
+
+  /// The token and where it came from, looking it up the first time
+  pub fn github_token_with_source(&self) -> Option<(String, TokenSource)> {
+    self
+      .github_token
+      .get_or_init(|| {
+        resolve_github_token(
+          &self.github_token_command,
+          |name| std::env::var(name).ok(),
+          run_token_command,
+          keyring_token,
+        )
+      })
+      .clone()
+  }
+}
+
+/// Try GITHUB_TOKEN, GH_TOKEN, the configured command, then the keyring
+///
+/// Blank values count as missing so an empty variable doesn't shadow the
+/// sources after it.
+fn resolve_github_token(
+  command: &str,
+  var: impl Fn(&str) -> Option<String>,
+  run: impl Fn(&str) -> Option<String>,
+  keyring: impl Fn() -> Option<String>,
+) -> Option<(String, TokenSource)> {
+  let found = |token: Option<String>, source| {
+    Some(token?.trim().to_string()).filter(|t| !t.is_empty()).map(|t| (t, source))
+  };
+  let command = command.trim();
+
+  found(var("GITHUB_TOKEN"), TokenSource::GithubToken)
+    .or_else(|| found(var("GH_TOKEN"), TokenSource::GhToken))
+    .or_else(|| {
+      let output = if command.is_empty() { None } else { run(command) };
+      found(output, TokenSource::Command)
+    })
+    .or_else(|| found(keyring(), TokenSource::Keyring))
+}
+
+/// `command` run through `sh -c`, or `cmd /C` on Windows
+pub fn shell(command: &str) -> std::process::Command {
+  if cfg!(windows) {
+    let mut shell = std::process::Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+  } else {
+    let mut shell = std::process::Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+  }
+}
+
+/// Run `github_token_command` through the shell and return its stdout
+fn run_token_command(command: &str) -> Option<String> {
+  let mut shell = shell(command);
+
+  // stderr is captured rather than inherited so it can't draw over the TUI
+  match shell.output() {
+    Ok(output) if output.status.success() => String::from_utf8(output.stdout).ok(),
+    Ok(output) => {
+      let stderr = String::from_utf8_lossy(&output.stderr);
+      tracing::warn!(command, status = %output.status, %stderr, "github_token_command failed");
+      None
+    }
+    Err(e) => {
+      tracing::warn!(command, error = %e, "couldn't run github_token_command");
+      None
+    }
+  }
+}
+
+/// Keyring entry holding the GitHub token: service "nbi", user "github"
+#[cfg(feature = "keyring")]
+fn keyring_entry() -> keyring::Result<keyring::Entry> {
+  keyring::Entry::new(APP_NAME, "github")
+}
+
+#[cfg(feature = "keyring")]
+fn keyring_token() -> Option<String> {
+  keyring_entry().and_then(|entry| entry.get_password()).ok()
+}
+
+/// Save a GitHub token to the OS keyring (`nbi auth login`)
+#[cfg(feature = "keyring")]
+pub fn store_keyring_token(token: &str) -> Result<()> {
+  keyring_entry()?.set_password(token)?;
+  Ok(())
+}
+
+/// Remove the keyring token, returning whether there was one
+#[cfg(feature = "keyring")]
+pub fn delete_keyring_token() -> Result<bool> {
+  match keyring_entry()?.delete_credential() {
+    Ok(()) => Ok(true),
+    Err(keyring::Error::NoEntry) => Ok(false),
+    Err(e) => Err(e.into()),
+  }
+}
+
+#[cfg(not(feature = "keyring"))]
+fn keyring_token() -> Option<String> {
+  None
+}
+
+fn collect_keys(table: &toml::Table, prefix: &str, keys: &mut Vec<String>) {
+  for (name, value) in table {
+    let key = format!("{}{}", prefix, name);
+    match value {
+      toml::Value::Table(inner) => collect_keys(inner, &format!("{}.", key), keys),
+      _ => keys.push(key),
+    }
+  }
+}
+
+fn collect_fields(table: &toml::Table, prefix: &str, fields: &mut Vec<(String, toml::Value)>) {
+  for (name, value) in table {
+    let key = format!("{}{}", prefix, name);
+    match value {
+      toml::Value::Table(inner) if !inner.is_empty() => {
+        collect_fields(inner, &format!("{}.", key), fields)
+      }
+      _ => fields.push((key, value.clone())),
+    }
+  }
+}
+
+/// Copy `patch` into `target`, checking each key against `schema`
+fn merge_table(
+  target: &mut toml::Table,
+  schema: &toml::Table,
+  patch: toml::Table,
+  prefix: &str,
+) -> Result<()> {
+  for (name, value) in patch {
+    let key = format!("{}{}", prefix, name);
+    match (schema.get(&name), value) {
+      (None, _) => return Err(unknown_key(&key)),
+      (Some(toml::Value::Table(inner)), toml::Value::Table(patch)) if !inner.is_empty() => {
+        let slot = target
+          .entry(name)
+          .or_insert_with(|| toml::Value::Table(Default::default()));
+        let toml::Value::Table(slot) = slot else {
+          return Err(anyhow::anyhow!("Invalid value for {}: expected a table", key));
+        };
+        merge_table(slot, inner, patch, &format!("{}.", key))?;
+      }
+      (Some(toml::Value::Table(inner)), _) if !inner.is_empty() => {
+        return Err(anyhow::anyhow!("Invalid value for {}: expected a table", key));
+      }
+      (Some(_), value) => {
+        target.insert(name, value);
+      }
+    }
+  }
+  Ok(())
+}
+
+/// A config file as a table; a missing file is an empty one
+fn read_table(path: &Path) -> Result<toml::Table> {
+  if !path.exists() {
+    return Ok(toml::Table::new());
+  }
+  let content = fs::read_to_string(path)
+    .with_context(|| format!("Failed to read {}", path.display()))?;
+  toml::from_str(&content).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// The base config file as a table, upgraded to [`CONFIG_VERSION`]
+///
+/// An older file is rewritten in the current format after copying the
+/// original to `<file>.v<N>.bak`. A missing file stays an empty table.
+fn read_current(path: &Path) -> Result<toml::Table> {
+  let mut table = read_table(path)?;
+  if !path.exists() {
+    return Ok(table);
+  }
+  let version = migrate(&mut table)?;
+  if version > CONFIG_VERSION {
+    tracing::warn!(path = %path.display(), version, "config is from a newer nbi; not saving it");
+  } else if version < CONFIG_VERSION {
+    table.insert("version".to_string(), toml::Value::Integer(CONFIG_VERSION.into()));
+    if let Err(e) = upgrade_file(path, version, &table) {
+      tracing::warn!(path = %path.display(), error = %e, "couldn't save the migrated config");
+    }
+  }
+  Ok(table)
+}
+
+/// Run the migrations `table` hasn't had yet, returning its version
+fn migrate(table: &mut toml::Table) -> Result<u32> {
+  let version = match table.get("version") {
+    None => 0,
+    Some(toml::Value::Integer(version)) => u32::try_from(*version)
+      .map_err(|_| anyhow::anyhow!("Invalid config version {}", version))?,
+    Some(value) => anyhow::bail!("Invalid config version {}: expected an integer", value),
+  };
+  for step in MIGRATIONS.iter().skip(version as usize) {
+    step(table);
+  }
+  Ok(version)
+}
+
+/// Back up a version `version` config and replace it with `table`
+fn upgrade_file(path: &Path, version: u32, table: &toml::Table) -> Result<()> {
+  let mut name = path.file_name().unwrap_or_default().to_os_string();
+  name.push(format!(".v{}.bak", version));
+  let backup = path.with_file_name(name);
+  // An earlier backup is the real original; don't replace it
+  if !backup.exists() {
+    fs::copy(path, &backup)
+      .with_context(|| format!("Failed to back up {} to {}", path.display(), backup.display()))?;
+  }
+  fs::write(path, toml::to_string_pretty(table)?)?;
+  tracing::info!(path = %path.display(), from = version, to = CONFIG_VERSION, "migrated config");
+  Ok(())
+}
+
+/// Strict check of a config table: unknown keys, then wrongly typed values
+fn check_table(table: &toml::Table) -> Vec<String> {
+  let mut problems = Vec::new();
+  match schema() {
+    Ok(schema) => unknown_keys(table, &schema, "", &mut problems),
+    Err(e) => problems.push(e.to_string()),
+  }
+  // One value at a time, so each error names its key
+  let mut values = Vec::new();
+  collect_fields(table, "", &mut values);
+  for (key, value) in values {
+    let alone = key.rsplit('.').fold(value, |value, part| {
+      toml::Value::Table(toml::Table::from_iter([(part.to_string(), value)]))
+    });
+    match alone.try_into::<Config>() {
+      Ok(config) => {
+        if let Err(e) = config.validate() {
+          problems.push(e.to_string());
+        }
+      }
+      Err(e) => problems.push(format!("invalid value for '{}': {}", key, e.message())),
+    }
+  }
+  problems
+}
+
+fn unknown_keys(
+  table: &toml::Table,
+  schema: &toml::Table,
+  prefix: &str,
+  problems: &mut Vec<String>,
+) {
+  for (name, value) in table {
+    let key = format!("{}{}", prefix, name);
+    match (schema.get(name), value) {
+      (None, _) => problems.push(match suggest(name, schema.keys()) {
+        Some(similar) => format!("unknown key '{}' (did you mean '{}{}'?)", key, prefix, similar),
+        None => format!("unknown key '{}'", key),
+      }),
+      // Free-form maps such as `tld_groups` take any key
+      (Some(toml::Value::Table(inner)), toml::Value::Table(value)) if !inner.is_empty() => {
+        unknown_keys(value, inner, &format!("{}.", key), problems)
+      }
+      _ => {}
+    }
+  }
+}
+
+/// The candidate closest to a misspelled `name`, if any is close enough
+fn suggest<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<&'a str> {
+  candidates
+    .map(|candidate| (edit_distance(name, candidate), candidate))
+    .filter(|(distance, _)| *distance <= name.chars().count().max(3) / 3)
+    .min_by_key(|(distance, _)| *distance)
+    .map(|(_, candidate)| candidate.as_str())
+}
+
+/// Levenshtein distance in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+  let b: Vec<char> = b.chars().collect();
+  let mut row: Vec<usize> = (0..=b.len()).collect();
+  for (i, ca) in a.chars().enumerate() {
+    let mut diagonal = row[0];
+    row[0] = i + 1;
+    for (j, &cb) in b.iter().enumerate() {
+      let above = row[j + 1];
+      row[j + 1] = if ca == cb { diagonal } else { 1 + diagonal.min(above).min(row[j]) };
+      diagonal = above;
+    }
+  }
+  row[b.len()]
+}
+
+/// `Config::default()` as a table, less `version`, which isn't a setting
+fn schema() -> Result<toml::Table> {
+  let toml::Value::Table(mut table) = toml::Value::try_from(Config::default())? else {
+    anyhow::bail!("Config isn't a table");
+  };
+  table.remove("version");
+  Ok(table)
+}
+
+/// Lay `overlay` over `base`: tables merge key by key, anything else replaces
+fn overlay_table(base: &mut toml::Table, overlay: toml::Table) {
+  for (name, value) in overlay {
+    match (base.get_mut(&name), value) {
+      (Some(toml::Value::Table(inner)), toml::Value::Table(value)) => overlay_table(inner, value),
+      (_, value) => {
+        base.insert(name, value);
+      }
+    }
+  }
+}
+
+/// The parts of `current` that differ from `base`, so that overlaying the
+/// result on `base` gives back `current`
+fn diff_table(base: &toml::Table, current: toml::Table) -> toml::Table {
+  let mut diff = toml::Table::new();
+  for (name, value) in current {
+    match (base.get(&name), value) {
+      (Some(old), value) if *old == value => {}
+      (Some(toml::Value::Table(old)), toml::Value::Table(value)) => {
+        let inner = diff_table(old, value);
+        if !inner.is_empty() {
+          diff.insert(name, toml::Value::Table(inner));
+        }
+      }
+      (_, value) => {
+        diff.insert(name, value);
+      }
+    }
+  }
+  diff
+}
+
+fn unknown_key(key: &str) -> anyhow::Error {
+  let keys = Config::keys();
+  let hint = suggest(key, keys.iter()).map(|k| format!(" Did you mean '{}'?", k));
+  anyhow::anyhow!(
+    "Unknown config key '{}'.{} Valid keys:\n  {}",
+    key,
+    hint.unwrap_or_default(),
+    keys.join("\n  ")
+  )
+}
+
+fn parse_value(raw: &str) -> toml::Value {
+  toml::from_str::<toml::Table>(&format!("value = {}", raw))
+    .ok()
+    .and_then(|mut table| table.remove("value"))
+    .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn resolve(
+    vars: &[(&str, &str)],
+    command: &str,
+    output: Option<&str>,
+    keyring: Option<&str>,
+  ) -> Option<(String, TokenSource)> {
+    resolve_github_token(
+      command,
+      |name| vars.iter().find(|(k, _)| *k == name).map(|(_, v)| v.to_string()),
+      |_| output.map(str::to_string),
+      || keyring.map(str::to_string),
+    )
+  }
+
+  #[test]
+  fn test_token_sources_are_tried_in_order() {
+    let all = [("GITHUB_TOKEN", "a"), ("GH_TOKEN", "b")];
+    let source = |found: Option<(String, TokenSource)>| found.map(|(t, s)| (t, s.label()));
+    let (c, d) = (Some("c\n"), Some("d"));
+
+    assert_eq!(source(resolve(&all, "cmd", c, d)), Some(("a".into(), "GITHUB_TOKEN")));
+    assert_eq!(source(resolve(&all[1..], "cmd", c, d)), Some(("b".into(), "GH_TOKEN")));
+    assert_eq!(source(resolve(&[], "cmd", c, d)), Some(("c".into(), "github_token_command")));
+    assert_eq!(source(resolve(&[], "cmd", None, d)), Some(("d".into(), "keyring")));
+    assert_eq!(resolve(&[], "cmd", None, None), None);
+  }
+
+  #[test]
+  fn test_blank_token_sources_are_skipped() {
+    let vars = [("GITHUB_TOKEN", ""), ("GH_TOKEN", "  ")];
+    assert_eq!(resolve(&vars, "cmd", Some("\n"), None), None);
+    // Without a configured command it isn't run at all
+    assert_eq!(resolve(&[], " ", Some("c"), None), None);
+  }
+
+  #[test]
+  fn test_filtered_include_replaces_toggles() {
+    let settings =
+      RegistrySettings::default().filtered(&[RegistryType::Npm, RegistryType::Crates], &[]);
+    for registry in RegistryType::ALL {
+      let expected = matches!(registry, RegistryType::Npm | RegistryType::Crates);
+      assert_eq!(settings.is_enabled(registry), expected, "{}", registry.id());
+    }
+  }
+
+  #[test]
+  fn test_filtered_exclude_applies_to_config() {
+    let base = RegistrySettings {
+      brew: false,
+      ..Default::default()
+    };
+    let settings = base.filtered(&[], &[RegistryType::Flatpak, RegistryType::Debian]);
+    assert!(settings.npm);
+    assert!(!settings.brew);
+    assert!(!settings.flatpak);
+    assert!(!settings.debian);
+  }
+
+  #[test]
+  fn test_toml_round_trip() {
+    let mut config = Config::default();
+    config.registries.flatpak = false;
+    let content = toml::to_string_pretty(&config).unwrap();
+    let parsed: Config = toml::from_str(&content).unwrap();
+    assert_eq!(toml::to_string_pretty(&parsed).unwrap(), content);
+  }
+
+  #[test]
+  fn test_default_tlds_round_trip() {
+    let config = Config {
+      default_tlds: vec!["rs".to_string(), "app".to_string()],
+      ..Default::default()
+    };
+    let content = toml::to_string_pretty(&config).unwrap();
+    let parsed: Config = toml::from_str(&content).unwrap();
+    assert_eq!(parsed.default_tlds, ["rs", "app"]);
+  }
+
+  #[test]
+  fn test_older_configs_keep_the_github_user_check() {
+    let parsed: Config = toml::from_str("[registries]\ngithub = true\nnpm = false\n").unwrap();
+    assert!(parsed.registries.github && parsed.registries.github_user);
+    assert!(!parsed.registries.npm_org);
+  }
+
+  #[test]
+  fn test_tld_groups_round_trip() {
+    let parsed: Config = toml::from_str("[tld_groups]\nstartup = [\"io\", \"dev\"]\n").unwrap();
+    assert_eq!(parsed.tld_groups["startup"], ["io", "dev"]);
+    let content = toml::to_string_pretty(&parsed).unwrap();
+    let reparsed: Config = toml::from_str(&content).unwrap();
+    assert_eq!(reparsed.tld_groups, parsed.tld_groups);
+  }
+
+  #[test]
+  fn test_register_defaults_round_trip() {
+    let options = RepoOptions {
+      description: Some("My tool".to_string()),
+      private: true,
+      org: Some("acme".to_string()),
+      homepage: Some("https://example.com".to_string()),
+      has_issues: Some(false),
+      ..RepoOptions::default()
+    };
+    let config = Config {
+      register: RegisterDefaults::from_repo_options(&options),
+      ..Config::default()
+    };
+    let parsed: Config = toml::from_str(&toml::to_string_pretty(&config).unwrap()).unwrap();
+    assert_eq!(parsed.register, config.register);
+
+    assert_eq!(parsed.register.repo_options(), options);
+    assert!(Config::default().register.repo_options().org.is_none());
+  }
+
+  #[test]
+  fn test_repo_defaults_fill_unset_options() {
+    let config = Config { default_gitignore: "Rust".to_string(), ..Config::default() };
+    let options = config.with_repo_defaults(RepoOptions::default());
+    assert_eq!(options.topics, ["reserved-name"]);
+    assert_eq!(options.gitignore_template.as_deref(), Some("Rust"));
+
+    let given = RepoOptions {
+      topics: vec!["cli".to_string()],
+      gitignore_template: Some("Node".to_string()),
+      ..RepoOptions::default()
+    };
+    let options = config.with_repo_defaults(given);
+    assert_eq!(options.topics, ["cli"]);
+    assert_eq!(options.gitignore_template.as_deref(), Some("Node"));
+  }
+
+  #[test]
+  fn test_set_register_org() {
+    let mut config = Config::default();
+    config.set("register.org", "acme").unwrap();
+    assert_eq!(config.register.repo_options().org.as_deref(), Some("acme"));
+  }
+
+  #[tokio::test]
+  async fn test_github_account_prefers_configured_username() {
+    let config = Config { github_username: "octocat".to_string(), ..Config::default() };
+    let account = config.github_account(&RegistrySettings::default()).await;
+    assert_eq!(account.owner.as_deref(), Some("octocat"));
+
+    let disabled = config.github_account(&RegistrySettings::none()).await;
+    assert_eq!(disabled, GitHubAccount::default());
+  }
+
+  #[test]
+  fn test_set_server_host() {
+    let mut config = Config::default();
+    assert!(config.server.host.is_loopback());
+    config.set("server.host", "0.0.0.0").unwrap();
+    assert_eq!(config.server.host, IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+    assert!(config.set("server.host", "example.com").is_err());
+  }
+
+  #[test]
+  fn test_set_register_on_failure() {
+    let mut config = Config::default();
+    assert_eq!(config.register.on_failure, OnFailure::Keep);
+    config.set("register.on_failure", "delete").unwrap();
+    assert_eq!(config.register.on_failure, OnFailure::Delete);
+    assert!(config.set("register.on_failure", "maybe").is_err());
+  }
+
+  #[test]
+  fn test_missing_default_tlds_uses_builtin_list() {
+    let parsed: Config = toml::from_str("[registries]\nnpm = false\n").unwrap();
+    assert_eq!(parsed.default_tlds, ["com", "net", "org", "io", "dev"]);
+    assert!(!parsed.registries.npm);
+  }
+
+  #[test]
+  fn test_set_default_tlds() {
+    let mut config = Config::default();
+    config.set("default_tlds", r#"["com", "rs"]"#).unwrap();
+    assert_eq!(config.default_tlds, ["com", "rs"]);
+  }
+
+  #[test]
+  fn test_keys_list_registry_toggles() {
+    let keys = Config::keys();
+    for registry in RegistryType::ALL {
+      assert!(keys.contains(&format!("registries.{}", registry.id())));
+    }
+  }
+
+  #[test]
+  fn test_set_then_get_round_trips() {
+    let mut config = Config::default();
+    config.set("registries.npm", "false").unwrap();
+    assert!(!config.registries.npm);
+    assert_eq!(config.get("registries.npm").unwrap(), toml::Value::Boolean(false));
+  }
+
+  #[test]
+  fn test_set_unknown_key_lists_valid_keys() {
+    let err = Config::default().set("registries.nmp", "false").unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("registries.nmp"));
+    assert!(message.contains("registries.npm"));
+  }
+
+  #[test]
+  fn test_set_rejects_wrong_type() {
+    let mut config = Config::default();
+    let err = config.set("registries.npm", "maybe").unwrap_err();
+    assert!(err.to_string().contains("registries.npm"));
+    assert!(config.registries.npm);
+  }
+
+  #[test]
+  fn test_set_enabled_matches_is_enabled() {
+    for registry in RegistryType::ALL {
+      let mut settings = RegistrySettings::none();
+      settings.set_enabled(registry, true);
+      for other in RegistryType::ALL {
+        assert_eq!(settings.is_enabled(other), other == registry);
+      }
+    }
+  }
+
+  fn table(json: serde_json::Value) -> toml::Table {
+    toml::Value::try_from(json).unwrap().as_table().unwrap().clone()
+  }
+
+  #[test]
+  fn test_merge_keeps_unrelated_fields_through_save() {
+    let path = std::env::temp_dir().join(format!("nbi-config-{}.toml", std::process::id()));
+    fs::write(
+      &path,
+      "author = \"Jane Doe\"\ndefault_tlds = [\"io\"]\n\n\
+       [registries]\nnpm = false\n\n[tld_groups]\nweb = [\"com\"]\n\n\
+       [register]\norg = \"acme\"\n",
+    )
+    .unwrap();
+
+    let mut config = Config::load_from(&path).unwrap();
+    config.merge(table(serde_json::json!({ "registries": { "crates": false } }))).unwrap();
+    config.save_to(&path).unwrap();
+    let saved = Config::load_from(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(!saved.registries.npm && !saved.registries.crates && saved.registries.pypi);
+    assert_eq!(saved.author, "Jane Doe");
+    assert_eq!(saved.default_tlds, ["io"]);
+    assert_eq!(saved.tld_groups["web"], ["com"]);
+    assert_eq!(saved.register.org, "acme");
+  }
+
+  #[test]
+  fn test_merge_replaces_free_form_maps() {
+    let mut config = Config::default();
+    config.tld_groups.insert("old".to_string(), vec!["net".to_string()]);
+    config.merge(table(serde_json::json!({ "tld_groups": { "web": ["com"] } }))).unwrap();
+    assert_eq!(config.tld_groups.keys().collect::<Vec<_>>(), ["web"]);
+  }
+
+  #[test]
+  fn test_merge_rejects_unknown_and_mistyped_fields() {
+    let mut config = Config::default();
+    let err = config.merge(table(serde_json::json!({ "registries": { "nmp": false } })));
+    assert!(err.unwrap_err().to_string().contains("registries.nmp"));
+    let err = config.merge(table(serde_json::json!({ "registries": true })));
+    assert!(err.unwrap_err().to_string().contains("expected a table"));
+    assert!(config.merge(table(serde_json::json!({ "add_readme": "yes" }))).is_err());
+    assert!(config.registries.npm && config.add_readme);
+  }
+
+  #[test]
+  fn test_load_reports_parse_errors() {
+    let path = std::env::temp_dir().join(format!("nbi-bad-config-{}.toml", std::process::id()));
+    fs::write(&path, "registries = [").unwrap();
+    let err = Config::load_from(&path).unwrap_err();
+    fs::remove_file(&path).unwrap();
+    assert!(format!("{:#}", err).contains("Failed to parse"));
+  }
+
+  /// A fresh config directory under the system temp dir
+  fn temp_source(name: &str, profile: Option<&str>) -> ConfigSource {
+    let dir = std::env::temp_dir().join(format!("nbi-{}-{}", name, std::process::id()));
+    ConfigSource::resolve(Some(dir.join("config.toml")), profile.map(String::from))
+  }
+
+  #[test]
+  fn test_profile_overlays_base_and_saves_only_differences() {
+    let source = temp_source("profiles", Some("work"));
+    let work = source.active_path().unwrap();
+    fs::create_dir_all(work.parent().unwrap()).unwrap();
+    fs::write(source.file(), "author = \"Jane\"\n\n[registries]\nnpm = false\n").unwrap();
+    fs::write(&work, "github_username = \"acme\"\n\n[registries]\ndebian = false\n").unwrap();
+
+    let mut config = source.load().unwrap();
+    assert_eq!((config.author.as_str(), config.github_username.as_str()), ("Jane", "acme"));
+    assert!(!config.registries.npm && !config.registries.debian && config.registries.crates);
+    assert_eq!(source.profiles().unwrap(), ["work"]);
+
+    config.registries.crates = false;
+    source.save(&config).unwrap();
+    let saved: toml::Table = toml::from_str(&fs::read_to_string(&work).unwrap()).unwrap();
+    let reloaded = source.load().unwrap();
+    let untouched = source.with_profile(None).load().unwrap();
+    fs::remove_dir_all(source.file().parent().unwrap()).unwrap();
+
+    assert_eq!(saved.keys().collect::<Vec<_>>(), ["github_username", "registries"]);
+    assert_eq!(saved["registries"].as_table().unwrap().len(), 2);
+    assert!(!reloaded.registries.crates && !reloaded.registries.debian);
+    assert!(untouched.registries.crates && untouched.registries.debian);
+  }
+
+  #[test]
+  fn test_missing_profile_fails_load_but_not_edits() {
+    let source = temp_source("no-profile", Some("work"));
+    let err = source.load().unwrap_err().to_string();
+    assert!(err.contains("Profile 'work' not found"), "{}", err);
+    let config = source.load_for_edit().unwrap();
+    assert_eq!(config.default_tlds, default_tlds());
+    assert_eq!(config.manifest_fields().templates_dir, Some(source.templates_dir()));
+  }
+
+  #[test]
+  fn test_profile_names_stay_in_profiles_dir() {
+    let source = temp_source("names", None);
+    let path = source.profile_path("work_2-b").unwrap();
+    assert_eq!(path, source.file().with_file_name("profiles").join("work_2-b.toml"));
+    for name in ["", "../config", "a/b", "work.toml"] {
+      assert!(source.profile_path(name).is_err(), "{:?}", name);
+    }
+  }
+
+  #[test]
+  fn test_config_location_precedence() {
+    let (file, dir) = (PathBuf::from("/x/custom.toml"), PathBuf::from("/y"));
+    assert_eq!(locate(Some(file.clone()), Some(dir.clone())), file);
+    assert_eq!(locate(None, Some(dir)), PathBuf::from("/y/config.toml"));
+    // An empty NBI_CONFIG_DIR falls through to the platform directory
+    assert_eq!(locate(None, Some(PathBuf::new())), locate(None, None));
+    assert!(locate(None, None).ends_with("config.toml"));
+  }
+
+  #[test]
+  fn test_set_keeps_templates_dir() {
+    let source = temp_source("templates", None);
+    let mut config = source.load().unwrap();
+    config.set("author", "Jane").unwrap();
+    config.merge(toml::Table::new()).unwrap();
+    assert_eq!(config.manifest_fields().templates_dir, Some(source.templates_dir()));
+  }
+
+  #[test]
+  fn test_next_profile_cycles_through_base() {
+    let profiles = ["home".to_string(), "work".to_string()];
+    assert_eq!(next_profile(&profiles, None).as_deref(), Some("home"));
+    assert_eq!(next_profile(&profiles, Some("home")).as_deref(), Some("work"));
+    assert_eq!(next_profile(&profiles, Some("work")), None);
+    assert_eq!(next_profile(&profiles, Some("deleted")), None);
+    assert_eq!(next_profile(&[], None), None);
+  }
+
+  #[test]
+  fn test_fields_list_free_form_maps_once() {
+    let fields = Config::fields();
+    let keys: Vec<_> = fields.iter().map(|(key, _)| key.as_str()).collect();
+    assert!(keys.contains(&"registries.npm") && keys.contains(&"tld_groups"));
+    assert!(!keys.iter().any(|k| k.starts_with("tld_groups.")));
+    assert!(!keys.contains(&"version"));
+  }
+
+  #[test]
+  fn test_invalid_endpoints_are_rejected_by_key() {
+    let mut config = Config::default();
+    config.set("endpoints.npm", "https://registry.npm.internal/").unwrap();
+    assert_eq!(config.endpoints.get(RegistryType::Npm), Some("https://registry.npm.internal"));
+    assert_eq!(config.endpoints.get(RegistryType::Crates), None);
+
+    let err = config.set("endpoints.crates", "crates.internal").unwrap_err().to_string();
+    assert_eq!(err, "Invalid URL for endpoints.crates: 'crates.internal'");
+    let err = config.set("endpoints.github", "ftp://ghe.internal").unwrap_err().to_string();
+    assert!(err.contains("endpoints.github"), "{}", err);
+    assert_eq!(config.endpoints.crates, "");
+
+    let table: toml::Table = toml::from_str("[endpoints]\npypi = \"not a url\"\n").unwrap();
+    assert_eq!(check_table(&table), ["Invalid URL for endpoints.pypi: 'not a url'"]);
+  }
+
+  #[test]
+  fn test_theme_preset_shorthand_and_invalid_colors() {
+    let config: Config = toml::from_str("theme = \"light\"\n").unwrap();
+    assert_eq!(config.theme.preset, "light");
+    let config: Config = toml::from_str("[theme]\npreset = \"dark\"\naccent = \"blue\"\n").unwrap();
+    assert_eq!((config.theme.preset.as_str(), config.theme.accent.as_str()), ("dark", "blue"));
+
+    let mut config = Config::default();
+    config.set("theme.muted", "#808080").unwrap();
+    let err = config.set("theme.error", "crimson").unwrap_err().to_string();
+    assert!(err.starts_with("Invalid color for theme.error: 'crimson'"), "{}", err);
+    assert_eq!(config.theme.error, "");
+
+    let table: toml::Table = toml::from_str("theme = \"sepia\"\n").unwrap();
+    assert_eq!(check_table(&table), ["Unknown theme 'sepia' (valid: dark, light)"]);
+  }
+
+  const V0: &str = include_str!("config_v0.toml");
+
+  #[test]
+  fn test_v0_config_migrates_with_backup() {
+    let source = temp_source("v0", None);
+    fs::create_dir_all(source.file().parent().unwrap()).unwrap();
+    fs::write(source.file(), V0).unwrap();
+
+    let config = source.load().unwrap();
+    let migrated: toml::Table =
+      toml::from_str(&fs::read_to_string(source.file()).unwrap()).unwrap();
+    let backup = source.file().with_file_name("config.toml.v0.bak");
+    let original = fs::read_to_string(&backup).unwrap();
+    // A second load finds nothing to migrate
+    fs::write(&backup, "kept").unwrap();
+    let again = source.load().unwrap();
+    let kept = fs::read_to_string(&backup).unwrap();
+    fs::remove_dir_all(source.file().parent().unwrap()).unwrap();
+
+    assert_eq!(original, V0);
+    assert_eq!(kept, "kept");
+    assert_eq!(migrated["version"].as_integer(), Some(CONFIG_VERSION.into()));
+    assert!(!migrated.contains_key("github_token"));
+    for config in [config, again] {
+      assert_eq!(config.version, CONFIG_VERSION);
+      assert_eq!((config.github_username.as_str(), config.concurrency), ("octocat", 8));
+      assert_eq!(config.default_tlds, ["com", "dev", "rs"]);
+      assert_eq!(config.tld_groups["mine"], ["rs", "sh"]);
+      assert!(!config.registries.pypi && !config.add_license && config.register.private);
+      assert_eq!(config.register.org, "acme");
+    }
+  }
+
+  #[test]
+  fn test_newer_config_loads_but_isnt_saved() {
+    let source = temp_source("newer", None);
+    let content = "version = 99\nauthor = \"Jane\"\nfuture = true\n";
+    fs::create_dir_all(source.file().parent().unwrap()).unwrap();
+    fs::write(source.file(), content).unwrap();
+
+    let config = source.load().unwrap();
+    let err = source.save(&config).unwrap_err().to_string();
+    let problems = source.validate().unwrap();
+    let after = fs::read_to_string(source.file()).unwrap();
+    fs::remove_dir_all(source.file().parent().unwrap()).unwrap();
+
+    assert_eq!(config.author, "Jane");
+    assert!(err.contains("newer nbi (config version 99)"), "{}", err);
+    assert_eq!(problems.len(), 1);
+    assert!(problems[0].contains("upgrade nbi"), "{}", problems[0]);
+    assert_eq!(after, content);
+  }
+
+  #[test]
+  fn test_validate_reports_misspelled_keys() {
+    let source = temp_source("validate", Some("work"));
+    let work = source.active_path().unwrap();
+    fs::create_dir_all(work.parent().unwrap()).unwrap();
+    fs::write(source.file(), V0).unwrap();
+    fs::write(
+      &work,
+      "autor = \"Jane\"\nconcurrency = \"many\"\nzzz = 1\n\n\
+       [registries]\nnpn = false\n\n[tld_groups]\nanything = [\"rs\"]\n",
+    )
+    .unwrap();
+
+    let problems = source.validate().unwrap();
+    let base = fs::read_to_string(source.file()).unwrap();
+    let missing = source.with_profile(Some("home".into())).validate().unwrap();
+    fs::remove_dir_all(source.file().parent().unwrap()).unwrap();
+
+    // The v0 base only differs by what migration drops, and isn't rewritten
+    assert_eq!(base, V0);
+    let problems: Vec<_> =
+      problems.iter().map(|p| p.split_once("work.toml: ").unwrap().1).collect();
+    assert_eq!(
+      problems[..3],
+      [
+        "unknown key 'autor' (did you mean 'author'?)",
+        "unknown key 'registries.npn' (did you mean 'registries.npm'?)",
+        "unknown key 'zzz'",
+      ]
+    );
+    assert_eq!(problems.len(), 4);
+    assert!(problems[3].starts_with("invalid value for 'concurrency'"), "{}", problems[3]);
+    assert_eq!(missing.len(), 1);
+    assert!(missing[0].ends_with("home.toml: profile not found"), "{}", missing[0]);
+  }
+
+  #[test]
+  fn test_unknown_key_suggests_the_closest() {
+    let err = Config::default().set("registries.crate", "false").unwrap_err().to_string();
+    assert!(err.contains("Did you mean 'registries.crates'?"), "{}", err);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(suggest("zzz", Config::keys().iter()), None);
   }
 }
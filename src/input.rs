@@ -0,0 +1,214 @@
+//! Single-line text input with a grapheme-aware cursor
+
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+/// Editable text plus a cursor, stored as a byte offset on a grapheme boundary
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+  value: String,
+  cursor: usize,
+}
+
+impl TextInput {
+  pub fn as_str(&self) -> &str {
+    &self.value
+  }
+
+  pub fn is_empty(&self) -> bool {
+    self.value.is_empty()
+  }
+
+  /// Replace the text and move the cursor to the end
+  pub fn set(&mut self, value: impl Into<String>) {
+    self.value = value.into();
+    self.cursor = self.value.len();
+  }
+
+  /// Display columns before the cursor, for placing the terminal cursor
+  pub fn cursor_column(&self) -> usize {
+    self.value[..self.cursor].width()
+  }
+
+  pub fn insert(&mut self, c: char) {
+    self.value.insert(self.cursor, c);
+    self.cursor += c.len_utf8();
+  }
+
+  /// Insert pasted text, dropping control characters and trailing whitespace
+  pub fn insert_str(&mut self, text: &str) {
+    let text: String = text.trim_end().chars().filter(|c| !c.is_control()).collect();
+    self.value.insert_str(self.cursor, &text);
+    self.cursor += text.len();
+  }
+
+  /// Clear the whole line (Ctrl+U)
+  pub fn clear(&mut self) {
+    self.value.clear();
+    self.cursor = 0;
+  }
+
+  /// Delete the word before the cursor, plus whitespace after it (Ctrl+W)
+  pub fn delete_word_before(&mut self) {
+    let before = self.value[..self.cursor].trim_end();
+    let start = before
+      .char_indices()
+      .rev()
+      .find(|(_, c)| c.is_whitespace())
+      .map_or(0, |(i, c)| i + c.len_utf8());
+    self.value.replace_range(start..self.cursor, "");
+    self.cursor = start;
+  }
+
+  /// Delete the grapheme before the cursor (Backspace)
+  pub fn delete_before(&mut self) {
+    let start = self.prev_boundary();
+    self.value.replace_range(start..self.cursor, "");
+    self.cursor = start;
+  }
+
+  /// Delete the grapheme under the cursor (Delete)
+  pub fn delete_at(&mut self) {
+    let end = self.next_boundary();
+    self.value.replace_range(self.cursor..end, "");
+  }
+
+  pub fn move_left(&mut self) {
+    self.cursor = self.prev_boundary();
+  }
+
+  pub fn move_right(&mut self) {
+    self.cursor = self.next_boundary();
+  }
+
+  pub fn move_home(&mut self) {
+    self.cursor = 0;
+  }
+
+  pub fn move_end(&mut self) {
+    self.cursor = self.value.len();
+  }
+
+  fn prev_boundary(&self) -> usize {
+    self.value[..self.cursor]
+      .grapheme_indices(true)
+      .next_back()
+      .map_or(0, |(i, _)| i)
+  }
+
+  fn next_boundary(&self) -> usize {
+    self.value[self.cursor..]
+      .graphemes(true)
+      .next()
+      .map_or(self.cursor, |g| self.cursor + g.len())
+  }
+}
+
+impl From<&str> for TextInput {
+  fn from(value: &str) -> Self {
+    let mut input = Self::default();
+    input.set(value);
+    input
+  }
+}
+
+impl PartialEq<str> for TextInput {
+  fn eq(&self, other: &str) -> bool {
+    self.value == other
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn typed(text: &str) -> TextInput {
+    let mut input = TextInput::default();
+    text.chars().for_each(|c| input.insert(c));
+    input
+  }
+
+  #[test]
+  fn test_backspace_removes_whole_multibyte_char() {
+    let mut input = typed("café");
+    input.delete_before();
+    assert_eq!(input.as_str(), "caf");
+    assert_eq!(input.cursor_column(), 3);
+  }
+
+  #[test]
+  fn test_backspace_removes_combining_sequence() {
+    // "e" followed by a combining acute accent is one grapheme
+    let mut input = typed("cafe\u{301}");
+    input.delete_before();
+    assert_eq!(input.as_str(), "caf");
+  }
+
+  #[test]
+  fn test_insert_and_delete_in_the_middle() {
+    let mut input = typed("naïve");
+    input.move_left();
+    input.move_left();
+    input.move_left();
+    input.insert('X');
+    assert_eq!(input.as_str(), "naXïve");
+    input.delete_at();
+    assert_eq!(input.as_str(), "naXve");
+    input.delete_before();
+    assert_eq!(input.as_str(), "nave");
+  }
+
+  #[test]
+  fn test_cursor_column_uses_display_width() {
+    let mut input = typed("名前ab");
+    assert_eq!(input.cursor_column(), 6);
+    input.move_home();
+    input.move_right();
+    assert_eq!(input.cursor_column(), 2);
+    input.move_end();
+    input.move_left();
+    assert_eq!(input.cursor_column(), 5);
+  }
+
+  #[test]
+  fn test_delete_word_before_cursor() {
+    let mut input = typed("my café  name");
+    input.delete_word_before();
+    assert_eq!(input.as_str(), "my café  ");
+    input.delete_word_before();
+    assert_eq!(input.as_str(), "my ");
+    input.delete_word_before();
+    input.delete_word_before();
+    assert!(input.is_empty());
+  }
+
+  #[test]
+  fn test_paste_strips_trailing_newline_and_controls() {
+    let mut input = typed("ab");
+    input.move_left();
+    input.insert_str("x\ty  \r\n");
+    assert_eq!(input.as_str(), "axyb");
+    assert_eq!(input.cursor_column(), 3);
+  }
+
+  #[test]
+  fn test_clear_resets_cursor() {
+    let mut input = typed("日本");
+    input.clear();
+    input.insert('a');
+    assert_eq!(input.as_str(), "a");
+  }
+
+  #[test]
+  fn test_edits_at_the_edges_are_noops() {
+    let mut input = typed("日本");
+    input.delete_at();
+    input.move_right();
+    assert_eq!(input.as_str(), "日本");
+    input.move_home();
+    input.delete_before();
+    input.move_left();
+    assert_eq!(input.as_str(), "日本");
+    assert_eq!(input.cursor_column(), 0);
+  }
+}
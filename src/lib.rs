@@ -0,0 +1,25 @@
+//! Package name availability across registries, domains and handles, with
+//! the TUI, CLI and web server of the `nbi` binary built on top
+//!
+//! Other tools can query a running `nbi serve` through [`client`], behind
+//! the `client` feature.
+
+pub mod api_types;
+pub mod app;
+pub mod cli;
+pub mod cli_commands;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod compare;
+pub mod config;
+pub mod input;
+pub mod logging;
+pub mod metrics;
+pub mod notify;
+pub mod output;
+pub mod publish;
+pub mod registry;
+pub mod report;
+pub mod server;
+pub mod tui;
+pub mod ui;
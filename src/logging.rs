@@ -0,0 +1,75 @@
+//! Logging setup
+//!
+//! CLI commands log to stderr. The TUI owns stdout/stderr, so it logs to a
+//! file under the state directory instead (`~/.local/state/nbi/nbi.log`).
+
+use anyhow::Result;
+use directories::ProjectDirs;
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_subscriber::EnvFilter;
+
+const APP_NAME: &str = "nbi";
+const LOG_ENV: &str = "NBI_LOG";
+const LOG_FILE_NAME: &str = "nbi.log";
+
+/// Where log output should go
+pub enum LogTarget {
+  Stderr,
+  File(PathBuf),
+}
+
+/// Default log file path used by the TUI
+pub fn default_log_file() -> Option<PathBuf> {
+  ProjectDirs::from("", "", APP_NAME).map(|dirs| {
+    dirs
+      .state_dir()
+      .unwrap_or_else(|| dirs.data_local_dir())
+      .join(LOG_FILE_NAME)
+  })
+}
+
+/// Build the log filter from `NBI_LOG`, falling back to the `-v` count
+fn build_filter(verbosity: u8) -> EnvFilter {
+  if let Ok(filter) = EnvFilter::try_from_env(LOG_ENV) {
+    return filter;
+  }
+
+  let directive = match verbosity {
+    0 => "warn",
+    1 => "nbi=debug,warn",
+    _ => "nbi=trace,debug",
+  };
+  EnvFilter::new(directive)
+}
+
+/// Initialize the global tracing subscriber
+pub fn init(verbosity: u8, target: LogTarget) -> Result<()> {
+  let filter = build_filter(verbosity);
+
+  match target {
+    LogTarget::Stderr => {
+      tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(std::io::stderr)
+        .init();
+    }
+    LogTarget::File(path) => {
+      let file = open_log_file(&path)?;
+      tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(Mutex::new(file))
+        .with_ansi(false)
+        .init();
+    }
+  }
+  Ok(())
+}
+
+fn open_log_file(path: &Path) -> Result<fs::File> {
+  if let Some(parent) = path.parent() {
+    fs::create_dir_all(parent)?;
+  }
+  Ok(OpenOptions::new().create(true).append(true).open(path)?)
+}
@@ -1,27 +1,132 @@
-mod app;
-mod cli;
-mod cli_commands;
-mod config;
-mod registry;
-mod server;
-mod tui;
-mod ui;
-
 use clap::Parser;
-use cli::{Cli, Commands};
+use nbi::cli::{self, Cli, Commands};
+use nbi::{config, logging, output, registry, tui};
 
-use cli_commands::*;
+use nbi::cli_commands::*;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
-  let cli = Cli::parse();
+  let cli = Cli::parse_from(cli::expand_shorthand(std::env::args_os().collect()));
+
+  let is_tui = matches!(cli.command, None | Some(Commands::Tui));
+  // The TUI owns the terminal, so it never logs to stderr
+  let log_target = match (cli.log_file, is_tui) {
+    (Some(path), _) => Some(logging::LogTarget::File(path)),
+    (None, true) => logging::default_log_file().map(logging::LogTarget::File),
+    (None, false) => Some(logging::LogTarget::Stderr),
+  };
+  if let Some(target) = log_target {
+    logging::init(cli.verbose, target)?;
+  }
+
+  let source = config::ConfigSource::resolve(cli.config, cli.profile);
+
+  // A broken config is reported by the command itself, the TUI included
+  let config = source.load().ok();
+  let concurrency = match cli.concurrency {
+    Some(n) => n as usize,
+    None => config.as_ref().map_or(registry::DEFAULT_CONCURRENCY, |c| c.concurrency),
+  };
+  registry::set_concurrency(concurrency);
+  if let Some(config) = &config {
+    registry::configure(config);
+  }
+
+  match cli.fixtures {
+    Some(path) => registry::offline::enable(registry::offline::Fixtures::load(&path)?),
+    None if cli.offline => registry::offline::enable(registry::offline::Fixtures::bundled()),
+    None => {}
+  }
 
+  let colors = output::Colors::new(cli.color.enabled());
   match cli.command {
-    None | Some(Commands::Tui) => tui::TuiRunner::run().await,
-    Some(Commands::Serve { port, open }) => server::start(port, open).await,
-    Some(Commands::Check { name, json }) => run_check(&name, json).await,
-    Some(Commands::Domain { name, tlds, json }) => run_domain_check(&name, &tlds, json).await,
+    None | Some(Commands::Tui) => tui::TuiRunner::run(source).await,
+    Some(Commands::Serve(args)) => run_serve(&source, args).await,
+    Some(Commands::Check {
+      names,
+      file,
+      out,
+      top,
+      registries,
+      exclude,
+      handles,
+      trademark,
+      github,
+      format,
+      json,
+      filter,
+      exit_code,
+    }) => {
+      let format = match format {
+        _ if json => output::OutputFormat::Json,
+        Some(format) => format,
+        None => out.as_deref().map_or(output::OutputFormat::Plain, output::OutputFormat::for_path),
+      };
+      let selection =
+        Selection { registries: &registries, exclude: &exclude, handles, trademark, github };
+      let batch = file.as_deref().map(|file| Batch { file, out: out.as_deref(), top });
+      let is_batch = batch.is_some();
+      let summary = run_check(&source, names, selection, format, &filter, colors, batch).await?;
+      exit_with_summary(summary, exit_code || is_batch)
+    }
+    Some(Commands::Domain { list_groups: true, .. }) => run_list_tld_groups(&source),
+    Some(Commands::Domain { name, tlds, format, json, filter, exit_code, .. }) => {
+      let format = if json { output::OutputFormat::Json } else { format };
+      let name = name.expect("clap requires a name unless --list-groups is set");
+      let tlds = tlds.as_deref();
+      let summary = run_domain_check(&source, &name, tlds, format, &filter, colors).await?;
+      exit_with_summary(summary, exit_code)
+    }
+    Some(Commands::Compare { names, registries, exclude, domains, tlds, format, json }) => {
+      let format = if json { output::OutputFormat::Json } else { format };
+      let (names, registries, exclude) = (&names, &registries, &exclude);
+      let candidates = Candidates { names, registries, exclude, domains, tlds: tlds.as_deref() };
+      run_compare(&source, candidates, format, colors).await
+    }
+    Some(Commands::Report { names, registries, exclude, domains, tlds, format, out }) => {
+      let (names, registries, exclude) = (&names, &registries, &exclude);
+      let candidates = Candidates { names, registries, exclude, domains, tlds: tlds.as_deref() };
+      run_report(&source, candidates, format, out.as_deref()).await
+    }
+    Some(Commands::Watch { names, test_notification: true, .. }) => {
+      run_test_notification(&source, names.first().map(String::as_str)).await
+    }
+    Some(Commands::Watch { names, registries, exclude, interval, .. }) => {
+      let interval = std::time::Duration::from_secs(interval);
+      run_watch(&source, names, &registries, &exclude, interval).await
+    }
+    Some(Commands::Register {
+      name,
+      registry,
+      private,
+      description,
+      org,
+      homepage,
+      no_issues,
+      gitignore,
+      topics,
+      on_failure,
+      yes,
+    }) => {
+      let options = registry::github::RepoOptions {
+        description,
+        private,
+        org,
+        homepage,
+        has_issues: no_issues.then_some(false),
+        gitignore_template: gitignore,
+        topics,
+        ..Default::default()
+      };
+      run_register(&source, &name, registry, &options, on_failure, yes).await
+    }
     Some(Commands::Publish { registry }) => run_publish(registry).await,
+    Some(Commands::Config { action }) => run_config(&source, action),
+    Some(Commands::Registry { action }) => run_registry(&source, action),
+    #[cfg(feature = "keyring")]
+    Some(Commands::Auth { action }) => run_auth(&source, action).await,
+    Some(Commands::Completions { shell }) => run_completions(shell),
+    Some(Commands::Manpage) => run_manpage(),
   }
 }
 
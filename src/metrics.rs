@@ -0,0 +1,165 @@
+//! Prometheus metrics for `nbi serve`
+//!
+//! Nothing is recorded until [`install`] runs, so the CLI and TUI only pay
+//! for checking whether it has.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::registry::{Availability, AvailabilityResult};
+
+/// Upper bounds, in seconds, of the check latency histogram buckets
+const LATENCY_BUCKETS: [f64; 9] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0];
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Start recording; later calls are no-ops
+pub fn install() {
+  METRICS.get_or_init(Metrics::default);
+}
+
+/// Count a finished registry check and its latency
+pub fn record_check(result: &AvailabilityResult, elapsed: Duration) {
+  if let Some(metrics) = METRICS.get() {
+    metrics.record_check(result, elapsed);
+  }
+}
+
+/// Count a handled HTTP request
+pub fn record_request(method: &str, route: &str, status: u16) {
+  if let Some(metrics) = METRICS.get() {
+    metrics.record_request(method, route, status);
+  }
+}
+
+/// Everything recorded so far in Prometheus text format, if installed
+pub fn render() -> Option<String> {
+  METRICS.get().map(Metrics::render)
+}
+
+#[derive(Default)]
+struct Metrics {
+  requests: Mutex<BTreeMap<(String, String, u16), u64>>,
+  checks: Mutex<BTreeMap<(&'static str, &'static str), u64>>,
+  latency: Mutex<BTreeMap<&'static str, Histogram>>,
+}
+
+#[derive(Default)]
+struct Histogram {
+  buckets: [u64; LATENCY_BUCKETS.len()],
+  sum: f64,
+  count: u64,
+}
+
+fn outcome(availability: Availability) -> &'static str {
+  match availability {
+    Availability::Available => "available",
+    Availability::Taken => "taken",
+    Availability::Reserved => "reserved",
+    Availability::Unknown => "error",
+  }
+}
+
+impl Metrics {
+  fn record_check(&self, result: &AvailabilityResult, elapsed: Duration) {
+    let registry = result.registry.id();
+    let key = (registry, outcome(result.availability));
+    *self.checks.lock().unwrap_or_else(|e| e.into_inner()).entry(key).or_default() += 1;
+
+    let seconds = elapsed.as_secs_f64();
+    let mut latency = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+    let histogram = latency.entry(registry).or_default();
+    for (bucket, bound) in histogram.buckets.iter_mut().zip(LATENCY_BUCKETS) {
+      if seconds <= bound {
+        *bucket += 1;
+      }
+    }
+    histogram.sum += seconds;
+    histogram.count += 1;
+  }
+
+  fn record_request(&self, method: &str, route: &str, status: u16) {
+    let key = (method.to_string(), route.to_string(), status);
+    *self.requests.lock().unwrap_or_else(|e| e.into_inner()).entry(key).or_default() += 1;
+  }
+
+  fn render(&self) -> String {
+    let mut out = String::new();
+
+    out.push_str("# HELP nbi_http_requests_total HTTP requests handled, by route\n");
+    out.push_str("# TYPE nbi_http_requests_total counter\n");
+    let requests = self.requests.lock().unwrap_or_else(|e| e.into_inner());
+    for ((method, route, status), count) in requests.iter() {
+      let _ = writeln!(
+        out,
+        "nbi_http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}",
+        method, route, status, count
+      );
+    }
+
+    out.push_str("# HELP nbi_checks_total Registry checks, by outcome\n");
+    out.push_str("# TYPE nbi_checks_total counter\n");
+    let checks = self.checks.lock().unwrap_or_else(|e| e.into_inner());
+    for ((registry, outcome), count) in checks.iter() {
+      let _ = writeln!(
+        out,
+        "nbi_checks_total{{registry=\"{}\",outcome=\"{}\"}} {}",
+        registry, outcome, count
+      );
+    }
+
+    out.push_str("# HELP nbi_check_duration_seconds Registry check latency\n");
+    out.push_str("# TYPE nbi_check_duration_seconds histogram\n");
+    let latency = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+    for (registry, histogram) in latency.iter() {
+      let name = "nbi_check_duration_seconds";
+      let labels = format!("registry=\"{}\"", registry);
+      for (count, bound) in histogram.buckets.iter().zip(LATENCY_BUCKETS) {
+        let _ = writeln!(out, "{}_bucket{{{},le=\"{}\"}} {}", name, labels, bound, count);
+      }
+      let _ = writeln!(out, "{}_bucket{{{},le=\"+Inf\"}} {}", name, labels, histogram.count);
+      let _ = writeln!(out, "{}_sum{{{}}} {}", name, labels, histogram.sum);
+      let _ = writeln!(out, "{}_count{{{}}} {}", name, labels, histogram.count);
+    }
+
+    out
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::RegistryType;
+
+  fn result(registry: RegistryType, available: Option<bool>) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: "foo".to_string(),
+      availability: available.into(),
+      error: None,
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  #[test]
+  fn test_render_counts_and_buckets() {
+    let metrics = Metrics::default();
+    metrics.record_check(&result(RegistryType::Npm, Some(true)), Duration::from_millis(80));
+    metrics.record_check(&result(RegistryType::Npm, None), Duration::from_secs(3));
+    metrics.record_request("GET", "/api/check", 200);
+
+    let text = metrics.render();
+    assert!(text.contains(r#"nbi_checks_total{registry="npm",outcome="available"} 1"#));
+    assert!(text.contains(r#"nbi_checks_total{registry="npm",outcome="error"} 1"#));
+    assert!(text.contains(r#"nbi_check_duration_seconds_bucket{registry="npm",le="0.05"} 0"#));
+    assert!(text.contains(r#"nbi_check_duration_seconds_bucket{registry="npm",le="0.1"} 1"#));
+    assert!(text.contains(r#"nbi_check_duration_seconds_bucket{registry="npm",le="5"} 2"#));
+    assert!(text.contains(r#"nbi_check_duration_seconds_count{registry="npm"} 2"#));
+    assert!(text.contains(
+      r#"nbi_http_requests_total{method="GET",route="/api/check",status="200"} 1"#
+    ));
+  }
+}
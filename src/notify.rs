@@ -0,0 +1,248 @@
+//! Telling the user a watched name became available
+
+use crate::config::NotificationSettings;
+use crate::registry::Availability;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+
+/// How long a webhook gets to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A name's status on one registry, sent to the webhook and command
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Event {
+  pub name: String,
+  /// Display name, e.g. "crates.io"
+  pub registry: String,
+  /// "available", "taken", "reserved" or "unknown"
+  pub status: String,
+}
+
+impl Event {
+  pub fn new(name: &str, registry: &str, availability: Availability) -> Self {
+    let status = match availability {
+      Availability::Available => "available",
+      Availability::Taken => "taken",
+      Availability::Reserved => "reserved",
+      Availability::Unknown => "unknown",
+    };
+    Self { name: name.to_string(), registry: registry.to_string(), status: status.to_string() }
+  }
+
+  /// One-line summary, e.g. "foo is now available on crates.io"
+  pub fn message(&self) -> String {
+    format!("{} is now {} on {}", self.name, self.status, self.registry)
+  }
+}
+
+/// Sends events to the configured webhook and command
+pub struct Notifier {
+  settings: NotificationSettings,
+  client: reqwest::Client,
+  retry_delay: Duration,
+}
+
+impl Notifier {
+  /// Fails early if the template won't produce JSON
+  pub fn new(settings: NotificationSettings) -> Result<Self> {
+    if !settings.template.trim().is_empty() {
+      let sample = Event::new("name", "registry", Availability::Available);
+      render_template(&settings.template, &sample)?;
+    }
+    Ok(Self { settings, client: reqwest::Client::new(), retry_delay: Duration::from_secs(2) })
+  }
+
+  /// Whether there's anywhere to send events
+  pub fn is_configured(&self) -> bool {
+    !self.settings.webhook_url.trim().is_empty() || !self.settings.notify_command.trim().is_empty()
+  }
+
+  /// Deliver `event` everywhere configured, trying the webhook a second time
+  /// if the first attempt fails
+  pub async fn send(&self, event: &Event) -> Result<()> {
+    let url = self.settings.webhook_url.trim();
+    if !url.is_empty() {
+      let body = self.webhook_body(event)?;
+      if let Err(e) = self.post(url, &body).await {
+        tracing::warn!(error = %format!("{:#}", e), "webhook failed, retrying once");
+        tokio::time::sleep(self.retry_delay).await;
+        self.post(url, &body).await?;
+      }
+    }
+    let command = self.settings.notify_command.trim();
+    if !command.is_empty() {
+      run_command(command, event).await?;
+    }
+    Ok(())
+  }
+
+  fn webhook_body(&self, event: &Event) -> Result<Value> {
+    if self.settings.template.trim().is_empty() {
+      let message = event.message();
+      // `text` is Slack's field and `content` Discord's; others get the rest
+      return Ok(serde_json::json!({
+        "text": message,
+        "content": message,
+        "name": event.name,
+        "registry": event.registry,
+        "status": event.status,
+      }));
+    }
+    render_template(&self.settings.template, event)
+  }
+
+  async fn post(&self, url: &str, body: &Value) -> Result<()> {
+    let response = self.client.post(url).json(body).timeout(WEBHOOK_TIMEOUT).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+      anyhow::bail!("Webhook answered {}", status);
+    }
+    Ok(())
+  }
+}
+
+/// Pop up a desktop notification without waiting for it
+///
+/// Shown by `notify-rust` on a blocking thread, since the D-Bus call can
+/// stall. A missing notification daemon is ignored; the TUI has nowhere to
+/// report it.
+#[cfg(feature = "desktop-notifications")]
+pub fn desktop(summary: &str, body: &str) {
+  let mut notification = notify_rust::Notification::new();
+  notification.appname("nbi").summary(summary).body(body);
+  tokio::task::spawn_blocking(move || {
+    if let Err(e) = notification.show() {
+      tracing::debug!(error = %e, "desktop notification failed");
+    }
+  });
+}
+
+#[cfg(not(feature = "desktop-notifications"))]
+pub fn desktop(_summary: &str, _body: &str) {}
+
+/// Fill the placeholders in `template`, escaped so they're safe inside JSON
+/// strings, and parse the result
+fn render_template(template: &str, event: &Event) -> Result<Value> {
+  let escape = |value: &str| {
+    let quoted = serde_json::to_string(value).expect("strings serialize");
+    quoted[1..quoted.len() - 1].to_string()
+  };
+  let body = template
+    .replace("{name}", &escape(&event.name))
+    .replace("{registry}", &escape(&event.registry))
+    .replace("{status}", &escape(&event.status));
+  serde_json::from_str(&body).context("notifications.template isn't valid JSON")
+}
+
+/// Run `notify_command` with `event` as JSON on stdin
+async fn run_command(command: &str, event: &Event) -> Result<()> {
+  let mut child = tokio::process::Command::from(crate::config::shell(command))
+    .stdin(Stdio::piped())
+    .spawn()
+    .with_context(|| format!("Couldn't run notify_command `{}`", command))?;
+  let mut stdin = child.stdin.take().expect("stdin is piped");
+  // The command may exit without reading, which is its business
+  let _ = stdin.write_all(&serde_json::to_vec(event)?).await;
+  drop(stdin);
+  let status = child.wait().await?;
+  if !status.success() {
+    anyhow::bail!("notify_command `{}` failed ({})", command, status);
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::{http::StatusCode, routing::post, Json, Router};
+  use std::sync::atomic::{AtomicUsize, Ordering};
+  use std::sync::{Arc, Mutex};
+
+  fn event() -> Event {
+    Event::new("foo", "crates.io", Availability::Available)
+  }
+
+  /// A webhook answering with `statuses` in turn, recording each body
+  async fn webhook(statuses: Vec<StatusCode>) -> (String, Arc<Mutex<Vec<Value>>>) {
+    let bodies = Arc::new(Mutex::new(Vec::new()));
+    let calls = Arc::new(AtomicUsize::new(0));
+    let seen = bodies.clone();
+    let app = Router::new().route(
+      "/hook",
+      post(move |Json(body): Json<Value>| async move {
+        seen.lock().unwrap().push(body);
+        statuses[calls.fetch_add(1, Ordering::SeqCst).min(statuses.len() - 1)]
+      }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    (format!("http://{}/hook", addr), bodies)
+  }
+
+  fn notifier(settings: NotificationSettings) -> Notifier {
+    Notifier { retry_delay: Duration::ZERO, ..Notifier::new(settings).unwrap() }
+  }
+
+  #[test]
+  fn test_template_placeholders_are_escaped() {
+    let event = Event::new("a\"b", "npm", Availability::Available);
+    let body = render_template(r#"{"msg": "{name} on {registry}: {status}"}"#, &event).unwrap();
+    assert_eq!(body["msg"], "a\"b on npm: available");
+  }
+
+  #[test]
+  fn test_invalid_template_is_rejected_up_front() {
+    let settings = NotificationSettings { template: "{name".to_string(), ..Default::default() };
+    assert!(Notifier::new(settings).is_err());
+  }
+
+  #[tokio::test]
+  async fn test_default_body_suits_slack_and_discord() {
+    let (url, bodies) = webhook(vec![StatusCode::NO_CONTENT]).await;
+    let settings = NotificationSettings { webhook_url: url, ..Default::default() };
+    notifier(settings).send(&event()).await.unwrap();
+    let bodies = bodies.lock().unwrap();
+    assert_eq!(bodies[0]["text"], "foo is now available on crates.io");
+    assert_eq!(bodies[0]["content"], bodies[0]["text"]);
+    assert_eq!(bodies[0]["status"], "available");
+  }
+
+  #[tokio::test]
+  async fn test_webhook_is_retried_once() {
+    let (url, bodies) = webhook(vec![StatusCode::BAD_GATEWAY, StatusCode::OK]).await;
+    let settings = NotificationSettings { webhook_url: url, ..Default::default() };
+    notifier(settings).send(&event()).await.unwrap();
+    assert_eq!(bodies.lock().unwrap().len(), 2);
+
+    let (url, bodies) = webhook(vec![StatusCode::INTERNAL_SERVER_ERROR]).await;
+    let settings = NotificationSettings { webhook_url: url, ..Default::default() };
+    let err = notifier(settings).send(&event()).await.unwrap_err();
+    assert!(err.to_string().contains("500"), "{}", err);
+    assert_eq!(bodies.lock().unwrap().len(), 2);
+  }
+
+  #[cfg(unix)]
+  #[tokio::test]
+  async fn test_command_gets_event_on_stdin() {
+    let path = std::env::temp_dir().join(format!("nbi-notify-{}.json", std::process::id()));
+    let command = format!("cat > '{}'", path.display());
+    let settings = NotificationSettings { notify_command: command, ..Default::default() };
+    notifier(settings).send(&event()).await.unwrap();
+    let written: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(written, serde_json::json!({
+      "name": "foo",
+      "registry": "crates.io",
+      "status": "available",
+    }));
+
+    let command = "exit 3".to_string();
+    let settings = NotificationSettings { notify_command: command, ..Default::default() };
+    assert!(notifier(settings).send(&event()).await.is_err());
+  }
+}
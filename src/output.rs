@@ -0,0 +1,846 @@
+//! Output formatting for CLI check results
+//!
+//! Every renderer returns a `String` so formats can be tested without
+//! network access or a terminal.
+
+use crate::compare::Candidate;
+use crate::registry::{Availability, AvailabilityResult, NameResults, RegistryType, TimedResult};
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+use std::fmt::Write;
+use std::io::IsTerminal;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+  /// Human-readable colored text
+  Plain,
+  /// Pretty-printed JSON
+  Json,
+  /// One JSON object per line, streamed as results complete
+  Jsonl,
+  /// `name,registry,available,error` rows
+  Csv,
+  /// GitHub-flavored Markdown table
+  Markdown,
+  /// GitHub Actions workflow commands, plus a table in the job summary
+  GhActions,
+}
+
+impl OutputFormat {
+  /// The format a file's extension asks for, e.g. CSV for `results.csv`;
+  /// plain text for anything else
+  pub fn for_path(path: &Path) -> Self {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    match extension.to_ascii_lowercase().as_str() {
+      "json" => OutputFormat::Json,
+      "jsonl" | "ndjson" => OutputFormat::Jsonl,
+      "csv" => OutputFormat::Csv,
+      "md" | "markdown" => OutputFormat::Markdown,
+      _ => OutputFormat::Plain,
+    }
+  }
+}
+
+/// Render package check results for one or more names
+pub fn format_checks(
+  format: OutputFormat,
+  checks: &[NameResults],
+  colors: Colors,
+) -> Result<String> {
+  Ok(match format {
+    OutputFormat::Plain => {
+      let mut out = match checks {
+        [check] => plain_details(check, colors),
+        _ => plain_matrix(checks, colors),
+      };
+      out.push_str(&plain_slowest(checks, colors));
+      out
+    }
+    OutputFormat::Json => serde_json::to_string_pretty(checks)? + "\n",
+    OutputFormat::Jsonl => checks
+      .iter()
+      .flat_map(|c| &c.results)
+      .map(|t| jsonl_result(&t.result, Some(t.duration_ms)))
+      .collect::<Result<String>>()?,
+    OutputFormat::Csv => csv(untimed(checks)),
+    OutputFormat::Markdown => markdown(untimed(checks)),
+    OutputFormat::GhActions => gh_actions(untimed(checks)),
+  })
+}
+
+/// Render domain check results
+pub fn format_domains(
+  format: OutputFormat,
+  name: &str,
+  results: &[AvailabilityResult],
+  colors: Colors,
+) -> Result<String> {
+  Ok(match format {
+    OutputFormat::Plain => plain_domains(name, results, colors),
+    OutputFormat::Json => serde_json::to_string_pretty(results)? + "\n",
+    OutputFormat::Jsonl => results
+      .iter()
+      .map(|r| jsonl_result(r, None))
+      .collect::<Result<String>>()?,
+    OutputFormat::Csv => csv(results),
+    OutputFormat::Markdown => markdown(results),
+    OutputFormat::GhActions => gh_actions(results),
+  })
+}
+
+/// Up to `top` names free on every checked registry, in input order, for
+/// `nbi check --file --out`
+pub fn format_available_everywhere(checks: &[NameResults], top: usize, colors: Colors) -> String {
+  let free: Vec<&str> = checks
+    .iter()
+    .filter(|c| {
+      let free = |t: &TimedResult| t.result.availability == Availability::Available;
+      !c.results.is_empty() && c.results.iter().all(free)
+    })
+    .map(|c| c.name.as_str())
+    .collect();
+  if free.is_empty() {
+    return format!("None of the {} names is available everywhere\n", checks.len());
+  }
+  let mut out = format!("{} of {} names available everywhere:\n", free.len(), checks.len());
+  for name in free.iter().take(top) {
+    let _ = writeln!(out, "  {} {}", colors.symbol(Availability::Available), name);
+  }
+  if free.len() > top {
+    let _ = writeln!(out, "  {}", colors.dim(&format!("... and {} more", free.len() - top)));
+  }
+  out
+}
+
+/// Render ranked candidates from `nbi compare`
+pub fn format_comparison(
+  format: OutputFormat,
+  candidates: &[Candidate],
+  colors: Colors,
+) -> Result<String> {
+  let all_results = || {
+    candidates
+      .iter()
+      .flat_map(|c| c.results.iter().map(|t| &t.result).chain(&c.domains))
+  };
+  Ok(match format {
+    OutputFormat::Plain => plain_comparison(candidates, colors),
+    OutputFormat::Json => serde_json::to_string_pretty(candidates)? + "\n",
+    OutputFormat::Jsonl => candidates
+      .iter()
+      .map(|c| Ok(serde_json::to_string(c)? + "\n"))
+      .collect::<Result<String>>()?,
+    OutputFormat::Csv => csv(all_results()),
+    OutputFormat::Markdown => markdown(all_results()),
+    OutputFormat::GhActions => gh_actions(all_results()),
+  })
+}
+
+/// Tally of results by availability
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ResultCounts {
+  pub available: usize,
+  pub taken: usize,
+  pub reserved: usize,
+  pub unknown: usize,
+}
+
+impl ResultCounts {
+  pub fn add(&mut self, availability: Availability) {
+    match availability {
+      Availability::Available => self.available += 1,
+      Availability::Taken => self.taken += 1,
+      Availability::Reserved => self.reserved += 1,
+      Availability::Unknown => self.unknown += 1,
+    }
+  }
+}
+
+/// One JSON Lines record
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum JsonLine<'a> {
+  Result {
+    #[serde(flatten)]
+    result: &'a AvailabilityResult,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    duration_ms: Option<u64>,
+  },
+  Summary {
+    #[serde(flatten)]
+    counts: &'a ResultCounts,
+    status: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    slowest: Option<Slowest<'a>>,
+  },
+}
+
+/// The slowest check of a run, as reported in summaries
+#[derive(Debug, Serialize)]
+pub struct Slowest<'a> {
+  pub registry: RegistryType,
+  pub name: &'a str,
+  pub duration_ms: u64,
+}
+
+impl<'a> From<&'a TimedResult> for Slowest<'a> {
+  fn from(timed: &'a TimedResult) -> Self {
+    let result = &timed.result;
+    Self { registry: result.registry, name: &result.name, duration_ms: timed.duration_ms }
+  }
+}
+
+/// A `{"type":"result",...}` line, newline included
+pub fn jsonl_result(result: &AvailabilityResult, duration_ms: Option<u64>) -> Result<String> {
+  Ok(serde_json::to_string(&JsonLine::Result { result, duration_ms })? + "\n")
+}
+
+/// The closing `{"type":"summary",...}` line, newline included
+///
+/// `slowest` is the run's slowest check, when checks were timed.
+pub fn jsonl_summary(
+  counts: &ResultCounts,
+  status: &str,
+  slowest: Option<&TimedResult>,
+) -> Result<String> {
+  let slowest = slowest.map(Slowest::from);
+  Ok(serde_json::to_string(&JsonLine::Summary { counts, status, slowest })? + "\n")
+}
+
+fn untimed(checks: &[NameResults]) -> impl Iterator<Item = &AvailabilityResult> {
+  checks.iter().flat_map(|c| &c.results).map(|t| &t.result)
+}
+
+pub fn status_symbol(availability: Availability) -> &'static str {
+  match availability {
+    Availability::Available => "✓",
+    Availability::Taken => "✗",
+    Availability::Reserved => "⊘",
+    Availability::Unknown => "?",
+  }
+}
+
+pub fn status_label(availability: Availability) -> &'static str {
+  match availability {
+    Availability::Available => "Available",
+    Availability::Taken => "Taken",
+    Availability::Reserved => "Reserved",
+    Availability::Unknown => "Unknown",
+  }
+}
+
+fn status_color(availability: Availability) -> &'static str {
+  match availability {
+    Availability::Available => "32",
+    Availability::Taken => "31",
+    Availability::Reserved => "34",
+    Availability::Unknown => "33",
+  }
+}
+
+/// When to emit ANSI colors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum ColorChoice {
+  /// Color when stdout is a terminal and `NO_COLOR` is unset
+  #[default]
+  Auto,
+  Always,
+  Never,
+}
+
+impl ColorChoice {
+  /// Whether stdout output should be colored
+  pub fn enabled(&self) -> bool {
+    match self {
+      ColorChoice::Always => true,
+      ColorChoice::Never => false,
+      ColorChoice::Auto => {
+        let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+        !no_color && std::io::stdout().is_terminal()
+      }
+    }
+  }
+}
+
+/// ANSI styling that collapses to plain text when colors are off
+#[derive(Debug, Clone, Copy)]
+pub struct Colors {
+  enabled: bool,
+}
+
+impl Colors {
+  pub fn new(enabled: bool) -> Self {
+    Self { enabled }
+  }
+
+  fn paint(&self, code: &str, text: &str) -> String {
+    if self.enabled {
+      format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+      text.to_string()
+    }
+  }
+
+  fn symbol(&self, availability: Availability) -> String {
+    self.paint(status_color(availability), status_symbol(availability))
+  }
+
+  fn status(&self, availability: Availability) -> String {
+    let text = format!("{} {}", status_symbol(availability), status_label(availability));
+    self.paint(status_color(availability), &text)
+  }
+
+  fn dim(&self, text: &str) -> String {
+    self.paint("2", text)
+  }
+}
+
+fn plain_details(check: &NameResults, colors: Colors) -> String {
+  let mut out = format!("Checking availability for: {}\n\n", check.name);
+  for TimedResult { result: r, duration_ms } in &check.results {
+    let _ = write!(
+      out,
+      "  {:<12} {}  {}",
+      r.registry.to_string(),
+      colors.status(r.availability),
+      colors.dim(&format!("{:>5}ms", duration_ms)),
+    );
+    if let Some(ref err) = r.error {
+      let _ = write!(out, " ({})", err);
+    }
+    if let Some(ref detail) = r.detail {
+      let _ = write!(out, " {}", colors.dim(detail));
+    }
+    out.push('\n');
+  }
+  out
+}
+
+/// A dim line naming the slowest check, when there was more than one
+fn plain_slowest(checks: &[NameResults], colors: Colors) -> String {
+  let results = || checks.iter().flat_map(|c| &c.results);
+  let Some(slowest) = crate::registry::slowest(results()).filter(|_| results().count() > 1) else {
+    return String::new();
+  };
+  let r = &slowest.result;
+  let check = match checks {
+    [_] => r.registry.to_string(),
+    _ => format!("{} on {}", r.name, r.registry),
+  };
+  format!("\n  {}\n", colors.dim(&format!("Slowest: {} ({}ms)", check, slowest.duration_ms)))
+}
+
+/// Names × registries matrix of ✓/✗/? cells
+///
+/// Columns cover every registry present in any row; cells removed by a
+/// result filter are left blank.
+fn plain_matrix(checks: &[NameResults], colors: Colors) -> String {
+  let mut registries: Vec<RegistryType> = Vec::new();
+  for r in untimed(checks) {
+    if !registries.contains(&r.registry) {
+      registries.push(r.registry);
+    }
+  }
+  let name_width = checks.iter().map(|c| c.name.chars().count()).max().unwrap_or(0).max(4);
+
+  let mut out = format!("  {:<name_width$}", "NAME");
+  for registry in &registries {
+    let _ = write!(out, "  {}", registry);
+  }
+  out.push('\n');
+
+  for check in checks {
+    let _ = write!(out, "  {:<name_width$}", check.name);
+    for registry in &registries {
+      let padding = registry.to_string().chars().count().saturating_sub(1);
+      let symbol = check
+        .results
+        .iter()
+        .find(|t| t.result.registry == *registry)
+        .map_or(" ".to_string(), |t| colors.symbol(t.result.availability));
+      let _ = write!(out, "  {}{:padding$}", symbol, "");
+    }
+    out.push('\n');
+  }
+  out
+}
+
+/// Registries × candidates matrix, best candidate in the first column
+///
+/// Domain rows are labelled by TLD, and a final row shows free counts.
+/// A comparison row: a registry or TLD label and each candidate's result
+/// for it, `None` where that candidate wasn't checked there
+pub type MatrixRow<'a> = (String, Vec<Option<&'a AvailabilityResult>>);
+
+/// Registries, then TLDs, against candidates, in first-seen order
+pub fn comparison_matrix(candidates: &[Candidate]) -> Vec<MatrixRow<'_>> {
+  let mut rows: Vec<MatrixRow> = Vec::new();
+  let mut cell = |label: String, column: usize, result| {
+    let index = match rows.iter().position(|(l, _)| *l == label) {
+      Some(index) => index,
+      None => {
+        rows.push((label, vec![None; candidates.len()]));
+        rows.len() - 1
+      }
+    };
+    rows[index].1[column] = Some(result);
+  };
+  for (column, candidate) in candidates.iter().enumerate() {
+    for t in &candidate.results {
+      cell(t.result.registry.to_string(), column, &t.result);
+    }
+    for r in &candidate.domains {
+      let tld = r.name.strip_prefix(&candidate.name).unwrap_or(&r.name);
+      cell(tld.to_string(), column, r);
+    }
+  }
+  rows
+}
+
+/// "available/checked", plus "+N" free domains when domains were checked
+pub fn free_count(candidate: &Candidate) -> String {
+  let summary = &candidate.summary;
+  let mut free = format!("{}/{}", summary.available, candidate.results.len());
+  if let Some(domains) = summary.domains_available {
+    let _ = write!(free, "+{}", domains);
+  }
+  free
+}
+
+fn plain_comparison(candidates: &[Candidate], colors: Colors) -> String {
+  let rows = comparison_matrix(candidates);
+
+  let label_width = rows.iter().map(|(l, _)| l.chars().count()).max().unwrap_or(0).max(8);
+  let widths: Vec<usize> = candidates.iter().map(|c| c.name.chars().count().max(5)).collect();
+
+  let mut out = format!("  {:<label_width$}", "REGISTRY");
+  for (candidate, width) in candidates.iter().zip(&widths) {
+    let _ = write!(out, "  {:<width$}", candidate.name);
+  }
+  out.push('\n');
+
+  for (label, cells) in &rows {
+    let _ = write!(out, "  {:<label_width$}", label);
+    for (result, width) in cells.iter().zip(&widths) {
+      let padding = width - 1;
+      let symbol = result.map_or(" ".to_string(), |r| colors.symbol(r.availability));
+      let _ = write!(out, "  {}{:padding$}", symbol, "");
+    }
+    out.push('\n');
+  }
+
+  let _ = write!(out, "  {:<label_width$}", "free");
+  for (candidate, width) in candidates.iter().zip(&widths) {
+    let _ = write!(out, "  {:<width$}", free_count(candidate));
+  }
+  out.push('\n');
+  out
+}
+
+fn plain_domains(name: &str, results: &[AvailabilityResult], colors: Colors) -> String {
+  let mut out = format!("Checking domain availability for: {}\n\n", name);
+  for r in results {
+    let _ = writeln!(out, "  {:<25} {}", r.name, colors.status(r.availability));
+  }
+  out
+}
+
+fn csv<'a>(results: impl IntoIterator<Item = &'a AvailabilityResult>) -> String {
+  let mut out = String::from("name,registry,available,error\n");
+  for r in results {
+    let available = match r.available() {
+      Some(true) => "true",
+      Some(false) => "false",
+      None => "",
+    };
+    let _ = writeln!(
+      out,
+      "{},{},{},{}",
+      csv_field(&r.name),
+      r.registry.id(),
+      available,
+      csv_field(r.error.as_deref().unwrap_or("")),
+    );
+  }
+  out
+}
+
+/// Quote a CSV field when it contains a delimiter, quote, or newline
+fn csv_field(value: &str) -> String {
+  if value.contains([',', '"', '\n', '\r']) {
+    format!("\"{}\"", value.replace('"', "\"\""))
+  } else {
+    value.to_string()
+  }
+}
+
+fn markdown<'a>(results: impl IntoIterator<Item = &'a AvailabilityResult>) -> String {
+  let mut out = String::from("| Name | Registry | Status | Error |\n|---|---|---|---|\n");
+  for r in results {
+    let _ = writeln!(
+      out,
+      "| {} | {} | {} {} | {} |",
+      markdown_cell(&r.name),
+      r.registry,
+      status_symbol(r.availability),
+      status_label(r.availability),
+      markdown_cell(r.error.as_deref().unwrap_or("")),
+    );
+  }
+  out
+}
+
+/// `::error::` for each taken or reserved result, `::warning::` for each failed check
+/// and `::notice::` for each name that's free everywhere it was checked
+fn gh_actions<'a>(results: impl IntoIterator<Item = &'a AvailabilityResult>) -> String {
+  let mut out = String::new();
+  let mut names: Vec<(&str, ResultCounts)> = Vec::new();
+  for r in results {
+    let (command, message) = match r.availability {
+      Availability::Available => ("", String::new()),
+      Availability::Taken => ("error", format!("{} is taken{}", r.name, on_registry(r))),
+      Availability::Reserved => {
+        let detail = r.detail.as_deref().unwrap_or("not open for registration");
+        ("error", format!("{} is reserved{}: {}", r.name, on_registry(r), detail))
+      }
+      Availability::Unknown => match (&r.error, &r.detail) {
+        (None, Some(detail)) => {
+          ("warning", format!("Review {}{}: {}", r.name, on_registry(r), detail))
+        }
+        (error, _) => {
+          let error = error.as_deref().unwrap_or("unknown error");
+          ("warning", format!("Couldn't check {}{}: {}", r.name, on_registry(r), error))
+        }
+      },
+    };
+    if !command.is_empty() {
+      let _ = writeln!(out, "::{}::{}", command, workflow_data(&message));
+    }
+    match names.iter_mut().find(|(name, _)| *name == r.name) {
+      Some((_, counts)) => counts.add(r.availability),
+      None => {
+        let mut counts = ResultCounts::default();
+        counts.add(r.availability);
+        names.push((&r.name, counts));
+      }
+    }
+  }
+  for (name, counts) in names {
+    if counts.taken == 0 && counts.reserved == 0 && counts.unknown == 0 {
+      let message = match counts.available {
+        1 => format!("{} is available", name),
+        n => format!("{} is available on all {} registries", name, n),
+      };
+      let _ = writeln!(out, "::notice::{}", workflow_data(&message));
+    }
+  }
+  out
+}
+
+/// " on npm", or nothing for a full domain like "foo.com"
+fn on_registry(r: &AvailabilityResult) -> String {
+  if r.registry == RegistryType::DevDomain && r.name.contains('.') {
+    String::new()
+  } else {
+    format!(" on {}", r.registry)
+  }
+}
+
+/// Escape a workflow command's message so newlines and `%` survive
+fn workflow_data(value: &str) -> String {
+  value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+/// Markdown for `$GITHUB_STEP_SUMMARY`: a heading with the verdict and the
+/// results table
+pub fn step_summary<'a>(
+  results: impl IntoIterator<Item = &'a AvailabilityResult>,
+  verdict: &str,
+) -> String {
+  format!("### Name availability: {}\n\n{}\n", verdict, markdown(results))
+}
+
+fn markdown_cell(value: &str) -> String {
+  value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const COLOR: Colors = Colors { enabled: true };
+
+  fn result(
+    registry: RegistryType,
+    available: Option<bool>,
+    error: Option<&str>,
+  ) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: "foo".to_string(),
+      availability: available.into(),
+      error: error.map(String::from),
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  fn timed(result: AvailabilityResult) -> TimedResult {
+    TimedResult {
+      result,
+      duration_ms: 120,
+    }
+  }
+
+  fn sample() -> Vec<NameResults> {
+    vec![NameResults {
+      name: "foo".to_string(),
+      results: vec![
+        timed(result(RegistryType::Npm, Some(false), None)),
+        timed(result(RegistryType::Crates, Some(true), None)),
+        timed(result(RegistryType::PyPi, None, Some("timeout, retry later"))),
+      ],
+    }]
+  }
+
+  #[test]
+  fn test_csv() {
+    let out = format_checks(OutputFormat::Csv, &sample(), COLOR).unwrap();
+    assert_eq!(
+      out,
+      "name,registry,available,error\n\
+       foo,npm,false,\n\
+       foo,crates,true,\n\
+       foo,pypi,,\"timeout, retry later\"\n"
+    );
+  }
+
+  #[test]
+  fn test_available_everywhere_lists_the_first_free_names() {
+    let check = |name: &str, available| NameResults {
+      name: name.to_string(),
+      results: vec![
+        timed(result(RegistryType::Npm, Some(true), None)),
+        timed(result(RegistryType::Crates, available, None)),
+      ],
+    };
+    let checks = [
+      check("foo", Some(true)),
+      check("bar", None),
+      check("baz", Some(true)),
+      check("qux", Some(true)),
+    ];
+    let out = format_available_everywhere(&checks, 2, Colors { enabled: false });
+    assert_eq!(out, "3 of 4 names available everywhere:\n  ✓ foo\n  ✓ baz\n  ... and 1 more\n");
+    let out = format_available_everywhere(&checks[1..2], 2, Colors { enabled: false });
+    assert_eq!(out, "None of the 1 names is available everywhere\n");
+  }
+
+  #[test]
+  fn test_format_for_path() {
+    assert_eq!(OutputFormat::for_path(Path::new("results.CSV")), OutputFormat::Csv);
+    assert_eq!(OutputFormat::for_path(Path::new("out.jsonl")), OutputFormat::Jsonl);
+    assert_eq!(OutputFormat::for_path(Path::new("results.txt")), OutputFormat::Plain);
+    assert_eq!(OutputFormat::for_path(Path::new("results")), OutputFormat::Plain);
+  }
+
+  #[test]
+  fn test_csv_field_escapes_quotes() {
+    assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+    assert_eq!(csv_field("plain"), "plain");
+  }
+
+  #[test]
+  fn test_markdown() {
+    let out = format_checks(OutputFormat::Markdown, &sample(), COLOR).unwrap();
+    assert_eq!(
+      out,
+      "| Name | Registry | Status | Error |\n\
+       |---|---|---|---|\n\
+       | foo | npm | ✗ Taken |  |\n\
+       | foo | crates.io | ✓ Available |  |\n\
+       | foo | PyPI | ? Unknown | timeout, retry later |\n"
+    );
+  }
+
+  #[test]
+  fn test_markdown_escapes_pipes() {
+    assert_eq!(markdown_cell("a|b"), "a\\|b");
+  }
+
+  #[test]
+  fn test_gh_actions_commands() {
+    let out = format_checks(OutputFormat::GhActions, &sample(), COLOR).unwrap();
+    assert_eq!(
+      out,
+      "::error::foo is taken on npm\n\
+       ::warning::Couldn't check foo on PyPI: timeout, retry later\n"
+    );
+
+    let free = vec![NameResults {
+      name: "foo".to_string(),
+      results: vec![
+        timed(result(RegistryType::Npm, Some(true), None)),
+        timed(result(RegistryType::Crates, Some(true), None)),
+      ],
+    }];
+    let out = format_checks(OutputFormat::GhActions, &free, COLOR).unwrap();
+    assert_eq!(out, "::notice::foo is available on all 2 registries\n");
+  }
+
+  #[test]
+  fn test_gh_actions_escapes_messages_and_names_domains() {
+    let mut taken = result(RegistryType::DevDomain, Some(false), None);
+    taken.name = "foo.com".to_string();
+    let failed = AvailabilityResult {
+      name: "foo.io".to_string(),
+      ..result(RegistryType::DevDomain, None, Some("50% lost\nretry"))
+    };
+    let out = format_domains(OutputFormat::GhActions, "foo", &[taken, failed], COLOR).unwrap();
+    assert_eq!(
+      out,
+      "::error::foo.com is taken\n::warning::Couldn't check foo.io: 50%25 lost%0Aretry\n"
+    );
+  }
+
+  #[test]
+  fn test_detail_follows_the_status() {
+    let screened = AvailabilityResult {
+      detail: Some("potential conflicts: 2".to_string()),
+      ..result(RegistryType::Trademark, None, None)
+    };
+    let checks =
+      vec![NameResults { name: "foo".to_string(), results: vec![timed(screened.clone())] }];
+    let out = format_checks(OutputFormat::Plain, &checks, Colors { enabled: false }).unwrap();
+    assert!(out.contains("? Unknown    120ms potential conflicts: 2\n"), "{}", out);
+    let out = gh_actions([&screened]);
+    assert!(out.starts_with("::warning::Review foo on Trademark: potential conflicts: 2\n"));
+  }
+
+  #[test]
+  fn test_step_summary() {
+    let checks = sample();
+    let out = step_summary(untimed(&checks), "taken");
+    assert!(out.starts_with("### Name availability: taken\n\n| Name | Registry |"));
+    assert!(out.contains("| foo | npm | ✗ Taken |  |\n"));
+  }
+
+  #[test]
+  fn test_json_is_array_keyed_by_name() {
+    let out = format_checks(OutputFormat::Json, &sample(), COLOR).unwrap();
+    let value: serde_json::Value = serde_json::from_str(&out).unwrap();
+    assert_eq!(value[0]["name"], "foo");
+    assert_eq!(value[0]["results"][1]["registry"], "crates");
+    assert_eq!(value[0]["results"][1]["duration_ms"], 120);
+  }
+
+  #[test]
+  fn test_plain_details_shows_dim_duration() {
+    let out = format_checks(OutputFormat::Plain, &sample(), COLOR).unwrap();
+    let npm_row = out.lines().find(|l| l.contains("npm")).unwrap();
+    assert!(npm_row.contains("\x1b[2m  120ms\x1b[0m"));
+  }
+
+  #[test]
+  fn test_plain_matrix_for_multiple_names() {
+    let mut checks = sample();
+    checks.push(NameResults {
+      name: "barbaz".to_string(),
+      ..checks[0].clone()
+    });
+    let out = format_checks(OutputFormat::Plain, &checks, COLOR).unwrap();
+    let header = out.lines().next().unwrap();
+    assert!(header.starts_with("  NAME    npm  crates.io  PyPI"));
+    assert_eq!(out.lines().count(), 5);
+    assert!(out.lines().last().unwrap().contains("Slowest: foo on PyPI (120ms)"));
+  }
+
+  #[test]
+  fn test_plain_matrix_leaves_filtered_cells_blank() {
+    let mut checks = sample();
+    checks.push(NameResults {
+      name: "bar".to_string(),
+      results: vec![timed(result(RegistryType::Crates, Some(true), None))],
+    });
+    let out = format_checks(OutputFormat::Plain, &checks, COLOR).unwrap();
+    let bar_row = out.lines().nth(2).unwrap();
+    assert!(bar_row.starts_with("  bar "));
+    assert!(bar_row.contains("\x1b[32m✓"));
+    assert!(!bar_row.contains("✗"));
+  }
+
+  #[test]
+  fn test_plain_without_color_has_no_escapes() {
+    let mut checks = sample();
+    let details = format_checks(OutputFormat::Plain, &checks, Colors::new(false)).unwrap();
+    assert!(!details.contains('\x1b'));
+    assert!(details.contains("✗ Taken    120ms"));
+
+    checks.push(NameResults {
+      name: "bar".to_string(),
+      ..checks[0].clone()
+    });
+    let matrix = format_checks(OutputFormat::Plain, &checks, Colors::new(false)).unwrap();
+    assert!(!matrix.contains('\x1b'));
+
+    let results = [result(RegistryType::DevDomain, Some(true), None)];
+    let domains = format_domains(OutputFormat::Plain, "foo", &results, Colors::new(false)).unwrap();
+    assert_eq!(domains.lines().last().unwrap().trim_end(), "  foo                       ✓ Available");
+  }
+
+  #[test]
+  fn test_jsonl_lines_are_tagged() {
+    let out = format_checks(OutputFormat::Jsonl, &sample(), COLOR).unwrap();
+    let lines: Vec<serde_json::Value> =
+      out.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+    assert_eq!(lines.len(), 3);
+    assert_eq!(lines[0]["type"], "result");
+    assert_eq!(lines[0]["registry"], "npm");
+    assert_eq!(lines[0]["duration_ms"], 120);
+
+    let mut counts = ResultCounts::default();
+    counts.add(Availability::Available);
+    counts.add(Availability::Unknown);
+    let slow = timed(result(RegistryType::PyPi, None, None));
+    let summary: serde_json::Value =
+      serde_json::from_str(&jsonl_summary(&counts, "unknown", Some(&slow)).unwrap()).unwrap();
+    assert_eq!(summary["type"], "summary");
+    assert_eq!(summary["available"], 1);
+    assert_eq!(summary["unknown"], 1);
+    assert_eq!(summary["status"], "unknown");
+    assert_eq!(summary["slowest"]["registry"], "pypi");
+    assert_eq!(summary["slowest"]["duration_ms"], 120);
+  }
+
+  #[test]
+  fn test_color_choice_overrides() {
+    assert!(ColorChoice::Always.enabled());
+    assert!(!ColorChoice::Never.enabled());
+  }
+
+  #[test]
+  fn test_plain_comparison_rows_and_free_counts() {
+    let mut checks = sample();
+    checks[0].name = "foo".to_string();
+    let domains = vec![AvailabilityResult {
+      name: "foo.com".to_string(),
+      ..result(RegistryType::DevDomain, Some(true), None)
+    }];
+    let candidates = [Candidate::new(checks.remove(0), Some(domains))];
+    let out = format_comparison(OutputFormat::Plain, &candidates, Colors::new(false)).unwrap();
+    let lines: Vec<&str> = out.lines().map(str::trim_end).collect();
+    assert_eq!(
+      lines,
+      [
+        "  REGISTRY   foo",
+        "  npm        ✗",
+        "  crates.io  ✓",
+        "  PyPI       ?",
+        "  .com       ✓",
+        "  free       1/3+1",
+      ]
+    );
+  }
+}
@@ -0,0 +1,408 @@
+//! Preflight checks and command construction for `nbi publish`
+
+use crate::registry::github::{ManifestType, PLACEHOLDER_DESCRIPTION};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use directories::BaseDirs;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Ok,
+  Warning,
+  Error,
+}
+
+/// Outcome of a single preflight check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+  pub severity: Severity,
+  pub message: String,
+}
+
+impl Finding {
+  fn ok(message: impl Into<String>) -> Self {
+    Self { severity: Severity::Ok, message: message.into() }
+  }
+
+  fn warning(message: impl Into<String>) -> Self {
+    Self { severity: Severity::Warning, message: message.into() }
+  }
+
+  fn error(message: impl Into<String>) -> Self {
+    Self { severity: Severity::Error, message: message.into() }
+  }
+}
+
+impl fmt::Display for Finding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    let symbol = match self.severity {
+      Severity::Ok => "✓",
+      Severity::Warning => "!",
+      Severity::Error => "✗",
+    };
+    write!(f, "  {} {}", symbol, self.message)
+  }
+}
+
+/// Name and description read from a manifest
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ManifestInfo {
+  pub name: Option<String>,
+  pub description: Option<String>,
+}
+
+/// Read the package name and description from manifest contents
+pub fn parse_manifest(manifest: ManifestType, content: &str) -> Result<ManifestInfo> {
+  let field = |value: Option<&str>| value.map(String::from);
+  Ok(match manifest {
+    ManifestType::Npm => {
+      let json: serde_json::Value = serde_json::from_str(content)?;
+      ManifestInfo {
+        name: field(json["name"].as_str()),
+        description: field(json["description"].as_str()),
+      }
+    }
+    ManifestType::Crates | ManifestType::PyPi => {
+      let table: toml::Table = toml::from_str(content)?;
+      let section = match manifest {
+        ManifestType::Crates => "package",
+        _ => "project",
+      };
+      let get = |key: &str| table.get(section).and_then(|s| s.get(key)).and_then(|v| v.as_str());
+      ManifestInfo {
+        name: field(get("name")),
+        description: field(get("description")),
+      }
+    }
+  })
+}
+
+/// Program that performs the publish and how to install it
+fn tool(manifest: ManifestType) -> (&'static str, &'static str) {
+  match manifest {
+    ManifestType::Npm => ("npm", "install Node.js from https://nodejs.org"),
+    ManifestType::Crates => ("cargo", "install Rust from https://rustup.rs"),
+    ManifestType::PyPi => ("python", "install Python 3, then `pip install build twine`"),
+  }
+}
+
+/// Find an executable in `PATH`
+pub fn find_on_path(program: &str) -> Option<PathBuf> {
+  let path = std::env::var_os("PATH")?;
+  std::env::split_paths(&path)
+    .map(|dir| dir.join(program))
+    .find(|candidate| candidate.is_file())
+}
+
+/// Run every preflight check for publishing from `dir`
+///
+/// `expected_name` is the name the user means to publish, if they said.
+pub fn preflight(manifest: ManifestType, dir: &Path, expected_name: Option<&str>) -> Vec<Finding> {
+  let mut findings = Vec::new();
+
+  let (program, hint) = tool(manifest);
+  match find_on_path(program) {
+    Some(path) => findings.push(Finding::ok(format!("{} found at {}", program, path.display()))),
+    None => {
+      findings.push(Finding::error(format!("{} not found on PATH; {}", program, hint)));
+      return findings;
+    }
+  }
+  if matches!(manifest, ManifestType::PyPi) && !python_module_available("twine") {
+    findings.push(Finding::error("twine is not installed; run `pip install twine`"));
+  }
+
+  findings.extend(check_manifest(manifest, dir, expected_name));
+  findings.push(check_auth(manifest, dir));
+  findings
+}
+
+/// Check the manifest exists and names the intended package
+pub fn check_manifest(
+  manifest: ManifestType,
+  dir: &Path,
+  expected_name: Option<&str>,
+) -> Vec<Finding> {
+  let path = dir.join(manifest.filename());
+  let info = std::fs::read_to_string(&path)
+    .with_context(|| format!("{} not found in {}", manifest.filename(), dir.display()))
+    .and_then(|content| {
+      parse_manifest(manifest, &content)
+        .with_context(|| format!("could not parse {}", path.display()))
+    });
+  let info = match info {
+    Ok(info) => info,
+    Err(e) => return vec![Finding::error(format!("{:#}", e))],
+  };
+
+  let mut findings = Vec::new();
+  match (info.name.as_deref(), expected_name) {
+    (None, _) => findings.push(Finding::error(format!("{} has no name", manifest.filename()))),
+    (Some(name), Some(expected)) if name != expected => findings.push(Finding::error(format!(
+      "{} names '{}', expected '{}'",
+      manifest.filename(),
+      name,
+      expected
+    ))),
+    (Some(name), _) => findings.push(Finding::ok(format!("{} names '{}'", manifest.filename(), name))),
+  }
+  if info.description.is_some_and(|d| d.starts_with(PLACEHOLDER_DESCRIPTION)) {
+    findings.push(Finding::warning(format!(
+      "{} still has the placeholder description from `nbi register`",
+      manifest.filename()
+    )));
+  }
+  findings
+}
+
+fn check_auth(manifest: ManifestType, dir: &Path) -> Finding {
+  match manifest {
+    ManifestType::Npm => {
+      let whoami = Command::new("npm").arg("whoami").current_dir(dir).output();
+      match whoami {
+        Ok(output) if output.status.success() => {
+          let user = String::from_utf8_lossy(&output.stdout);
+          Finding::ok(format!("logged in to npm as {}", user.trim()))
+        }
+        _ => Finding::error("not logged in to npm; run `npm login`"),
+      }
+    }
+    ManifestType::Crates => {
+      if cargo_token_present() {
+        Finding::ok("crates.io token found")
+      } else {
+        Finding::error("no crates.io token; run `cargo login`")
+      }
+    }
+    ManifestType::PyPi => {
+      let env_password = std::env::var_os("TWINE_PASSWORD").is_some_and(|v| !v.is_empty());
+      let pypirc = BaseDirs::new().is_some_and(|dirs| dirs.home_dir().join(".pypirc").is_file());
+      if env_password || pypirc {
+        Finding::ok("PyPI credentials found")
+      } else {
+        Finding::warning("no TWINE_PASSWORD or ~/.pypirc; twine will prompt unless keyring has a token")
+      }
+    }
+  }
+}
+
+fn python_module_available(module: &str) -> bool {
+  Command::new("python")
+    .args(["-m", module, "--version"])
+    .output()
+    .is_ok_and(|output| output.status.success())
+}
+
+/// Whether cargo has a crates.io token from the environment or credentials file
+fn cargo_token_present() -> bool {
+  if std::env::var_os("CARGO_REGISTRY_TOKEN").is_some_and(|v| !v.is_empty()) {
+    return true;
+  }
+  let cargo_home = std::env::var_os("CARGO_HOME")
+    .map(PathBuf::from)
+    .or_else(|| BaseDirs::new().map(|dirs| dirs.home_dir().join(".cargo")));
+  let Some(cargo_home) = cargo_home else {
+    return false;
+  };
+  ["credentials.toml", "credentials"]
+    .iter()
+    .filter_map(|file| std::fs::read_to_string(cargo_home.join(file)).ok())
+    .any(|content| credentials_have_token(&content))
+}
+
+fn credentials_have_token(content: &str) -> bool {
+  toml::from_str::<toml::Table>(content)
+    .ok()
+    .and_then(|table| table.get("registry")?.get("token")?.as_str().map(|t| !t.is_empty()))
+    .unwrap_or(false)
+}
+
+/// Who can install an npm package
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum NpmAccess {
+  Public,
+  Restricted,
+}
+
+/// Flags for the publishing tool; npm- and PyPI-only fields are ignored
+/// for other targets
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PublishOptions {
+  pub dry_run: bool,
+  /// npm one-time password for 2FA
+  pub otp: Option<String>,
+  /// npm dist-tag
+  pub tag: Option<String>,
+  pub access: Option<NpmAccess>,
+  /// twine repository from ~/.pypirc, e.g. `testpypi`
+  pub repository: Option<String>,
+  /// Forwarded verbatim to the publish command
+  pub extra: Vec<String>,
+}
+
+/// Commands run for a publish, in order
+pub fn publish_commands(manifest: ManifestType, options: &PublishOptions) -> Vec<Vec<String>> {
+  let argv = |args: &[&str]| args.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+  let flag = |command: &mut Vec<String>, name: &str, value: Option<&str>| {
+    if let Some(value) = value {
+      command.extend([name.to_string(), value.to_string()]);
+    }
+  };
+
+  match manifest {
+    ManifestType::Npm => {
+      let mut publish = argv(&["npm", "publish"]);
+      if options.dry_run {
+        publish.push("--dry-run".to_string());
+      }
+      flag(&mut publish, "--tag", options.tag.as_deref());
+      let access = options.access.map(|a| match a {
+        NpmAccess::Public => "public",
+        NpmAccess::Restricted => "restricted",
+      });
+      flag(&mut publish, "--access", access);
+      flag(&mut publish, "--otp", options.otp.as_deref());
+      publish.extend(options.extra.iter().cloned());
+      vec![publish]
+    }
+    ManifestType::Crates => {
+      let mut publish = argv(&["cargo", "publish"]);
+      if options.dry_run {
+        publish.push("--dry-run".to_string());
+      }
+      publish.extend(options.extra.iter().cloned());
+      vec![publish]
+    }
+    ManifestType::PyPi => {
+      // twine has no dry run, so a dry run validates the built files instead
+      let upload = if options.dry_run {
+        argv(&["python", "-m", "twine", "check"])
+      } else {
+        let mut upload = argv(&["python", "-m", "twine", "upload"]);
+        flag(&mut upload, "--repository", options.repository.as_deref());
+        upload.extend(options.extra.iter().cloned());
+        upload
+      };
+      vec![argv(&["python", "-m", "build"]), [upload, argv(&["dist/*"])].concat()]
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::github::ManifestFields;
+
+  #[test]
+  fn test_parse_manifests() {
+    let fields = ManifestFields::default();
+    let npm = ManifestType::Npm.generate_content("foo", "A foo", &fields);
+    let info = parse_manifest(ManifestType::Npm, &npm).unwrap();
+    assert_eq!(info.name.as_deref(), Some("foo"));
+    assert_eq!(info.description.as_deref(), Some("A foo"));
+
+    for manifest in [ManifestType::Crates, ManifestType::PyPi] {
+      let content = manifest.generate_content("bar", "A bar", &fields);
+      let info = parse_manifest(manifest, &content).unwrap();
+      assert_eq!(info.name.as_deref(), Some("bar"));
+    }
+  }
+
+  fn temp_project(manifest: ManifestType, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir()
+      .join(format!("nbi-publish-{}-{}", manifest.filename(), std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join(manifest.filename()), content).unwrap();
+    dir
+  }
+
+  #[test]
+  fn test_check_manifest_flags_mismatch_and_placeholder() {
+    let description = format!("{} Cargo.toml", PLACEHOLDER_DESCRIPTION);
+    let content =
+      ManifestType::Crates.generate_content("foo", &description, &ManifestFields::default());
+    let dir = temp_project(ManifestType::Crates, &content);
+
+    let findings = check_manifest(ManifestType::Crates, &dir, Some("bar"));
+    assert_eq!(findings[0].severity, Severity::Error);
+    assert!(findings[0].message.contains("expected 'bar'"));
+    assert_eq!(findings[1].severity, Severity::Warning);
+
+    let findings = check_manifest(ManifestType::Crates, &dir, Some("foo"));
+    assert_eq!(findings[0].severity, Severity::Ok);
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_check_manifest_missing_file() {
+    let dir = std::env::temp_dir().join("nbi-publish-missing");
+    let findings = check_manifest(ManifestType::Npm, &dir, None);
+    assert_eq!(findings.len(), 1);
+    assert!(findings[0].message.contains("package.json not found"));
+  }
+
+  #[test]
+  fn test_credentials_have_token() {
+    assert!(credentials_have_token("[registry]\ntoken = \"abc\"\n"));
+    assert!(!credentials_have_token("[registry]\ntoken = \"\"\n"));
+    assert!(!credentials_have_token("[net]\nretry = 2\n"));
+  }
+
+  #[test]
+  fn test_dry_run_commands() {
+    let dry_run = PublishOptions {
+      dry_run: true,
+      repository: Some("testpypi".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(publish_commands(ManifestType::Npm, &dry_run), [["npm", "publish", "--dry-run"]]);
+    let pypi = publish_commands(ManifestType::PyPi, &dry_run);
+    assert_eq!(pypi[1], ["python", "-m", "twine", "check", "dist/*"]);
+  }
+
+  #[test]
+  fn test_npm_flags() {
+    let options = PublishOptions {
+      otp: Some("123456".to_string()),
+      tag: Some("next".to_string()),
+      access: Some(NpmAccess::Public),
+      extra: vec!["--provenance".to_string()],
+      ..Default::default()
+    };
+    assert_eq!(
+      publish_commands(ManifestType::Npm, &options),
+      [["npm", "publish", "--tag", "next", "--access", "public", "--otp", "123456", "--provenance"]]
+    );
+  }
+
+  #[test]
+  fn test_crates_extra_args() {
+    let options = PublishOptions {
+      extra: vec!["--allow-dirty".to_string()],
+      otp: Some("ignored".to_string()),
+      ..Default::default()
+    };
+    assert_eq!(
+      publish_commands(ManifestType::Crates, &options),
+      [["cargo", "publish", "--allow-dirty"]]
+    );
+  }
+
+  #[test]
+  fn test_pypi_repository_and_extra_args() {
+    let options = PublishOptions {
+      repository: Some("testpypi".to_string()),
+      extra: vec!["--verbose".to_string()],
+      ..Default::default()
+    };
+    let commands = publish_commands(ManifestType::PyPi, &options);
+    assert_eq!(commands[0], ["python", "-m", "build"]);
+    assert_eq!(
+      commands[1],
+      ["python", "-m", "twine", "upload", "--repository", "testpypi", "--verbose", "dist/*"]
+    );
+  }
+}
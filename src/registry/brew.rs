@@ -1,40 +1,216 @@
-use super::{AvailabilityResult, RegistryType};
-use reqwest::StatusCode;
+use super::{
+  http_client, redirect_detail, request_error, trace_response, AvailabilityResult, RegistryType,
+};
+use reqwest::{header, StatusCode};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
+use std::time::Instant;
 
-const BREW_API_URL: &str = "https://formulae.brew.sh/api/formula";
+/// Default base URL, replaced by `endpoints.brew`
+pub const BREW_API_URL: &str = "https://formulae.brew.sh/api/formula";
+
+static TAPS: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Also look in `taps` (e.g. "hashicorp/tap") from now on; empty, the
+/// default, only checks Homebrew's own formulae and casks
+pub fn set_taps(taps: &[String]) {
+  *TAPS.write().unwrap_or_else(PoisonError::into_inner) = taps.to_vec();
+}
+
+/// The taps set by [`set_taps`]
+pub fn taps() -> Vec<String> {
+  TAPS.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+/// Old names and aliases of homebrew-core formulae, by `formula.json` URL,
+/// mapped to the detail naming the formula they point at
+///
+/// The list is several megabytes, so it's fetched once per run.
+static ALIASES: Mutex<BTreeMap<String, Arc<HashMap<String, String>>>> =
+  Mutex::new(BTreeMap::new());
+
+/// Where else a lookup found the name: `Some(detail)` when it's taken there
+type Lookup = Result<Option<String>, String>;
 
 /// Check if a formula name is available on Homebrew
 ///
 /// API: GET https://formulae.brew.sh/api/formula/{name}.json
 /// - 200: Formula exists (not available)
-/// - 404: Formula not found (available)
-pub async fn check(name: &str) -> AvailabilityResult {
-  let url = format!("{}/{}.json", BREW_API_URL, name);
-
-  match reqwest::get(&url).await {
-    Ok(response) => {
-      let available = match response.status() {
-        StatusCode::NOT_FOUND => Some(true),
-        StatusCode::OK => Some(false),
-        _ => None,
-      };
-      AvailabilityResult {
-        registry: RegistryType::Brew,
-        name: name.to_string(),
-        available,
-        error: if available.is_none() {
-          Some(format!("Unexpected status: {}", response.status()))
-        } else {
-          None
-        },
-      }
-    }
-    Err(e) => AvailabilityResult {
+/// - 404: Formula not found, so the name is then looked up as a cask, as
+///   an alias or old name of a formula, and in each of `taps`, and is
+///   only available if none of them has it
+///
+/// A name taken by something other than a homebrew-core formula says where
+/// in its detail.
+#[tracing::instrument(level = "debug", skip(token), fields(registry = "brew"))]
+pub async fn check(
+  name: &str,
+  base: &str,
+  taps: &[String],
+  github_api: &str,
+  token: Option<&str>,
+) -> AvailabilityResult {
+  let url = format!("{}/{}.json", base, name);
+
+  let started = Instant::now();
+  let response = http_client().get(&url).send().await;
+  trace_response(&url, started, &response);
+
+  let result = |available: Option<bool>, error: Option<String>, detail: Option<String>| {
+    AvailabilityResult {
       registry: RegistryType::Brew,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: available.into(),
+      error,
+      detail,
+      qualifier: None,
+    }
+  };
+
+  match response {
+    Ok(response) => match response.status() {
+      StatusCode::OK => result(Some(false), None, redirect_detail(&url, &response, name)),
+      StatusCode::NOT_FOUND => match find_elsewhere(name, base, taps, github_api, token).await {
+        Ok(None) => result(Some(true), None, None),
+        Ok(Some(detail)) => result(Some(false), None, Some(detail)),
+        Err(error) => result(None, Some(error), None),
+      },
+      status => result(None, Some(format!("Unexpected status: {}", status)), None),
     },
+    Err(e) => result(None, Some(request_error(e)), None),
+  }
+}
+
+/// Look for `name` outside homebrew-core's formulae, stopping at the first
+/// place that has it
+async fn find_elsewhere(
+  name: &str,
+  base: &str,
+  taps: &[String],
+  github_api: &str,
+  token: Option<&str>,
+) -> Lookup {
+  if let Some(detail) = find_cask(name, base).await? {
+    return Ok(Some(detail));
+  }
+  if let Some(detail) = find_alias(name, base).await? {
+    return Ok(Some(detail));
+  }
+  for tap in taps {
+    if let Some(detail) = find_in_tap(name, tap, github_api, token).await? {
+      return Ok(Some(detail));
+    }
+  }
+  Ok(None)
+}
+
+/// API: GET https://formulae.brew.sh/api/cask/{name}.json, next to the
+/// formula API; skipped for a `endpoints.brew` not ending in `/formula`
+async fn find_cask(name: &str, base: &str) -> Lookup {
+  let Some(api) = base.strip_suffix("/formula") else {
+    return Ok(None);
+  };
+  let url = format!("{}/cask/{}.json", api, name);
+
+  let started = Instant::now();
+  let response = http_client().get(&url).send().await;
+  trace_response(&url, started, &response);
+
+  match response.map_err(|e| format!("Cask lookup failed: {}", e))?.status() {
+    StatusCode::OK => Ok(Some(format!("cask {} in homebrew-cask", name))),
+    StatusCode::NOT_FOUND => Ok(None),
+    status => Err(format!("Unexpected status from the cask API: {}", status)),
+  }
+}
+
+/// One entry of `formula.json`, with only the names it answers to
+#[derive(Deserialize)]
+struct FormulaNames {
+  name: String,
+  #[serde(default)]
+  aliases: Vec<String>,
+  #[serde(default)]
+  oldnames: Vec<String>,
+  /// Older API versions have a single old name
+  #[serde(default)]
+  oldname: Option<String>,
+}
+
+/// API: GET https://formulae.brew.sh/api/formula.json, every formula with
+/// its aliases and old names (`formula_renames.json` in homebrew-core)
+async fn find_alias(name: &str, base: &str) -> Lookup {
+  let url = format!("{}.json", base);
+  let cached = ALIASES.lock().unwrap_or_else(PoisonError::into_inner).get(&url).cloned();
+  let aliases = match cached {
+    Some(aliases) => aliases,
+    None => {
+      let aliases = Arc::new(fetch_aliases(&url).await?);
+      let mut cache = ALIASES.lock().unwrap_or_else(PoisonError::into_inner);
+      cache.insert(url, aliases.clone());
+      aliases
+    }
+  };
+  Ok(aliases.get(name).cloned())
+}
+
+async fn fetch_aliases(url: &str) -> Result<HashMap<String, String>, String> {
+  let started = Instant::now();
+  let response = http_client().get(url).send().await;
+  trace_response(url, started, &response);
+
+  let response = response.map_err(|e| format!("Alias lookup failed: {}", e))?;
+  if response.status() != StatusCode::OK {
+    return Err(format!("Unexpected status from the formula list: {}", response.status()));
+  }
+  let formulae: Vec<FormulaNames> =
+    response.json().await.map_err(|e| format!("Parse error in the formula list: {}", e))?;
+
+  let mut aliases = HashMap::new();
+  for formula in formulae {
+    for alias in formula.aliases {
+      aliases.insert(alias, format!("alias of formula {}", formula.name));
+    }
+    for old in formula.oldnames.into_iter().chain(formula.oldname) {
+      aliases.insert(old, format!("old name of formula {}", formula.name));
+    }
+  }
+  Ok(aliases)
+}
+
+/// GitHub repo behind `tap`: "user/name" is `user/homebrew-name` and a bare
+/// "name" is `Homebrew/homebrew-name`, while a repo already called
+/// `homebrew-*` is used as is
+fn tap_repo(tap: &str) -> String {
+  let (user, repo) = tap.split_once('/').unwrap_or(("Homebrew", tap));
+  match repo.strip_prefix("homebrew-") {
+    Some(_) => format!("{}/{}", user, repo),
+    None => format!("{}/homebrew-{}", user, repo),
+  }
+}
+
+/// API: GET https://api.github.com/repos/{user}/homebrew-{tap}/contents/Formula/{name}.rb
+/// - 200: The tap has the formula (not available)
+/// - 404: It doesn't
+async fn find_in_tap(name: &str, tap: &str, github_api: &str, token: Option<&str>) -> Lookup {
+  let url = format!("{}/repos/{}/contents/Formula/{}.rb", github_api, tap_repo(tap), name);
+  let request = http_client()
+    .get(&url)
+    .header(header::USER_AGENT, "nbi/0.1.0")
+    .header(header::ACCEPT, "application/vnd.github+json");
+  let request = match token {
+    Some(token) => request.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+    None => request,
+  };
+
+  let started = Instant::now();
+  let response = request.send().await;
+  trace_response(&url, started, &response);
+
+  match response.map_err(|e| format!("Lookup in tap {} failed: {}", tap, e))?.status() {
+    StatusCode::OK => Ok(Some(format!("formula in tap {}", tap))),
+    StatusCode::NOT_FOUND => Ok(None),
+    status => Err(format!("Unexpected status from tap {}: {}", tap, status)),
   }
 }
 
@@ -43,14 +219,68 @@ mod tests {
   use super::*;
 
   #[tokio::test]
+  #[ignore = "live: queries formulae.brew.sh"]
   async fn test_check_existing_formula() {
-    let result = check("git").await;
-    assert_eq!(result.available, Some(false));
+    let result = check("git", BREW_API_URL, &[], "https://api.github.com", None).await;
+    assert_eq!(result.available(), Some(false));
   }
 
   #[tokio::test]
+  #[ignore = "live: queries formulae.brew.sh"]
   async fn test_check_nonexistent_formula() {
-    let result = check("this-formula-definitely-does-not-exist-xyz123abc").await;
-    assert_eq!(result.available, Some(true));
+    let name = "this-formula-definitely-does-not-exist-xyz123abc";
+    let result = check(name, BREW_API_URL, &[], "https://api.github.com", None).await;
+    assert_eq!(result.available(), Some(true));
+  }
+
+  #[test]
+  fn test_tap_repo() {
+    assert_eq!(tap_repo("hashicorp/tap"), "hashicorp/homebrew-tap");
+    assert_eq!(tap_repo("cask-fonts"), "Homebrew/homebrew-cask-fonts");
+    assert_eq!(tap_repo("acme/homebrew-tools"), "acme/homebrew-tools");
+  }
+
+  #[tokio::test]
+  async fn test_names_taken_outside_homebrew_core() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    let ok = || ResponseTemplate::new(200);
+    Mock::given(path("/api/formula.json"))
+      .respond_with(ok().set_body_json(serde_json::json!([
+        { "name": "python@3.12", "aliases": ["python3"], "oldnames": [] },
+        { "name": "podman", "aliases": [], "oldname": "podman-remote" },
+      ])))
+      .mount(&server)
+      .await;
+    Mock::given(path("/api/cask/firefox.json")).respond_with(ok()).mount(&server).await;
+    Mock::given(path("/repos/hashicorp/homebrew-tap/contents/Formula/terraform.rb"))
+      .respond_with(ok())
+      .mount(&server)
+      .await;
+
+    let base = format!("{}/api/formula", server.uri());
+    let taps = ["hashicorp/tap".to_string()];
+    let detail = |name: &'static str| {
+      let base = base.clone();
+      let (taps, api) = (taps.clone(), server.uri());
+      async move {
+        let result = check(name, &base, &taps, &api, None).await;
+        assert_eq!(result.error, None);
+        match result.available() {
+          Some(false) => result.detail,
+          _ => None,
+        }
+      }
+    };
+
+    assert_eq!(detail("firefox").await.as_deref(), Some("cask firefox in homebrew-cask"));
+    assert_eq!(detail("python3").await.as_deref(), Some("alias of formula python@3.12"));
+    assert_eq!(detail("podman-remote").await.as_deref(), Some("old name of formula podman"));
+    assert_eq!(detail("terraform").await.as_deref(), Some("formula in tap hashicorp/tap"));
+
+    let result = check("free-name", &base, &taps, &server.uri(), None).await;
+    assert_eq!(result.available(), Some(true));
   }
 }
@@ -1,7 +1,12 @@
-use super::{AvailabilityResult, RegistryType};
+use super::{
+  http_client, redirect_detail, request_error, trace_response, Availability, AvailabilityResult,
+  RegistryType,
+};
 use reqwest::StatusCode;
+use std::time::Instant;
 
-const CRATES_API_URL: &str = "https://crates.io/api/v1/crates";
+/// Default base URL, replaced by `endpoints.crates`
+pub const CRATES_API_URL: &str = "https://crates.io/api/v1/crates";
 
 /// Check if a crate name is available on crates.io
 ///
@@ -10,38 +15,49 @@ const CRATES_API_URL: &str = "https://crates.io/api/v1/crates";
 /// - 404: Crate not found (available)
 ///
 /// Note: crates.io requires a User-Agent header
-pub async fn check(name: &str) -> AvailabilityResult {
-  let url = format!("{}/{}", CRATES_API_URL, name);
+#[tracing::instrument(level = "debug", fields(registry = "crates"))]
+pub async fn check(name: &str, base: &str) -> AvailabilityResult {
+  let url = format!("{}/{}", base, name);
 
-  let client = reqwest::Client::new();
-  match client
+  let client = http_client();
+  let started = Instant::now();
+  let response = client
     .get(&url)
     .header("User-Agent", "nbi/0.1.0 (package-name-checker)")
     .send()
-    .await
-  {
+    .await;
+  trace_response(&url, started, &response);
+
+  match response {
     Ok(response) => {
       let available = match response.status() {
         StatusCode::NOT_FOUND => Some(true),
         StatusCode::OK => Some(false),
         _ => None,
       };
+      let detail = (available == Some(false))
+        .then(|| redirect_detail(&url, &response, name))
+        .flatten();
       AvailabilityResult {
         registry: RegistryType::Crates,
         name: name.to_string(),
-        available,
+        availability: available.into(),
         error: if available.is_none() {
           Some(format!("Unexpected status: {}", response.status()))
         } else {
           None
         },
+        detail,
+        qualifier: None,
       }
     }
     Err(e) => AvailabilityResult {
       registry: RegistryType::Crates,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: Availability::Unknown,
+      error: Some(request_error(e)),
+      detail: None,
+      qualifier: None,
     },
   }
 }
@@ -51,14 +67,36 @@ mod tests {
   use super::*;
 
   #[tokio::test]
+  #[ignore = "live: queries crates.io"]
   async fn test_check_existing_crate() {
-    let result = check("serde").await;
-    assert_eq!(result.available, Some(false));
+    let result = check("serde", CRATES_API_URL).await;
+    assert_eq!(result.available(), Some(false));
   }
 
   #[tokio::test]
+  #[ignore = "live: queries crates.io"]
   async fn test_check_nonexistent_crate() {
-    let result = check("this-crate-definitely-does-not-exist-xyz123abc").await;
-    assert_eq!(result.available, Some(true));
+    let result = check("this-crate-definitely-does-not-exist-xyz123abc", CRATES_API_URL).await;
+    assert_eq!(result.available(), Some(true));
+  }
+
+  #[tokio::test]
+  async fn test_check_against_configured_endpoint() {
+    use axum::{extract::Path, http::StatusCode, routing::get, Router};
+
+    let mirror = Router::new().route(
+      "/api/v1/crates/{name}",
+      get(|Path(name): Path<String>| async move {
+        if name == "taken" { StatusCode::OK } else { StatusCode::NOT_FOUND }
+      }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/api/v1/crates/", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, mirror).await.unwrap() });
+
+    let endpoints = crate::config::EndpointSettings { crates: url, ..Default::default() };
+    let base = endpoints.get(RegistryType::Crates).unwrap();
+    assert_eq!(check("taken", base).await.available(), Some(false));
+    assert_eq!(check("free", base).await.available(), Some(true));
   }
 }
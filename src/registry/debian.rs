@@ -1,7 +1,12 @@
-use super::{AvailabilityResult, RegistryType};
+use super::{
+  http_client, redirect_detail, request_error, trace_response, Availability, AvailabilityResult,
+  RegistryType,
+};
 use reqwest::StatusCode;
+use std::time::Instant;
 
-const DEBIAN_API_URL: &str = "https://sources.debian.org/api/src";
+/// Default base URL, replaced by `endpoints.debian`
+pub const DEBIAN_API_URL: &str = "https://sources.debian.org/api/src";
 
 /// Check if a package name is available on Debian
 ///
@@ -9,10 +14,15 @@ const DEBIAN_API_URL: &str = "https://sources.debian.org/api/src";
 /// - 200 with versions: Package exists (not available)
 /// - 200 with error: Package not found (available)
 /// - 404: Package not found (available)
-pub async fn check(name: &str) -> AvailabilityResult {
-  let url = format!("{}/{}/", DEBIAN_API_URL, name);
+#[tracing::instrument(level = "debug", fields(registry = "debian"))]
+pub async fn check(name: &str, base: &str) -> AvailabilityResult {
+  let url = format!("{}/{}/", base, name);
 
-  match reqwest::get(&url).await {
+  let started = Instant::now();
+  let response = http_client().get(&url).send().await;
+  trace_response(&url, started, &response);
+
+  match response {
     Ok(response) => {
       let status = response.status();
 
@@ -20,8 +30,10 @@ pub async fn check(name: &str) -> AvailabilityResult {
         return AvailabilityResult {
           registry: RegistryType::Debian,
           name: name.to_string(),
-          available: Some(true),
+          availability: Availability::Available,
           error: None,
+          detail: None,
+          qualifier: None,
         };
       }
 
@@ -29,11 +41,15 @@ pub async fn check(name: &str) -> AvailabilityResult {
         return AvailabilityResult {
           registry: RegistryType::Debian,
           name: name.to_string(),
-          available: None,
+          availability: Availability::Unknown,
           error: Some(format!("Unexpected status: {}", status)),
+          detail: None,
+          qualifier: None,
         };
       }
 
+      let redirected = redirect_detail(&url, &response, name);
+
       // Parse response - check if package has versions
       match response.json::<serde_json::Value>().await {
         Ok(json) => {
@@ -42,8 +58,10 @@ pub async fn check(name: &str) -> AvailabilityResult {
             return AvailabilityResult {
               registry: RegistryType::Debian,
               name: name.to_string(),
-              available: Some(true),
+              availability: Availability::Available,
               error: None,
+              detail: None,
+              qualifier: None,
             };
           }
 
@@ -51,28 +69,34 @@ pub async fn check(name: &str) -> AvailabilityResult {
           let has_versions = json
             .get("versions")
             .and_then(|v| v.as_array())
-            .map_or(false, |arr| !arr.is_empty());
+            .is_some_and(|arr| !arr.is_empty());
 
           AvailabilityResult {
             registry: RegistryType::Debian,
             name: name.to_string(),
-            available: Some(!has_versions),
+            availability: Some(!has_versions).into(),
             error: None,
+            detail: redirected.filter(|_| has_versions),
+            qualifier: None,
           }
         }
         Err(e) => AvailabilityResult {
           registry: RegistryType::Debian,
           name: name.to_string(),
-          available: None,
+          availability: Availability::Unknown,
           error: Some(format!("Parse error: {}", e)),
+          detail: None,
+          qualifier: None,
         },
       }
     }
     Err(e) => AvailabilityResult {
       registry: RegistryType::Debian,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: Availability::Unknown,
+      error: Some(request_error(e)),
+      detail: None,
+      qualifier: None,
     },
   }
 }
@@ -82,14 +106,38 @@ mod tests {
   use super::*;
 
   #[tokio::test]
+  #[ignore = "live: queries sources.debian.org"]
   async fn test_check_existing_package() {
-    let result = check("bash").await;
-    assert_eq!(result.available, Some(false));
+    let result = check("bash", DEBIAN_API_URL).await;
+    assert_eq!(result.available(), Some(false));
   }
 
   #[tokio::test]
+  #[ignore = "live: queries sources.debian.org"]
   async fn test_check_nonexistent_package() {
-    let result = check("this-package-definitely-does-not-exist-xyz123abc").await;
-    assert_eq!(result.available, Some(true));
+    let result = check("this-package-definitely-does-not-exist-xyz123abc", DEBIAN_API_URL).await;
+    assert_eq!(result.available(), Some(true));
+  }
+
+  #[tokio::test]
+  async fn test_trailing_slash_redirect_is_the_same_package() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(path("/api/src/bash/"))
+      .respond_with(ResponseTemplate::new(302).insert_header("Location", "/api/src/bash"))
+      .mount(&server)
+      .await;
+    Mock::given(path("/api/src/bash"))
+      .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+        "package": "bash",
+        "versions": [{"version": "5.2.15-2"}],
+      })))
+      .mount(&server)
+      .await;
+
+    let result = check("bash", &format!("{}/api/src", server.uri())).await;
+    assert_eq!((result.available(), result.detail), (Some(false), None));
   }
 }
@@ -1,7 +1,68 @@
-use super::{AvailabilityResult, RegistryType};
+use super::{Availability, AvailabilityResult, RegistryType};
+use futures::stream::{self, StreamExt};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::time::Instant;
 use trust_dns_resolver::config::{ResolverConfig, ResolverOpts};
+use trust_dns_resolver::error::ResolveError;
+use trust_dns_resolver::lookup_ip::LookupIp;
 use trust_dns_resolver::TokioAsyncResolver;
 
+/// Built-in TLD groups, referenced as `@name`
+pub const BUILTIN_TLD_GROUPS: &[(&str, &[&str])] = &[
+  ("common", &["com", "net", "org"]),
+  ("tech", &["io", "dev", "app", "sh", "rs", "tools", "codes", "ai"]),
+  ("country-eu", &["eu", "de", "fr", "nl", "it", "es", "se", "pl", "at", "be"]),
+];
+
+/// Error for an `@group` that is neither built in nor configured
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown TLD group '@{0}' (see `nbi domain --list-groups`)")]
+pub struct UnknownTldGroup(pub String);
+
+/// Parse a comma-separated TLD list, tolerating spaces and leading dots
+pub fn parse_tlds(list: &str) -> Vec<String> {
+  list
+    .split(',')
+    .map(|tld| tld.trim().trim_start_matches('.').to_lowercase())
+    .filter(|tld| !tld.is_empty())
+    .collect()
+}
+
+/// Expand `@group` entries and deduplicate, keeping first-seen order
+///
+/// Configured `groups` take precedence over built-ins with the same name.
+pub fn expand_tlds(
+  entries: &[String],
+  groups: &BTreeMap<String, Vec<String>>,
+) -> Result<Vec<String>, UnknownTldGroup> {
+  let mut tlds: Vec<String> = Vec::new();
+  for entry in entries {
+    let expanded = match entry.strip_prefix('@') {
+      Some(group) => match groups.get(group) {
+        Some(members) => members.clone(),
+        None => BUILTIN_TLD_GROUPS
+          .iter()
+          .find(|(name, _)| *name == group)
+          .map(|(_, members)| members.iter().map(|m| m.to_string()).collect())
+          .ok_or_else(|| UnknownTldGroup(group.to_string()))?,
+      },
+      None => vec![entry.clone()],
+    };
+    for tld in parse_tlds(&expanded.join(",")) {
+      if !tlds.contains(&tld) {
+        tlds.push(tld);
+      }
+    }
+  }
+  Ok(tlds)
+}
+
+/// Registrar search page for registering `domain`
+pub fn registrar_url(domain: &str) -> String {
+  format!("https://www.namecheap.com/domains/registration/results/?domain={}", domain)
+}
+
 /// Check if a .dev domain is potentially available
 ///
 /// Uses DNS lookup to check if the domain has any A records
@@ -10,21 +71,31 @@ pub async fn check(name: &str) -> AvailabilityResult {
 }
 
 /// Check if a domain with specific TLD is available
+#[tracing::instrument(level = "debug", fields(registry = "dev_domain"))]
 pub async fn check_tld(name: &str, tld: &str) -> AvailabilityResult {
   let domain = format!("{}.{}", name, tld);
+  if let Some(fixtures) = super::offline::fixtures() {
+    return fixtures.check(RegistryType::DevDomain, &domain);
+  }
 
   let resolver =
     TokioAsyncResolver::tokio(ResolverConfig::google(), ResolverOpts::default());
 
-  match resolver.lookup_ip(&domain).await {
+  let started = Instant::now();
+  let lookup = resolver.lookup_ip(&domain).await;
+  trace_lookup(&domain, started, &lookup);
+
+  match lookup {
     Ok(response) => {
       // If we get IP addresses, domain is taken (not available)
       let has_records = response.iter().count() > 0;
       AvailabilityResult {
         registry: RegistryType::DevDomain,
         name: domain,
-        available: Some(!has_records),
+        availability: Some(!has_records).into(),
         error: None,
+        detail: None,
+        qualifier: None,
       }
     }
     Err(e) => {
@@ -34,41 +105,92 @@ pub async fn check_tld(name: &str, tld: &str) -> AvailabilityResult {
         AvailabilityResult {
           registry: RegistryType::DevDomain,
           name: domain,
-          available: Some(true),
+          availability: Availability::Available,
           error: None,
+          detail: None,
+          qualifier: None,
         }
       } else {
         AvailabilityResult {
           registry: RegistryType::DevDomain,
           name: domain,
-          available: None,
+          availability: Availability::Unknown,
           error: Some(error_str),
+          detail: None,
+          qualifier: None,
         }
       }
     }
   }
 }
 
+/// Log the outcome and elapsed time of a DNS lookup
+fn trace_lookup(domain: &str, started: Instant, lookup: &Result<LookupIp, ResolveError>) {
+  let elapsed_ms = started.elapsed().as_millis() as u64;
+  match lookup {
+    Ok(response) => {
+      tracing::debug!(domain, records = response.iter().count(), elapsed_ms, "dns lookup succeeded")
+    }
+    Err(e) => tracing::debug!(domain, error = %e, elapsed_ms, "dns lookup failed"),
+  }
+}
+
+/// DNS lookups the multi-domain checks run at once
+const MAX_CONCURRENT_LOOKUPS: usize = 10;
+
+/// Run `lookup` on every domain or TLD, a bounded number at a time and
+/// under the shared [`super::throttle`], keeping input order
+///
+/// Taking owned strings keeps the futures free of borrowed lifetimes, which
+/// spawned tasks and axum handlers need.
+async fn bounded<F, Fut>(items: Vec<String>, mut lookup: F) -> Vec<AvailabilityResult>
+where
+  F: FnMut(String) -> Fut,
+  Fut: Future<Output = AvailabilityResult>,
+{
+  stream::iter(items)
+    .map(move |item| super::throttle().run(lookup(item)))
+    .buffered(MAX_CONCURRENT_LOOKUPS)
+    .collect()
+    .await
+}
+
 /// Check multiple TLDs at once
-pub async fn check_multiple_tlds(name: &str, tlds: &[&str]) -> Vec<AvailabilityResult> {
-  let futures: Vec<_> = tlds.iter().map(|tld| check_tld(name, tld)).collect();
-  futures::future::join_all(futures).await
+pub async fn check_multiple_tlds<S: AsRef<str>>(name: &str, tlds: &[S]) -> Vec<AvailabilityResult> {
+  let tlds: Vec<String> = tlds.iter().map(|tld| tld.as_ref().to_string()).collect();
+  bounded(tlds, |tld| async move { check_tld(name, &tld).await }).await
+}
+
+/// Check several full domains at once, in the order given
+pub async fn check_full_domains<S: AsRef<str>>(domains: &[S]) -> Vec<AvailabilityResult> {
+  let domains: Vec<String> = domains.iter().map(|domain| domain.as_ref().to_string()).collect();
+  bounded(domains, |domain| async move { check_full_domain(&domain).await }).await
 }
 
 /// Check a full domain (e.g., "banana.wiki")
+#[tracing::instrument(level = "debug", fields(registry = "dev_domain"))]
 pub async fn check_full_domain(domain: &str) -> AvailabilityResult {
+  if let Some(fixtures) = super::offline::fixtures() {
+    return fixtures.check(RegistryType::DevDomain, domain);
+  }
   let resolver =
     TokioAsyncResolver::tokio(ResolverConfig::google(), ResolverOpts::default());
 
-  match resolver.lookup_ip(domain).await {
+  let started = Instant::now();
+  let lookup = resolver.lookup_ip(domain).await;
+  trace_lookup(domain, started, &lookup);
+
+  match lookup {
     Ok(response) => {
       // If we get IP addresses, domain is taken (not available)
       let has_records = response.iter().count() > 0;
       AvailabilityResult {
         registry: RegistryType::DevDomain,
         name: domain.to_string(),
-        available: Some(!has_records),
+        availability: Some(!has_records).into(),
         error: None,
+        detail: None,
+        qualifier: None,
       }
     }
     Err(e) => {
@@ -77,15 +199,19 @@ pub async fn check_full_domain(domain: &str) -> AvailabilityResult {
         AvailabilityResult {
           registry: RegistryType::DevDomain,
           name: domain.to_string(),
-          available: Some(true),
+          availability: Availability::Available,
           error: None,
+          detail: None,
+          qualifier: None,
         }
       } else {
         AvailabilityResult {
           registry: RegistryType::DevDomain,
           name: domain.to_string(),
-          available: None,
+          availability: Availability::Unknown,
           error: Some(error_str),
+          detail: None,
+          qualifier: None,
         }
       }
     }
@@ -95,11 +221,76 @@ pub async fn check_full_domain(domain: &str) -> AvailabilityResult {
 #[cfg(test)]
 mod tests {
   use super::*;
+  use std::time::Duration;
+
+  #[tokio::test]
+  async fn test_bounded_runs_lookups_concurrently_in_order() {
+    let domains: Vec<String> = (0..20).map(|i| format!("name{}.com", i)).collect();
+    let started = Instant::now();
+    let results = bounded(domains.clone(), |domain| async move {
+      tokio::time::sleep(Duration::from_millis(100)).await;
+      AvailabilityResult {
+        registry: RegistryType::DevDomain,
+        name: domain,
+        availability: Availability::Available,
+        error: None,
+        detail: None,
+        qualifier: None,
+      }
+    })
+    .await;
+
+    // 20 sequential lookups would take 2s; 10 at a time take about 200ms
+    assert!(started.elapsed() < Duration::from_millis(1000), "{:?}", started.elapsed());
+    let names: Vec<_> = results.iter().map(|r| r.name.clone()).collect();
+    assert_eq!(names, domains);
+  }
+
+  #[test]
+  fn test_parse_tlds() {
+    assert_eq!(parse_tlds("com, .IO,,dev "), ["com", "io", "dev"]);
+    assert!(parse_tlds(" ").is_empty());
+  }
+
+  fn entries(list: &str) -> Vec<String> {
+    list.split(',').map(String::from).collect()
+  }
+
+  #[test]
+  fn test_registrar_url_searches_full_domain() {
+    assert_eq!(
+      registrar_url("foo.dev"),
+      "https://www.namecheap.com/domains/registration/results/?domain=foo.dev"
+    );
+  }
+
+  #[test]
+  fn test_expand_tlds_merges_groups_and_literals() {
+    let tlds = expand_tlds(&entries("@common,me,com,.ORG"), &BTreeMap::new()).unwrap();
+    assert_eq!(tlds, ["com", "net", "org", "me"]);
+  }
+
+  #[test]
+  fn test_expand_tlds_prefers_configured_groups() {
+    let groups = BTreeMap::from([
+      ("startup".to_string(), entries("io,dev,app")),
+      ("common".to_string(), entries("com")),
+    ]);
+    let tlds = expand_tlds(&entries("@startup,@common"), &groups).unwrap();
+    assert_eq!(tlds, ["io", "dev", "app", "com"]);
+  }
+
+  #[test]
+  fn test_expand_tlds_unknown_group() {
+    let err = expand_tlds(&entries("@nope"), &BTreeMap::new()).unwrap_err();
+    assert_eq!(err, UnknownTldGroup("nope".to_string()));
+  }
 
   #[tokio::test]
+  #[ignore = "live: queries public DNS"]
   async fn test_check_existing_domain() {
     // google.dev should exist
     let result = check("google").await;
-    assert_eq!(result.available, Some(false));
+    assert_eq!(result.available(), Some(false));
   }
 }
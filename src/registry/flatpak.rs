@@ -1,39 +1,49 @@
-use super::{AvailabilityResult, RegistryType};
+use super::{
+  http_client, request_error, trace_response, Availability, AvailabilityResult, RegistryType,
+};
 use reqwest::StatusCode;
+use std::time::Instant;
 
-const FLATHUB_API_URL: &str = "https://flathub.org/api/v1/apps";
+/// Default base URL, replaced by `endpoints.flatpak`
+pub const FLATHUB_API_URL: &str = "https://flathub.org/api/v1/apps";
 
 /// Check if an app name is available on Flathub (Flatpak)
 ///
 /// API: GET https://flathub.org/api/v1/apps
 /// Returns list of all apps; we check if name matches any app
-pub async fn check(name: &str) -> AvailabilityResult {
+#[tracing::instrument(level = "debug", fields(registry = "flatpak"))]
+pub async fn check(name: &str, base: &str) -> AvailabilityResult {
   // Try searching via the apps endpoint with query
-  let url = format!("{}/search/{}", FLATHUB_API_URL, name);
+  let url = format!("{}/search/{}", base, name);
 
-  let client = reqwest::Client::new();
-  match client
+  let client = http_client();
+  let started = Instant::now();
+  let response = client
     .get(&url)
     .header("Accept", "application/json")
     .header("User-Agent", "nbi/0.1.0")
     .send()
-    .await
-  {
+    .await;
+  trace_response(&url, started, &response);
+
+  match response {
     Ok(response) => {
       let status = response.status();
 
       // If search endpoint doesn't work, try checking if app exists directly
       if status == StatusCode::NOT_FOUND || status == StatusCode::METHOD_NOT_ALLOWED {
         // Try alternative: check apps list
-        return check_via_apps_list(name).await;
+        return check_via_apps_list(name, base).await;
       }
 
       if status != StatusCode::OK {
         return AvailabilityResult {
           registry: RegistryType::Flatpak,
           name: name.to_string(),
-          available: None,
+          availability: Availability::Unknown,
           error: Some(format!("Status: {}", status)),
+          detail: None,
+          qualifier: None,
         };
       }
 
@@ -55,46 +65,56 @@ pub async fn check(name: &str) -> AvailabilityResult {
           AvailabilityResult {
             registry: RegistryType::Flatpak,
             name: name.to_string(),
-            available: Some(!has_match),
+            availability: Some(!has_match).into(),
             error: None,
+            detail: None,
+            qualifier: None,
           }
         }
         Err(e) => AvailabilityResult {
           registry: RegistryType::Flatpak,
           name: name.to_string(),
-          available: None,
+          availability: Availability::Unknown,
           error: Some(format!("Parse error: {}", e)),
+          detail: None,
+          qualifier: None,
         },
       }
     }
     Err(e) => AvailabilityResult {
       registry: RegistryType::Flatpak,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: Availability::Unknown,
+      error: Some(request_error(e)),
+      detail: None,
+      qualifier: None,
     },
   }
 }
 
 /// Fallback: fetch apps list and search locally
-async fn check_via_apps_list(name: &str) -> AvailabilityResult {
-  let url = "https://flathub.org/api/v1/apps";
+async fn check_via_apps_list(name: &str, url: &str) -> AvailabilityResult {
 
-  let client = reqwest::Client::new();
-  match client
+  let client = http_client();
+  let started = Instant::now();
+  let response = client
     .get(url)
     .header("Accept", "application/json")
     .header("User-Agent", "nbi/0.1.0")
     .send()
-    .await
-  {
+    .await;
+  trace_response(url, started, &response);
+
+  match response {
     Ok(response) => {
       if response.status() != StatusCode::OK {
         return AvailabilityResult {
           registry: RegistryType::Flatpak,
           name: name.to_string(),
-          available: None,
+          availability: Availability::Unknown,
           error: Some(format!("Status: {}", response.status())),
+          detail: None,
+          qualifier: None,
         };
       }
 
@@ -113,23 +133,29 @@ async fn check_via_apps_list(name: &str) -> AvailabilityResult {
           AvailabilityResult {
             registry: RegistryType::Flatpak,
             name: name.to_string(),
-            available: Some(!has_match),
+            availability: Some(!has_match).into(),
             error: None,
+            detail: None,
+            qualifier: None,
           }
         }
         Err(e) => AvailabilityResult {
           registry: RegistryType::Flatpak,
           name: name.to_string(),
-          available: None,
+          availability: Availability::Unknown,
           error: Some(format!("Parse error: {}", e)),
+          detail: None,
+          qualifier: None,
         },
       }
     }
     Err(e) => AvailabilityResult {
       registry: RegistryType::Flatpak,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: Availability::Unknown,
+      error: Some(request_error(e)),
+      detail: None,
+      qualifier: None,
     },
   }
 }
@@ -139,15 +165,17 @@ mod tests {
   use super::*;
 
   #[tokio::test]
+  #[ignore = "live: queries flathub.org"]
   async fn test_check_existing_app() {
-    let result = check("firefox").await;
+    let result = check("firefox", FLATHUB_API_URL).await;
     // Firefox exists on Flathub
-    assert!(result.available == Some(false) || result.error.is_some());
+    assert!(result.available() == Some(false) || result.error.is_some());
   }
 
   #[tokio::test]
+  #[ignore = "live: queries flathub.org"]
   async fn test_check_nonexistent_app() {
-    let result = check("xyznonexistentapp123456").await;
-    assert!(result.available == Some(true) || result.error.is_some());
+    let result = check("xyznonexistentapp123456", FLATHUB_API_URL).await;
+    assert!(result.available() == Some(true) || result.error.is_some());
   }
 }
@@ -1,15 +1,82 @@
-use super::{AvailabilityResult, RegistryType};
+use super::{
+  http_client, request_error, trace_response, Availability, AvailabilityResult, RegistryType,
+};
+use chrono::{Datelike, Local, TimeZone};
 use reqwest::{header, StatusCode};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
 const GITHUB_API_URL: &str = "https://api.github.com";
 
+/// API base URL; `NBI_GITHUB_API_URL`, else `endpoints.github`, points nbi
+/// at GitHub Enterprise (`https://host/api/v3`) or a test server
+pub(crate) fn api_url() -> String {
+  match std::env::var("NBI_GITHUB_API_URL") {
+    Ok(url) if !url.trim().is_empty() => url.trim().trim_end_matches('/').to_string(),
+    _ => super::endpoint(RegistryType::GitHub, GITHUB_API_URL),
+  }
+}
+
+/// Description prefix of manifests generated for name reservations
+pub const PLACEHOLDER_DESCRIPTION: &str = "Reserved package name for";
+
 #[derive(Debug, Serialize)]
 struct CreateRepoRequest {
   name: String,
   description: Option<String>,
   private: bool,
   auto_init: bool,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  homepage: Option<String>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  has_issues: Option<bool>,
+  #[serde(skip_serializing_if = "Option::is_none")]
+  gitignore_template: Option<String>,
+}
+
+/// Options for a new repository
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepoOptions {
+  pub description: Option<String>,
+  pub private: bool,
+  /// Create the repo under this organization instead of the token's user
+  pub org: Option<String>,
+  pub homepage: Option<String>,
+  /// Enable issues; `None` keeps GitHub's default (enabled)
+  pub has_issues: Option<bool>,
+  /// GitHub .gitignore template, e.g. "Rust" or "Node"
+  pub gitignore_template: Option<String>,
+  /// Topics for every new repo; each manifest's registry is added on top
+  pub topics: Vec<String>,
+  /// Fields for the manifests committed to the repo
+  pub manifest: ManifestFields,
+}
+
+/// Author, license and user templates for generated manifests
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestFields {
+  pub author: String,
+  pub license: String,
+  /// Directory of templates named like the manifest, e.g. `Cargo.toml`
+  pub templates_dir: Option<PathBuf>,
+  /// Commit a README.md explaining the reservation to new repos
+  pub readme: bool,
+  /// Commit a LICENSE file matching `license` to new repos
+  pub license_file: bool,
+}
+
+impl Default for ManifestFields {
+  fn default() -> Self {
+    Self {
+      author: String::new(),
+      license: "MIT".to_string(),
+      templates_dir: None,
+      readme: true,
+      license_file: true,
+    }
+  }
 }
 
 #[derive(Debug, Deserialize)]
@@ -32,76 +99,156 @@ pub enum GitHubError {
   #[error("Invalid repository name")]
   InvalidName,
 
-  #[error("Rate limited")]
-  RateLimited,
+  /// `reset_at` is the unix time GitHub said to retry at, if it said
+  #[error(
+    "Rate limited{}",
+    .reset_at.map(|t| format!(" until {}", clock_time(t))).unwrap_or_default()
+  )]
+  RateLimited { reset_at: Option<u64> },
 
   #[error("API error: {0}")]
   ApiError(String),
 
   #[error("Network error: {0}")]
   NetworkError(#[from] reqwest::Error),
+
+  /// A new repo didn't become readable before the polling gave up
+  #[error("Repository still wasn't ready after {waited_secs}s")]
+  RepoNotReady { waited_secs: u64 },
+
+  /// The repo was created but its manifest couldn't be committed
+  #[error("Created {repo_url}, but committing the manifest failed: {source}")]
+  ManifestFailed {
+    /// "owner/name", e.g. for deleting the repo
+    full_name: String,
+    repo_url: String,
+    source: Box<GitHubError>,
+  },
 }
 
-/// Check if a GitHub user or organization name is available
-///
-/// API: GET https://api.github.com/users/{username}
-/// - 404: User/org not found (available)
-/// - 200: User/org exists (not available)
-pub async fn check_name(name: &str) -> AvailabilityResult {
-  let url = format!("{}/users/{}", GITHUB_API_URL, name);
+impl GitHubError {
+  /// Stable identifier for machine-readable errors, e.g. "repo_exists"
+  pub fn kind(&self) -> &'static str {
+    match self {
+      GitHubError::AuthRequired => "auth_required",
+      GitHubError::RepoExists => "repo_exists",
+      GitHubError::InvalidName => "invalid_name",
+      GitHubError::RateLimited { .. } => "rate_limited",
+      GitHubError::ApiError(_) => "api_error",
+      GitHubError::NetworkError(_) => "network_error",
+      GitHubError::RepoNotReady { .. } => "repo_not_ready",
+      GitHubError::ManifestFailed { .. } => "manifest_failed",
+    }
+  }
+}
 
-  let client = reqwest::Client::new();
-  match client
-    .get(&url)
-    .header(header::USER_AGENT, "nbi/0.1.0")
-    .header(header::ACCEPT, "application/vnd.github+json")
-    .send()
-    .await
-  {
-    Ok(response) => {
-      let available = match response.status() {
-        StatusCode::NOT_FOUND => Some(true),
-        StatusCode::OK => Some(false),
-        _ => None,
-      };
-      AvailabilityResult {
-        registry: RegistryType::GitHub,
-        name: name.to_string(),
-        available,
-        error: if available.is_none() {
-          Some(format!("Unexpected status: {}", response.status()))
-        } else {
-          None
-        },
-      }
+/// Progress state of one registration step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StepStatus {
+  Running,
+  Done,
+  Failed(String),
+}
+
+/// Label of the step after which a cancelled registration may leave a repo
+/// behind
+pub const CREATE_REPO_STEP: &str = "Creating repository";
+
+/// One reported registration step, e.g. "Creating repository"
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+  pub label: String,
+  pub status: StepStatus,
+}
+
+/// Reports registration steps over a channel; the default reports nothing
+#[derive(Debug, Clone, Default)]
+pub struct Progress(Option<mpsc::UnboundedSender<Step>>);
+
+impl Progress {
+  pub fn new(sender: mpsc::UnboundedSender<Step>) -> Self {
+    Self(Some(sender))
+  }
+
+  fn send(&self, label: &str, status: StepStatus) {
+    if let Some(sender) = &self.0 {
+      // The receiver going away just means nobody is watching anymore
+      let _ = sender.send(Step { label: label.to_string(), status });
     }
-    Err(e) => AvailabilityResult {
+  }
+
+  /// Run `step`, reporting it as running and then done or failed
+  pub async fn track<T>(
+    &self,
+    label: &str,
+    step: impl std::future::Future<Output = Result<T, GitHubError>>,
+  ) -> Result<T, GitHubError> {
+    self.send(label, StepStatus::Running);
+    let result = step.await;
+    match &result {
+      Ok(_) => self.send(label, StepStatus::Done),
+      Err(e) => self.send(label, StepStatus::Failed(e.to_string())),
+    }
+    result
+  }
+}
+
+/// Owner whose repos the GitHub check looks at, and the token to look with
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitHubAccount {
+  /// `github_username` from config, or the token's user
+  pub owner: Option<String>,
+  pub token: Option<String>,
+}
+
+/// Check if `{owner}/{name}` is free for the configured account
+///
+/// Without an owner there is nothing to check, so the result explains how
+/// to configure one.
+pub async fn check(name: &str, account: &GitHubAccount) -> AvailabilityResult {
+  match &account.owner {
+    Some(owner) => check_repo(owner, name, &api_url(), account.token.as_deref()).await,
+    None => AvailabilityResult {
       registry: RegistryType::GitHub,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: Availability::Unknown,
+      error: Some("Set github_username or a GitHub token to check repos".to_string()),
+      detail: None,
+      qualifier: None,
     },
   }
 }
 
-/// Check if a GitHub repository name is available for the authenticated user
+/// Check if a GitHub repository name is available for an owner
 ///
-/// API: GET https://api.github.com/repos/{owner}/{repo}
+/// API: GET https://api.github.com/repos/{owner}/{repo}, with `base` in
+/// place of `https://api.github.com`, unauthenticated (public repos only)
+/// when there is no token
 /// - 404: Repository not found (available)
 /// - 200: Repository exists (not available)
-#[allow(dead_code)]
-pub async fn check_repo(owner: &str, name: &str, token: &str) -> AvailabilityResult {
-  let url = format!("{}/repos/{}/{}", GITHUB_API_URL, owner, name);
+#[tracing::instrument(level = "debug", skip(token), fields(registry = "github"))]
+pub async fn check_repo(
+  owner: &str,
+  name: &str,
+  base: &str,
+  token: Option<&str>,
+) -> AvailabilityResult {
+  let url = format!("{}/repos/{}/{}", base, owner, name);
 
-  let client = reqwest::Client::new();
-  match client
+  let client = http_client();
+  let started = Instant::now();
+  let request = client
     .get(&url)
     .header(header::USER_AGENT, "nbi/0.1.0")
-    .header(header::AUTHORIZATION, format!("Bearer {}", token))
-    .header(header::ACCEPT, "application/vnd.github+json")
-    .send()
-    .await
-  {
+    .header(header::ACCEPT, "application/vnd.github+json");
+  let request = match token {
+    Some(token) => request.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+    None => request,
+  };
+  let response = request.send().await;
+  trace_response(&url, started, &response);
+
+  match response {
     Ok(response) => {
       let available = match response.status() {
         StatusCode::NOT_FOUND => Some(true),
@@ -110,52 +257,55 @@ pub async fn check_repo(owner: &str, name: &str, token: &str) -> AvailabilityRes
       };
       AvailabilityResult {
         registry: RegistryType::GitHub,
-        name: format!("{}/{}", owner, name),
-        available,
+        name: name.to_string(),
+        availability: available.into(),
         error: if available.is_none() {
           Some(format!("Unexpected status: {}", response.status()))
         } else {
           None
         },
+        detail: None,
+        qualifier: Some(owner.to_string()),
       }
     }
     Err(e) => AvailabilityResult {
       registry: RegistryType::GitHub,
-      name: format!("{}/{}", owner, name),
-      available: None,
-      error: Some(e.to_string()),
+      name: name.to_string(),
+      availability: Availability::Unknown,
+      error: Some(request_error(e)),
+      detail: None,
+      qualifier: Some(owner.to_string()),
     },
   }
 }
 
 /// Create a new GitHub repository
 ///
-/// API: POST https://api.github.com/user/repos
+/// API: POST https://api.github.com/user/repos (or /orgs/{org}/repos)
 /// Required scope: public_repo (for public) or repo (for private)
 pub async fn create_repo(
   name: &str,
-  description: Option<&str>,
-  private: bool,
+  options: &RepoOptions,
   token: &str,
+  progress: &Progress,
 ) -> Result<RepoResponse, GitHubError> {
-  let url = format!("{}/user/repos", GITHUB_API_URL);
+  let url = match options.org {
+    Some(ref org) => format!("{}/orgs/{}/repos", api_url(), org),
+    None => format!("{}/user/repos", api_url()),
+  };
 
   let request = CreateRepoRequest {
     name: name.to_string(),
-    description: description.map(String::from),
-    private,
+    description: options.description.clone(),
+    private: options.private,
     auto_init: true, // Create with README to initialize
+    homepage: options.homepage.clone(),
+    has_issues: options.has_issues,
+    gitignore_template: options.gitignore_template.clone(),
   };
 
   let client = reqwest::Client::new();
-  let response = client
-    .post(&url)
-    .header(header::USER_AGENT, "nbi/0.1.0")
-    .header(header::AUTHORIZATION, format!("Bearer {}", token))
-    .header(header::ACCEPT, "application/vnd.github+json")
-    .json(&request)
-    .send()
-    .await?;
+  let response = send_write(authed(client.post(&url), token).json(&request), progress).await?;
 
   match response.status() {
     StatusCode::CREATED => {
@@ -171,7 +321,7 @@ pub async fn create_repo(
         Err(GitHubError::InvalidName)
       }
     }
-    StatusCode::FORBIDDEN => Err(GitHubError::RateLimited),
+    StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => Err(rate_limited(&response)),
     _ => {
       let body = response.text().await.unwrap_or_default();
       Err(GitHubError::ApiError(body))
@@ -179,9 +329,52 @@ pub async fn create_repo(
   }
 }
 
-/// Get authenticated user's username
-pub async fn get_username(token: &str) -> Result<String, GitHubError> {
-  let url = format!("{}/user", GITHUB_API_URL);
+/// Who a token authenticates as, and what it may do
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInfo {
+  pub login: String,
+  /// OAuth scopes of a classic token; `None` for fine-grained tokens,
+  /// which don't report scopes
+  pub scopes: Option<Vec<String>>,
+}
+
+impl TokenInfo {
+  /// Warning if a classic token can't create a repo with this visibility
+  pub fn scope_warning(&self, private: bool) -> Option<String> {
+    let scopes = self.scopes.as_ref()?;
+    let has = |scope: &str| scopes.iter().any(|s| s == scope);
+    if has("repo") || (!private && has("public_repo")) {
+      return None;
+    }
+    let needed = if private { "repo" } else { "public_repo" };
+    Some(format!("token lacks {} scope", needed))
+  }
+
+  /// "scopes: repo, read:org", or a note that the token doesn't list any
+  pub fn scopes_label(&self) -> String {
+    match &self.scopes {
+      Some(scopes) if scopes.is_empty() => "no scopes".to_string(),
+      Some(scopes) => format!("scopes: {}", scopes.join(", ")),
+      None => "fine-grained token".to_string(),
+    }
+  }
+}
+
+/// Parse an `X-OAuth-Scopes` header such as "repo, read:org"
+fn parse_scopes(header: &str) -> Vec<String> {
+  header
+    .split(',')
+    .map(str::trim)
+    .filter(|s| !s.is_empty())
+    .map(str::to_string)
+    .collect()
+}
+
+/// Look up the token's user and scopes
+///
+/// API: GET https://api.github.com/user
+pub async fn token_info(token: &str) -> Result<TokenInfo, GitHubError> {
+  let url = format!("{}/user", api_url());
 
   let client = reqwest::Client::new();
   let response = client
@@ -192,21 +385,230 @@ pub async fn get_username(token: &str) -> Result<String, GitHubError> {
     .send()
     .await?;
 
-  if response.status() == StatusCode::UNAUTHORIZED {
-    return Err(GitHubError::AuthRequired);
+  match response.status() {
+    StatusCode::OK => {}
+    StatusCode::UNAUTHORIZED => return Err(GitHubError::AuthRequired),
+    StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => return Err(rate_limited(&response)),
+    _ => {
+      let body = response.text().await.unwrap_or_default();
+      return Err(GitHubError::ApiError(body));
+    }
   }
 
+  let scopes = response
+    .headers()
+    .get("x-oauth-scopes")
+    .and_then(|value| value.to_str().ok())
+    .map(parse_scopes);
+
   #[derive(Deserialize)]
   struct User {
     login: String,
   }
 
   let user: User = response.json().await?;
-  Ok(user.login)
+  Ok(TokenInfo { login: user.login, scopes })
+}
+
+/// Get authenticated user's username
+pub async fn get_username(token: &str) -> Result<String, GitHubError> {
+  Ok(token_info(token).await?.login)
+}
+
+/// Organizations the token's user belongs to
+///
+/// API: GET https://api.github.com/user/orgs (needs the read:org scope)
+pub async fn list_orgs(token: &str) -> Result<Vec<String>, GitHubError> {
+  let url = format!("{}/user/orgs", api_url());
+
+  let client = reqwest::Client::new();
+  let response = client
+    .get(&url)
+    .header(header::USER_AGENT, "nbi/0.1.0")
+    .header(header::AUTHORIZATION, format!("Bearer {}", token))
+    .header(header::ACCEPT, "application/vnd.github+json")
+    .send()
+    .await?;
+
+  match response.status() {
+    StatusCode::OK => {}
+    StatusCode::UNAUTHORIZED => return Err(GitHubError::AuthRequired),
+    StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => return Err(rate_limited(&response)),
+    _ => {
+      let body = response.text().await.unwrap_or_default();
+      return Err(GitHubError::ApiError(body));
+    }
+  }
+
+  #[derive(Deserialize)]
+  struct Org {
+    login: String,
+  }
+
+  let orgs: Vec<Org> = response.json().await?;
+  Ok(orgs.into_iter().map(|org| org.login).collect())
+}
+
+/// OAuth device-flow login for `nbi auth login --device`
+#[cfg(feature = "keyring")]
+pub mod device {
+  use super::GitHubError;
+  use reqwest::header;
+  use serde::Deserialize;
+  use std::time::{Duration, Instant};
+
+  const GITHUB_URL: &str = "https://github.com";
+
+  /// Scopes requested by device-flow login: repos plus org listing
+  pub const DEVICE_FLOW_SCOPES: &str = "repo read:org";
+
+  /// Code the user enters at `verification_uri` to authorize a device login
+  #[derive(Debug, Clone, Deserialize)]
+  pub struct DeviceCode {
+    pub device_code: String,
+    pub user_code: String,
+    pub verification_uri: String,
+    /// Seconds until `device_code` expires
+    pub expires_in: u64,
+    /// Minimum seconds between token polls
+    pub interval: u64,
+  }
+
+  /// Start a device authorization for an OAuth app
+  ///
+  /// API: POST https://github.com/login/device/code
+  pub async fn request_device_code(client_id: &str) -> Result<DeviceCode, GitHubError> {
+    let client = reqwest::Client::new();
+    let response = client
+      .post(format!("{}/login/device/code", GITHUB_URL))
+      .header(header::USER_AGENT, "nbi/0.1.0")
+      .header(header::ACCEPT, "application/json")
+      .query(&[("client_id", client_id), ("scope", DEVICE_FLOW_SCOPES)])
+      .send()
+      .await?;
+
+    if !response.status().is_success() {
+      let body = response.text().await.unwrap_or_default();
+      return Err(GitHubError::ApiError(body));
+    }
+    Ok(response.json().await?)
+  }
+
+  /// Body of a device token poll: a token or an `error` code
+  #[derive(Debug, Default, Deserialize)]
+  struct DeviceTokenResponse {
+    access_token: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+    interval: Option<u64>,
+  }
+
+  /// What to do after one device token poll
+  #[derive(Debug, PartialEq, Eq)]
+  enum DevicePoll {
+    Token(String),
+    /// Poll again after this many seconds
+    Wait(u64),
+    Failed(String),
+  }
+
+  fn device_poll_outcome(response: DeviceTokenResponse, interval: u64) -> DevicePoll {
+    if let Some(token) = response.access_token {
+      return DevicePoll::Token(token);
+    }
+    match response.error.as_deref() {
+      Some("authorization_pending") => DevicePoll::Wait(interval),
+      // GitHub asks for a longer interval, normally 5 seconds more
+      Some("slow_down") => DevicePoll::Wait(response.interval.unwrap_or(interval + 5)),
+      Some("expired_token") => DevicePoll::Failed("the code expired before it was entered".into()),
+      Some("access_denied") => DevicePoll::Failed("authorization was denied".into()),
+      error => DevicePoll::Failed(
+        response
+          .error_description
+          .or_else(|| error.map(str::to_string))
+          .unwrap_or_else(|| "unexpected response".to_string()),
+      ),
+    }
+  }
+
+  /// Poll until the user authorizes `device`, returning the access token
+  ///
+  /// API: POST https://github.com/login/oauth/access_token
+  pub async fn poll_device_token(
+    client_id: &str,
+    device: &DeviceCode,
+  ) -> Result<String, GitHubError> {
+    let client = reqwest::Client::new();
+    let deadline = Instant::now() + Duration::from_secs(device.expires_in);
+    let mut interval = device.interval;
+
+    loop {
+      tokio::time::sleep(Duration::from_secs(interval)).await;
+      if Instant::now() >= deadline {
+        return Err(GitHubError::ApiError("Device login expired; start again".to_string()));
+      }
+
+      let response = client
+        .post(format!("{}/login/oauth/access_token", GITHUB_URL))
+        .header(header::USER_AGENT, "nbi/0.1.0")
+        .header(header::ACCEPT, "application/json")
+        .query(&[
+          ("client_id", client_id),
+          ("device_code", device.device_code.as_str()),
+          ("grant_type", "urn:ietf:params:oauth:grant-type:device_code"),
+        ])
+        .send()
+        .await?;
+
+      match device_poll_outcome(response.json().await?, interval) {
+        DevicePoll::Token(token) => return Ok(token),
+        DevicePoll::Wait(next) => interval = next,
+        DevicePoll::Failed(reason) => {
+          return Err(GitHubError::ApiError(format!("Device login failed: {}", reason)));
+        }
+      }
+    }
+  }
+
+  #[cfg(test)]
+  mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_poll_outcomes() {
+      let response = |json: &str| serde_json::from_str::<DeviceTokenResponse>(json).unwrap();
+
+      assert_eq!(
+        device_poll_outcome(response(r#"{"access_token":"gho_x","token_type":"bearer"}"#), 5),
+        DevicePoll::Token("gho_x".to_string())
+      );
+      assert_eq!(
+        device_poll_outcome(response(r#"{"error":"authorization_pending"}"#), 5),
+        DevicePoll::Wait(5)
+      );
+      let slow_down = response(r#"{"error":"slow_down"}"#);
+      assert_eq!(device_poll_outcome(slow_down, 5), DevicePoll::Wait(10));
+      assert_eq!(
+        device_poll_outcome(response(r#"{"error":"slow_down","interval":15}"#), 5),
+        DevicePoll::Wait(15)
+      );
+      assert!(matches!(
+        device_poll_outcome(response(r#"{"error":"access_denied"}"#), 5),
+        DevicePoll::Failed(reason) if reason.contains("denied")
+      ));
+      assert_eq!(
+        device_poll_outcome(
+          response(r#"{"error":"incorrect_client_credentials","error_description":"bad id"}"#),
+          5
+        ),
+        DevicePoll::Failed("bad id".to_string())
+      );
+    }
+  }
 }
 
 /// Registry type for manifest generation
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ManifestType {
   Npm,
   Crates,
@@ -214,6 +616,24 @@ pub enum ManifestType {
 }
 
 impl ManifestType {
+  /// Manifest committed when reserving a name on `registry`, if any
+  pub fn for_registry(registry: RegistryType) -> Option<Self> {
+    match registry {
+      RegistryType::Npm => Some(ManifestType::Npm),
+      RegistryType::Crates => Some(ManifestType::Crates),
+      RegistryType::PyPi => Some(ManifestType::PyPi),
+      _ => None,
+    }
+  }
+
+  /// Description used for the repo and manifest: the given one or a placeholder
+  pub fn description(&self, options: &RepoOptions) -> String {
+    options
+      .description
+      .clone()
+      .unwrap_or_else(|| format!("{} {}", PLACEHOLDER_DESCRIPTION, self.filename()))
+  }
+
   pub fn filename(&self) -> &'static str {
     match self {
       ManifestType::Npm => "package.json",
@@ -222,62 +642,142 @@ impl ManifestType {
     }
   }
 
-  pub fn generate_content(&self, name: &str, description: &str) -> String {
+  /// Registry the manifest publishes to
+  pub fn registry(&self) -> RegistryType {
+    match self {
+      ManifestType::Npm => RegistryType::Npm,
+      ManifestType::Crates => RegistryType::Crates,
+      ManifestType::PyPi => RegistryType::PyPi,
+    }
+  }
+
+  /// Command that claims the name once the manifest is in place
+  pub fn publish_command(&self) -> &'static str {
     match self {
-      ManifestType::Npm => format!(
-        r#"{{
-  "name": "{}",
+      ManifestType::Npm => "npm publish",
+      ManifestType::Crates => "cargo publish",
+      ManifestType::PyPi => "twine upload",
+    }
+  }
+
+  /// Manifest contents from the user's template, or the built-in one
+  ///
+  /// Templates use `{{name}}`, `{{description}}`, `{{author}}` and
+  /// `{{license}}` placeholders; values are escaped for use inside quoted
+  /// JSON or TOML strings.
+  pub fn generate_content(&self, name: &str, description: &str, fields: &ManifestFields) -> String {
+    let template = fields
+      .templates_dir
+      .as_ref()
+      .and_then(|dir| std::fs::read_to_string(dir.join(self.filename())).ok());
+    let template = template.as_deref().unwrap_or_else(|| self.builtin_template(fields));
+
+    [
+      ("{{name}}", name),
+      ("{{description}}", description),
+      ("{{author}}", fields.author.as_str()),
+      ("{{license}}", fields.license.as_str()),
+    ]
+    .iter()
+    .fold(template.to_string(), |content, (placeholder, value)| {
+      content.replace(placeholder, &escape_string(value))
+    })
+  }
+
+  /// Built-in template; optional author fields are left out when unset
+  fn builtin_template(&self, fields: &ManifestFields) -> &'static str {
+    let has_author = !fields.author.is_empty();
+    match self {
+      ManifestType::Npm => NPM_TEMPLATE,
+      ManifestType::Crates if has_author => CRATES_TEMPLATE_WITH_AUTHOR,
+      ManifestType::Crates => CRATES_TEMPLATE,
+      ManifestType::PyPi if has_author => PYPI_TEMPLATE_WITH_AUTHOR,
+      ManifestType::PyPi => PYPI_TEMPLATE,
+    }
+  }
+}
+
+/// Escape a value for a double-quoted JSON or TOML string
+fn escape_string(value: &str) -> String {
+  let quoted = serde_json::to_string(value).unwrap_or_default();
+  quoted[1..quoted.len() - 1].to_string()
+}
+
+const NPM_TEMPLATE: &str = r#"{
+  "name": "{{name}}",
   "version": "0.0.1",
-  "description": "{}",
+  "description": "{{description}}",
   "main": "index.js",
-  "scripts": {{
+  "scripts": {
     "test": "echo \"Error: no test specified\" && exit 1"
-  }},
+  },
   "keywords": [],
-  "author": "",
-  "license": "MIT"
-}}
-"#,
-        name, description
-      ),
-      ManifestType::Crates => format!(
-        r#"[package]
-name = "{}"
+  "author": "{{author}}",
+  "license": "{{license}}"
+}
+"#;
+
+const CRATES_TEMPLATE: &str = r#"[package]
+name = "{{name}}"
 version = "0.0.1"
 edition = "2021"
-description = "{}"
-license = "MIT"
+description = "{{description}}"
+license = "{{license}}"
 
 [dependencies]
-"#,
-        name, description
-      ),
-      ManifestType::PyPi => format!(
-        r#"[build-system]
+"#;
+
+const CRATES_TEMPLATE_WITH_AUTHOR: &str = r#"[package]
+name = "{{name}}"
+version = "0.0.1"
+edition = "2021"
+authors = ["{{author}}"]
+description = "{{description}}"
+license = "{{license}}"
+
+[dependencies]
+"#;
+
+const PYPI_TEMPLATE: &str = r#"[build-system]
 requires = ["setuptools>=61.0"]
 build-backend = "setuptools.build_meta"
 
 [project]
-name = "{}"
+name = "{{name}}"
 version = "0.0.1"
-description = "{}"
+description = "{{description}}"
 readme = "README.md"
-license = {{text = "MIT"}}
+license = {text = "{{license}}"}
 requires-python = ">=3.8"
 classifiers = [
     "Programming Language :: Python :: 3",
-    "License :: OSI Approved :: MIT License",
     "Operating System :: OS Independent",
 ]
 
 [project.urls]
-Homepage = "https://github.com/OWNER/{}"
-"#,
-        name, description, name
-      ),
-    }
-  }
-}
+Homepage = "https://github.com/OWNER/{{name}}"
+"#;
+
+const PYPI_TEMPLATE_WITH_AUTHOR: &str = r#"[build-system]
+requires = ["setuptools>=61.0"]
+build-backend = "setuptools.build_meta"
+
+[project]
+name = "{{name}}"
+version = "0.0.1"
+description = "{{description}}"
+readme = "README.md"
+license = {text = "{{license}}"}
+authors = [{name = "{{author}}"}]
+requires-python = ">=3.8"
+classifiers = [
+    "Programming Language :: Python :: 3",
+    "Operating System :: OS Independent",
+]
+
+[project.urls]
+Homepage = "https://github.com/OWNER/{{name}}"
+"#;
 
 #[derive(Debug, Serialize)]
 struct CreateFileRequest {
@@ -299,7 +799,7 @@ pub async fn check_file_exists(
   path: &str,
   token: &str,
 ) -> Result<Option<String>, GitHubError> {
-  let url = format!("{}/repos/{}/{}/contents/{}", GITHUB_API_URL, owner, repo, path);
+  let url = format!("{}/repos/{}/{}/contents/{}", api_url(), owner, repo, path);
 
   let client = reqwest::Client::new();
   let response = client
@@ -332,10 +832,11 @@ pub async fn create_or_update_file(
   content: &str,
   message: &str,
   token: &str,
+  progress: &Progress,
 ) -> Result<(), GitHubError> {
   use base64::{Engine as _, engine::general_purpose::STANDARD};
   
-  let url = format!("{}/repos/{}/{}/contents/{}", GITHUB_API_URL, owner, repo, path);
+  let url = format!("{}/repos/{}/{}/contents/{}", api_url(), owner, repo, path);
   let encoded_content = STANDARD.encode(content);
 
   let request = CreateFileRequest {
@@ -345,18 +846,12 @@ pub async fn create_or_update_file(
   };
 
   let client = reqwest::Client::new();
-  let response = client
-    .put(&url)
-    .header(header::USER_AGENT, "nbi/0.1.0")
-    .header(header::AUTHORIZATION, format!("Bearer {}", token))
-    .header(header::ACCEPT, "application/vnd.github+json")
-    .json(&request)
-    .send()
-    .await?;
+  let response = send_write(authed(client.put(&url), token).json(&request), progress).await?;
 
   match response.status() {
     StatusCode::CREATED | StatusCode::OK => Ok(()),
     StatusCode::UNAUTHORIZED => Err(GitHubError::AuthRequired),
+    StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => Err(rate_limited(&response)),
     StatusCode::UNPROCESSABLE_ENTITY => {
       let body = response.text().await.unwrap_or_default();
       Err(GitHubError::ApiError(format!("File operation failed: {}", body)))
@@ -368,63 +863,856 @@ pub async fn create_or_update_file(
   }
 }
 
-/// Create a repository with manifest file for the specified registry
-pub async fn create_repo_with_manifest(
+/// A repository that manifests can be committed to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnsuredRepo {
+  pub owner: String,
+  pub name: String,
+  pub html_url: String,
+  /// False when the repository already existed and was reused
+  pub created: bool,
+}
+
+impl EnsuredRepo {
+  /// "owner/name"
+  pub fn full_name(&self) -> String {
+    format!("{}/{}", self.owner, self.name)
+  }
+
+  /// Wrap a failure to commit to this repo after creating it
+  pub fn manifest_failed(&self, source: GitHubError) -> GitHubError {
+    GitHubError::ManifestFailed {
+      full_name: self.full_name(),
+      repo_url: self.html_url.clone(),
+      source: Box::new(source),
+    }
+  }
+}
+
+/// What to do with a repo nbi just created when committing to it fails
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum OnFailure {
+  /// Leave the empty repo so the manifest can be added later
+  #[default]
+  Keep,
+  /// Delete the repo again (needs the delete_repo scope)
+  Delete,
+}
+
+impl OnFailure {
+  pub fn toggle(self) -> Self {
+    match self {
+      OnFailure::Keep => OnFailure::Delete,
+      OnFailure::Delete => OnFailure::Keep,
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      OnFailure::Keep => "keep the repo",
+      OnFailure::Delete => "delete the repo",
+    }
+  }
+}
+
+/// Topics for a reservation repo: `options.topics` plus each manifest's
+/// registry id, lowercased and without duplicates
+pub fn repo_topics(options: &RepoOptions, manifests: &[ManifestType]) -> Vec<String> {
+  let registries = manifests.iter().map(|m| m.registry().id().to_string());
+  let mut topics: Vec<String> = Vec::new();
+  for topic in options.topics.iter().cloned().chain(registries) {
+    let topic = topic.trim().to_lowercase().replace(' ', "-");
+    if !topic.is_empty() && !topics.contains(&topic) {
+      topics.push(topic);
+    }
+  }
+  topics
+}
+
+/// Replace a repository's topics
+///
+/// API: PUT https://api.github.com/repos/{owner}/{repo}/topics
+pub async fn set_topics(
+  full_name: &str,
+  topics: &[String],
+  token: &str,
+  progress: &Progress,
+) -> Result<(), GitHubError> {
+  let url = format!("{}/repos/{}/topics", api_url(), full_name);
+  let body = serde_json::json!({ "names": topics });
+  let request = authed(reqwest::Client::new().put(&url), token).json(&body);
+  check_status(send_write(request, progress).await?).await?;
+  Ok(())
+}
+
+/// Tag a new repo with [`repo_topics`]
+///
+/// Topics are only for discovery, so a failure shows up as a failed step
+/// without stopping the reservation.
+pub async fn add_topics(
+  full_name: &str,
+  manifests: &[ManifestType],
+  options: &RepoOptions,
+  token: &str,
+  progress: &Progress,
+) {
+  let topics = repo_topics(options, manifests);
+  if topics.is_empty() {
+    return;
+  }
+  let label = format!("Adding topics {}", topics.join(", "));
+  let _ = progress.track(&label, set_topics(full_name, &topics, token, progress)).await;
+}
+
+/// Delete a repository
+///
+/// API: DELETE https://api.github.com/repos/{owner}/{repo} (needs the
+/// delete_repo scope)
+pub async fn delete_repo(full_name: &str, token: &str) -> Result<(), GitHubError> {
+  let url = format!("{}/repos/{}", api_url(), full_name);
+  let response = authed(reqwest::Client::new().delete(&url), token).send().await?;
+  match response.status() {
+    StatusCode::NO_CONTENT => Ok(()),
+    StatusCode::FORBIDDEN => Err(GitHubError::ApiError(
+      "deleting needs a token with the delete_repo scope".to_string(),
+    )),
+    _ => check_status(response).await.map(|_| ()),
+  }
+}
+
+/// Create the repository, or reuse it if it already exists
+///
+/// The owner is resolved first so nothing can fail once the repo exists;
+/// pass `login` when the token's user is already known to skip a lookup.
+/// Call [`wait_until_ready`] before committing to a new repo.
+pub async fn ensure_repo(
   name: &str,
-  manifest_type: ManifestType,
+  options: &RepoOptions,
+  login: Option<&str>,
   token: &str,
-) -> Result<RepoResponse, GitHubError> {
-  let description = format!("Reserved package name for {}", manifest_type.filename());
-  
-  // First create the repo
-  let repo = create_repo(name, Some(&description), false, token).await?;
-  
-  // Get username for the owner
-  let username = get_username(token).await?;
-  
-  // Wait a moment for GitHub to initialize the repo
-  tokio::time::sleep(tokio::time::Duration::from_secs(2)).await;
-  
-  // Add manifest file
-  let manifest_content = manifest_type.generate_content(name, &description);
-  create_or_update_file(
-    &username,
-    name,
-    manifest_type.filename(),
-    &manifest_content,
-    &format!("Add {} for package reservation", manifest_type.filename()),
-    token,
-  ).await?;
-  
-  Ok(repo)
+  progress: &Progress,
+) -> Result<EnsuredRepo, GitHubError> {
+  // The owner is the org when one was given, otherwise the token's user
+  let owner = match (&options.org, login) {
+    (Some(org), _) => org.clone(),
+    (None, Some(login)) => login.to_string(),
+    (None, None) => progress.track("Fetching username", get_username(token)).await?,
+  };
+
+  let created = progress
+    .track(CREATE_REPO_STEP, async {
+      match create_repo(name, options, token, progress).await {
+        Ok(repo) => Ok(Some(repo)),
+        Err(GitHubError::RepoExists) => Ok(None),
+        Err(e) => Err(e),
+      }
+    })
+    .await?;
+
+  Ok(EnsuredRepo {
+    html_url: created
+      .as_ref()
+      .map_or_else(|| format!("https://github.com/{}/{}", owner, name), |r| r.html_url.clone()),
+    owner,
+    name: name.to_string(),
+    created: created.is_some(),
+  })
 }
 
-/// Add manifest to existing repository if it doesn't exist
-pub async fn add_manifest_if_missing(
-  owner: &str,
-  repo: &str,
+/// Pauses between readiness probes after creating a repo, about 10s in total
+const READY_BACKOFF_MS: [u64; 7] = [250, 500, 750, 1000, 1500, 2500, 3500];
+
+/// Run `probe` until it reports ready, sleeping through `delays` in between
+async fn poll_until_ready<F, Fut>(delays: &[Duration], mut probe: F) -> Result<(), GitHubError>
+where
+  F: FnMut() -> Fut,
+  Fut: std::future::Future<Output = Result<bool, GitHubError>>,
+{
+  let mut remaining = delays.iter();
+  loop {
+    if probe().await? {
+      return Ok(());
+    }
+    let Some(delay) = remaining.next() else {
+      let waited: Duration = delays.iter().sum();
+      return Err(GitHubError::RepoNotReady { waited_secs: waited.as_secs() });
+    };
+    tokio::time::sleep(*delay).await;
+  }
+}
+
+/// Wait until a newly created repo accepts commits
+///
+/// Commits 404 until GitHub has finished writing the initial README, so
+/// poll for it with backoff instead of guessing how long that takes.
+pub async fn wait_until_ready(
+  repo: &EnsuredRepo,
+  token: &str,
+  progress: &Progress,
+) -> Result<(), GitHubError> {
+  let delays = READY_BACKOFF_MS.map(Duration::from_millis);
+  let readme_exists = || async {
+    Ok(check_file_exists(&repo.owner, &repo.name, "README.md", token).await?.is_some())
+  };
+  progress
+    .track("Waiting for GitHub to initialize the repo", poll_until_ready(&delays, readme_exists))
+    .await
+}
+
+/// Commit a manifest to the repo unless one is already there
+///
+/// Returns whether the file was added.
+pub async fn add_manifest(
+  repo: &EnsuredRepo,
   manifest_type: ManifestType,
+  options: &RepoOptions,
   token: &str,
+  progress: &Progress,
 ) -> Result<bool, GitHubError> {
   let filename = manifest_type.filename();
-  
-  // Check if file already exists
-  if check_file_exists(owner, repo, filename, token).await?.is_some() {
-    return Ok(false); // File already exists
+  let commit = async {
+    if check_file_exists(&repo.owner, &repo.name, filename, token).await?.is_some() {
+      return Ok(false);
+    }
+
+    let description = manifest_type.description(options);
+    let content = manifest_type.generate_content(&repo.name, &description, &options.manifest);
+    create_or_update_file(
+      &repo.owner,
+      &repo.name,
+      filename,
+      &content,
+      &format!("Add {} for package reservation", filename),
+      token,
+      progress,
+    )
+    .await?;
+    Ok(true)
+  };
+
+  progress.track(&format!("Committing {}", filename), commit).await
+}
+
+/// A file committed to a reservation repo
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepoFile {
+  pub path: String,
+  pub content: String,
+}
+
+/// README explaining that the repo holds a name reservation
+pub fn reservation_readme(name: &str, options: &RepoOptions) -> String {
+  let mut readme = format!(
+    "# {name}\n\nThis name is reserved for {name}; development starts soon.\n"
+  );
+  if let Some(description) = &options.description {
+    readme.push_str(&format!("\n{}\n", description));
+  }
+  readme
+}
+
+/// Manifests plus the README and LICENSE for a new reservation repo
+///
+/// A LICENSE that can't be fetched (unknown id, or an expression such as
+/// "MIT OR Apache-2.0") is left out rather than failing the registration.
+pub async fn reservation_files(
+  repo: &EnsuredRepo,
+  manifests: &[ManifestType],
+  options: &RepoOptions,
+  token: &str,
+  progress: &Progress,
+) -> Vec<RepoFile> {
+  let fields = &options.manifest;
+  let mut files: Vec<RepoFile> = manifests
+    .iter()
+    .map(|m| RepoFile {
+      path: m.filename().to_string(),
+      content: m.generate_content(&repo.name, &m.description(options), fields),
+    })
+    .collect();
+
+  if fields.readme {
+    files.push(RepoFile {
+      path: "README.md".to_string(),
+      content: reservation_readme(&repo.name, options),
+    });
+  }
+
+  if fields.license_file && is_single_license(&fields.license) {
+    let holder = copyright_holder(&fields.author, &repo.owner);
+    let label = format!("Fetching {} license text", fields.license);
+    let text = progress.track(&label, license_text(&fields.license, holder, token)).await;
+    if let Ok(content) = text {
+      files.push(RepoFile { path: "LICENSE".to_string(), content });
+    }
+  }
+  files
+}
+
+/// Whether `spdx` names one license rather than an expression
+fn is_single_license(spdx: &str) -> bool {
+  !spdx.is_empty() && !spdx.contains([' ', '/', '(', ')'])
+}
+
+/// Name for the copyright line: the author without their email, or the owner
+fn copyright_holder<'a>(author: &'a str, owner: &'a str) -> &'a str {
+  let name = author.split('<').next().unwrap_or_default().trim();
+  if name.is_empty() {
+    owner
+  } else {
+    name
+  }
+}
+
+/// License text for an SPDX id, filled in with the year and holder
+///
+/// API: GET https://api.github.com/licenses/{key}
+pub async fn license_text(spdx: &str, holder: &str, token: &str) -> Result<String, GitHubError> {
+  let url = format!("{}/licenses/{}", api_url(), spdx.to_lowercase());
+  let response = authed(reqwest::Client::new().get(&url), token).send().await?;
+  if response.status() == StatusCode::NOT_FOUND {
+    return Err(GitHubError::ApiError(format!("GitHub has no license template for {}", spdx)));
+  }
+  let response = check_status(response).await?;
+
+  #[derive(Deserialize)]
+  struct License {
+    body: String,
+  }
+
+  let license: License = response.json().await?;
+  Ok(fill_license(&license.body, current_year(), holder))
+}
+
+/// Substitute the placeholders GitHub's license templates use
+fn fill_license(body: &str, year: i32, holder: &str) -> String {
+  let year = year.to_string();
+  body
+    .replace("[year]", &year)
+    .replace("[yyyy]", &year)
+    .replace("[fullname]", holder)
+    .replace("[name of copyright owner]", holder)
+}
+
+fn current_year() -> i32 {
+  Local::now().year()
+}
+
+/// Add the standard headers and token to an API request
+fn authed(request: reqwest::RequestBuilder, token: &str) -> reqwest::RequestBuilder {
+  request
+    .header(header::USER_AGENT, "nbi/0.1.0")
+    .header(header::AUTHORIZATION, format!("Bearer {}", token))
+    .header(header::ACCEPT, "application/vnd.github+json")
+}
+
+/// Pass successful responses through, mapping failures to [`GitHubError`]
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, GitHubError> {
+  match response.status() {
+    status if status.is_success() => Ok(response),
+    StatusCode::UNAUTHORIZED => Err(GitHubError::AuthRequired),
+    StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS => Err(rate_limited(&response)),
+    _ => Err(GitHubError::ApiError(response.text().await.unwrap_or_default())),
+  }
+}
+
+/// Longest rate-limit wait a write sits out before retrying; longer waits
+/// fail with the reset time instead
+const MAX_RATE_LIMIT_WAIT_SECS: u64 = 60;
+
+fn unix_now() -> u64 {
+  std::time::SystemTime::now()
+    .duration_since(std::time::UNIX_EPOCH)
+    .map_or(0, |d| d.as_secs())
+}
+
+/// When a rate limit lifts, as unix time: `Retry-After` seconds from now,
+/// or `x-ratelimit-reset` once no requests remain
+fn rate_limit_reset(headers: &header::HeaderMap, now: u64) -> Option<u64> {
+  let number = |name: &str| headers.get(name)?.to_str().ok()?.trim().parse::<u64>().ok();
+  if let Some(secs) = number("retry-after") {
+    return Some(now + secs);
+  }
+  match number("x-ratelimit-remaining") {
+    Some(0) => number("x-ratelimit-reset"),
+    _ => None,
+  }
+}
+
+fn rate_limited(response: &reqwest::Response) -> GitHubError {
+  GitHubError::RateLimited { reset_at: rate_limit_reset(response.headers(), unix_now()) }
+}
+
+/// "23:32 (UTC+09:00)" for a unix time, in the local time zone
+pub fn clock_time(unix: u64) -> String {
+  clock_time_in(&Local, unix)
+}
+
+/// [`clock_time`] in `zone`; the offset is spelled out since a server's
+/// errors may be read elsewhere
+fn clock_time_in<Tz: TimeZone>(zone: &Tz, unix: u64) -> String
+where
+  Tz::Offset: std::fmt::Display,
+{
+  match zone.timestamp_opt(unix as i64, 0).single() {
+    Some(time) => time.format("%H:%M (UTC%:z)").to_string(),
+    None => format!("unix time {}", unix),
+  }
+}
+
+/// Send a write request, retrying once if GitHub rate limits it for at
+/// most [`MAX_RATE_LIMIT_WAIT_SECS`]
+///
+/// Other rate-limited responses come back as-is for the caller to map.
+async fn send_write(
+  request: reqwest::RequestBuilder,
+  progress: &Progress,
+) -> Result<reqwest::Response, GitHubError> {
+  let retry = request.try_clone();
+  let response = request.send().await?;
+  if !matches!(response.status(), StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS) {
+    return Ok(response);
+  }
+  let now = unix_now();
+  let Some(reset_at) = rate_limit_reset(response.headers(), now) else {
+    return Ok(response);
+  };
+  let wait = reset_at.saturating_sub(now);
+  match retry {
+    Some(retry) if wait <= MAX_RATE_LIMIT_WAIT_SECS => {
+      let label = format!("Rate limited, waiting {}s", wait);
+      let sleep = async {
+        tokio::time::sleep(Duration::from_secs(wait)).await;
+        Ok(())
+      };
+      progress.track(&label, sleep).await?;
+      Ok(retry.send().await?)
+    }
+    _ => Err(GitHubError::RateLimited { reset_at: Some(reset_at) }),
+  }
+}
+
+#[derive(Deserialize)]
+struct ShaObject {
+  sha: String,
+}
+
+/// Commit several files to the default branch in a single commit
+///
+/// Uses the Git data API: read the branch head, build a tree on top of it,
+/// commit that tree and move the branch to the new commit.
+pub async fn commit_files(
+  repo: &EnsuredRepo,
+  files: &[RepoFile],
+  message: &str,
+  token: &str,
+  progress: &Progress,
+) -> Result<(), GitHubError> {
+  let paths: Vec<_> = files.iter().map(|f| f.path.as_str()).collect();
+  let label = format!("Committing {}", paths.join(", "));
+  progress.track(&label, commit_tree(repo, files, message, token, progress)).await
+}
+
+async fn commit_tree(
+  repo: &EnsuredRepo,
+  files: &[RepoFile],
+  message: &str,
+  token: &str,
+  progress: &Progress,
+) -> Result<(), GitHubError> {
+  let client = reqwest::Client::new();
+  let base = format!("{}/repos/{}/{}", api_url(), repo.owner, repo.name);
+
+  #[derive(Deserialize)]
+  struct RepoInfo {
+    default_branch: String,
+  }
+  #[derive(Deserialize)]
+  struct Ref {
+    object: ShaObject,
+  }
+  #[derive(Deserialize)]
+  struct Commit {
+    tree: ShaObject,
+  }
+
+  let info: RepoInfo = get_json(&client, &base, token).await?;
+  let ref_url = format!("{}/git/refs/heads/{}", base, info.default_branch);
+  let parent = get_json::<Ref>(&client, &ref_url, token).await?.object.sha;
+  let commit_url = format!("{}/git/commits/{}", base, parent);
+  let base_tree = get_json::<Commit>(&client, &commit_url, token).await?.tree.sha;
+
+  let entries: Vec<_> = files
+    .iter()
+    .map(|f| {
+      serde_json::json!({ "path": f.path, "mode": "100644", "type": "blob", "content": f.content })
+    })
+    .collect();
+  let tree_request = serde_json::json!({ "base_tree": base_tree, "tree": entries });
+  let trees_url = format!("{}/git/trees", base);
+  let tree: ShaObject = post_json(&client, &trees_url, &tree_request, token, progress).await?;
+
+  let commit_request = serde_json::json!({
+    "message": message,
+    "tree": tree.sha,
+    "parents": [parent],
+  });
+  let commit: ShaObject =
+    post_json(&client, &format!("{}/git/commits", base), &commit_request, token, progress).await?;
+
+  let update = serde_json::json!({ "sha": commit.sha });
+  let request = authed(client.patch(&ref_url), token).json(&update);
+  check_status(send_write(request, progress).await?).await?;
+  Ok(())
+}
+
+async fn get_json<T: serde::de::DeserializeOwned>(
+  client: &reqwest::Client,
+  url: &str,
+  token: &str,
+) -> Result<T, GitHubError> {
+  let response = authed(client.get(url), token).send().await?;
+  Ok(check_status(response).await?.json().await?)
+}
+
+async fn post_json<T: serde::de::DeserializeOwned>(
+  client: &reqwest::Client,
+  url: &str,
+  body: &serde_json::Value,
+  token: &str,
+  progress: &Progress,
+) -> Result<T, GitHubError> {
+  let response = send_write(authed(client.post(url), token).json(body), progress).await?;
+  Ok(check_status(response).await?.json().await?)
+}
+
+/// Create a new repository with the manifest for the specified registry
+///
+/// The manifest, README and LICENSE go in as a single commit. Fails with
+/// [`GitHubError::RepoExists`] if the repo is already there; failures after
+/// the repo exists are returned as [`GitHubError::ManifestFailed`].
+pub async fn create_repo_with_manifest(
+  name: &str,
+  manifest_type: ManifestType,
+  options: &RepoOptions,
+  token: &str,
+  progress: &Progress,
+) -> Result<EnsuredRepo, GitHubError> {
+  let options = RepoOptions {
+    description: Some(manifest_type.description(options)),
+    ..options.clone()
+  };
+
+  let repo = ensure_repo(name, &options, None, token, progress).await?;
+  if !repo.created {
+    return Err(GitHubError::RepoExists);
+  }
+
+  let commit = async {
+    wait_until_ready(&repo, token, progress).await?;
+    add_topics(&repo.full_name(), &[manifest_type], &options, token, progress).await;
+    let files = reservation_files(&repo, &[manifest_type], &options, token, progress).await;
+    let message = format!("Reserve {} with {}", name, manifest_type.filename());
+    commit_files(&repo, &files, &message, token, progress).await
+  };
+  match commit.await {
+    Ok(_) => Ok(repo),
+    Err(e) => Err(repo.manifest_failed(e)),
+  }
+}
+
+/// Reserve `name` with a new repo: with the manifest for `manifest_type`,
+/// or a bare repo when reserving the GitHub name itself
+///
+/// Returns the repo's URL.
+pub async fn reserve(
+  name: &str,
+  manifest_type: Option<ManifestType>,
+  options: &RepoOptions,
+  token: &str,
+  progress: &Progress,
+) -> Result<String, GitHubError> {
+  if let Some(manifest_type) = manifest_type {
+    let repo = create_repo_with_manifest(name, manifest_type, options, token, progress).await?;
+    return Ok(repo.html_url);
+  }
+  let repo = create_repo(name, options, token, progress).await?;
+  add_topics(&repo.full_name, &[], options, token, progress).await;
+  Ok(repo.html_url)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[tokio::test]
+  async fn test_progress_reports_running_then_outcome() {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let progress = Progress::new(tx);
+
+    progress.track("ok", async { Ok(()) }).await.unwrap();
+    let failed = async { Err::<(), _>(GitHubError::RateLimited { reset_at: None }) };
+    let _ = progress.track("bad", failed).await;
+    drop(progress);
+
+    let mut steps = Vec::new();
+    while let Some(step) = rx.recv().await {
+      steps.push((step.label, step.status));
+    }
+    assert_eq!(
+      steps,
+      [
+        ("ok".to_string(), StepStatus::Running),
+        ("ok".to_string(), StepStatus::Done),
+        ("bad".to_string(), StepStatus::Running),
+        ("bad".to_string(), StepStatus::Failed("Rate limited".to_string())),
+      ]
+    );
+  }
+
+  #[test]
+  fn test_builtin_manifests_use_author_and_license() {
+    let fields = ManifestFields {
+      author: "Jane Doe <jane@example.com>".to_string(),
+      license: "Apache-2.0".to_string(),
+      ..ManifestFields::default()
+    };
+    let npm = ManifestType::Npm.generate_content("foo", "A \"quoted\" tool", &fields);
+    let json: serde_json::Value = serde_json::from_str(&npm).unwrap();
+    assert_eq!(json["author"], "Jane Doe <jane@example.com>");
+    assert_eq!(json["license"], "Apache-2.0");
+    assert_eq!(json["description"], "A \"quoted\" tool");
+
+    let cargo: toml::Table =
+      toml::from_str(&ManifestType::Crates.generate_content("foo", "A foo", &fields)).unwrap();
+    assert_eq!(cargo["package"]["authors"][0].as_str(), Some("Jane Doe <jane@example.com>"));
+    assert_eq!(cargo["package"]["license"].as_str(), Some("Apache-2.0"));
+
+    let pyproject: toml::Table =
+      toml::from_str(&ManifestType::PyPi.generate_content("foo", "A foo", &fields)).unwrap();
+    assert_eq!(pyproject["project"]["license"]["text"].as_str(), Some("Apache-2.0"));
+
+    // No author means no authors entry rather than an empty one
+    let cargo = ManifestType::Crates.generate_content("foo", "A foo", &ManifestFields::default());
+    assert!(!cargo.contains("authors"));
+    assert!(cargo.contains("license = \"MIT\""));
+  }
+
+  #[test]
+  fn test_manifest_templates_render_and_fall_back() {
+    let dir = std::env::temp_dir().join(format!("nbi-templates-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(
+      dir.join("Cargo.toml"),
+      "[package]\nname = \"{{name}}\"\ndescription = \"{{description}}\"\n\
+       authors = [\"{{author}}\"]\nlicense = \"{{license}}\"\n",
+    )
+    .unwrap();
+    let fields = ManifestFields {
+      author: "Me".to_string(),
+      license: "MIT OR Apache-2.0".to_string(),
+      templates_dir: Some(dir.clone()),
+      ..ManifestFields::default()
+    };
+
+    assert_eq!(
+      ManifestType::Crates.generate_content("foo", "A foo", &fields),
+      "[package]\nname = \"foo\"\ndescription = \"A foo\"\nauthors = [\"Me\"]\n\
+       license = \"MIT OR Apache-2.0\"\n"
+    );
+    // No package.json template, so the built-in one is used
+    let npm = ManifestType::Npm.generate_content("foo", "A foo", &fields);
+    assert!(npm.contains("\"license\": \"MIT OR Apache-2.0\""));
+
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[test]
+  fn test_manifest_failed_names_the_repo() {
+    let repo = EnsuredRepo {
+      owner: "acme".to_string(),
+      name: "foo".to_string(),
+      html_url: "https://github.com/acme/foo".to_string(),
+      created: true,
+    };
+    let GitHubError::ManifestFailed { full_name, repo_url, source } =
+      repo.manifest_failed(GitHubError::RateLimited { reset_at: None })
+    else {
+      panic!("expected ManifestFailed");
+    };
+    assert_eq!(full_name, "acme/foo");
+    assert_eq!(repo_url, "https://github.com/acme/foo");
+    assert!(matches!(*source, GitHubError::RateLimited { .. }));
+    assert_eq!(OnFailure::default().toggle(), OnFailure::Delete);
+  }
+
+  #[tokio::test]
+  async fn test_check_without_owner_explains_setup() {
+    let result = check("foo", &GitHubAccount::default()).await;
+    assert_eq!(result.registry, RegistryType::GitHub);
+    assert_eq!(result.available(), None);
+    assert!(result.error.unwrap().contains("github_username"));
+  }
+
+  #[test]
+  fn test_repo_topics_add_registries() {
+    let options = RepoOptions {
+      topics: vec!["reserved-name".to_string(), "Name Squat".to_string(), "npm".to_string()],
+      ..RepoOptions::default()
+    };
+    let topics = repo_topics(&options, &[ManifestType::Npm, ManifestType::Crates]);
+    assert_eq!(topics, ["reserved-name", "name-squat", "npm", "crates"]);
+    assert!(repo_topics(&RepoOptions::default(), &[]).is_empty());
+  }
+
+  #[test]
+  fn test_create_request_omits_unset_options() {
+    let request = CreateRepoRequest {
+      name: "foo".to_string(),
+      description: None,
+      private: false,
+      auto_init: true,
+      homepage: None,
+      has_issues: Some(false),
+      gitignore_template: Some("Rust".to_string()),
+    };
+    let json = serde_json::to_value(&request).unwrap();
+    assert!(json.get("homepage").is_none());
+    assert_eq!(json["has_issues"], false);
+    assert_eq!(json["gitignore_template"], "Rust");
+  }
+
+  #[test]
+  fn test_rate_limit_reset() {
+    let headers = |pairs: &[(&'static str, &str)]| {
+      let mut map = header::HeaderMap::new();
+      for (name, value) in pairs {
+        map.insert(*name, value.parse().unwrap());
+      }
+      map
+    };
+    let now = 1_700_000_000;
+    assert_eq!(rate_limit_reset(&headers(&[("retry-after", "30")]), now), Some(now + 30));
+    let exhausted = headers(&[("x-ratelimit-remaining", "0"), ("x-ratelimit-reset", "1700000900")]);
+    assert_eq!(rate_limit_reset(&exhausted, now), Some(1_700_000_900));
+    let remaining = headers(&[("x-ratelimit-remaining", "12"), ("x-ratelimit-reset", "1700000900")]);
+    assert_eq!(rate_limit_reset(&remaining, now), None);
+    assert_eq!(rate_limit_reset(&headers(&[]), now), None);
+  }
+
+  #[test]
+  fn test_rate_limited_shows_reset_time() {
+    // 2023-11-14 22:13:20 UTC
+    assert_eq!(clock_time_in(&chrono::Utc, 1_700_000_000), "22:13 (UTC+00:00)");
+    let seoul = chrono::FixedOffset::east_opt(9 * 3_600).unwrap();
+    assert_eq!(clock_time_in(&seoul, 1_700_000_000), "07:13 (UTC+09:00)");
+    let error = GitHubError::RateLimited { reset_at: Some(1_700_000_000) };
+    assert_eq!(error.to_string(), format!("Rate limited until {}", clock_time(1_700_000_000)));
+    assert_eq!(GitHubError::RateLimited { reset_at: None }.to_string(), "Rate limited");
+  }
+
+  #[test]
+  fn test_license_placeholders_are_filled() {
+    let body = "Copyright (c) [year] [fullname]\nCopyright [yyyy] [name of copyright owner]";
+    assert_eq!(
+      fill_license(body, 2025, "Jane Doe"),
+      "Copyright (c) 2025 Jane Doe\nCopyright 2025 Jane Doe"
+    );
+    assert_eq!(copyright_holder("Jane Doe <jane@example.com>", "jdoe"), "Jane Doe");
+    assert_eq!(copyright_holder("", "jdoe"), "jdoe");
+  }
+
+  #[test]
+  fn test_license_expressions_get_no_license_file() {
+    assert!(is_single_license("MIT"));
+    assert!(is_single_license("Apache-2.0"));
+    assert!(!is_single_license("MIT OR Apache-2.0"));
+    assert!(!is_single_license("MIT/Apache-2.0"));
+    assert!(!is_single_license(""));
+  }
+
+  #[test]
+  fn test_reservation_readme_mentions_name() {
+    let readme = reservation_readme("foo", &RepoOptions::default());
+    assert!(readme.starts_with("# foo\n"));
+    assert!(readme.contains("reserved for foo"));
+
+    let options = RepoOptions { description: Some("A foo".to_string()), ..RepoOptions::default() };
+    assert!(reservation_readme("foo", &options).ends_with("\nA foo\n"));
+  }
+
+  #[test]
+  fn test_parse_scopes_header() {
+    assert_eq!(parse_scopes("repo, read:org"), ["repo", "read:org"]);
+    assert!(parse_scopes("").is_empty());
+  }
+
+  #[test]
+  fn test_scope_warning_depends_on_visibility() {
+    let info = |scopes: Option<&[&str]>| TokenInfo {
+      login: "me".to_string(),
+      scopes: scopes.map(|s| s.iter().map(|s| s.to_string()).collect()),
+    };
+
+    assert_eq!(info(Some(&["repo"])).scope_warning(true), None);
+    assert_eq!(info(Some(&["public_repo"])).scope_warning(false), None);
+    assert_eq!(
+      info(Some(&["public_repo"])).scope_warning(true).as_deref(),
+      Some("token lacks repo scope")
+    );
+    assert_eq!(
+      info(Some(&["read:org"])).scope_warning(false).as_deref(),
+      Some("token lacks public_repo scope")
+    );
+    // Fine-grained tokens don't report scopes
+    assert_eq!(info(None).scope_warning(true), None);
+
+    assert_eq!(info(Some(&["repo", "read:org"])).scopes_label(), "scopes: repo, read:org");
+    assert_eq!(info(Some(&[])).scopes_label(), "no scopes");
+    assert_eq!(info(None).scopes_label(), "fine-grained token");
+  }
+
+  #[tokio::test]
+  async fn test_poll_until_ready_retries_until_found() {
+    // 404, 404, then 200
+    let mut responses = vec![true, false, false];
+    let mut probes = 0;
+    let result = poll_until_ready(&[Duration::ZERO; 5], || {
+      probes += 1;
+      let ready = responses.pop().unwrap();
+      async move { Ok(ready) }
+    })
+    .await;
+    assert!(result.is_ok());
+    assert_eq!(probes, 3);
+  }
+
+  #[tokio::test]
+  async fn test_poll_until_ready_gives_up() {
+    let mut probes = 0;
+    let delays = [Duration::from_millis(1), Duration::from_millis(1)];
+    let result = poll_until_ready(&delays, || {
+      probes += 1;
+      async { Ok(false) }
+    })
+    .await;
+    assert!(matches!(result, Err(GitHubError::RepoNotReady { waited_secs: 0 })));
+    assert_eq!(probes, 3);
+  }
+
+  #[tokio::test]
+  async fn test_poll_until_ready_stops_on_error() {
+    let result = poll_until_ready(&[Duration::ZERO; 3], || async {
+      Err(GitHubError::AuthRequired)
+    })
+    .await;
+    assert!(matches!(result, Err(GitHubError::AuthRequired)));
+  }
+
+  #[tokio::test]
+  async fn test_default_progress_reports_nothing() {
+    let value = Progress::default().track("step", async { Ok(1) }).await.unwrap();
+    assert_eq!(value, 1);
   }
-  
-  // Create the manifest file
-  let description = format!("Reserved package name for {}", filename);
-  let content = manifest_type.generate_content(repo, &description);
-  
-  create_or_update_file(
-    owner,
-    repo,
-    filename,
-    &content,
-    &format!("Add {} for package reservation", filename),
-    token,
-  ).await?;
-  
-  Ok(true) // File was created
 }
@@ -0,0 +1,182 @@
+use super::{
+  http_client, request_error, trace_response, Availability, AvailabilityResult, RegistryType,
+};
+use reqwest::{header, StatusCode};
+use std::time::Instant;
+
+/// Default Bluesky PDS, replaced by `endpoints.bluesky`
+pub const BLUESKY_URL: &str = "https://bsky.social";
+
+/// Default Mastodon instance, replaced by `endpoints.mastodon`
+pub const MASTODON_URL: &str = "https://mastodon.social";
+
+/// Check if a GitHub user or organization name is free
+///
+/// API: GET https://api.github.com/users/{name}, with `base` in place of
+/// `https://api.github.com`
+/// - 200: Account exists (not available)
+/// - 404: No such account (available)
+#[tracing::instrument(level = "debug", skip(token), fields(registry = "github_user"))]
+pub async fn check_github_user(name: &str, base: &str, token: Option<&str>) -> AvailabilityResult {
+  let registry = RegistryType::GitHubUser;
+  if let Some(result) = invalid(registry, name, |c| c.is_ascii_alphanumeric() || c == '-') {
+    return result;
+  }
+  let url = format!("{}/users/{}", base, name);
+  let request = http_client()
+    .get(&url)
+    .header(header::USER_AGENT, "nbi/0.1.0")
+    .header(header::ACCEPT, "application/vnd.github+json");
+  let request = match token {
+    Some(token) => request.header(header::AUTHORIZATION, format!("Bearer {}", token)),
+    None => request,
+  };
+  by_status(registry, name, &url, request, StatusCode::NOT_FOUND).await
+}
+
+/// Check if an npm organization (and so the `@name` scope) is free
+///
+/// API: GET https://registry.npmjs.org/-/org/{name}/user
+/// - 200: Organization exists (not available)
+/// - 404: No such organization (available)
+#[tracing::instrument(level = "debug", fields(registry = "npm_org"))]
+pub async fn check_npm_org(name: &str, base: &str) -> AvailabilityResult {
+  let url = format!("{}/-/org/{}/user", base, name);
+  let request = http_client().get(&url);
+  by_status(RegistryType::NpmOrg, name, &url, request, StatusCode::NOT_FOUND).await
+}
+
+/// Check if the handle `{name}.bsky.social` is free
+///
+/// API: GET https://bsky.social/xrpc/com.atproto.identity.resolveHandle?handle={name}.bsky.social
+/// - 200: Handle resolves to an account (not available)
+/// - 400: Handle doesn't resolve (available)
+#[tracing::instrument(level = "debug", fields(registry = "bluesky"))]
+pub async fn check_bluesky(name: &str, base: &str) -> AvailabilityResult {
+  let registry = RegistryType::Bluesky;
+  if let Some(result) = invalid(registry, name, |c| c.is_ascii_alphanumeric() || c == '-') {
+    return result;
+  }
+  let url = format!(
+    "{}/xrpc/com.atproto.identity.resolveHandle?handle={}.bsky.social",
+    base,
+    name.to_lowercase()
+  );
+  let request = http_client().get(&url);
+  by_status(registry, name, &url, request, StatusCode::BAD_REQUEST).await
+}
+
+/// Check if `@name` is free on the configured Mastodon instance
+///
+/// API: GET https://mastodon.social/api/v1/accounts/lookup?acct={name}
+/// - 200: Account exists (not available)
+/// - 404: No such account (available)
+#[tracing::instrument(level = "debug", fields(registry = "mastodon"))]
+pub async fn check_mastodon(name: &str, base: &str) -> AvailabilityResult {
+  let registry = RegistryType::Mastodon;
+  if let Some(result) = invalid(registry, name, |c| c.is_ascii_alphanumeric() || c == '_') {
+    return result;
+  }
+  let url = format!("{}/api/v1/accounts/lookup?acct={}", base, name);
+  let request = http_client().get(&url);
+  by_status(registry, name, &url, request, StatusCode::NOT_FOUND).await
+}
+
+/// A failed result when `name` has characters handles on `registry` can't
+///
+/// The services answer "not found" for such names, which would read as
+/// available.
+fn invalid(
+  registry: RegistryType,
+  name: &str,
+  allowed: fn(char) -> bool,
+) -> Option<AvailabilityResult> {
+  if name.chars().all(allowed) && !name.starts_with('-') && !name.ends_with('-') {
+    return None;
+  }
+  Some(AvailabilityResult {
+    registry,
+    name: name.to_string(),
+    availability: Availability::Unknown,
+    error: Some(format!("Not a valid {} handle", registry)),
+    detail: None,
+    qualifier: None,
+  })
+}
+
+/// Send `request`; `free` means available and 200 means taken
+async fn by_status(
+  registry: RegistryType,
+  name: &str,
+  url: &str,
+  request: reqwest::RequestBuilder,
+  free: StatusCode,
+) -> AvailabilityResult {
+  let started = Instant::now();
+  let response = request.send().await;
+  trace_response(url, started, &response);
+
+  let (available, error) = match response {
+    Ok(response) => match response.status() {
+      status if status == free => (Some(true), None),
+      StatusCode::OK => (Some(false), None),
+      status => (None, Some(format!("Unexpected status: {}", status))),
+    },
+    Err(e) => (None, Some(request_error(e))),
+  };
+  AvailabilityResult {
+    registry,
+    name: name.to_string(),
+    availability: available.into(),
+    error,
+    detail: None,
+    qualifier: None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::{extract::Query, http::StatusCode, routing::get, Router};
+  use std::collections::HashMap;
+
+  /// A server answering like Mastodon and Bluesky, where only "taken" exists
+  async fn mock() -> String {
+    let lookup = |query: Query<HashMap<String, String>>, key: &'static str, missing| {
+      let found = query.get(key).is_some_and(|v| v.starts_with("taken"));
+      if found { StatusCode::OK } else { missing }
+    };
+    let app = Router::new()
+      .route(
+        "/api/v1/accounts/lookup",
+        get(move |q| async move { lookup(q, "acct", StatusCode::NOT_FOUND) }),
+      )
+      .route(
+        "/xrpc/com.atproto.identity.resolveHandle",
+        get(move |q| async move { lookup(q, "handle", StatusCode::BAD_REQUEST) }),
+      );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    url
+  }
+
+  #[tokio::test]
+  async fn test_handles_against_mock_instance() {
+    let base = mock().await;
+    assert_eq!(check_mastodon("taken", &base).await.available(), Some(false));
+    assert_eq!(check_mastodon("free", &base).await.available(), Some(true));
+    assert_eq!(check_bluesky("taken", &base).await.available(), Some(false));
+    let free = check_bluesky("free", &base).await;
+    assert_eq!((free.registry, free.available()), (RegistryType::Bluesky, Some(true)));
+  }
+
+  #[tokio::test]
+  async fn test_invalid_handles_fail_without_a_request() {
+    let result = check_mastodon("my-app", "http://127.0.0.1:9").await;
+    assert_eq!(result.available(), None);
+    assert_eq!(result.error.as_deref(), Some("Not a valid Mastodon handle"));
+    assert!(check_bluesky("my_app", "http://127.0.0.1:9").await.error.is_some());
+    assert!(check_github_user("-app", "https://api.github.com", None).await.error.is_some());
+  }
+}
@@ -4,30 +4,387 @@ pub mod debian;
 pub mod domain;
 pub mod flatpak;
 pub mod github;
+pub mod handles;
 pub mod npm;
+pub mod offline;
 pub mod pypi;
+pub mod trademark;
 
+use futures::future::{BoxFuture, FutureExt, Shared, WeakShared};
+use futures::stream::{self, FuturesUnordered, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::future::Future;
+use std::sync::{Mutex, MutexGuard, OnceLock, PoisonError, RwLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// Maximum number of names checked concurrently by [`check_many`]
+const MAX_CONCURRENT_NAMES: usize = 4;
+
+/// Checks in flight at once unless `--concurrency` or config say otherwise
+pub const DEFAULT_CONCURRENCY: usize = 16;
+
+static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+/// Caps how many checks run at once
+pub struct Throttle {
+  permits: Semaphore,
+  size: usize,
+}
+
+impl Throttle {
+  pub fn new(size: usize) -> Self {
+    let size = size.max(1);
+    Self { permits: Semaphore::new(size), size }
+  }
+
+  /// Run `check` once a permit is free
+  pub async fn run<T>(&self, check: impl Future<Output = T>) -> T {
+    let _permit = self.acquire().await;
+    check.await
+  }
+
+  /// Wait for a permit, held until the returned guard is dropped
+  pub async fn acquire(&self) -> SemaphorePermit<'_> {
+    self.permits.acquire().await.expect("the semaphore is never closed")
+  }
+}
+
+/// Size the shared throttle; only a call before the first check counts
+pub fn set_concurrency(size: usize) {
+  let _ = THROTTLE.set(Throttle::new(size));
+}
+
+/// The throttle every check goes through, so multi-name runs, `compare`
+/// and the server together stay under one limit
+pub fn throttle() -> &'static Throttle {
+  THROTTLE.get_or_init(|| Throttle::new(DEFAULT_CONCURRENCY))
+}
+
+/// Size of the shared throttle
+pub fn concurrency() -> usize {
+  throttle().size
+}
+
+static ENDPOINTS: RwLock<Option<EndpointSettings>> = RwLock::new(None);
+
+/// Point checks at the base URLs in `[endpoints]`, screen the configured
+/// trademark classes and look in the configured Homebrew taps; replaces
+/// earlier calls, so a reloaded config takes effect
+pub fn configure(config: &Config) {
+  *ENDPOINTS.write().unwrap_or_else(PoisonError::into_inner) = Some(config.endpoints.clone());
+  trademark::set_classes(&config.trademark_classes);
+  brew::set_taps(&config.brew_taps);
+}
+
+/// Base URL for `registry`'s checks: the configured one, else `default`
+pub(crate) fn endpoint(registry: RegistryType, default: &str) -> String {
+  let endpoints = ENDPOINTS.read().unwrap_or_else(PoisonError::into_inner);
+  let configured = endpoints.as_ref().and_then(|e| e.get(registry));
+  configured.unwrap_or(default).to_string()
+}
+
+/// What a check found out about a name
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Availability {
+  Available,
+  Taken,
+  /// Nobody uses the name, but the registry won't hand it out either, e.g.
+  /// a package npm security holds
+  Reserved,
+  /// The check failed
+  Unknown,
+}
+
+impl Availability {
+  /// The yes/no answer from before [`Availability`] existed: reserved
+  /// names can't be had, so they count as taken
+  pub fn as_bool(self) -> Option<bool> {
+    match self {
+      Availability::Available => Some(true),
+      Availability::Taken | Availability::Reserved => Some(false),
+      Availability::Unknown => None,
+    }
+  }
+}
+
+impl From<Option<bool>> for Availability {
+  fn from(available: Option<bool>) -> Self {
+    match available {
+      Some(true) => Availability::Available,
+      Some(false) => Availability::Taken,
+      None => Availability::Unknown,
+    }
+  }
+}
 
 /// Availability check result for a registry
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ResultJson", into = "ResultJson")]
 pub struct AvailabilityResult {
   pub registry: RegistryType,
   pub name: String,
-  pub available: Option<bool>, // None = check failed
+  pub availability: Availability,
   pub error: Option<String>,
+  /// What the check found beyond yes or no, e.g. "potential conflicts: 2"
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub detail: Option<String>,
+  /// Whose namespace `name` was checked in, e.g. the owner of a GitHub repo;
+  /// `name` itself is always the name that was asked about
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  pub qualifier: Option<String>,
+}
+
+impl AvailabilityResult {
+  /// Whether the name can be had; `None` when the check failed
+  pub fn available(&self) -> Option<bool> {
+    self.availability.as_bool()
+  }
+
+  /// The name within its namespace, e.g. "owner/foo" for a GitHub repo
+  pub fn qualified_name(&self) -> String {
+    match &self.qualifier {
+      Some(qualifier) => format!("{}/{}", qualifier, self.name),
+      None => self.name.clone(),
+    }
+  }
+
+  /// Public page for what this result checked
+  pub fn page_url(&self) -> String {
+    self.registry.page_url(&self.qualified_name())
+  }
+}
+
+/// [`AvailabilityResult`] as JSON, which still carries the old `available`
+/// boolean next to `availability` for consumers that haven't moved over
+///
+/// `available` is deprecated since 0.1.9 and goes away in 0.2.0, leaving
+/// `availability` alone.
+#[derive(Serialize, Deserialize)]
+struct ResultJson {
+  registry: RegistryType,
+  name: String,
+  /// Missing from results written before it existed
+  #[serde(default)]
+  availability: Option<Availability>,
+  #[serde(default)]
+  available: Option<bool>,
+  error: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  detail: Option<String>,
+  #[serde(default, skip_serializing_if = "Option::is_none")]
+  qualifier: Option<String>,
+}
+
+impl From<ResultJson> for AvailabilityResult {
+  fn from(json: ResultJson) -> Self {
+    Self {
+      registry: json.registry,
+      name: json.name,
+      availability: json.availability.unwrap_or_else(|| json.available.into()),
+      error: json.error,
+      detail: json.detail,
+      qualifier: json.qualifier,
+    }
+  }
+}
+
+impl From<AvailabilityResult> for ResultJson {
+  fn from(result: AvailabilityResult) -> Self {
+    Self {
+      registry: result.registry,
+      name: result.name,
+      availability: Some(result.availability),
+      available: result.availability.as_bool(),
+      error: result.error,
+      detail: result.detail,
+      qualifier: result.qualifier,
+    }
+  }
+}
+
+/// Which results to keep when displaying
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultFilter {
+  #[default]
+  All,
+  Available,
+  Taken,
+  /// Failed and unknown checks only
+  Problems,
+}
+
+impl ResultFilter {
+  /// Whether a result passes the filter; failed checks are kept unless
+  /// `show_errors` is false
+  pub fn matches(&self, result: &AvailabilityResult, show_errors: bool) -> bool {
+    match (self, result.available()) {
+      (ResultFilter::Problems, available) => available.is_none(),
+      (_, None) => show_errors,
+      (ResultFilter::All, Some(_)) => true,
+      (ResultFilter::Available, Some(available)) => available,
+      (ResultFilter::Taken, Some(available)) => !available,
+    }
+  }
+
+  /// Next filter mode when cycling
+  pub fn next(self) -> Self {
+    match self {
+      ResultFilter::All => ResultFilter::Available,
+      ResultFilter::Available => ResultFilter::Taken,
+      ResultFilter::Taken => ResultFilter::Problems,
+      ResultFilter::Problems => ResultFilter::All,
+    }
+  }
+
+  pub fn label(&self) -> &'static str {
+    match self {
+      ResultFilter::All => "all",
+      ResultFilter::Available => "available",
+      ResultFilter::Taken => "taken",
+      ResultFilter::Problems => "problems",
+    }
+  }
+}
+
+/// All registry results for a single name
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NameResults {
+  pub name: String,
+  pub results: Vec<TimedResult>,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum RegistryType {
+  #[serde(rename = "npm")]
   Npm,
+  #[serde(rename = "crates")]
   Crates,
+  #[serde(rename = "pypi")]
   PyPi,
+  #[serde(rename = "brew")]
   Brew,
+  #[serde(rename = "flatpak")]
   Flatpak,
+  #[serde(rename = "debian")]
   Debian,
+  #[serde(rename = "dev_domain")]
   DevDomain,
+  #[serde(rename = "github")]
   GitHub,
+  #[serde(rename = "github_user")]
+  GitHubUser,
+  #[serde(rename = "npm_org")]
+  NpmOrg,
+  #[serde(rename = "bluesky")]
+  Bluesky,
+  #[serde(rename = "mastodon")]
+  Mastodon,
+  #[serde(rename = "trademark")]
+  Trademark,
+}
+
+/// Error for a registry identifier that doesn't match any [`RegistryType`]
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("unknown registry '{0}' (valid: {valid})", valid = RegistryType::valid_ids())]
+pub struct UnknownRegistry(pub String);
+
+impl RegistryType {
+  /// All registries, in display order
+  pub const ALL: [RegistryType; 13] = [
+    RegistryType::Npm,
+    RegistryType::Crates,
+    RegistryType::PyPi,
+    RegistryType::GitHub,
+    RegistryType::Brew,
+    RegistryType::Flatpak,
+    RegistryType::Debian,
+    RegistryType::DevDomain,
+    RegistryType::GitHubUser,
+    RegistryType::NpmOrg,
+    RegistryType::Bluesky,
+    RegistryType::Mastodon,
+    RegistryType::Trademark,
+  ];
+
+  /// Account and social handle checks, turned on as a group; only the
+  /// GitHub user check is on by default
+  pub const HANDLES: [RegistryType; 4] = [
+    RegistryType::GitHubUser,
+    RegistryType::NpmOrg,
+    RegistryType::Bluesky,
+    RegistryType::Mastodon,
+  ];
+
+  /// Whether this is one of [`RegistryType::HANDLES`]
+  pub fn is_handle(&self) -> bool {
+    Self::HANDLES.contains(self)
+  }
+
+  /// Stable identifier used in CLI flags, config keys, and JSON
+  pub fn id(&self) -> &'static str {
+    match self {
+      RegistryType::Npm => "npm",
+      RegistryType::Crates => "crates",
+      RegistryType::PyPi => "pypi",
+      RegistryType::Brew => "brew",
+      RegistryType::Flatpak => "flatpak",
+      RegistryType::Debian => "debian",
+      RegistryType::DevDomain => "dev_domain",
+      RegistryType::GitHub => "github",
+      RegistryType::GitHubUser => "github_user",
+      RegistryType::NpmOrg => "npm_org",
+      RegistryType::Bluesky => "bluesky",
+      RegistryType::Mastodon => "mastodon",
+      RegistryType::Trademark => "trademark",
+    }
+  }
+
+  /// Public page for `name` on this registry
+  ///
+  /// GitHub repos are linked by "owner/name", see
+  /// [`AvailabilityResult::page_url`]. Domain results already carry the full
+  /// domain (e.g. "foo.dev") as their name.
+  pub fn page_url(&self, name: &str) -> String {
+    match self {
+      RegistryType::Npm => format!("https://www.npmjs.com/package/{}", name),
+      RegistryType::Crates => format!("https://crates.io/crates/{}", name),
+      RegistryType::PyPi => format!("https://pypi.org/project/{}/", name),
+      RegistryType::Brew => format!("https://formulae.brew.sh/formula/{}", name),
+      RegistryType::Flatpak => format!("https://flathub.org/apps/search?q={}", name),
+      RegistryType::Debian => format!("https://sources.debian.org/src/{}/", name),
+      RegistryType::DevDomain => format!("https://{}", name),
+      RegistryType::GitHub | RegistryType::GitHubUser => format!("https://github.com/{}", name),
+      RegistryType::NpmOrg => format!("https://www.npmjs.com/org/{}", name),
+      RegistryType::Bluesky => format!("https://bsky.app/profile/{}.bsky.social", name),
+      RegistryType::Mastodon => {
+        format!("{}/@{}", endpoint(RegistryType::Mastodon, handles::MASTODON_URL), name)
+      }
+      RegistryType::Trademark => {
+        format!("https://tmsearch.uspto.gov/search/search-results?query={}", name)
+      }
+    }
+  }
+
+  /// Comma-separated list of every valid identifier
+  pub fn valid_ids() -> String {
+    Self::ALL.iter().map(|r| r.id()).collect::<Vec<_>>().join(", ")
+  }
+}
+
+impl std::str::FromStr for RegistryType {
+  type Err = UnknownRegistry;
+
+  fn from_str(s: &str) -> Result<Self, Self::Err> {
+    let id = s.trim().to_lowercase();
+    Self::ALL
+      .into_iter()
+      .find(|r| r.id() == id)
+      .ok_or_else(|| UnknownRegistry(s.to_string()))
+  }
 }
 
 impl std::fmt::Display for RegistryType {
@@ -41,35 +398,619 @@ impl std::fmt::Display for RegistryType {
       RegistryType::Debian => write!(f, "Debian"),
       RegistryType::DevDomain => write!(f, ".dev"),
       RegistryType::GitHub => write!(f, "GitHub"),
+      RegistryType::GitHubUser => write!(f, "GitHub user"),
+      RegistryType::NpmOrg => write!(f, "npm org"),
+      RegistryType::Bluesky => write!(f, "Bluesky"),
+      RegistryType::Mastodon => write!(f, "Mastodon"),
+      RegistryType::Trademark => write!(f, "Trademark"),
     }
   }
 }
 
-use crate::config::RegistrySettings;
+use crate::config::{Config, EndpointSettings, RegistrySettings};
+use github::GitHubAccount;
 
-/// Check availability across enabled registries
-pub async fn check_all(name: &str, settings: &RegistrySettings) -> Vec<AvailabilityResult> {
-  let mut results = Vec::new();
-
-  let (npm_res, crates_res, pypi_res, brew_res, flatpak_res, debian_res, domain_res, github_res) = tokio::join!(
-    async { if settings.npm { Some(npm::check(name).await) } else { None } },
-    async { if settings.crates { Some(crates::check(name).await) } else { None } },
-    async { if settings.pypi { Some(pypi::check(name).await) } else { None } },
-    async { if settings.brew { Some(brew::check(name).await) } else { None } },
-    async { if settings.flatpak { Some(flatpak::check(name).await) } else { None } },
-    async { if settings.debian { Some(debian::check(name).await) } else { None } },
-    async { if settings.dev_domain { Some(domain::check(name).await) } else { None } },
-    async { if settings.github { Some(github::check_name(name).await) } else { None } },
-  );
-
-  if let Some(r) = npm_res { results.push(r); }
-  if let Some(r) = crates_res { results.push(r); }
-  if let Some(r) = pypi_res { results.push(r); }
-  if let Some(r) = github_res { results.push(r); }
-  if let Some(r) = brew_res { results.push(r); }
-  if let Some(r) = flatpak_res { results.push(r); }
-  if let Some(r) = debian_res { results.push(r); }
-  if let Some(r) = domain_res { results.push(r); }
+/// Redirects a package check follows before failing
+const MAX_REDIRECTS: usize = 5;
+
+/// How long a registry request may take unless [`set_request_timeout`]
+/// says otherwise; Homebrew's formula list is several megabytes
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+static REQUEST_TIMEOUT: OnceLock<Duration> = OnceLock::new();
+
+/// Give up on registry requests after `timeout`; only a call before the
+/// first check counts
+pub fn set_request_timeout(timeout: Duration) {
+  let _ = REQUEST_TIMEOUT.set(timeout);
+}
+
+/// How long a registry request may take
+pub fn request_timeout() -> Duration {
+  *REQUEST_TIMEOUT.get_or_init(|| DEFAULT_REQUEST_TIMEOUT)
+}
 
+/// Client for registry checks, which follow redirects so that
+/// [`redirect_detail`] can compare where they landed with what was asked
+pub(crate) fn http_client() -> reqwest::Client {
+  reqwest::Client::builder()
+    .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS))
+    .timeout(request_timeout())
+    .build()
+    .unwrap_or_default()
+}
+
+/// Error text for a request that got no response; reqwest's own only
+/// names the URL when the request timed out
+pub(crate) fn request_error(error: reqwest::Error) -> String {
+  if error.is_timeout() {
+    format!("Timeout after {:?}", request_timeout())
+  } else {
+    error.to_string()
+  }
+}
+
+/// Log the URL, status, and elapsed time of a registry HTTP request, and
+/// where it ended up when redirected
+pub(crate) fn trace_response(
+  url: &str,
+  started: Instant,
+  response: &Result<reqwest::Response, reqwest::Error>,
+) {
+  let elapsed_ms = started.elapsed().as_millis() as u64;
+  match response {
+    Ok(r) if r.url().as_str() != url => {
+      let final_url = r.url().as_str();
+      tracing::debug!(url, final_url, status = %r.status(), elapsed_ms, "registry redirected")
+    }
+    Ok(r) => tracing::debug!(url, status = %r.status(), elapsed_ms, "registry responded"),
+    Err(e) => tracing::debug!(url, error = %e, elapsed_ms, "registry request failed"),
+  }
+}
+
+/// Detail for a check of `name` at `url` that was redirected to another
+/// package, e.g. npm sending "Foo" to "foo" or PyPI sending "foo_bar" to
+/// "foo-bar": the name is taken by that package
+///
+/// Redirects that only add or drop a trailing slash don't count.
+pub(crate) fn redirect_detail(
+  url: &str,
+  response: &reqwest::Response,
+  name: &str,
+) -> Option<String> {
+  let landed = response.url().as_str();
+  if landed.trim_end_matches('/') == url.trim_end_matches('/') {
+    return None;
+  }
+  // The landed URL has the requested one's shape with another name in it
+  let at = url.rfind(name)?;
+  let (prefix, suffix) = (&url[..at], url[at + name.len()..].trim_end_matches('/'));
+  let target = landed.strip_prefix(prefix).unwrap_or(landed).trim_end_matches('/');
+  let target = target.strip_suffix(suffix).unwrap_or(target);
+  (target != name).then(|| format!("redirects to existing package {}", target))
+}
+
+/// A check result paired with how long the registry took to answer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimedResult {
+  #[serde(flatten)]
+  pub result: AvailabilityResult,
+  pub duration_ms: u64,
+}
+
+/// The result that took longest, to call out a slow registry
+pub fn slowest<'a>(results: impl IntoIterator<Item = &'a TimedResult>) -> Option<&'a TimedResult> {
+  results.into_iter().max_by_key(|t| t.duration_ms)
+}
+
+/// Run a single registry check; `github` is only used for GitHub
+///
+/// In offline mode the answer comes from [`offline::fixtures`] instead.
+pub async fn check_one(
+  registry: RegistryType,
+  name: &str,
+  github: &GitHubAccount,
+) -> AvailabilityResult {
+  match (offline::fixtures(), registry) {
+    // Domain checks consult the fixtures themselves, by full domain
+    (Some(fixtures), registry) if registry != RegistryType::DevDomain => {
+      return fixtures.check(registry, name);
+    }
+    _ => {}
+  }
+  match registry {
+    RegistryType::Npm => {
+      let base = endpoint(registry, npm::NPM_REGISTRY_URL);
+      // Mirrors have no website to ask about held names
+      let web = (base == npm::NPM_REGISTRY_URL).then_some(npm::NPM_WEB_URL);
+      npm::check(name, &base, web).await
+    }
+    RegistryType::Crates => crates::check(name, &endpoint(registry, crates::CRATES_API_URL)).await,
+    RegistryType::PyPi => pypi::check(name, &endpoint(registry, pypi::PYPI_SIMPLE_URL)).await,
+    RegistryType::GitHub => github::check(name, github).await,
+    RegistryType::Brew => {
+      let base = endpoint(registry, brew::BREW_API_URL);
+      let token = github.token.as_deref();
+      brew::check(name, &base, &brew::taps(), &github::api_url(), token).await
+    }
+    RegistryType::Flatpak => {
+      flatpak::check(name, &endpoint(registry, flatpak::FLATHUB_API_URL)).await
+    }
+    RegistryType::Debian => debian::check(name, &endpoint(registry, debian::DEBIAN_API_URL)).await,
+    RegistryType::DevDomain => domain::check(name).await,
+    RegistryType::GitHubUser => {
+      handles::check_github_user(name, &github::api_url(), github.token.as_deref()).await
+    }
+    RegistryType::NpmOrg => {
+      handles::check_npm_org(name, &endpoint(registry, npm::NPM_REGISTRY_URL)).await
+    }
+    RegistryType::Bluesky => {
+      handles::check_bluesky(name, &endpoint(registry, handles::BLUESKY_URL)).await
+    }
+    RegistryType::Mastodon => {
+      handles::check_mastodon(name, &endpoint(registry, handles::MASTODON_URL)).await
+    }
+    RegistryType::Trademark => {
+      let base = endpoint(registry, trademark::USPTO_SEARCH_URL);
+      trademark::check(name, &base, &trademark::classes()).await
+    }
+  }
+}
+
+/// Check enabled registries concurrently, yielding results as they finish
+///
+/// The stream owns its inputs, and dropping it cancels the checks still
+/// in flight.
+pub fn check_stream(
+  name: &str,
+  settings: &RegistrySettings,
+  github: &GitHubAccount,
+) -> impl Stream<Item = TimedResult> {
+  RegistryType::ALL
+    .into_iter()
+    .filter(|r| settings.is_enabled(*r))
+    .map(|registry| check_timed(registry, name.to_string(), github.clone()))
+    .collect::<FuturesUnordered<_>>()
+}
+
+/// A check other callers can join while it runs
+type InFlight = Shared<BoxFuture<'static, TimedResult>>;
+
+type RunningChecks = HashMap<CheckKey, WeakShared<BoxFuture<'static, TimedResult>>>;
+
+/// Registry, name and, for GitHub, the owner whose repos are checked
+type CheckKey = (RegistryType, String, Option<String>);
+
+/// Running checks, held weakly so a check nobody waits for any more is
+/// dropped rather than kept half-done
+static IN_FLIGHT: OnceLock<Mutex<RunningChecks>> = OnceLock::new();
+
+fn in_flight() -> MutexGuard<'static, RunningChecks> {
+  IN_FLIGHT.get_or_init(Mutex::default).lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// The running check for `key`, if anyone still waits on it
+fn join(key: &CheckKey) -> Option<InFlight> {
+  in_flight().get(key).and_then(WeakShared::upgrade)
+}
+
+/// One caller waiting on a shared check; the last to go removes the entry
+struct Waiter {
+  key: CheckKey,
+  check: Option<InFlight>,
+}
+
+impl Waiter {
+  async fn wait(mut self) -> TimedResult {
+    let check = self.check.clone().expect("set until dropped");
+    let timed = check.clone().await;
+    // Later callers start a fresh check rather than reuse this answer
+    let mut checks = in_flight();
+    if checks.get(&self.key).and_then(WeakShared::upgrade).is_some_and(|c| c.ptr_eq(&check)) {
+      checks.remove(&self.key);
+    }
+    drop(checks);
+    self.check = None;
+    timed
+  }
+}
+
+impl Drop for Waiter {
+  fn drop(&mut self) {
+    // Let go first, so a check nobody else waits on can't be upgraded
+    self.check = None;
+    let mut checks = in_flight();
+    if checks.get(&self.key).is_some_and(|weak| weak.upgrade().is_none()) {
+      checks.remove(&self.key);
+    }
+  }
+}
+
+/// Run `check` for `key` once `throttle` has a permit, or join the
+/// identical check already running, so overlapping searches (suggestions,
+/// compare, several clients of the server) send one request per registry
+/// and name
+///
+/// The caller that starts the check holds the permit; callers that join
+/// don't take one. Dropping every caller drops the check and its permit.
+pub(crate) async fn single_flight(
+  key: CheckKey,
+  throttle: &Throttle,
+  check: impl Future<Output = TimedResult> + Send + 'static,
+) -> TimedResult {
+  if let Some(running) = join(&key) {
+    return Waiter { key, check: Some(running) }.wait().await;
+  }
+  let permit = throttle.acquire().await;
+  let started = {
+    let mut checks = in_flight();
+    // Someone may have started it while this waited for a permit
+    match checks.get(&key).and_then(WeakShared::upgrade) {
+      Some(running) => Err(running),
+      None => {
+        let shared = check.boxed().shared();
+        checks.insert(key.clone(), shared.downgrade().expect("not polled yet"));
+        Ok(shared)
+      }
+    }
+  };
+  match started {
+    Ok(shared) => {
+      let timed = Waiter { key, check: Some(shared) }.wait().await;
+      drop(permit);
+      timed
+    }
+    Err(running) => {
+      drop(permit);
+      Waiter { key, check: Some(running) }.wait().await
+    }
+  }
+}
+
+/// Run one throttled check, recording its metrics and duration
+///
+/// Identical checks running at the same time share one upstream request.
+pub async fn check_timed(
+  registry: RegistryType,
+  name: String,
+  github: GitHubAccount,
+) -> TimedResult {
+  // Only the GitHub check depends on the account's owner
+  let owner = github.owner.clone().filter(|_| registry == RegistryType::GitHub);
+  let key = (registry, name.clone(), owner);
+  // Timed once running, so waiting for the throttle isn't blamed on the registry
+  let check = async move {
+    let started = Instant::now();
+    let result = check_one(registry, &name, &github).await;
+    let elapsed = started.elapsed();
+    crate::metrics::record_check(&result, elapsed);
+    let duration_ms = elapsed.as_millis() as u64;
+    tracing::debug!(%registry, name = %result.name, duration_ms, "check finished");
+    TimedResult { result, duration_ms }
+  };
+  single_flight(key, throttle(), check).await
+}
+
+/// Drop repeated results for the same registry and name, keeping the first
+pub fn dedupe(results: &mut Vec<AvailabilityResult>) {
+  let mut seen = HashSet::new();
+  results.retain(|r| seen.insert((r.registry, r.qualified_name())));
+}
+
+/// Position of a registry in [`RegistryType::ALL`], used as a sort key
+pub fn display_order(registry: RegistryType) -> usize {
+  RegistryType::ALL.iter().position(|r| *r == registry).unwrap_or(usize::MAX)
+}
+
+/// Sort key putting available results first, then taken, then unknown and
+/// failed ones, each group by registry name
+pub fn result_order(result: &AvailabilityResult) -> (u8, String) {
+  let group = match result.available() {
+    Some(true) => 0,
+    Some(false) => 1,
+    None => 2,
+  };
+  (group, result.registry.to_string().to_lowercase())
+}
+
+/// Check availability across enabled registries
+pub async fn check_all(
+  name: &str,
+  settings: &RegistrySettings,
+  github: &GitHubAccount,
+) -> Vec<AvailabilityResult> {
+  let mut results: Vec<_> = check_stream(name, settings, github).map(|t| t.result).collect().await;
+  results.sort_by_key(|r| display_order(r.registry));
   results
 }
+
+/// Check several names, at most `MAX_CONCURRENT_NAMES` at a time
+///
+/// `on_result` is called as each registry finishes. Results are returned in
+/// the same order as `names`, each sorted in display order.
+pub async fn check_many(
+  names: &[String],
+  settings: &RegistrySettings,
+  github: &GitHubAccount,
+  on_result: &(dyn Fn(&TimedResult) + Sync),
+) -> Vec<NameResults> {
+  stream::iter(names)
+    .map(|name| async move {
+      let mut results: Vec<_> = check_stream(name, settings, github)
+        .inspect(|t| on_result(t))
+        .collect()
+        .await;
+      results.sort_by_key(|t: &TimedResult| display_order(t.result.registry));
+      NameResults {
+        name: name.clone(),
+        results,
+      }
+    })
+    .buffered(MAX_CONCURRENT_NAMES)
+    .collect()
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_id_round_trips_through_from_str() {
+    for registry in RegistryType::ALL {
+      assert_eq!(registry.id().parse::<RegistryType>(), Ok(registry));
+    }
+  }
+
+  #[tokio::test]
+  async fn test_throttle_caps_checks_in_flight() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    let throttle = Throttle::new(3);
+    let (in_flight, peak) = (AtomicUsize::new(0), AtomicUsize::new(0));
+    let check = || async {
+      let now = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+      peak.fetch_max(now, Ordering::SeqCst);
+      tokio::time::sleep(std::time::Duration::from_millis(5)).await;
+      in_flight.fetch_sub(1, Ordering::SeqCst);
+    };
+    futures::future::join_all((0..20).map(|_| throttle.run(check()))).await;
+    assert_eq!(peak.load(Ordering::SeqCst), 3);
+  }
+
+  #[test]
+  fn test_page_urls() {
+    let urls: Vec<_> = RegistryType::ALL.iter().map(|r| r.page_url("foo")).collect();
+    assert_eq!(
+      urls,
+      [
+        "https://www.npmjs.com/package/foo",
+        "https://crates.io/crates/foo",
+        "https://pypi.org/project/foo/",
+        "https://github.com/foo",
+        "https://formulae.brew.sh/formula/foo",
+        "https://flathub.org/apps/search?q=foo",
+        "https://sources.debian.org/src/foo/",
+        "https://foo",
+        "https://github.com/foo",
+        "https://www.npmjs.com/org/foo",
+        "https://bsky.app/profile/foo.bsky.social",
+        "https://mastodon.social/@foo",
+        "https://tmsearch.uspto.gov/search/search-results?query=foo",
+      ]
+    );
+    assert_eq!(RegistryType::DevDomain.page_url("foo.dev"), "https://foo.dev");
+  }
+
+  #[test]
+  fn test_serializes_as_id() {
+    for registry in RegistryType::ALL {
+      let json = serde_json::to_string(&registry).unwrap();
+      assert_eq!(json, format!("\"{}\"", registry.id()));
+    }
+  }
+
+  fn result(available: Option<bool>) -> AvailabilityResult {
+    AvailabilityResult {
+      registry: RegistryType::Npm,
+      name: "foo".to_string(),
+      availability: available.into(),
+      error: None,
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  #[test]
+  fn test_result_filter_matches() {
+    let (free, taken, failed) = (result(Some(true)), result(Some(false)), result(None));
+
+    assert!(ResultFilter::All.matches(&free, true) && ResultFilter::All.matches(&taken, true));
+    assert!(ResultFilter::Available.matches(&free, true));
+    assert!(!ResultFilter::Available.matches(&taken, true));
+    assert!(ResultFilter::Taken.matches(&taken, true));
+    assert!(!ResultFilter::Taken.matches(&free, true));
+
+    assert!(ResultFilter::Available.matches(&failed, true));
+    assert!(!ResultFilter::Available.matches(&failed, false));
+    assert!(!ResultFilter::All.matches(&failed, false));
+
+    assert!(ResultFilter::Problems.matches(&failed, false));
+    assert!(!ResultFilter::Problems.matches(&free, true));
+    assert!(!ResultFilter::Problems.matches(&taken, true));
+
+    let reserved = AvailabilityResult { availability: Availability::Reserved, ..result(None) };
+    assert!(ResultFilter::Taken.matches(&reserved, false));
+    assert!(!ResultFilter::Available.matches(&reserved, true));
+  }
+
+  #[test]
+  fn test_qualifier_stays_out_of_the_name() {
+    let repo = AvailabilityResult {
+      registry: RegistryType::GitHub,
+      qualifier: Some("octocat".to_string()),
+      ..result(Some(true))
+    };
+    assert_eq!(repo.qualified_name(), "octocat/foo");
+    assert_eq!(repo.page_url(), "https://github.com/octocat/foo");
+    let value = serde_json::to_value(&repo).unwrap();
+    assert_eq!((&value["name"], &value["qualifier"]), (&"foo".into(), &"octocat".into()));
+
+    let value = serde_json::to_value(result(Some(true))).unwrap();
+    assert!(value.get("qualifier").is_none());
+  }
+
+  #[test]
+  fn test_availability_round_trips_with_the_old_boolean() {
+    let cases = [
+      (Availability::Available, "available", Some(true)),
+      (Availability::Taken, "taken", Some(false)),
+      (Availability::Reserved, "reserved", Some(false)),
+      (Availability::Unknown, "unknown", None),
+    ];
+    for (availability, id, available) in cases {
+      let original = AvailabilityResult { availability, ..result(None) };
+      let value = serde_json::to_value(&original).unwrap();
+      assert_eq!(value["availability"], id);
+      assert_eq!(value["available"], serde_json::json!(available));
+      assert_eq!(serde_json::from_value::<AvailabilityResult>(value).unwrap(), original);
+
+      let timed = TimedResult { result: original.clone(), duration_ms: 7 };
+      let json = serde_json::to_string(&timed).unwrap();
+      assert_eq!(serde_json::from_str::<TimedResult>(&json).unwrap().result, original);
+    }
+  }
+
+  #[test]
+  fn test_results_without_availability_fall_back_to_the_boolean() {
+    let old = r#"{"registry":"npm","name":"foo","available":false,"error":null}"#;
+    let parsed: AvailabilityResult = serde_json::from_str(old).unwrap();
+    assert_eq!(parsed.availability, Availability::Taken);
+    let old = r#"{"registry":"npm","name":"foo","available":null,"error":"timeout"}"#;
+    let parsed: AvailabilityResult = serde_json::from_str(old).unwrap();
+    assert_eq!(parsed.availability, Availability::Unknown);
+    assert_eq!(parsed.error.as_deref(), Some("timeout"));
+
+    // The new field wins over a stale boolean
+    let both = r#"{"registry":"npm","name":"foo","availability":"reserved","available":true,
+      "error":null}"#;
+    let parsed: AvailabilityResult = serde_json::from_str(both).unwrap();
+    assert_eq!(parsed.availability, Availability::Reserved);
+  }
+
+  #[test]
+  fn test_result_filter_cycles() {
+    let filter = ResultFilter::All;
+    assert_eq!(filter.next().next().next(), ResultFilter::Problems);
+    assert_eq!(filter.next().next().next().next(), ResultFilter::All);
+  }
+
+  #[test]
+  fn test_timed_result_flattens_duration() {
+    let timed = TimedResult {
+      result: result(Some(true)),
+      duration_ms: 42,
+    };
+    let value = serde_json::to_value(&timed).unwrap();
+    assert_eq!(value["registry"], "npm");
+    assert_eq!(value["duration_ms"], 42);
+  }
+
+  #[test]
+  fn test_display_order_follows_all() {
+    let mut registries = vec![RegistryType::DevDomain, RegistryType::GitHub, RegistryType::Npm];
+    registries.sort_by_key(|r| display_order(*r));
+    assert_eq!(registries, [RegistryType::Npm, RegistryType::GitHub, RegistryType::DevDomain]);
+  }
+
+  #[tokio::test]
+  async fn test_redirect_loop_fails_the_check() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(path("/loop"))
+      .respond_with(ResponseTemplate::new(302).insert_header("Location", "/loop"))
+      .mount(&server)
+      .await;
+
+    let result = npm::check("loop", &server.uri(), None).await;
+    assert_eq!(result.available(), None);
+    assert!(result.error.is_some());
+  }
+
+  #[tokio::test]
+  async fn test_identical_checks_in_flight_share_one_request() {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let upstream = Arc::new(AtomicUsize::new(0));
+    let check = |upstream: Arc<AtomicUsize>| async move {
+      upstream.fetch_add(1, Ordering::SeqCst);
+      tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+      TimedResult { result: result(Some(true)), duration_ms: 20 }
+    };
+    let key = || (RegistryType::Npm, "single-flight".to_string(), None);
+    let throttle = Throttle::new(2);
+
+    let checks = (0..8).map(|_| single_flight(key(), &throttle, check(upstream.clone())));
+    let results = futures::future::join_all(checks).await;
+    assert_eq!(upstream.load(Ordering::SeqCst), 1);
+    assert!(results.iter().all(|t| t.result.available() == Some(true)));
+
+    // Once finished, the next check goes upstream again
+    single_flight(key(), &throttle, check(upstream.clone())).await;
+    assert_eq!(upstream.load(Ordering::SeqCst), 2);
+
+    // Other owners' GitHub repos are separate checks
+    let github = |owner: &str| (RegistryType::GitHub, "foo".to_string(), Some(owner.to_string()));
+    let checks = ["alice", "bob"].map(|owner| {
+      single_flight(github(owner), &throttle, check(upstream.clone()))
+    });
+    futures::future::join_all(checks).await;
+    assert_eq!(upstream.load(Ordering::SeqCst), 4);
+  }
+
+  #[tokio::test]
+  async fn test_dropped_checks_free_their_entry_and_permit() {
+    let throttle = Throttle::new(1);
+    let key = || (RegistryType::Npm, "abandoned".to_string(), None);
+    let stuck = || async { futures::future::pending::<TimedResult>().await };
+
+    let mut leader = Box::pin(single_flight(key(), &throttle, stuck()));
+    let mut joiner = Box::pin(single_flight(key(), &throttle, stuck()));
+    assert!(futures::poll!(&mut leader).is_pending());
+    assert!(futures::poll!(&mut joiner).is_pending());
+    assert!(in_flight().contains_key(&key()));
+    assert_eq!(throttle.permits.available_permits(), 0);
+
+    drop(leader);
+    assert!(in_flight().contains_key(&key()), "the joiner still waits");
+    drop(joiner);
+    assert!(!in_flight().contains_key(&key()));
+    assert_eq!(throttle.permits.available_permits(), 1);
+
+    // A new check starts over instead of joining the abandoned one
+    let fresh = async { TimedResult { result: result(Some(true)), duration_ms: 0 } };
+    let timed = single_flight(key(), &throttle, fresh).await;
+    assert_eq!(timed.result.available(), Some(true));
+  }
+
+  #[test]
+  fn test_dedupe_keeps_the_first_of_each_registry_and_name() {
+    let taken = AvailabilityResult { availability: Availability::Taken, ..result(Some(true)) };
+    let crates = AvailabilityResult { registry: RegistryType::Crates, ..result(Some(true)) };
+    let mut results = vec![result(Some(true)), crates.clone(), taken];
+    dedupe(&mut results);
+    assert_eq!(results, [result(Some(true)), crates]);
+  }
+
+  #[test]
+  fn test_unknown_registry_lists_valid_ids() {
+    let err = "npmm".parse::<RegistryType>().unwrap_err();
+    let message = err.to_string();
+    assert!(message.contains("npmm"));
+    assert!(message.contains("crates"));
+    assert!(message.contains("dev_domain"));
+  }
+}
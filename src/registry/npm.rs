@@ -1,56 +1,136 @@
-use super::{AvailabilityResult, RegistryType};
+use super::{
+  http_client, redirect_detail, request_error, trace_response, Availability, AvailabilityResult,
+  RegistryType,
+};
 use reqwest::StatusCode;
+use std::time::Instant;
 
-const NPM_REGISTRY_URL: &str = "https://registry.npmjs.org";
+/// Default base URL, replaced by `endpoints.npm`
+pub const NPM_REGISTRY_URL: &str = "https://registry.npmjs.org";
+
+/// Website of the default registry, asked about names the registry hides
+pub const NPM_WEB_URL: &str = "https://www.npmjs.com";
+
+/// Detail of a name npm holds for security reasons
+pub const HELD: &str = "name held by npm security";
 
 /// Check if a package name is available on npm
 ///
 /// API: GET https://registry.npmjs.org/{package}
 /// - 200: Package exists (not available)
-/// - 404: Package not found (available)
-pub async fn check(name: &str) -> AvailabilityResult {
-  let url = format!("{}/{}", NPM_REGISTRY_URL, name);
+/// - 404: Package not found (available), unless `web` (the registry's
+///   website) still has a page for it at GET https://www.npmjs.com/package/{package},
+///   as it does for names npm took down and holds
+#[tracing::instrument(level = "debug", fields(registry = "npm"))]
+pub async fn check(name: &str, base: &str, web: Option<&str>) -> AvailabilityResult {
+  let url = format!("{}/{}", base, name);
+
+  let started = Instant::now();
+  let response = http_client().get(&url).send().await;
+  trace_response(&url, started, &response);
 
-  match reqwest::get(&url).await {
+  match response {
     Ok(response) => {
-      let available = match response.status() {
-        StatusCode::NOT_FOUND => Some(true),
-        StatusCode::OK => Some(false),
-        _ => None,
+      let (availability, detail) = match response.status() {
+        StatusCode::NOT_FOUND => match web {
+          Some(web) if has_page(name, web).await => {
+            (Availability::Reserved, Some(HELD.to_string()))
+          }
+          _ => (Availability::Available, None),
+        },
+        StatusCode::OK => (Availability::Taken, redirect_detail(&url, &response, name)),
+        _ => (Availability::Unknown, None),
       };
       AvailabilityResult {
         registry: RegistryType::Npm,
         name: name.to_string(),
-        available,
-        error: if available.is_none() {
+        availability,
+        error: if availability == Availability::Unknown {
           Some(format!("Unexpected status: {}", response.status()))
         } else {
           None
         },
+        detail,
+        qualifier: None,
       }
     }
     Err(e) => AvailabilityResult {
       registry: RegistryType::Npm,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: Availability::Unknown,
+      error: Some(request_error(e)),
+      detail: None,
+      qualifier: None,
     },
   }
 }
 
+/// Whether the website has a page for `name`; a failed request counts as no
+async fn has_page(name: &str, web: &str) -> bool {
+  let url = format!("{}/package/{}", web, name);
+  let started = Instant::now();
+  let response = http_client().get(&url).send().await;
+  trace_response(&url, started, &response);
+  response.is_ok_and(|r| r.status() == StatusCode::OK)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
   #[tokio::test]
+  #[ignore = "live: queries registry.npmjs.org"]
   async fn test_check_existing_package() {
-    let result = check("react").await;
-    assert_eq!(result.available, Some(false));
+    let result = check("react", NPM_REGISTRY_URL, Some(NPM_WEB_URL)).await;
+    assert_eq!(result.available(), Some(false));
   }
 
   #[tokio::test]
+  #[ignore = "live: queries registry.npmjs.org"]
   async fn test_check_nonexistent_package() {
-    let result = check("this-package-definitely-does-not-exist-xyz123abc").await;
-    assert_eq!(result.available, Some(true));
+    let name = "this-package-definitely-does-not-exist-xyz123abc";
+    let result = check(name, NPM_REGISTRY_URL, None).await;
+    assert_eq!(result.available(), Some(true));
+  }
+
+  #[tokio::test]
+  async fn test_redirect_to_another_name_is_taken() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(path("/Left-Pad"))
+      .respond_with(ResponseTemplate::new(301).insert_header("Location", "/left-pad"))
+      .mount(&server)
+      .await;
+    Mock::given(path("/left-pad")).respond_with(ResponseTemplate::new(200)).mount(&server).await;
+
+    let result = check("Left-Pad", &server.uri(), None).await;
+    assert_eq!(result.available(), Some(false));
+    assert_eq!(result.detail.as_deref(), Some("redirects to existing package left-pad"));
+
+    let result = check("left-pad", &server.uri(), None).await;
+    assert_eq!((result.available(), result.detail), (Some(false), None));
+  }
+
+  #[tokio::test]
+  async fn test_held_name_is_reserved() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    // Recorded from www.npmjs.com for a name held after a malware takedown
+    let recorded = include_str!("testdata/npm_security_holding.html");
+    Mock::given(path("/package/held-xyz"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(recorded, "text/html"))
+      .mount(&server)
+      .await;
+
+    let result = check("held-xyz", &server.uri(), Some(&server.uri())).await;
+    assert_eq!(result.availability, Availability::Reserved);
+    assert_eq!(result.detail.as_deref(), Some(HELD));
+
+    let result = check("free-xyz", &server.uri(), Some(&server.uri())).await;
+    assert_eq!((result.available(), result.detail), (Some(true), None));
   }
 }
@@ -0,0 +1,121 @@
+//! Canned results for demos and tests, with no network I/O
+//!
+//! Fixtures map a name to `false` (taken everywhere), `null` (every check
+//! fails) or an object of registry ids to those values; registries not
+//! listed are available. Domains are looked up by their full name, e.g.
+//! `"example.com": false`. Names without an entry are available.
+
+use super::{AvailabilityResult, RegistryType};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+const BUNDLED: &str = include_str!("offline_fixtures.json");
+
+static FIXTURES: OnceLock<Fixtures> = OnceLock::new();
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+enum Entry {
+  Everywhere(Option<bool>),
+  PerRegistry(HashMap<String, Option<bool>>),
+}
+
+/// Name to availability table consulted instead of the registries
+#[derive(Debug, Clone, Default)]
+pub struct Fixtures(HashMap<String, Entry>);
+
+impl Fixtures {
+  /// The table shipped with nbi, with a few well-known names taken
+  pub fn bundled() -> Self {
+    Self::parse(BUNDLED).expect("bundled fixtures are valid")
+  }
+
+  /// Read a fixtures JSON file
+  pub fn load(path: &Path) -> Result<Self> {
+    let content = std::fs::read_to_string(path)
+      .with_context(|| format!("Failed to read {}", path.display()))?;
+    Self::parse(&content).with_context(|| format!("Failed to parse {}", path.display()))
+  }
+
+  fn parse(json: &str) -> Result<Self> {
+    let entries: HashMap<String, Entry> = serde_json::from_str(json)?;
+    for (name, entry) in &entries {
+      if let Entry::PerRegistry(registries) = entry {
+        for id in registries.keys() {
+          id.parse::<RegistryType>().with_context(|| format!("In the entry for '{}'", name))?;
+        }
+      }
+    }
+    let entries = entries.into_iter().map(|(name, entry)| (name.to_lowercase(), entry));
+    Ok(Self(entries.collect()))
+  }
+
+  /// The canned result for `name` on `registry`; `name` is the full domain
+  /// for [`RegistryType::DevDomain`]
+  pub fn check(&self, registry: RegistryType, name: &str) -> AvailabilityResult {
+    let available = match self.0.get(&name.to_lowercase()) {
+      None => Some(true),
+      Some(Entry::Everywhere(available)) => *available,
+      Some(Entry::PerRegistry(registries)) => registries
+        .iter()
+        .find(|(id, _)| id.parse() == Ok(registry))
+        .map_or(Some(true), |(_, available)| *available),
+    };
+    AvailabilityResult {
+      registry,
+      name: name.to_string(),
+      availability: available.into(),
+      error: available.is_none().then(|| "Offline fixture marks this check as failed".into()),
+      detail: None,
+      qualifier: None,
+    }
+  }
+}
+
+/// Answer every check from `fixtures` from now on; only the first call counts
+pub fn enable(fixtures: Fixtures) {
+  let _ = FIXTURES.set(fixtures);
+}
+
+/// The fixtures in use, if offline mode is on
+pub fn fixtures() -> Option<&'static Fixtures> {
+  FIXTURES.get()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_entries_cover_every_registry_or_some() {
+    let fixtures = Fixtures::parse(
+      r#"{"Taken": false, "partial": {"npm": false, "pypi": null}, "broken": null}"#,
+    )
+    .unwrap();
+    let available = |registry, name| fixtures.check(registry, name).available();
+
+    assert_eq!(available(RegistryType::Crates, "taken"), Some(false));
+    assert_eq!(available(RegistryType::Npm, "partial"), Some(false));
+    assert_eq!(available(RegistryType::PyPi, "partial"), None);
+    assert_eq!(available(RegistryType::Crates, "partial"), Some(true));
+    assert_eq!(available(RegistryType::Npm, "unlisted"), Some(true));
+    let broken = fixtures.check(RegistryType::Npm, "broken");
+    assert!(broken.available().is_none() && broken.error.is_some());
+  }
+
+  #[test]
+  fn test_unknown_registry_ids_are_rejected() {
+    let err = Fixtures::parse(r#"{"foo": {"nmp": false}}"#).unwrap_err();
+    assert!(format!("{:#}", err).contains("'foo'"), "{:#}", err);
+  }
+
+  #[test]
+  fn test_bundled_fixtures_parse() {
+    let fixtures = Fixtures::bundled();
+    assert_eq!(fixtures.check(RegistryType::Crates, "serde").available(), Some(false));
+    assert_eq!(fixtures.check(RegistryType::DevDomain, "example.com").available(), Some(false));
+  }
+}
@@ -1,59 +1,160 @@
-use super::{AvailabilityResult, RegistryType};
+use super::{
+  http_client, redirect_detail, request_error, trace_response, Availability, AvailabilityResult,
+  RegistryType,
+};
 use reqwest::StatusCode;
+use std::time::Instant;
 
-const PYPI_SIMPLE_URL: &str = "https://pypi.org/simple";
+/// Default base URL, replaced by `endpoints.pypi`
+pub const PYPI_SIMPLE_URL: &str = "https://pypi.org/simple";
+
+/// Detail of a name /simple/ doesn't list that PyPI still won't accept
+pub const UNRELEASED: &str = "name registered without releases / blocked";
 
 /// Check if a package name is available on PyPI
 ///
 /// API: GET https://pypi.org/simple/{name}/
 /// - 200: Package exists (not available)
-/// - 404: Package not found (available)
-///
-/// Note: Using /simple/ endpoint as it correctly returns 404 for
-/// packages that are registered but have no releases
-pub async fn check(name: &str) -> AvailabilityResult {
-  let url = format!("{}/{}/", PYPI_SIMPLE_URL, name);
+/// - 404: No releases, so [`registered_without_releases`] decides
+#[tracing::instrument(level = "debug", fields(registry = "pypi"))]
+pub async fn check(name: &str, base: &str) -> AvailabilityResult {
+  let url = format!("{}/{}/", base, name);
+
+  let started = Instant::now();
+  let response = http_client().get(&url).send().await;
+  trace_response(&url, started, &response);
 
-  match reqwest::get(&url).await {
+  match response {
     Ok(response) => {
-      let available = match response.status() {
-        StatusCode::NOT_FOUND => Some(true),
-        StatusCode::OK => Some(false),
-        _ => None,
+      let (availability, detail) = match response.status() {
+        StatusCode::NOT_FOUND => {
+          if registered_without_releases(name, base).await {
+            (Availability::Reserved, Some(UNRELEASED.to_string()))
+          } else {
+            (Availability::Available, None)
+          }
+        }
+        StatusCode::OK => (Availability::Taken, redirect_detail(&url, &response, name)),
+        _ => (Availability::Unknown, None),
       };
       AvailabilityResult {
         registry: RegistryType::PyPi,
         name: name.to_string(),
-        available,
-        error: if available.is_none() {
+        availability,
+        error: if availability == Availability::Unknown {
           Some(format!("Unexpected status: {}", response.status()))
         } else {
           None
         },
+        detail,
+        qualifier: None,
       }
     }
     Err(e) => AvailabilityResult {
       registry: RegistryType::PyPi,
       name: name.to_string(),
-      available: None,
-      error: Some(e.to_string()),
+      availability: Availability::Unknown,
+      error: Some(request_error(e)),
+      detail: None,
+      qualifier: None,
     },
   }
 }
 
+/// Whether PyPI knows a project /simple/ has no releases for, which
+/// `twine upload` would then reject as a name conflict
+///
+/// API: GET https://pypi.org/pypi/{name}/json, then
+/// GET https://pypi.org/project/{name}/; a 200 from either means taken.
+/// Only asked for a `endpoints.pypi` ending in `/simple`, and a failed
+/// request leaves the name available.
+async fn registered_without_releases(name: &str, base: &str) -> bool {
+  let Some(site) = base.strip_suffix("/simple") else {
+    return false;
+  };
+  for url in [format!("{}/pypi/{}/json", site, name), format!("{}/project/{}/", site, name)] {
+    let started = Instant::now();
+    let response = http_client().get(&url).send().await;
+    trace_response(&url, started, &response);
+    if response.is_ok_and(|r| r.status() == StatusCode::OK) {
+      return true;
+    }
+  }
+  false
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
   #[tokio::test]
+  #[ignore = "live: queries pypi.org"]
   async fn test_check_existing_package() {
-    let result = check("requests").await;
-    assert_eq!(result.available, Some(false));
+    let result = check("requests", PYPI_SIMPLE_URL).await;
+    assert_eq!(result.available(), Some(false));
   }
 
   #[tokio::test]
+  #[ignore = "live: queries pypi.org"]
   async fn test_check_nonexistent_package() {
-    let result = check("this-package-definitely-does-not-exist-xyz123abc").await;
-    assert_eq!(result.available, Some(true));
+    let result = check("this-package-definitely-does-not-exist-xyz123abc", PYPI_SIMPLE_URL).await;
+    assert_eq!(result.available(), Some(true));
+  }
+
+  #[tokio::test]
+  async fn test_registered_without_releases_is_reserved() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    // Recorded from pypi.org for a project whose only release was deleted
+    let recorded = include_str!("testdata/pypi_no_releases.json");
+    Mock::given(path("/pypi/reserved-xyz/json"))
+      .respond_with(ResponseTemplate::new(200).set_body_raw(recorded, "application/json"))
+      .mount(&server)
+      .await;
+    Mock::given(path("/project/blocked-xyz/"))
+      .respond_with(ResponseTemplate::new(200))
+      .mount(&server)
+      .await;
+    let base = format!("{}/simple", server.uri());
+
+    for name in ["reserved-xyz", "blocked-xyz"] {
+      let result = check(name, &base).await;
+      assert_eq!(result.availability, Availability::Reserved, "{}", name);
+      assert_eq!(result.detail.as_deref(), Some(UNRELEASED));
+    }
+    let result = check("free-xyz", &base).await;
+    assert_eq!((result.available(), result.detail), (Some(true), None));
+  }
+
+  #[tokio::test]
+  async fn test_redirects_to_the_normalized_name() {
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    let redirect = |to: &str| ResponseTemplate::new(301).insert_header("Location", to);
+    Mock::given(path("/simple/Foo_Bar/"))
+      .respond_with(redirect("/simple/foo-bar/"))
+      .mount(&server)
+      .await;
+    Mock::given(path("/simple/foo-bar/"))
+      .respond_with(ResponseTemplate::new(200))
+      .mount(&server)
+      .await;
+    // Normalized, but nothing there
+    Mock::given(path("/simple/Free_Name/"))
+      .respond_with(redirect("/simple/free-name/"))
+      .mount(&server)
+      .await;
+    let base = format!("{}/simple", server.uri());
+
+    let result = check("Foo_Bar", &base).await;
+    assert_eq!(result.available(), Some(false));
+    assert_eq!(result.detail.as_deref(), Some("redirects to existing package foo-bar"));
+
+    let result = check("Free_Name", &base).await;
+    assert_eq!((result.available(), result.detail), (Some(true), None));
   }
 }
@@ -0,0 +1,172 @@
+use super::{
+  http_client, request_error, trace_response, Availability, AvailabilityResult, RegistryType,
+};
+use reqwest::StatusCode;
+use std::sync::{PoisonError, RwLock};
+use std::time::Instant;
+
+/// Default search backend, replaced by `endpoints.trademark`
+pub const USPTO_SEARCH_URL: &str = "https://tmsearch.uspto.gov/api-v1-0-0/tmsearch";
+
+/// Nice classes screened unless `trademark_classes` says otherwise:
+/// 9 (software) and 42 (software services)
+pub const DEFAULT_CLASSES: [u32; 2] = [9, 42];
+
+static CLASSES: RwLock<Option<Vec<u32>>> = RwLock::new(None);
+
+/// Screen against `classes` from now on; empty screens every class
+pub fn set_classes(classes: &[u32]) {
+  *CLASSES.write().unwrap_or_else(PoisonError::into_inner) = Some(classes.to_vec());
+}
+
+/// The classes set by [`set_classes`], else [`DEFAULT_CLASSES`]
+pub fn classes() -> Vec<u32> {
+  let classes = CLASSES.read().unwrap_or_else(PoisonError::into_inner);
+  classes.clone().unwrap_or_else(|| DEFAULT_CLASSES.to_vec())
+}
+
+/// Screen `name` against live US trademarks in `classes`
+///
+/// API: POST https://tmsearch.uspto.gov/api-v1-0-0/tmsearch with a search
+/// query for the word mark. This isn't a clearance search: an exact live
+/// mark in a screened class counts as a potential conflict, leaving the
+/// result unknown with "potential conflicts: N" as its detail.
+#[tracing::instrument(level = "debug", fields(registry = "trademark"))]
+pub async fn check(name: &str, base: &str, classes: &[u32]) -> AvailabilityResult {
+  let query = serde_json::json!({
+    "query": {
+      "bool": {
+        "must": [{ "match_phrase": { "wordmark": name } }],
+        "filter": [{ "term": { "alive": true } }],
+      }
+    },
+    "size": 100,
+  });
+
+  let client = http_client();
+  let started = Instant::now();
+  let response = client
+    .post(base)
+    .header("User-Agent", "nbi/0.1.0")
+    .json(&query)
+    .send()
+    .await;
+  trace_response(base, started, &response);
+
+  let failed = |error: String| AvailabilityResult {
+    registry: RegistryType::Trademark,
+    name: name.to_string(),
+    availability: Availability::Unknown,
+    error: Some(error),
+    detail: None,
+    qualifier: None,
+  };
+  let response = match response {
+    Ok(response) if response.status() == StatusCode::OK => response,
+    Ok(response) => return failed(format!("Unexpected status: {}", response.status())),
+    Err(e) => return failed(request_error(e)),
+  };
+  let json = match response.json::<serde_json::Value>().await {
+    Ok(json) => json,
+    Err(e) => return failed(format!("Parse error: {}", e)),
+  };
+
+  let (available, detail) = match conflicts(&json, name, classes) {
+    0 if classes.is_empty() => (Some(true), "no live marks".to_string()),
+    0 => (Some(true), format!("no live marks in classes {}", class_list(classes))),
+    n => (None, format!("potential conflicts: {}", n)),
+  };
+  AvailabilityResult {
+    registry: RegistryType::Trademark,
+    name: name.to_string(),
+    availability: available.into(),
+    error: None,
+    detail: Some(detail),
+    qualifier: None,
+  }
+}
+
+/// Live marks in `json` whose word mark is `name` and that cover one of
+/// `classes` (any class when it's empty)
+///
+/// Search hits also include marks that merely contain the name, so the
+/// word mark is compared here.
+fn conflicts(json: &serde_json::Value, name: &str, classes: &[u32]) -> usize {
+  let Some(hits) = json["hits"]["hits"].as_array() else {
+    return 0;
+  };
+  hits
+    .iter()
+    .filter_map(|hit| hit.get("source").or_else(|| hit.get("_source")))
+    .filter(|mark| mark["wordmark"].as_str().is_some_and(|w| w.trim().eq_ignore_ascii_case(name)))
+    .filter(|mark| mark["alive"].as_bool().unwrap_or(true))
+    .filter(|mark| {
+      // Classes come as "IC 009"; a mark without any is kept to be safe
+      let marked: Vec<u32> = mark["internationalClass"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|c| c.as_str()?.trim_start_matches(|c: char| !c.is_ascii_digit()).parse().ok())
+        .collect();
+      classes.is_empty() || marked.is_empty() || marked.iter().any(|c| classes.contains(c))
+    })
+    .count()
+}
+
+fn class_list(classes: &[u32]) -> String {
+  classes.iter().map(u32::to_string).collect::<Vec<_>>().join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use serde_json::json;
+
+  fn hits(marks: serde_json::Value) -> serde_json::Value {
+    let hits: Vec<_> = marks.as_array().unwrap().iter().map(|m| json!({ "source": m })).collect();
+    json!({ "hits": { "totalValue": hits.len(), "hits": hits } })
+  }
+
+  #[test]
+  fn test_conflicts_need_exact_live_marks_in_screened_classes() {
+    let json = hits(json!([
+      { "wordmark": "FERRITE", "alive": true, "internationalClass": ["IC 009"] },
+      { "wordmark": "Ferrite", "alive": true, "internationalClass": ["IC 042", "IC 035"] },
+      { "wordmark": "FERRITE", "alive": false, "internationalClass": ["IC 009"] },
+      { "wordmark": "FERRITE", "alive": true, "internationalClass": ["IC 025"] },
+      { "wordmark": "FERRITE CORE", "alive": true, "internationalClass": ["IC 009"] },
+    ]));
+    assert_eq!(conflicts(&json, "ferrite", &DEFAULT_CLASSES), 2);
+    assert_eq!(conflicts(&json, "ferrite", &[25]), 1);
+    assert_eq!(conflicts(&json, "ferrite", &[]), 3);
+    assert_eq!(conflicts(&json!({}), "ferrite", &[]), 0);
+  }
+
+  #[tokio::test]
+  async fn test_check_reports_conflicts_as_detail() {
+    use axum::{routing::post, Json, Router};
+
+    let search = Router::new().route(
+      "/tmsearch",
+      post(|Json(query): Json<serde_json::Value>| async move {
+        let name = query["query"]["bool"]["must"][0]["match_phrase"]["wordmark"].clone();
+        let marks = if name == "taken" {
+          json!([{ "wordmark": "TAKEN", "alive": true, "internationalClass": ["IC 042"] }])
+        } else {
+          json!([])
+        };
+        Json(hits(marks))
+      }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let base = format!("http://{}/tmsearch", listener.local_addr().unwrap());
+    tokio::spawn(async move { axum::serve(listener, search).await.unwrap() });
+
+    let taken = check("taken", &base, &DEFAULT_CLASSES).await;
+    assert_eq!((taken.available(), taken.error), (None, None));
+    assert_eq!(taken.detail.as_deref(), Some("potential conflicts: 1"));
+    let free = check("free", &base, &DEFAULT_CLASSES).await;
+    assert_eq!(free.available(), Some(true));
+    assert_eq!(free.detail.as_deref(), Some("no live marks in classes 9, 42"));
+  }
+}
@@ -0,0 +1,257 @@
+//! Shareable HTML and Markdown reports comparing candidate names
+
+use crate::compare::Candidate;
+use crate::output::{comparison_matrix, free_count, status_label, status_symbol};
+use crate::registry::{Availability, AvailabilityResult};
+use clap::ValueEnum;
+use std::fmt::Write;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+  /// Self-contained HTML page
+  Html,
+  /// Markdown document
+  Md,
+}
+
+impl ReportFormat {
+  /// Markdown for `.md` files, HTML otherwise
+  pub fn for_path(path: &Path) -> Self {
+    match path.extension().and_then(|e| e.to_str()) {
+      Some(ext) if ext.eq_ignore_ascii_case("md") => ReportFormat::Md,
+      _ => ReportFormat::Html,
+    }
+  }
+}
+
+const HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+<style>
+  body { font-family: system-ui, sans-serif; margin: 2rem; color: #222; }
+  table { border-collapse: collapse; }
+  th, td { border: 1px solid #ddd; padding: .4rem .8rem; text-align: center; }
+  th:first-child, td:first-child { text-align: left; }
+  td a { color: inherit; text-decoration: none; display: block; }
+  .available { background: #e6f4ea; }
+  .taken { background: #fce8e6; }
+  .reserved { background: #e8f0fe; }
+  .unknown { background: #fef7e0; }
+  .free td { font-weight: bold; }
+  p, footer { color: #666; }
+</style>
+</head>
+<body>
+<h1>{{title}}</h1>
+<p>Checked {{checked_at}}</p>
+{{table}}
+<footer>✓ available · ✗ taken · ⊘ reserved · ? check failed. Candidates are ranked best
+first; each mark links to the registry page.</footer>
+</body>
+</html>
+"#;
+
+const MARKDOWN_TEMPLATE: &str = r#"# {{title}}
+
+Checked {{checked_at}}
+
+{{table}}
+✓ available · ✗ taken · ⊘ reserved · ? check failed. Candidates are ranked best first; each
+mark links to the registry page.
+"#;
+
+/// Render `candidates`, checked at `checked_at` (unix seconds), as a report
+pub fn render(format: ReportFormat, candidates: &[Candidate], checked_at: u64) -> String {
+  let names: Vec<&str> = candidates.iter().map(|c| c.name.as_str()).collect();
+  let title = title(&names);
+  let (template, title, table) = match format {
+    ReportFormat::Html => (HTML_TEMPLATE, escape_html(&title), html_table(candidates)),
+    ReportFormat::Md => (MARKDOWN_TEMPLATE, markdown_cell(&title), markdown_table(candidates)),
+  };
+  [("{{title}}", title), ("{{checked_at}}", utc_timestamp(checked_at)), ("{{table}}", table)]
+    .into_iter()
+    .fold(template.to_string(), |content, (placeholder, value)| {
+      content.replace(placeholder, &value)
+    })
+}
+
+fn title(names: &[&str]) -> String {
+  format!("Name availability: {}", names.join(", "))
+}
+
+fn html_table(candidates: &[Candidate]) -> String {
+  let mut out = String::from("<table>\n<thead><tr><th>Registry</th>");
+  for candidate in candidates {
+    let _ = write!(out, "<th>{}</th>", escape_html(&candidate.name));
+  }
+  out.push_str("</tr></thead>\n<tbody>\n");
+  for (label, cells) in comparison_matrix(candidates) {
+    let _ = write!(out, "<tr><td>{}</td>", escape_html(&label));
+    for cell in cells {
+      match cell {
+        Some(r) => {
+          let _ = write!(
+            out,
+            r#"<td class="{}"><a href="{}" title="{}">{}</a></td>"#,
+            status_label(r.availability).to_lowercase(),
+            escape_html(&r.page_url()),
+            escape_html(&describe(r)),
+            status_symbol(r.availability),
+          );
+        }
+        None => out.push_str("<td></td>"),
+      }
+    }
+    out.push_str("</tr>\n");
+  }
+  out.push_str(r#"<tr class="free"><td>Free</td>"#);
+  for candidate in candidates {
+    let _ = write!(out, "<td>{}</td>", free_count(candidate));
+  }
+  out.push_str("</tr>\n</tbody>\n</table>");
+  out
+}
+
+fn markdown_table(candidates: &[Candidate]) -> String {
+  let mut out = String::from("| Registry |");
+  for candidate in candidates {
+    let _ = write!(out, " {} |", markdown_cell(&candidate.name));
+  }
+  out.push_str("\n|---|");
+  out.push_str(&"---|".repeat(candidates.len()));
+  out.push('\n');
+  for (label, cells) in comparison_matrix(candidates) {
+    let _ = write!(out, "| {} |", markdown_cell(&label));
+    for cell in cells {
+      match cell {
+        Some(r) => {
+          let url = r.page_url();
+          let _ = write!(out, " [{}]({}) |", status_symbol(r.availability), url);
+        }
+        None => out.push_str("  |"),
+      }
+    }
+    out.push('\n');
+  }
+  out.push_str("| **Free** |");
+  for candidate in candidates {
+    let _ = write!(out, " **{}** |", free_count(candidate));
+  }
+  out.push('\n');
+  out
+}
+
+/// Tooltip for a cell: the status, and the error when the check failed or
+/// why a reserved name can't be had
+fn describe(r: &AvailabilityResult) -> String {
+  match (&r.error, r.availability, &r.detail) {
+    (Some(error), _, _) => format!("{}: {}", status_label(r.availability), error),
+    (None, Availability::Reserved, Some(detail)) => format!("Reserved: {}", detail),
+    _ => status_label(r.availability).to_string(),
+  }
+}
+
+fn escape_html(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+    .replace('\'', "&#39;")
+}
+
+fn markdown_cell(value: &str) -> String {
+  value.replace('|', "\\|").replace('<', "&lt;")
+}
+
+/// "2026-10-16 09:30 UTC" for a unix time
+fn utc_timestamp(secs: u64) -> String {
+  let (days, rest) = (secs / 86_400, secs % 86_400);
+  // Hinnant's civil_from_days
+  let z = days as i64 + 719_468;
+  let era = z.div_euclid(146_097);
+  let doe = z - era * 146_097;
+  let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+  let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+  let mp = (5 * doy + 2) / 153;
+  let day = doy - (153 * mp + 2) / 5 + 1;
+  let month = if mp < 10 { mp + 3 } else { mp - 9 };
+  let year = yoe + era * 400 + i64::from(month <= 2);
+  format!(
+    "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+    year,
+    month,
+    day,
+    rest / 3600,
+    rest % 3600 / 60
+  )
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::{NameResults, RegistryType, TimedResult};
+
+  /// 2026-10-16 09:30 UTC
+  const CHECKED_AT: u64 = 1_792_143_000;
+
+  fn result(name: &str, registry: RegistryType, available: Option<bool>) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: name.to_string(),
+      availability: available.into(),
+      error: available.is_none().then(|| "timed out".to_string()),
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  fn candidate(name: &str, statuses: [Option<bool>; 3], domain: Option<bool>) -> Candidate {
+    let registries = [RegistryType::Npm, RegistryType::Crates, RegistryType::PyPi];
+    let results = registries
+      .into_iter()
+      .zip(statuses)
+      .map(|(registry, available)| TimedResult {
+        result: result(name, registry, available),
+        duration_ms: 100,
+      })
+      .collect();
+    let domains = vec![result(&format!("{}.com", name), RegistryType::DevDomain, domain)];
+    Candidate::new(NameResults { name: name.to_string(), results }, Some(domains))
+  }
+
+  fn candidates() -> Vec<Candidate> {
+    vec![
+      candidate("ferrite", [Some(true), Some(true), None], Some(true)),
+      candidate("rust<le>", [Some(false), Some(true), Some(true)], Some(false)),
+    ]
+  }
+
+  #[test]
+  fn test_html_report() {
+    insta::assert_snapshot!(render(ReportFormat::Html, &candidates(), CHECKED_AT));
+  }
+
+  #[test]
+  fn test_markdown_report() {
+    insta::assert_snapshot!(render(ReportFormat::Md, &candidates(), CHECKED_AT));
+  }
+
+  #[test]
+  fn test_utc_timestamp() {
+    assert_eq!(utc_timestamp(0), "1970-01-01 00:00 UTC");
+    assert_eq!(utc_timestamp(CHECKED_AT), "2026-10-16 09:30 UTC");
+    assert_eq!(utc_timestamp(951_782_400), "2000-02-29 00:00 UTC");
+  }
+
+  #[test]
+  fn test_format_follows_extension() {
+    assert_eq!(ReportFormat::for_path(Path::new("out/report.MD")), ReportFormat::Md);
+    assert_eq!(ReportFormat::for_path(Path::new("report.html")), ReportFormat::Html);
+    assert_eq!(ReportFormat::for_path(Path::new("report")), ReportFormat::Html);
+  }
+}
@@ -1,141 +1,977 @@
 use axum::{
-  http::StatusCode,
-  response::{Html, IntoResponse},
+  extract::{FromRef, State},
+  http::{header, HeaderMap, StatusCode},
+  response::sse::{Event, KeepAlive, Sse},
+  response::{IntoResponse, Response},
   Json,
 };
+use futures::stream::{self, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-use crate::config::{Config, RegistrySettings};
-use crate::registry::{self, AvailabilityResult};
+use super::assets::Assets;
+use super::cache::ResultCache;
+use super::error::{ApiError, ApiJson, ApiQuery};
+use crate::api_types::{
+  Cached, CheckRequest, CheckResponse, DomainRequest, DomainResponse, DomainResult,
+  FullDomainRequest, RegistrySelection,
+};
+use crate::cli_commands::CheckSummary;
+use crate::config::{Config, ConfigSource, RegistrySettings};
+use crate::output::{ResultCounts, Slowest};
+use crate::registry::github::{self, GitHubError, ManifestType, RepoOptions};
+use crate::registry::{self, RegistryType, TimedResult, UnknownRegistry};
+
+/// State shared by the API handlers
+#[derive(Clone, Default)]
+pub struct AppState {
+  pub config: Arc<ConfigSource>,
+  pub register: Arc<RegisterLimiter>,
+  pub cache: Arc<ResultCache>,
+  pub assets: Arc<Assets>,
+}
+
+impl FromRef<AppState> for Arc<ConfigSource> {
+  fn from_ref(state: &AppState) -> Self {
+    state.config.clone()
+  }
+}
+
+impl FromRef<AppState> for Arc<RegisterLimiter> {
+  fn from_ref(state: &AppState) -> Self {
+    state.register.clone()
+  }
+}
+
+impl FromRef<AppState> for Arc<ResultCache> {
+  fn from_ref(state: &AppState) -> Self {
+    state.cache.clone()
+  }
+}
+
+impl FromRef<AppState> for Arc<Assets> {
+  fn from_ref(state: &AppState) -> Self {
+    state.assets.clone()
+  }
+}
+
+/// OpenAPI 3 description of every route
+pub async fn openapi() -> Json<serde_json::Value> {
+  Json(openapi_spec())
+}
+
+/// The embedded spec, stamped with this build's version
+fn openapi_spec() -> serde_json::Value {
+  let mut spec: serde_json::Value = serde_json::from_str(include_str!("../../static/openapi.json"))
+    .expect("static/openapi.json is valid JSON");
+  spec["info"]["version"] = env!("CARGO_PKG_VERSION").into();
+  spec
+}
+
+impl CheckRequest {
+  /// Resolve the requested registry selection into settings
+  fn settings(&self) -> Result<RegistrySettings, ApiError> {
+    let exclude = parse_ids(&self.exclude, "exclude")?;
+    Ok(match &self.registries {
+      Some(RegistrySelection::Ids(ids)) => {
+        RegistrySettings::default().filtered(&parse_ids(ids, "registries")?, &exclude)
+      }
+      Some(RegistrySelection::Settings(settings)) => settings.filtered(&[], &exclude),
+      None => RegistrySettings::default().filtered(&[], &exclude),
+    })
+  }
+}
+
+fn parse_ids(ids: &[String], field: &'static str) -> Result<Vec<RegistryType>, ApiError> {
+  ids
+    .iter()
+    .map(|id| id.parse())
+    .collect::<Result<_, UnknownRegistry>>()
+    .map_err(|e| ApiError::invalid(field, "invalid_registry", e))
+}
+
+/// Longest name accepted: npm's limit, the most generous of the registries
+const MAX_NAME_LEN: usize = 214;
 
-/// Index page with embedded React app
-pub async fn index() -> Html<&'static str> {
-  Html(include_str!("../../static/index.html"))
+fn validate_name(name: &str) -> Result<(), ApiError> {
+  if name.trim().is_empty() {
+    return Err(ApiError::invalid("name", "invalid_name", "name must not be empty"));
+  }
+  let len = name.chars().count();
+  if len > MAX_NAME_LEN {
+    let message = format!("name is {} characters, at most {} allowed", len, MAX_NAME_LEN);
+    return Err(ApiError::invalid("name", "invalid_name", message));
+  }
+  Ok(())
 }
 
+/// Query-string form of [`CheckRequest`], e.g.
+/// `?name=foo&registries=npm,crates&exclude=pypi`
 #[derive(Deserialize)]
-pub struct CheckRequest {
+pub struct CheckQuery {
   pub name: String,
+  /// Comma-separated registry identifiers; every registry when omitted
+  #[serde(default)]
+  pub registries: Option<String>,
+  /// Comma-separated registry identifiers to skip
   #[serde(default)]
-  pub registries: Option<RegistrySettings>,
+  pub exclude: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct CheckResponse {
-  pub name: String,
-  pub results: Vec<AvailabilityResult>,
+impl From<CheckQuery> for CheckRequest {
+  fn from(query: CheckQuery) -> Self {
+    Self {
+      name: query.name,
+      registries: query.registries.as_deref().map(|ids| RegistrySelection::Ids(split_list(ids))),
+      exclude: split_list(query.exclude.as_deref().unwrap_or_default()),
+    }
+  }
+}
+
+/// Entries of a comma-separated query parameter, ignoring blanks
+fn split_list(list: &str) -> Vec<String> {
+  list.split(',').map(str::trim).filter(|s| !s.is_empty()).map(String::from).collect()
 }
 
 /// Check package name availability
-pub async fn check_availability(Json(req): Json<CheckRequest>) -> impl IntoResponse {
-  let settings = req.registries.unwrap_or_default();
-  let results = registry::check_all(&req.name, &settings).await;
+pub async fn check_availability(
+  State(config): State<Arc<ConfigSource>>,
+  State(cache): State<Arc<ResultCache>>,
+  ApiJson(req): ApiJson<CheckRequest>,
+) -> Result<Json<CheckResponse>, ApiError> {
+  check(&config, &cache, req).await
+}
 
-  Json(CheckResponse {
+/// `GET` form of [`check_availability`] for curl and bookmarks
+pub async fn check_availability_query(
+  State(config): State<Arc<ConfigSource>>,
+  State(cache): State<Arc<ResultCache>>,
+  ApiQuery(query): ApiQuery<CheckQuery>,
+) -> Result<Json<CheckResponse>, ApiError> {
+  check(&config, &cache, query.into()).await
+}
+
+/// Check the registries `cache` has no fresh result for, and cache those
+pub(super) async fn check(
+  config: &ConfigSource,
+  cache: &ResultCache,
+  req: CheckRequest,
+) -> Result<Json<CheckResponse>, ApiError> {
+  validate_name(&req.name)?;
+  let settings = req.settings()?;
+  let (hits, misses) = cache.lookup(&req.name, &settings);
+  let github = load_config(config)?.github_account(&misses).await;
+  let fresh = registry::check_all(&req.name, &misses, &github).await;
+  for result in &fresh {
+    cache.insert(&req.name, result);
+  }
+
+  let hits = hits.into_iter().map(|result| Cached { result, cached: true });
+  let fresh = fresh.into_iter().map(|result| Cached { result, cached: false });
+  let mut results: Vec<_> = hits.chain(fresh).collect();
+  results.sort_by_key(|r| registry::display_order(r.result.registry));
+
+  Ok(Json(CheckResponse {
     name: req.name,
     results,
+  }))
+}
+
+/// How often an idle check stream sends a comment so proxies keep it open
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Stream check results as Server-Sent Events
+///
+/// Sends a `result` event as each registry finishes and a closing `summary`
+/// event. A client disconnecting drops the stream, cancelling the checks.
+pub async fn check_availability_stream(
+  State(config): State<Arc<ConfigSource>>,
+  State(cache): State<Arc<ResultCache>>,
+  ApiQuery(query): ApiQuery<CheckQuery>,
+) -> Result<Response, ApiError> {
+  let req = CheckRequest::from(query);
+  validate_name(&req.name)?;
+  let settings = req.settings()?;
+  let (hits, misses) = cache.lookup(&req.name, &settings);
+  let github = load_config(&config)?.github_account(&misses).await;
+  let hits = hits.into_iter().map(|result| TimedResult { result, duration_ms: 0 });
+  let hits = stream::iter(hits.map(|result| Cached { result, cached: true }));
+  let fresh = registry::check_stream(&req.name, &misses, &github).map(move |result| {
+    cache.insert(&req.name, &result.result);
+    Cached { result, cached: false }
+  });
+  let events = Sse::new(check_events(hits.chain(fresh)))
+    .keep_alive(KeepAlive::new().interval(HEARTBEAT_INTERVAL).text("heartbeat"));
+  Ok(events.into_response())
+}
+
+/// A `result` event per finished check, then a `summary` event with counts
+fn check_events(
+  results: impl Stream<Item = Cached<TimedResult>> + Send + 'static,
+) -> impl Stream<Item = Result<Event, axum::Error>> {
+  let state = Some((Box::pin(results), Vec::new()));
+  stream::unfold(state, |state| async move {
+    let (mut results, mut finished) = state?;
+    match results.next().await {
+      Some(timed) => {
+        let event = Event::default().event("result").json_data(&timed);
+        finished.push(timed);
+        Some((event, Some((results, finished))))
+      }
+      None => {
+        let results = || finished.iter().map(|c: &Cached<TimedResult>| &c.result.result);
+        let mut counts = ResultCounts::default();
+        for result in results() {
+          counts.add(result.availability);
+        }
+        let status = CheckSummary::from_results(results()).label();
+        // Cached results weren't timed, so they can't be the slowest
+        let timed = finished.iter().filter(|c| !c.cached).map(|c| &c.result);
+        let slowest = registry::slowest(timed).map(Slowest::from);
+        let summary = serde_json::json!({ "counts": counts, "status": status, "slowest": slowest });
+        Some((Event::default().event("summary").json_data(summary), None))
+      }
+    }
   })
 }
 
+/// Query-string form of [`DomainRequest`], e.g. `?name=foo&tlds=com,dev`
 #[derive(Deserialize)]
-pub struct DomainRequest {
+pub struct DomainQuery {
   pub name: String,
-  pub tlds: Vec<String>,
+  /// Comma-separated TLDs or `@group` presets
+  #[serde(default)]
+  pub tlds: Option<String>,
 }
 
-#[derive(Serialize)]
-pub struct DomainResponse {
-  pub name: String,
-  pub results: Vec<DomainResult>,
+impl From<DomainQuery> for DomainRequest {
+  fn from(query: DomainQuery) -> Self {
+    Self { name: query.name, tlds: query.tlds.as_deref().map(split_list) }
+  }
 }
 
-#[derive(Serialize)]
-pub struct DomainResult {
-  pub domain: String,
-  pub available: Option<bool>,
-  pub error: Option<String>,
+/// Check domain availability across multiple TLDs
+pub async fn check_domain(
+  State(config): State<Arc<ConfigSource>>,
+  ApiJson(req): ApiJson<DomainRequest>,
+) -> Result<Json<DomainResponse>, ApiError> {
+  domain(&config, req).await
 }
 
-/// Check domain availability across multiple TLDs
-pub async fn check_domain(Json(req): Json<DomainRequest>) -> impl IntoResponse {
-  use crate::registry::domain::check_tld;
+/// `GET` form of [`check_domain`] for curl and bookmarks
+pub async fn check_domain_query(
+  State(config): State<Arc<ConfigSource>>,
+  ApiQuery(query): ApiQuery<DomainQuery>,
+) -> Result<Json<DomainResponse>, ApiError> {
+  domain(&config, query.into()).await
+}
 
-  let mut results = Vec::new();
+async fn domain(
+  source: &ConfigSource,
+  req: DomainRequest,
+) -> Result<Json<DomainResponse>, ApiError> {
+  use crate::registry::domain::check_multiple_tlds;
 
-  for tld in &req.tlds {
-    let domain = format!("{}.{}", req.name, tld);
-    let result = check_tld(&req.name, tld).await;
-    results.push(DomainResult {
-      domain,
-      available: result.available,
-      error: result.error,
-    });
-  }
+  validate_name(&req.name)?;
+  let config = load_config(source)?;
+  let tlds = config
+    .expand_tlds(req.tlds.as_ref().unwrap_or(&config.default_tlds))
+    .map_err(|e| ApiError::invalid("tlds", "invalid_tld", e))?;
+  check_domain_count(tlds.len(), "tlds")?;
+  let results = check_multiple_tlds(&req.name, &tlds).await;
 
-  Json(DomainResponse {
+  Ok(Json(DomainResponse {
     name: req.name,
-    results,
+    results: results.into_iter().map(DomainResult::from).collect(),
+  }))
+}
+
+/// Domains one request may check, so a single call can't fan out unbounded
+const MAX_DOMAINS_PER_REQUEST: usize = 50;
+
+fn check_domain_count(count: usize, field: &'static str) -> Result<(), ApiError> {
+  if count > MAX_DOMAINS_PER_REQUEST {
+    let message =
+      format!("Too many domains ({}), at most {} per request", count, MAX_DOMAINS_PER_REQUEST);
+    return Err(ApiError::invalid(field, "too_many_domains", message));
+  }
+  Ok(())
+}
+
+/// Check full domain availability (e.g., banana.wiki)
+pub async fn check_full_domains(
+  ApiJson(req): ApiJson<FullDomainRequest>,
+) -> Result<Json<DomainResponse>, ApiError> {
+  check_domain_count(req.domains.len(), "domains")?;
+  let results = registry::domain::check_full_domains(&req.domains).await;
+
+  Ok(Json(DomainResponse {
+    name: req.domains.join(", "),
+    results: results.into_iter().map(DomainResult::from).collect(),
+  }))
+}
+
+/// The server's config, or a 500 naming what's wrong with the file, so a
+/// broken `--config` or missing `--profile` isn't quietly replaced by defaults
+fn load_config(source: &ConfigSource) -> Result<Config, ApiError> {
+  source.load().map_err(|e| ApiError::internal(format!("Failed to load config: {:#}", e)))
+}
+
+/// Get current config
+pub async fn get_config(
+  State(source): State<Arc<ConfigSource>>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+  let config = load_config(&source)?;
+  let value = serde_json::to_value(config)
+    .map_err(|e| ApiError::internal(format!("Failed to serialize config: {}", e)))?;
+  Ok(Json(value))
+}
+
+/// Liveness probe for load balancers and reverse proxies
+pub async fn health() -> Json<serde_json::Value> {
+  Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// Prometheus scrape target: request counts, check outcomes and latency
+pub async fn metrics() -> Response {
+  let content_type = [(header::CONTENT_TYPE, "text/plain; version=0.0.4")];
+  (content_type, crate::metrics::render().unwrap_or_default()).into_response()
+}
+
+#[derive(Serialize)]
+pub struct VersionResponse {
+  pub version: &'static str,
+  /// Short commit hash, when built from a git checkout
+  pub git_hash: Option<&'static str>,
+}
+
+pub async fn version() -> Json<VersionResponse> {
+  Json(VersionResponse {
+    version: env!("CARGO_PKG_VERSION"),
+    git_hash: option_env!("NBI_GIT_HASH"),
   })
 }
 
+#[derive(Serialize)]
+pub struct RegistriesResponse {
+  pub registries: Vec<RegistryInfo>,
+}
+
+#[derive(Serialize)]
+pub struct RegistryInfo {
+  pub id: &'static str,
+  pub name: String,
+  pub enabled: bool,
+}
+
+/// Every supported registry, in display order, and whether config enables it
+pub async fn registries(
+  State(source): State<Arc<ConfigSource>>,
+) -> Result<Json<RegistriesResponse>, ApiError> {
+  let config = load_config(&source)?;
+  Ok(Json(RegistriesResponse { registries: registry_list(&config.registries) }))
+}
+
+fn registry_list(settings: &RegistrySettings) -> Vec<RegistryInfo> {
+  RegistryType::ALL
+    .iter()
+    .map(|r| RegistryInfo { id: r.id(), name: r.to_string(), enabled: settings.is_enabled(*r) })
+    .collect()
+}
+
+/// Save config, updating only the fields present in the request body
+///
+/// A config file that fails to load is reported rather than overwritten.
+pub async fn save_config(
+  State(source): State<Arc<ConfigSource>>,
+  ApiJson(patch): ApiJson<serde_json::Value>,
+) -> Result<Json<serde_json::Value>, ApiError> {
+  let mut config = load_config(&source)?;
+  let patch = match toml::Value::try_from(patch) {
+    Ok(toml::Value::Table(patch)) => patch,
+    Ok(_) => {
+      return Err(ApiError::bad_request("invalid_config", "Expected a JSON object of config fields"))
+    }
+    Err(e) => {
+      return Err(ApiError::bad_request("invalid_config", format!("Invalid config value: {}", e)))
+    }
+  };
+  config.merge(patch).map_err(|e| ApiError::bad_request("invalid_config", e))?;
+
+  source
+    .save(&config)
+    .map_err(|e| ApiError::internal(format!("Failed to save config: {:#}", e)))?;
+  crate::registry::configure(&config);
+  Ok(Json(serde_json::json!({ "success": true })))
+}
+
+#[derive(Serialize)]
+pub struct ConfigSchemaResponse {
+  pub fields: Vec<ConfigField>,
+}
+
+#[derive(Serialize)]
+pub struct ConfigField {
+  /// Dotted path, e.g. `registries.npm`; nest it as objects when saving
+  pub key: String,
+  #[serde(rename = "type")]
+  pub kind: &'static str,
+  pub default: serde_json::Value,
+}
+
+/// Every field `POST /api/config` accepts, with its type and default
+pub async fn config_schema() -> Json<ConfigSchemaResponse> {
+  let fields = Config::fields()
+    .into_iter()
+    .map(|(key, default)| ConfigField {
+      key,
+      kind: default.type_str(),
+      default: serde_json::to_value(default).unwrap_or_default(),
+    })
+    .collect();
+  Json(ConfigSchemaResponse { fields })
+}
+
+/// Drop every cached check result
+pub async fn clear_cache(State(cache): State<Arc<ResultCache>>) -> Json<serde_json::Value> {
+  Json(serde_json::json!({ "cleared": cache.clear() }))
+}
+
 #[derive(Deserialize)]
-pub struct FullDomainRequest {
-  pub domains: Vec<String>,
+pub struct RegisterRequest {
+  pub name: String,
+  /// One of "github", "npm", "crates", "pypi"
+  pub registry: String,
+  #[serde(default)]
+  pub private: bool,
+  #[serde(default)]
+  pub description: Option<String>,
 }
 
-/// Check full domain availability (e.g., banana.wiki)
-pub async fn check_full_domains(Json(req): Json<FullDomainRequest>) -> impl IntoResponse {
-  use crate::registry::domain::check_full_domain;
+#[derive(Serialize)]
+pub struct RegisterResponse {
+  pub name: String,
+  pub registry: RegistryType,
+  pub url: String,
+}
 
-  let mut results = Vec::new();
+/// Caps how many registrations the server starts per time window, so a
+/// runaway page can't create repos in bulk
+pub struct RegisterLimiter {
+  max: usize,
+  window: Duration,
+  recent: Mutex<VecDeque<Instant>>,
+}
 
-  for domain in &req.domains {
-    let result = check_full_domain(domain).await;
-    results.push(DomainResult {
-      domain: domain.clone(),
-      available: result.available,
-      error: result.error,
-    });
+impl Default for RegisterLimiter {
+  fn default() -> Self {
+    Self::new(5, Duration::from_secs(60))
   }
+}
 
-  Json(DomainResponse {
-    name: req.domains.join(", "),
-    results,
-  })
+impl RegisterLimiter {
+  pub fn new(max: usize, window: Duration) -> Self {
+    Self { max, window, recent: Mutex::new(VecDeque::new()) }
+  }
+
+  /// Count an attempt, or say how long until the next one is allowed
+  fn acquire(&self) -> Result<(), Duration> {
+    let now = Instant::now();
+    let mut recent = self.recent.lock().unwrap_or_else(|e| e.into_inner());
+    while recent.front().is_some_and(|t| now.duration_since(*t) >= self.window) {
+      recent.pop_front();
+    }
+    if recent.len() >= self.max {
+      let oldest = recent.front().copied().unwrap_or(now);
+      return Err(self.window.saturating_sub(now.duration_since(oldest)));
+    }
+    recent.push_back(now);
+    Ok(())
+  }
 }
 
-/// Get current config
-pub async fn get_config() -> impl IntoResponse {
-  match Config::load() {
-    Ok(config) => match serde_json::to_value(config) {
-      Ok(value) => (StatusCode::OK, Json(value)),
-      Err(e) => (
-        StatusCode::INTERNAL_SERVER_ERROR,
-        Json(serde_json::json!({ "error": format!("Failed to serialize config: {}", e) })),
-      ),
-    },
-    Err(e) => (
-      StatusCode::INTERNAL_SERVER_ERROR,
-      Json(serde_json::json!({ "error": e.to_string() })),
-    ),
+/// Reserve a name by creating its GitHub repo
+///
+/// Uses the token from an `Authorization: Bearer` header, or else the
+/// server's own (always, when the server requires an access token).
+/// Errors carry the [`GitHubError`] kind and details.
+pub async fn register(
+  State(source): State<Arc<ConfigSource>>,
+  State(limiter): State<Arc<RegisterLimiter>>,
+  headers: HeaderMap,
+  ApiJson(req): ApiJson<RegisterRequest>,
+) -> Response {
+  if let Err(wait) = limiter.acquire() {
+    return too_many_requests("registrations", wait);
+  }
+
+  if let Err(e) = validate_name(&req.name) {
+    return e.into_response();
+  }
+  let registry = match req.registry.parse::<RegistryType>() {
+    Ok(r) if r == RegistryType::GitHub || ManifestType::for_registry(r).is_some() => r,
+    _ => {
+      let message =
+        format!("Can't register on '{}' (valid: github, npm, crates, pypi)", req.registry);
+      return ApiError::invalid("registry", "invalid_registry", message).into_response();
+    }
+  };
+
+  let config = match load_config(&source) {
+    Ok(config) => config,
+    Err(e) => return e.into_response(),
+  };
+  let Some(token) = bearer_token(&headers).or_else(|| config.get_github_token()) else {
+    return github_error_response(GitHubError::AuthRequired);
+  };
+  let options = config.with_repo_defaults(RepoOptions {
+    description: req.description.filter(|d| !d.trim().is_empty()),
+    private: req.private,
+    ..RepoOptions::default()
+  });
+
+  let manifest = ManifestType::for_registry(registry);
+  let progress = github::Progress::default();
+  match github::reserve(&req.name, manifest, &options, &token, &progress).await {
+    Ok(url) => Json(RegisterResponse { name: req.name, registry, url }).into_response(),
+    Err(e) => github_error_response(e),
   }
 }
 
-#[derive(Deserialize)]
-pub struct SaveConfigRequest {
-  pub registries: RegistrySettings,
+/// 429 with a `Retry-After` of at least a second
+pub(super) fn too_many_requests(what: &str, wait: Duration) -> Response {
+  let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+  let body = serde_json::json!({
+    "error": format!("Too many {}, try again in {}s", what, retry_after),
+    "kind": "rate_limited",
+  });
+  let retry_after = [(header::RETRY_AFTER, retry_after.to_string())];
+  (StatusCode::TOO_MANY_REQUESTS, retry_after, Json(body)).into_response()
+}
+
+/// Token from an `Authorization: Bearer <token>` (or `token <token>`) header
+pub(super) fn bearer_token(headers: &HeaderMap) -> Option<String> {
+  let value = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+  let (scheme, token) = value.trim().split_once(' ')?;
+  let token = token.trim();
+  let known = scheme.eq_ignore_ascii_case("bearer") || scheme.eq_ignore_ascii_case("token");
+  (known && !token.is_empty()).then(|| token.to_string())
 }
 
-/// Save config
-pub async fn save_config(Json(req): Json<SaveConfigRequest>) -> impl IntoResponse {
-  let mut config = Config::load().unwrap_or_default();
-  config.registries = req.registries;
+fn github_error_response(error: GitHubError) -> Response {
+  let status = match &error {
+    GitHubError::AuthRequired => StatusCode::UNAUTHORIZED,
+    GitHubError::RepoExists => StatusCode::CONFLICT,
+    GitHubError::InvalidName => StatusCode::UNPROCESSABLE_ENTITY,
+    GitHubError::RateLimited { .. } => StatusCode::TOO_MANY_REQUESTS,
+    GitHubError::RepoNotReady { .. } => StatusCode::GATEWAY_TIMEOUT,
+    GitHubError::ApiError(_)
+    | GitHubError::NetworkError(_)
+    | GitHubError::ManifestFailed { .. } => StatusCode::BAD_GATEWAY,
+  };
+  (status, Json(github_error_body(&error))).into_response()
+}
+
+/// `{"error": message, "kind": ...}` plus the variant's fields
+fn github_error_body(error: &GitHubError) -> serde_json::Value {
+  let mut body = serde_json::json!({ "error": error.to_string(), "kind": error.kind() });
+  match error {
+    GitHubError::RateLimited { reset_at: Some(reset_at) } => body["reset_at"] = (*reset_at).into(),
+    GitHubError::RepoNotReady { waited_secs } => body["waited_secs"] = (*waited_secs).into(),
+    GitHubError::ManifestFailed { full_name, repo_url, source } => {
+      body["full_name"] = full_name.as_str().into();
+      body["repo_url"] = repo_url.as_str().into();
+      body["source"] = github_error_body(source);
+    }
+    _ => {}
+  }
+  body
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::{Availability, AvailabilityResult};
+  use serde_json::{json, Value};
+  use std::collections::{HashMap, HashSet};
+
+  fn request(body: serde_json::Value) -> CheckRequest {
+    serde_json::from_value(body).unwrap()
+  }
+
+  #[test]
+  fn test_registry_list_reports_every_registry_and_setting() {
+    let settings = RegistrySettings { brew: false, ..RegistrySettings::default() };
+    let list = registry_list(&settings);
+    assert_eq!(list.len(), RegistryType::ALL.len());
+    assert_eq!((list[1].id, list[1].name.as_str(), list[1].enabled), ("crates", "crates.io", true));
+    let brew = list.iter().find(|r| r.id == "brew").unwrap();
+    assert!(!brew.enabled);
+  }
+
+  #[test]
+  fn test_check_request_accepts_identifier_list() {
+    let settings = request(serde_json::json!({
+      "name": "foo",
+      "registries": ["npm", "crates"],
+    }))
+    .settings()
+    .unwrap();
+    assert!(settings.npm && settings.crates);
+    assert!(!settings.pypi && !settings.github);
+  }
+
+  #[test]
+  fn test_check_request_accepts_settings_object_and_exclude() {
+    let settings = request(serde_json::json!({
+      "name": "foo",
+      "registries": { "npm": true, "crates": false },
+      "exclude": ["flatpak"],
+    }))
+    .settings()
+    .unwrap();
+    assert!(settings.npm && settings.pypi);
+    assert!(!settings.crates && !settings.flatpak);
+  }
+
+  #[test]
+  fn test_check_request_rejects_unknown_identifier() {
+    let err = request(serde_json::json!({ "name": "foo", "registries": ["nmp"] }))
+      .settings()
+      .unwrap_err();
+    assert!(err.to_string().contains("valid:"));
+  }
+
+  #[test]
+  fn test_check_query_parses_lists() {
+    let query = CheckQuery {
+      name: "foo".to_string(),
+      registries: Some("npm, crates".to_string()),
+      exclude: Some("crates".to_string()),
+    };
+    let settings = CheckRequest::from(query).settings().unwrap();
+    assert!(settings.npm && !settings.crates && !settings.pypi);
+
+    let all = || CheckQuery { name: "foo".to_string(), registries: None, exclude: None };
+    assert!(CheckRequest::from(all()).settings().unwrap().github);
+    let bad = CheckQuery { registries: Some("nmp".to_string()), ..all() };
+    assert!(CheckRequest::from(bad).settings().is_err());
+  }
+
+  #[test]
+  fn test_domain_query_splits_tlds() {
+    let query = DomainQuery { name: "foo".to_string(), tlds: Some("com, @popular,".to_string()) };
+    assert_eq!(DomainRequest::from(query).tlds.unwrap(), vec!["com", "@popular"]);
+    let query = DomainQuery { name: "foo".to_string(), tlds: None };
+    assert_eq!(DomainRequest::from(query).tlds, None);
+  }
+
+  #[tokio::test]
+  async fn test_check_events_end_with_summary() {
+    let timed = |registry, available: Option<bool>| TimedResult {
+      result: AvailabilityResult {
+        registry,
+        name: "foo".to_string(),
+        availability: available.into(),
+        error: None,
+        detail: None,
+        qualifier: None,
+      },
+      duration_ms: 5,
+    };
+    let results = stream::iter([
+      Cached { result: timed(RegistryType::Npm, Some(true)), cached: true },
+      Cached { result: timed(RegistryType::Crates, Some(false)), cached: false },
+    ]);
+    let response = Sse::new(check_events(results)).into_response();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+
+    assert_eq!(body.matches("event: result").count(), 2);
+    assert!(body.contains(r#""registry":"npm""#));
+    assert!(body.contains(r#""duration_ms":5"#));
+    assert!(body.contains(r#""cached":true"#));
+    let summary = body.split("event: summary\n").nth(1).unwrap();
+    assert!(summary.contains(r#""taken":1"#));
+    assert!(summary.contains(r#""status":"taken""#));
+  }
+
+  #[test]
+  fn test_bearer_token() {
+    let headers = |value: &str| {
+      let mut headers = HeaderMap::new();
+      headers.insert(header::AUTHORIZATION, value.parse().unwrap());
+      headers
+    };
+    assert_eq!(bearer_token(&headers("Bearer ghp_abc")).as_deref(), Some("ghp_abc"));
+    assert_eq!(bearer_token(&headers("token ghp_abc")).as_deref(), Some("ghp_abc"));
+    assert_eq!(bearer_token(&headers("Basic dXNlcg==")), None);
+    assert_eq!(bearer_token(&HeaderMap::new()), None);
+  }
+
+  #[test]
+  fn test_register_limiter_caps_attempts_per_window() {
+    let limiter = RegisterLimiter::new(2, Duration::from_secs(60));
+    assert!(limiter.acquire().is_ok() && limiter.acquire().is_ok());
+    let wait = limiter.acquire().unwrap_err();
+    assert!(wait > Duration::from_secs(55));
+  }
+
+  #[test]
+  fn test_manifest_failed_body_nests_source() {
+    let error = GitHubError::ManifestFailed {
+      full_name: "octocat/foo".to_string(),
+      repo_url: "https://github.com/octocat/foo".to_string(),
+      source: Box::new(GitHubError::RateLimited { reset_at: Some(1_700_000_000) }),
+    };
+    let body = github_error_body(&error);
+    assert_eq!(body["kind"], "manifest_failed");
+    assert_eq!(body["full_name"], "octocat/foo");
+    assert_eq!(body["source"]["kind"], "rate_limited");
+    assert_eq!(body["source"]["reset_at"], 1_700_000_000);
+  }
+
+  #[tokio::test]
+  async fn test_domain_request_rejects_unknown_tld_group() {
+    let req = DomainRequest {
+      name: "foo".to_string(),
+      tlds: Some(vec!["@nope".to_string()]),
+    };
+    // No config file there, so only the built-in groups exist
+    let source = ConfigSource::resolve(Some(std::env::temp_dir().join("nbi-none.toml")), None);
+    let Err(err) = domain(&source, req).await else { panic!("unknown group accepted") };
+    assert_eq!(err.status, StatusCode::BAD_REQUEST);
+    assert_eq!((err.code, err.field), ("invalid_tld", Some("tlds")));
+  }
+
+  #[test]
+  fn test_validate_name_rejects_blank_and_overlong() {
+    assert_eq!(validate_name("  ").unwrap_err().field, Some("name"));
+    assert!(validate_name(&"a".repeat(MAX_NAME_LEN)).is_ok());
+    let err = validate_name(&"a".repeat(MAX_NAME_LEN + 1)).unwrap_err();
+    assert_eq!(err.code, "invalid_name");
+    assert!(err.message.contains("215 characters"));
+  }
+
+  #[test]
+  fn test_unknown_registry_names_its_field() {
+    let err = request(serde_json::json!({ "name": "foo", "exclude": ["nmp"] }))
+      .settings()
+      .unwrap_err();
+    assert_eq!((err.code, err.field), ("invalid_registry", Some("exclude")));
+    let err = request(serde_json::json!({ "name": "foo", "registries": ["nmp"] }))
+      .settings()
+      .unwrap_err();
+    assert_eq!(err.field, Some("registries"));
+  }
+
+  #[test]
+  fn test_domain_count_is_capped() {
+    assert!(check_domain_count(MAX_DOMAINS_PER_REQUEST, "tlds").is_ok());
+    let err = check_domain_count(MAX_DOMAINS_PER_REQUEST + 1, "domains").unwrap_err();
+    assert_eq!((err.code, err.field), ("too_many_domains", Some("domains")));
+  }
+
+  /// Where `value` strays from `schema`, as "path: problem" lines
+  ///
+  /// Understands what the spec uses: `$ref`, `allOf`, `oneOf`, `enum`,
+  /// `nullable`, arrays and objects, whose unlisted keys count as drift
+  /// unless `additionalProperties` allows them.
+  fn drift(spec: &Value, schema: &Value, value: &Value, path: &str) -> Vec<String> {
+    let schema = resolve(spec, schema);
+    if value.is_null() {
+      if schema["nullable"] == true {
+        return vec![];
+      }
+      return vec![format!("{}: null, but not nullable", path)];
+    }
+    if let Some(branches) = schema["oneOf"].as_array() {
+      if branches.iter().any(|branch| drift(spec, branch, value, path).is_empty()) {
+        return vec![];
+      }
+      return vec![format!("{}: matches no oneOf branch", path)];
+    }
+    if schema["enum"].as_array().is_some_and(|allowed| !allowed.contains(value)) {
+      return vec![format!("{}: {} isn't in the enum", path, value)];
+    }
+    let fits = match schema["type"].as_str() {
+      Some("string") => value.is_string(),
+      Some("integer") => value.is_u64() || value.is_i64(),
+      Some("boolean") => value.is_boolean(),
+      Some("array") => value.is_array(),
+      Some("object") => value.is_object(),
+      _ => true,
+    };
+    if !fits {
+      return vec![format!("{}: {} isn't a {}", path, value, schema["type"])];
+    }
+
+    let mut problems = Vec::new();
+    for (i, item) in value.as_array().into_iter().flatten().enumerate() {
+      problems.extend(drift(spec, &schema["items"], item, &format!("{}[{}]", path, i)));
+    }
+    if let Some(fields) = value.as_object() {
+      let properties = properties(spec, &schema);
+      for (key, field) in fields {
+        let path = format!("{}.{}", path, key);
+        match properties.get(key) {
+          Some(property) => problems.extend(drift(spec, property, field, &path)),
+          None if schema["additionalProperties"] != true => {
+            problems.push(format!("{}: not in the schema", path))
+          }
+          None => {}
+        }
+      }
+      for key in required(spec, &schema) {
+        if !fields.contains_key(&key) {
+          problems.push(format!("{}.{}: required, but missing", path, key));
+        }
+      }
+    }
+    problems
+  }
+
+  /// `schema`, or what its `$ref` points at
+  fn resolve(spec: &Value, schema: &Value) -> Value {
+    match schema["$ref"].as_str() {
+      Some(reference) => resolve(spec, spec.pointer(&reference[1..]).unwrap()),
+      None => schema.clone(),
+    }
+  }
+
+  /// Properties of `schema` and of every part of its `allOf`
+  fn properties(spec: &Value, schema: &Value) -> serde_json::Map<String, Value> {
+    let mut properties = schema["properties"].as_object().cloned().unwrap_or_default();
+    for part in schema["allOf"].as_array().into_iter().flatten() {
+      properties.extend(self::properties(spec, &resolve(spec, part)));
+    }
+    properties
+  }
+
+  fn required(spec: &Value, schema: &Value) -> Vec<String> {
+    let own = schema["required"].as_array().into_iter().flatten();
+    let mut required: Vec<String> = own.filter_map(|k| k.as_str().map(String::from)).collect();
+    for part in schema["allOf"].as_array().into_iter().flatten() {
+      required.extend(self::required(spec, &resolve(spec, part)));
+    }
+    required
+  }
+
+  /// A result with every optional field set
+  fn full_result(registry: RegistryType, name: &str) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: name.to_string(),
+      availability: Availability::Unknown,
+      error: Some("timeout".to_string()),
+      detail: Some("potential conflicts: 2".to_string()),
+      qualifier: Some("alice".to_string()),
+    }
+  }
+
+  /// Each `(event, data)` of a Server-Sent Events body
+  async fn events(response: Response) -> Vec<(String, Value)> {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let body = String::from_utf8(body.to_vec()).unwrap();
+    body
+      .split("\n\n")
+      .filter_map(|event| {
+        let (name, data) = event.strip_prefix("event: ")?.split_once("\ndata: ")?;
+        Some((name.to_string(), serde_json::from_str(data).unwrap()))
+      })
+      .collect()
+  }
+
+  async fn json_body(response: Response) -> Value {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_openapi_schemas_match_what_the_api_sends() {
+    let spec = openapi_spec();
+    let npm = full_result(RegistryType::Npm, "foo");
+    let cached = Cached { result: npm.clone(), cached: true };
+    let domain = DomainResult::from(full_result(RegistryType::DevDomain, "foo.dev"));
+    let manifest_failed = GitHubError::ManifestFailed {
+      full_name: "alice/foo".to_string(),
+      repo_url: "https://github.com/alice/foo".to_string(),
+      source: Box::new(GitHubError::RepoExists),
+    };
+
+    let settings = RegistrySettings::default();
+    let name = || "foo".to_string();
+    let registered = RegisterResponse {
+      name: name(),
+      registry: RegistryType::Npm,
+      url: "https://github.com/alice/foo".to_string(),
+    };
+
+    let mut samples = vec![
+      ("Health", health().await.0),
+      ("Version", json!(version().await.0)),
+      ("RegistriesResponse", json!(RegistriesResponse { registries: registry_list(&settings) })),
+      ("RegistrySettings", json!(settings)),
+      ("ConfigSchema", json!(config_schema().await.0)),
+      ("SaveConfigRequest", json!({ "registries": settings, "default_tlds": ["com"] })),
+      ("AvailabilityResult", json!(npm)),
+      ("CheckResponse", json!(CheckResponse { name: name(), results: vec![cached] })),
+      ("DomainResult", json!(domain)),
+      ("DomainResponse", json!(DomainResponse { name: name(), results: vec![domain] })),
+      ("DomainRequest", json!(DomainRequest { name: name(), tlds: Some(vec!["com".into()]) })),
+      ("FullDomainRequest", json!(FullDomainRequest { domains: vec!["foo.com".into()] })),
+      ("RegisterResponse", json!(registered)),
+      ("Error", json_body(ApiError::invalid("name", "invalid_name", "x").into_response()).await),
+      ("Error", json_body(too_many_requests("checks", Duration::from_secs(3))).await),
+      ("Error", github_error_body(&GitHubError::RateLimited { reset_at: Some(1_700_000_000) })),
+      ("Error", github_error_body(&GitHubError::RepoNotReady { waited_secs: 30 })),
+      ("Error", github_error_body(&manifest_failed)),
+    ];
+    for registries in [
+      RegistrySelection::Ids(vec!["npm".to_string()]),
+      RegistrySelection::Settings(RegistrySettings::default()),
+    ] {
+      let exclude = vec!["brew".to_string()];
+      let request = CheckRequest { name: name(), registries: Some(registries), exclude };
+      samples.push(("CheckRequest", json!(request)));
+    }
+    let timed = TimedResult { result: npm, duration_ms: 5 };
+    let stream = stream::iter([Cached { result: timed, cached: false }]);
+    for (event, data) in events(Sse::new(check_events(stream)).into_response()).await {
+      samples.push((if event == "result" { "TimedResult" } else { "CheckSummary" }, data));
+    }
+
+    let version = json!({ "$ref": "#/components/schemas/Version" });
+    assert_eq!(drift(&spec, &version, &json!({ "version": 1 }), "Version").len(), 1);
 
-  match config.save() {
-    Ok(_) => (StatusCode::OK, Json(serde_json::json!({ "success": true }))),
-    Err(e) => (
-      StatusCode::INTERNAL_SERVER_ERROR,
-      Json(serde_json::json!({ "error": e.to_string() })),
-    ),
+    let mut problems = Vec::new();
+    let mut sent: HashMap<&str, HashSet<String>> = HashMap::new();
+    for (name, value) in &samples {
+      let schema = json!({ "$ref": format!("#/components/schemas/{}", name) });
+      problems.extend(drift(&spec, &schema, value, name));
+      let keys = value.as_object().into_iter().flat_map(|fields| fields.keys().cloned());
+      sent.entry(name).or_default().extend(keys);
+    }
+    // Every documented field turns up in some sample, so none outlives its type
+    for (name, keys) in &sent {
+      let schema = resolve(&spec, &spec["components"]["schemas"][*name]);
+      for key in properties(&spec, &schema).keys().filter(|key| !keys.contains(*key)) {
+        problems.push(format!("{}.{}: documented, but never sent", name, key));
+      }
+    }
+    use Availability::*;
+    let enums = [
+      ("RegistryId", RegistryType::ALL.iter().map(|r| json!(r)).collect::<Vec<_>>()),
+      ("Availability", [Available, Taken, Reserved, Unknown].iter().map(|a| json!(a)).collect()),
+    ];
+    for (name, values) in enums {
+      let documented = spec["components"]["schemas"][name]["enum"].as_array().unwrap();
+      if *documented != values {
+        problems.push(format!("{}: enum is {:?}, the type sends {:?}", name, documented, values));
+      }
+    }
+    assert!(problems.is_empty(), "openapi.json drifted:\n{}", problems.join("\n"));
   }
 }
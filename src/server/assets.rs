@@ -0,0 +1,180 @@
+use axum::{
+  body::Body,
+  extract::{Path, Request, State},
+  http::{header, HeaderMap, HeaderValue, StatusCode, Uri},
+  response::{IntoResponse, Response},
+};
+use std::hash::{DefaultHasher, Hasher};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tower_http::services::ServeDir;
+
+/// The web UI's files, compiled into the binary
+const EMBEDDED: [(&str, &[u8]); 4] = [
+  ("index.html", include_bytes!("../../static/index.html")),
+  ("docs.html", include_bytes!("../../static/docs.html")),
+  ("app.css", include_bytes!("../../static/app.css")),
+  ("app.jsx", include_bytes!("../../static/app.jsx")),
+];
+
+/// Where the web UI's files come from
+///
+/// Files in `dir` win over the embedded ones, so the frontend can be edited
+/// without rebuilding; anything missing there falls back to the binary.
+#[derive(Debug, Default)]
+pub struct Assets {
+  dir: Option<PathBuf>,
+}
+
+impl Assets {
+  pub fn new(dir: Option<PathBuf>) -> Self {
+    Self { dir }
+  }
+
+  /// `name` from the static dir if it has it, else from the binary
+  async fn serve(&self, name: &str, request: Request) -> Response {
+    let headers = request.headers().clone();
+    if let Some(dir) = &self.dir {
+      if let Some(response) = from_dir(dir, name, request).await {
+        return response;
+      }
+    }
+    embedded(name, &headers)
+  }
+}
+
+/// Index page with the React app
+pub async fn index(State(assets): State<Arc<Assets>>, request: Request) -> Response {
+  assets.serve("index.html", request).await
+}
+
+/// Interactive API docs rendered from `/api/openapi.json`
+pub async fn docs(State(assets): State<Arc<Assets>>, request: Request) -> Response {
+  assets.serve("docs.html", request).await
+}
+
+/// Scripts and styles the pages load from `/static/`
+pub async fn file(
+  State(assets): State<Arc<Assets>>,
+  Path(name): Path<String>,
+  request: Request,
+) -> Response {
+  assets.serve(&name, request).await
+}
+
+/// Serve `name` from `dir`, or `None` if it isn't there
+///
+/// Files are revalidated on every load, since they're expected to change.
+/// Precompressed `.br` and `.gz` siblings are sent to clients accepting them.
+async fn from_dir(dir: &std::path::Path, name: &str, request: Request) -> Option<Response> {
+  let (mut parts, body) = request.into_parts();
+  // ServeDir resolves the request path, so point it at the asset
+  parts.uri = Uri::builder().path_and_query(format!("/{}", name)).build().ok()?;
+  let mut serve_dir = ServeDir::new(dir).precompressed_br().precompressed_gzip();
+  match serve_dir.try_call(Request::from_parts(parts, body)).await {
+    Ok(response) if response.status() == StatusCode::NOT_FOUND => None,
+    Ok(mut response) => {
+      let cache = HeaderValue::from_static("no-cache");
+      response.headers_mut().insert(header::CACHE_CONTROL, cache);
+      Some(response.map(Body::new))
+    }
+    Err(e) => Some((StatusCode::INTERNAL_SERVER_ERROR, e.to_string()).into_response()),
+  }
+}
+
+/// Serve the embedded `name`, or 304 if the client's copy is current
+fn embedded(name: &str, headers: &HeaderMap) -> Response {
+  let Some((_, body)) = EMBEDDED.iter().find(|(n, _)| *n == name) else {
+    return StatusCode::NOT_FOUND.into_response();
+  };
+  let etag = etag(body);
+  // Pages are revalidated so an upgrade shows up on the next load
+  let cache = if name.ends_with(".html") { "no-cache" } else { "public, max-age=3600" };
+  let caching = [(header::ETAG, etag.clone()), (header::CACHE_CONTROL, cache.to_string())];
+  if if_none_match(headers, &etag) {
+    return (StatusCode::NOT_MODIFIED, caching).into_response();
+  }
+
+  let content_type = mime_guess::from_path(name).first_or_octet_stream();
+  (caching, [(header::CONTENT_TYPE, content_type.to_string())], *body).into_response()
+}
+
+fn etag(body: &[u8]) -> String {
+  let mut hasher = DefaultHasher::new();
+  hasher.write(body);
+  format!("\"{:016x}\"", hasher.finish())
+}
+
+/// Whether `If-None-Match` lists `etag`, ignoring weak prefixes
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+  let Some(value) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) else {
+    return false;
+  };
+  value
+    .split(',')
+    .map(|tag| tag.trim().trim_start_matches("W/"))
+    .any(|tag| tag == "*" || tag == etag)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn get(headers: &[(header::HeaderName, &str)]) -> Request {
+    let mut request = Request::new(Body::empty());
+    for (name, value) in headers {
+      request.headers_mut().insert(name, HeaderValue::from_str(value).unwrap());
+    }
+    request
+  }
+
+  async fn text(response: Response) -> String {
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    String::from_utf8(bytes.to_vec()).unwrap()
+  }
+
+  #[tokio::test]
+  async fn test_embedded_assets_revalidate_with_etag() {
+    let assets = Assets::default();
+    let response = assets.serve("app.css", get(&[])).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "text/css");
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=3600");
+    let etag = response.headers()[header::ETAG].to_str().unwrap().to_string();
+    assert!(text(response).await.contains(".spinner"));
+
+    let response = assets.serve("app.css", get(&[(header::IF_NONE_MATCH, &etag)])).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert!(text(response).await.is_empty());
+
+    let response = assets.serve("index.html", get(&[(header::IF_NONE_MATCH, "\"x\"")])).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "no-cache");
+    assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/html"));
+
+    let response = assets.serve("missing.js", get(&[])).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+  }
+
+  #[tokio::test]
+  async fn test_static_dir_overrides_embedded_assets() {
+    let dir = std::env::temp_dir().join(format!("nbi-static-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("index.html"), "<p>local</p>").unwrap();
+    std::fs::write(dir.join("extra.js"), "console.log(1)").unwrap();
+    let assets = Assets::new(Some(dir.clone()));
+
+    let index = assets.serve("index.html", get(&[])).await;
+    assert_eq!(index.headers()[header::CACHE_CONTROL], "no-cache");
+    assert_eq!(text(index).await, "<p>local</p>");
+    let extra = assets.serve("extra.js", get(&[])).await;
+    assert_eq!(extra.headers()[header::CONTENT_TYPE], "text/javascript");
+    // Files the dir lacks still come from the binary
+    let css = assets.serve("app.css", get(&[])).await;
+    assert!(text(css).await.contains(".spinner"));
+    let escape = assets.serve("../Cargo.toml", get(&[])).await;
+    assert_eq!(escape.status(), StatusCode::NOT_FOUND);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+}
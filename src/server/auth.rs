@@ -0,0 +1,64 @@
+use axum::{
+  extract::{Request, State},
+  http::{header, StatusCode},
+  middleware::Next,
+  response::{IntoResponse, Response},
+  Json,
+};
+use std::sync::Arc;
+
+use super::api::bearer_token;
+
+/// Token every `/api` request must present as `Authorization: Bearer`
+#[derive(Clone)]
+pub struct AuthToken(Arc<str>);
+
+impl AuthToken {
+  pub fn new(token: &str) -> Self {
+    Self(token.into())
+  }
+
+  /// Compare without stopping at the first differing byte
+  fn matches(&self, given: &str) -> bool {
+    let (expected, given) = (self.0.as_bytes(), given.as_bytes());
+    expected.len() == given.len()
+      && expected.iter().zip(given).fold(0, |diff, (a, b)| diff | (a ^ b)) == 0
+  }
+}
+
+/// Reject requests without the server's token with 401
+///
+/// The header is consumed here, so `/api/register` falls back to the
+/// server's GitHub token rather than treating it as one.
+pub async fn require_token(
+  State(token): State<AuthToken>,
+  mut request: Request,
+  next: Next,
+) -> Response {
+  match bearer_token(request.headers()) {
+    Some(given) if token.matches(&given) => {
+      request.headers_mut().remove(header::AUTHORIZATION);
+      next.run(request).await
+    }
+    given => {
+      let error = if given.is_some() { "Invalid access token" } else { "Access token required" };
+      let body = serde_json::json!({ "error": error, "kind": "unauthorized" });
+      let challenge = [(header::WWW_AUTHENTICATE, "Bearer")];
+      (StatusCode::UNAUTHORIZED, challenge, Json(body)).into_response()
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_matches_exact_token_only() {
+    let token = AuthToken::new("s3cret");
+    assert!(token.matches("s3cret"));
+    assert!(!token.matches("s3cre"));
+    assert!(!token.matches("s3cret!"));
+    assert!(!token.matches("S3CRET"));
+  }
+}
@@ -0,0 +1,143 @@
+use axum::{
+  extract::{Path, State},
+  http::{header, StatusCode},
+  response::{IntoResponse, Response},
+};
+use std::sync::Arc;
+
+use super::api;
+use super::cache::ResultCache;
+use super::error::ApiError;
+use crate::api_types::{CheckRequest, RegistrySelection};
+use crate::config::ConfigSource;
+use crate::registry::{Availability, RegistryType};
+
+/// How long clients may reuse a definite answer; matches the default
+/// result cache TTL
+const MAX_AGE_SECS: u64 = 300;
+
+/// Approximate width of an 11px Verdana character, as shields.io renders
+const CHAR_WIDTH: usize = 7;
+const PADDING: usize = 10;
+
+/// Shields-style SVG saying whether `name` is free on `registry`
+///
+/// `GET /api/badge/{registry}/{name}.svg`; unknown registries are 404.
+pub async fn badge(
+  State(config): State<Arc<ConfigSource>>,
+  State(cache): State<Arc<ResultCache>>,
+  Path((registry, file)): Path<(String, String)>,
+) -> Result<Response, ApiError> {
+  let Some(name) = file.strip_suffix(".svg") else {
+    return Err(ApiError::not_found("not_found", "Badges end in .svg"));
+  };
+  let registry: RegistryType = registry
+    .parse()
+    .map_err(|e| ApiError::not_found("unknown_registry", e))?;
+  let req = CheckRequest {
+    name: name.to_string(),
+    registries: Some(RegistrySelection::Ids(vec![registry.id().to_string()])),
+    exclude: Vec::new(),
+  };
+  let availability = api::check(&config, &cache, req)
+    .await?
+    .results
+    .first()
+    .map_or(Availability::Unknown, |r| r.result.availability);
+
+  let cache_control = match availability {
+    // Errors are retried, so don't let a proxy pin "unknown"
+    Availability::Unknown => "no-cache".to_string(),
+    _ => format!("public, max-age={}", MAX_AGE_SECS),
+  };
+  let headers = [
+    (header::CONTENT_TYPE, "image/svg+xml".to_string()),
+    (header::CACHE_CONTROL, cache_control),
+  ];
+  Ok((StatusCode::OK, headers, render(name, &registry.to_string(), availability)).into_response())
+}
+
+/// A flat two-part badge: "`name` on `registry`" and the verdict, colored
+/// green for available, red for taken, blue for reserved and grey when the
+/// check failed
+pub fn render(name: &str, registry: &str, availability: Availability) -> String {
+  let (message, color) = match availability {
+    Availability::Available => ("available", "#4c1"),
+    Availability::Taken => ("taken", "#e05d44"),
+    Availability::Reserved => ("reserved", "#007ec6"),
+    Availability::Unknown => ("unknown", "#9f9f9f"),
+  };
+  let label = format!("{} on {}", name, registry);
+  let label_width = label.chars().count() * CHAR_WIDTH + PADDING;
+  let message_width = message.len() * CHAR_WIDTH + PADDING;
+  let width = label_width + message_width;
+  let label = escape(&label);
+  // Centers, in tenths of a pixel as shields does, for crisp text
+  let label_x = label_width * 5;
+  let message_x = label_width * 10 + message_width * 5;
+
+  format!(
+    r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img"
+  aria-label="{label}: {message}">
+  <title>{label}: {message}</title>
+  <linearGradient id="s" x2="0" y2="100%">
+    <stop offset="0" stop-color="#bbb" stop-opacity=".1"/>
+    <stop offset="1" stop-opacity=".1"/>
+  </linearGradient>
+  <clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+  <g clip-path="url(#r)">
+    <rect width="{label_width}" height="20" fill="#555"/>
+    <rect x="{label_width}" width="{message_width}" height="20" fill="{color}"/>
+    <rect width="{width}" height="20" fill="url(#s)"/>
+  </g>
+  <g fill="#fff" text-anchor="middle" font-size="110" transform="scale(.1)"
+    font-family="Verdana,Geneva,DejaVu Sans,sans-serif">
+    <text x="{label_x}" y="150" fill="#010101" fill-opacity=".3">{label}</text>
+    <text x="{label_x}" y="140">{label}</text>
+    <text x="{message_x}" y="150" fill="#010101" fill-opacity=".3">{message}</text>
+    <text x="{message_x}" y="140">{message}</text>
+  </g>
+</svg>
+"##
+  )
+}
+
+fn escape(text: &str) -> String {
+  text
+    .replace('&', "&amp;")
+    .replace('<', "&lt;")
+    .replace('>', "&gt;")
+    .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_available_badge() {
+    insta::assert_snapshot!(render("foo", "crates.io", Availability::Available));
+  }
+
+  #[test]
+  fn test_taken_badge() {
+    insta::assert_snapshot!(render("serde", "crates.io", Availability::Taken));
+  }
+
+  #[test]
+  fn test_unknown_badge() {
+    insta::assert_snapshot!(render("foo", "npm", Availability::Unknown));
+  }
+
+  #[test]
+  fn test_reserved_badge() {
+    insta::assert_snapshot!(render("held", "npm", Availability::Reserved));
+  }
+
+  #[test]
+  fn test_badge_escapes_name() {
+    let svg = render("<a&b>", "npm", Availability::Available);
+    assert!(svg.contains("&lt;a&amp;b&gt; on npm"));
+    assert!(!svg.contains("<a&b>"));
+  }
+}
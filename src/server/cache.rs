@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::RegistrySettings;
+use crate::registry::{AvailabilityResult, RegistryType};
+
+/// How long results are reused when `--cache-ttl` isn't given
+pub const DEFAULT_TTL: Duration = Duration::from_secs(300);
+
+/// Entries kept before expired ones are swept
+const MAX_ENTRIES: usize = 10_000;
+
+/// Check results shared by every client, keyed by (name, registry)
+///
+/// Failed checks aren't stored, so they're retried on the next request.
+pub struct ResultCache {
+  ttl: Duration,
+  entries: Mutex<HashMap<(String, RegistryType), (Instant, AvailabilityResult)>>,
+}
+
+impl Default for ResultCache {
+  fn default() -> Self {
+    Self::new(DEFAULT_TTL)
+  }
+}
+
+impl ResultCache {
+  /// A zero `ttl` disables caching
+  pub fn new(ttl: Duration) -> Self {
+    Self { ttl, entries: Mutex::new(HashMap::new()) }
+  }
+
+  /// Fresh results for the registries `settings` enables, and settings
+  /// enabling only the registries that still need checking
+  pub fn lookup(
+    &self,
+    name: &str,
+    settings: &RegistrySettings,
+  ) -> (Vec<AvailabilityResult>, RegistrySettings) {
+    let now = Instant::now();
+    let entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    let mut hits = Vec::new();
+    let mut misses = RegistrySettings::none();
+    for registry in RegistryType::ALL.into_iter().filter(|r| settings.is_enabled(*r)) {
+      match entries.get(&(name.to_string(), registry)) {
+        Some((stored, result)) if now.duration_since(*stored) < self.ttl => {
+          hits.push(result.clone())
+        }
+        _ => misses.set_enabled(registry, true),
+      }
+    }
+    (hits, misses)
+  }
+
+  /// Remember a result checked for `name`
+  pub fn insert(&self, name: &str, result: &AvailabilityResult) {
+    if self.ttl.is_zero() || result.available().is_none() {
+      return;
+    }
+    let now = Instant::now();
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    if entries.len() >= MAX_ENTRIES {
+      entries.retain(|_, (stored, _)| now.duration_since(*stored) < self.ttl);
+    }
+    entries.insert((name.to_string(), result.registry), (now, result.clone()));
+  }
+
+  /// Forget everything, returning how many entries there were
+  pub fn clear(&self) -> usize {
+    let mut entries = self.entries.lock().unwrap_or_else(|e| e.into_inner());
+    let count = entries.len();
+    entries.clear();
+    count
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn result(registry: RegistryType, available: Option<bool>) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: "foo".to_string(),
+      availability: available.into(),
+      error: available.is_none().then(|| "timeout".to_string()),
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  #[test]
+  fn test_lookup_splits_hits_from_misses() {
+    let cache = ResultCache::default();
+    cache.insert("foo", &result(RegistryType::Npm, Some(true)));
+    cache.insert("foo", &result(RegistryType::Crates, None));
+    cache.insert("bar", &result(RegistryType::PyPi, Some(false)));
+
+    let settings = RegistrySettings::default().filtered(
+      &[RegistryType::Npm, RegistryType::Crates, RegistryType::PyPi],
+      &[],
+    );
+    let (hits, misses) = cache.lookup("foo", &settings);
+    assert_eq!(hits.len(), 1);
+    assert_eq!(hits[0].registry, RegistryType::Npm);
+    assert!(!misses.npm && misses.crates && misses.pypi && !misses.github);
+  }
+
+  #[test]
+  fn test_zero_ttl_and_clear() {
+    let cache = ResultCache::new(Duration::ZERO);
+    cache.insert("foo", &result(RegistryType::Npm, Some(true)));
+    assert_eq!(cache.clear(), 0);
+
+    let cache = ResultCache::default();
+    cache.insert("foo", &result(RegistryType::Npm, Some(true)));
+    assert_eq!(cache.clear(), 1);
+    let (hits, misses) = cache.lookup("foo", &RegistrySettings::default());
+    assert!(hits.is_empty() && misses.npm);
+  }
+}
@@ -0,0 +1,116 @@
+use anyhow::Result;
+use axum::{
+  extract::{Request, State},
+  http::{header, HeaderValue, Method, StatusCode},
+  middleware::Next,
+  response::{IntoResponse, Json, Response},
+};
+use std::sync::Arc;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// Sites besides the server's own whose pages may call it
+///
+/// Empty by default, so a page on some other site can't use a running
+/// server to e.g. rewrite the config.
+#[derive(Clone, Debug, Default)]
+pub struct AllowedOrigins(Arc<[HeaderValue]>);
+
+impl AllowedOrigins {
+  /// Validate origins like `https://example.com`, ignoring a trailing slash
+  pub fn parse(origins: &[String]) -> Result<Self> {
+    let origins = origins
+      .iter()
+      .map(|origin| {
+        let origin = origin.trim().trim_end_matches('/');
+        let rest = origin
+          .strip_prefix("http://")
+          .or_else(|| origin.strip_prefix("https://"))
+          .filter(|rest| !rest.is_empty() && !rest.contains('/'));
+        match rest.and_then(|_| HeaderValue::from_str(origin).ok()) {
+          Some(value) => Ok(value),
+          None => anyhow::bail!(
+            "Invalid CORS origin '{}': expected scheme and host, e.g. https://example.com",
+            origin
+          ),
+        }
+      })
+      .collect::<Result<Vec<_>>>()?;
+    Ok(Self(origins.into()))
+  }
+
+  /// CORS headers for the allowed sites, or `None` when there are none
+  pub fn layer(&self) -> Option<CorsLayer> {
+    if self.0.is_empty() {
+      return None;
+    }
+    let layer = CorsLayer::new()
+      .allow_origin(AllowOrigin::list(self.0.iter().cloned()))
+      .allow_methods([Method::GET, Method::POST])
+      .allow_headers([header::CONTENT_TYPE, header::AUTHORIZATION])
+      .expose_headers([header::RETRY_AFTER]);
+    Some(layer)
+  }
+
+  fn allows(&self, origin: &HeaderValue, host: Option<&HeaderValue>) -> bool {
+    self.0.contains(origin) || is_same_origin(origin, host)
+  }
+}
+
+/// Whether `origin` names the host the request was sent to
+fn is_same_origin(origin: &HeaderValue, host: Option<&HeaderValue>) -> bool {
+  let (Ok(origin), Some(Ok(host))) = (origin.to_str(), host.map(HeaderValue::to_str)) else {
+    return false;
+  };
+  let authority = origin.strip_prefix("http://").or_else(|| origin.strip_prefix("https://"));
+  authority == Some(host)
+}
+
+/// Refuse requests from other sites' pages, preflights included
+///
+/// Browsers send `Origin` on cross-site requests, and CORS alone doesn't stop
+/// "simple" ones from reaching handlers; requests without it are let through.
+pub async fn reject_cross_origin(
+  State(origins): State<AllowedOrigins>,
+  request: Request,
+  next: Next,
+) -> Response {
+  let Some(origin) = request.headers().get(header::ORIGIN) else {
+    return next.run(request).await;
+  };
+  if origins.allows(origin, request.headers().get(header::HOST)) {
+    return next.run(request).await;
+  }
+  let error = serde_json::json!({
+    "error": format!("Origin {} isn't allowed", origin.to_str().unwrap_or("(invalid)")),
+    "kind": "forbidden_origin",
+  });
+  (StatusCode::FORBIDDEN, Json(error)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn value(s: &str) -> HeaderValue {
+    HeaderValue::from_str(s).unwrap()
+  }
+
+  #[test]
+  fn test_parse_normalizes_and_rejects_paths() {
+    let origins = AllowedOrigins::parse(&["https://example.com/".to_string()]).unwrap();
+    assert_eq!(&*origins.0, [value("https://example.com")]);
+    for bad in ["example.com", "https://example.com/app", "ftp://example.com", "https://"] {
+      assert!(AllowedOrigins::parse(&[bad.to_string()]).is_err(), "{}", bad);
+    }
+  }
+
+  #[test]
+  fn test_same_origin_matches_host_header() {
+    let host = value("127.0.0.1:3000");
+    let origins = AllowedOrigins::default();
+    assert!(origins.allows(&value("http://127.0.0.1:3000"), Some(&host)));
+    assert!(!origins.allows(&value("http://127.0.0.1:3001"), Some(&host)));
+    assert!(!origins.allows(&value("null"), Some(&host)));
+    assert!(!origins.allows(&value("http://127.0.0.1:3000"), None));
+  }
+}
@@ -0,0 +1,110 @@
+use axum::{
+  extract::{
+    rejection::{JsonRejection, QueryRejection},
+    FromRequest, FromRequestParts, Query, Request,
+  },
+  http::{request::Parts, StatusCode},
+  response::{IntoResponse, Response},
+  Json,
+};
+use crate::api_types::ErrorBody;
+use serde::de::DeserializeOwned;
+use std::fmt;
+
+/// An API failure, sent as `{"error": message, "kind": code, "field": ...}`
+///
+/// `kind` is the same machine-readable cause the auth, rate limit and GitHub
+/// errors carry; `field` names the request field at fault, if any.
+#[derive(Debug)]
+pub struct ApiError {
+  pub status: StatusCode,
+  pub code: &'static str,
+  pub message: String,
+  pub field: Option<&'static str>,
+}
+
+impl ApiError {
+  /// 400 for a request that can't be served as sent
+  pub fn bad_request(code: &'static str, message: impl fmt::Display) -> Self {
+    Self { status: StatusCode::BAD_REQUEST, code, message: message.to_string(), field: None }
+  }
+
+  /// 400 blaming one request field
+  pub fn invalid(field: &'static str, code: &'static str, message: impl fmt::Display) -> Self {
+    Self { field: Some(field), ..Self::bad_request(code, message) }
+  }
+
+  /// 404 for a path naming something that doesn't exist
+  pub fn not_found(code: &'static str, message: impl fmt::Display) -> Self {
+    Self { status: StatusCode::NOT_FOUND, ..Self::bad_request(code, message) }
+  }
+
+  /// 500 for failures on the server's side, e.g. an unreadable config
+  pub fn internal(message: impl fmt::Display) -> Self {
+    Self {
+      status: StatusCode::INTERNAL_SERVER_ERROR,
+      code: "internal_error",
+      message: message.to_string(),
+      field: None,
+    }
+  }
+}
+
+impl fmt::Display for ApiError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(&self.message)
+  }
+}
+
+impl IntoResponse for ApiError {
+  fn into_response(self) -> Response {
+    let body = ErrorBody {
+      error: self.message,
+      kind: self.code.to_string(),
+      field: self.field.map(String::from),
+    };
+    (self.status, Json(body)).into_response()
+  }
+}
+
+/// Malformed or mistyped bodies are the caller's fault, so 422s become 400s;
+/// a missing JSON content type or oversized body keeps its own status
+impl From<JsonRejection> for ApiError {
+  fn from(rejection: JsonRejection) -> Self {
+    let status = match rejection.status() {
+      StatusCode::UNPROCESSABLE_ENTITY => StatusCode::BAD_REQUEST,
+      status => status,
+    };
+    Self { status, ..Self::bad_request("invalid_body", rejection.body_text()) }
+  }
+}
+
+impl From<QueryRejection> for ApiError {
+  fn from(rejection: QueryRejection) -> Self {
+    Self::bad_request("invalid_query", rejection.body_text())
+  }
+}
+
+/// [`Json`] whose rejections are [`ApiError`]s
+pub struct ApiJson<T>(pub T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequest<S> for ApiJson<T> {
+  type Rejection = ApiError;
+
+  async fn from_request(request: Request, state: &S) -> Result<Self, ApiError> {
+    let Json(value) = Json::<T>::from_request(request, state).await?;
+    Ok(Self(value))
+  }
+}
+
+/// [`Query`] whose rejections are [`ApiError`]s
+pub struct ApiQuery<T>(pub T);
+
+impl<T: DeserializeOwned, S: Send + Sync> FromRequestParts<S> for ApiQuery<T> {
+  type Rejection = ApiError;
+
+  async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, ApiError> {
+    let Query(value) = Query::<T>::from_request_parts(parts, state).await?;
+    Ok(Self(value))
+  }
+}
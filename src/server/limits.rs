@@ -0,0 +1,202 @@
+use axum::{
+  body::{Body, Bytes},
+  extract::{DefaultBodyLimit, Request, State},
+  http::{header, StatusCode},
+  middleware::{self, Next},
+  response::{IntoResponse, Response},
+  Router,
+};
+use http_body::{Frame, SizeHint};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tower_http::limit::RequestBodyLimitLayer;
+use tower_http::timeout::TimeoutLayer;
+
+use super::error::ApiError;
+
+/// Caps that keep one client from exhausting the server
+#[derive(Clone, Debug)]
+pub struct Limits {
+  /// Largest request body, in bytes
+  pub max_body: usize,
+  /// Requests handled at once, open streams included; 0 for no limit
+  pub max_concurrent: usize,
+  /// How long a request may take; zero for no limit
+  pub request_timeout: Duration,
+}
+
+impl Default for Limits {
+  fn default() -> Self {
+    Self { max_body: 64 * 1024, max_concurrent: 128, request_timeout: Duration::from_secs(30) }
+  }
+}
+
+impl Limits {
+  /// `router` with the request timeout, for routes expected to answer
+  /// promptly
+  pub fn timed<S: Clone + Send + Sync + 'static>(&self, router: Router<S>) -> Router<S> {
+    if self.request_timeout.is_zero() {
+      return router;
+    }
+    router.layer(TimeoutLayer::with_status_code(StatusCode::REQUEST_TIMEOUT, self.request_timeout))
+  }
+
+  /// `router` with the body size and concurrency limits, answering with
+  /// the API's JSON errors when they reject a request
+  pub fn apply(&self, router: Router) -> Router {
+    let mut router = router
+      // Replaces the extractors' own 2 MB cap, so the flag can raise it too
+      .layer(DefaultBodyLimit::disable())
+      .layer(RequestBodyLimitLayer::new(self.max_body));
+    if self.max_concurrent > 0 {
+      let permits = Arc::new(Semaphore::new(self.max_concurrent));
+      router = router.layer(middleware::from_fn_with_state(permits, limit_concurrency));
+    }
+    router.layer(middleware::map_response_with_state(self.max_body, explain_rejections))
+  }
+}
+
+/// Refuse requests beyond the concurrency limit with 503
+///
+/// The permit is held until the response body ends, so a check stream
+/// counts for as long as it's open.
+async fn limit_concurrency(
+  State(permits): State<Arc<Semaphore>>,
+  request: Request,
+  next: Next,
+) -> Response {
+  let Ok(permit) = permits.try_acquire_owned() else {
+    let error = ApiError {
+      status: StatusCode::SERVICE_UNAVAILABLE,
+      ..ApiError::bad_request("overloaded", "Server is busy, try again shortly")
+    };
+    return ([(header::RETRY_AFTER, "1")], error).into_response();
+  };
+  let response = next.run(request).await;
+  response.map(|body| Body::new(PermitBody { body, _permit: permit }))
+}
+
+/// A response body holding a concurrency permit until it's dropped
+struct PermitBody {
+  body: Body,
+  _permit: OwnedSemaphorePermit,
+}
+
+impl http_body::Body for PermitBody {
+  type Data = Bytes;
+  type Error = axum::Error;
+
+  fn poll_frame(
+    mut self: Pin<&mut Self>,
+    cx: &mut Context<'_>,
+  ) -> Poll<Option<Result<Frame<Bytes>, axum::Error>>> {
+    Pin::new(&mut self.body).poll_frame(cx)
+  }
+
+  fn is_end_stream(&self) -> bool {
+    self.body.is_end_stream()
+  }
+
+  fn size_hint(&self) -> SizeHint {
+    self.body.size_hint()
+  }
+}
+
+/// The body limit and timeout layers answer with bare statuses; give them
+/// the same JSON errors as everything else
+async fn explain_rejections(State(max_body): State<usize>, response: Response) -> Response {
+  let is_json = response
+    .headers()
+    .get(header::CONTENT_TYPE)
+    .is_some_and(|value| value.as_bytes().starts_with(b"application/json"));
+  if is_json {
+    return response;
+  }
+  let error = match response.status() {
+    StatusCode::PAYLOAD_TOO_LARGE => {
+      let message = format!("Request body is over the {} byte limit", max_body);
+      ApiError::bad_request("payload_too_large", message)
+    }
+    StatusCode::REQUEST_TIMEOUT => ApiError::bad_request("timeout", "Request took too long"),
+    _ => return response,
+  };
+  ApiError { status: response.status(), ..error }.into_response()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use axum::routing::{get, post};
+  use serde_json::Value;
+
+  async fn serve(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{}", addr)
+  }
+
+  async fn slow() -> &'static str {
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    "done"
+  }
+
+  #[tokio::test]
+  async fn test_oversized_body_is_rejected() {
+    let limits = Limits { max_body: 1024, ..Limits::default() };
+    let echo = Router::new().route("/echo", post(|body: String| async move { body }));
+    let base = serve(limits.apply(echo)).await;
+    let client = reqwest::Client::new();
+
+    let response = client.post(format!("{}/echo", base)).body("a".repeat(2048)).send();
+    let response = response.await.unwrap();
+    assert_eq!(response.status().as_u16(), 413);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "payload_too_large");
+    assert!(body["error"].as_str().unwrap().contains("1024 byte"));
+
+    let response = client.post(format!("{}/echo", base)).body("a".repeat(512)).send();
+    assert_eq!(response.await.unwrap().status().as_u16(), 200);
+  }
+
+  #[tokio::test]
+  async fn test_slow_handler_times_out() {
+    let limits = Limits { request_timeout: Duration::from_millis(50), ..Limits::default() };
+    let timed = limits.timed(Router::new().route("/slow", get(slow)));
+    let untimed = Router::new().route("/stream", get(slow));
+    let base = serve(limits.apply(timed.merge(untimed))).await;
+
+    let response = reqwest::get(format!("{}/slow", base)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 408);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "timeout");
+    let response = reqwest::get(format!("{}/stream", base)).await.unwrap();
+    assert_eq!(response.text().await.unwrap(), "done");
+  }
+
+  #[tokio::test]
+  async fn test_requests_past_concurrency_limit_get_503() {
+    let limits = Limits { max_concurrent: 1, ..Limits::default() };
+    let started = Arc::new(tokio::sync::Notify::new());
+    let notify = started.clone();
+    let handler = move || {
+      notify.notify_one();
+      slow()
+    };
+    let base = serve(limits.apply(Router::new().route("/slow", get(handler)))).await;
+
+    let first = tokio::spawn(reqwest::get(format!("{}/slow", base)));
+    started.notified().await;
+    let response = reqwest::get(format!("{}/slow", base)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 503);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    assert_eq!(first.await.unwrap().unwrap().text().await.unwrap(), "done");
+
+    // The permit comes back once the first response is done
+    let response = reqwest::get(format!("{}/slow", base)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+  }
+}
@@ -1,40 +1,1017 @@
 mod api;
+mod assets;
+mod auth;
+mod badge;
+mod cache;
+mod cors;
+mod error;
+mod limits;
+mod rate_limit;
 
+pub use auth::AuthToken;
+use assets::Assets;
+use cache::ResultCache;
+pub use cors::AllowedOrigins;
+use cors::reject_cross_origin;
+pub use limits::Limits;
+use rate_limit::{limit_clients, ClientLimiter};
+
+use crate::config::ConfigSource;
 use anyhow::Result;
 use axum::{
+  extract::{MatchedPath, Request},
+  http::header,
+  middleware::{self, Next},
+  response::Response,
   routing::{get, post},
   Router,
 };
-use std::net::SocketAddr;
-use tower_http::cors::{Any, CorsLayer};
+use std::future::{Future, IntoFuture};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tower_http::compression::CompressionLayer;
+use tower_http::sensitive_headers::SetSensitiveRequestHeadersLayer;
+use tower_http::trace::TraceLayer;
+
+/// What protects the server beyond its handlers' own checks
+#[derive(Default)]
+struct Guards {
+  /// Caps each IP's lookups and registrations
+  clients: Option<Arc<ClientLimiter>>,
+  /// Required on every `/api` route
+  auth: Option<AuthToken>,
+  /// Other sites whose pages may call the API
+  origins: AllowedOrigins,
+  limits: Limits,
+}
 
-pub async fn start(port: u16, open_browser: bool) -> Result<()> {
-  let cors = CorsLayer::new()
-    .allow_origin(Any)
-    .allow_methods(Any)
-    .allow_headers(Any);
+/// The web UI and its API, with `state` holding the registration limit,
+/// result cache and page files
+fn router(state: api::AppState, guards: Guards) -> Router {
+  let Guards { clients, auth, origins, limits } = guards;
 
-  let app = Router::new()
-    .route("/", get(api::index))
+  // Routes that hit registries, DNS or GitHub on the caller's behalf
+  let mut lookups = Router::new()
+    .route("/api/check", get(api::check_availability_query))
     .route("/api/check", post(api::check_availability))
+    .route("/api/domain", get(api::check_domain_query))
     .route("/api/domain", post(api::check_domain))
     .route("/api/domain/full", post(api::check_full_domains))
+    .route("/api/badge/{registry}/{file}", get(badge::badge));
+  // Streams run as long as the checks do, and cutting a registration short
+  // could leave a repo without its manifest
+  let mut untimed = Router::new()
+    .route("/api/check/stream", get(api::check_availability_stream))
+    .route("/api/register", post(api::register));
+  if let Some(clients) = clients {
+    let limit = middleware::from_fn_with_state(clients, limit_clients);
+    lookups = lookups.route_layer(limit.clone());
+    untimed = untimed.route_layer(limit);
+  }
+
+  let pages = Router::new()
+    .route("/", get(assets::index))
+    .route("/docs", get(assets::docs))
+    .route("/static/{*path}", get(assets::file))
+    .with_state(state.clone());
+
+  let routes = Router::new()
+    .route("/api/version", get(api::version))
+    .route("/api/registries", get(api::registries))
     .route("/api/config", get(api::get_config))
     .route("/api/config", post(api::save_config))
-    .layer(cors);
+    .route("/api/config/schema", get(api::config_schema))
+    .route("/api/cache/clear", post(api::clear_cache))
+    .merge(lookups);
+  let mut routes = limits.timed(routes).merge(untimed).with_state(state);
+  if let Some(token) = auth {
+    routes = routes.route_layer(middleware::from_fn_with_state(token, auth::require_token));
+  }
+
+  // The pages, spec and health probe stay open so the page can ask for a
+  // token and proxies can probe without one
+  let mut app = Router::new()
+    .merge(pages)
+    .route("/api/openapi.json", get(api::openapi))
+    .route("/api/health", get(api::health))
+    .route("/metrics", get(api::metrics))
+    .merge(routes)
+    .route_layer(middleware::from_fn(track_requests));
+  if let Some(cors) = origins.layer() {
+    app = app.layer(cors);
+  }
+  limits
+    .apply(app)
+    // gzip or br by Accept-Encoding; skips event streams, images and files
+    // ServeDir already sent precompressed
+    .layer(CompressionLayer::new())
+    // Outside CORS, so other sites' preflights are refused too
+    .layer(middleware::from_fn_with_state(origins, reject_cross_origin))
+    .layer(TraceLayer::new_for_http())
+    // Outermost, so tracing never prints a caller's token
+    .layer(SetSensitiveRequestHeadersLayer::new([header::AUTHORIZATION]))
+}
+
+/// Count each request under its route pattern, e.g. `/api/check`
+async fn track_requests(request: Request, next: Next) -> Response {
+  let method = request.method().to_string();
+  let route = request.extensions().get::<MatchedPath>().map(|path| path.as_str().to_string());
+  let response = next.run(request).await;
+  if let Some(route) = route {
+    crate::metrics::record_request(&method, &route, response.status().as_u16());
+  }
+  response
+}
+
+/// How `nbi serve` listens and guards the API
+pub struct ServeOptions {
+  /// Config the API reads and `POST /api/config` writes
+  pub config: ConfigSource,
+  /// Port 0 picks a free port
+  pub addr: SocketAddr,
+  pub open_browser: bool,
+  pub auth: Option<AuthToken>,
+  /// Requests per minute per client IP; 0 for no limit
+  pub rate_limit: u32,
+  /// How long in-flight requests get to finish after a shutdown signal
+  pub grace_period: Duration,
+  /// How long check results are reused; zero disables the cache
+  pub cache_ttl: Duration,
+  /// Directory whose files replace the embedded web UI's
+  pub static_dir: Option<PathBuf>,
+  /// Other sites whose pages may call the API
+  pub cors_origins: AllowedOrigins,
+  pub limits: Limits,
+}
+
+/// Serve the web UI until Ctrl+C or SIGTERM
+pub async fn start(options: ServeOptions) -> Result<()> {
+  let (addr, open_browser, grace_period) =
+    (options.addr, options.open_browser, options.grace_period);
+  let authenticated = options.auth.is_some();
+  let app = app(options)?;
 
-  let addr = SocketAddr::from(([127, 0, 0, 1], port));
+  let listener = tokio::net::TcpListener::bind(addr).await?;
+  let addr = listener.local_addr()?;
   println!("🚀 Server running at http://{}", addr);
+  if let Some(warning) = exposure_warning(addr.ip(), authenticated) {
+    eprintln!("{}", warning);
+  }
 
   if open_browser {
-    let url = format!("http://{}", addr);
-    if let Err(e) = open::that(&url) {
+    if let Err(e) = open::that(browser_url(addr)) {
       eprintln!("Failed to open browser: {}", e);
     }
   }
 
-  let listener = tokio::net::TcpListener::bind(addr).await?;
-  axum::serve(listener, app).await?;
+  let shutdown = async move {
+    shutdown_signal().await;
+    println!("Shutting down, waiting up to {}s for open requests...", grace_period.as_secs());
+  };
+  let drained = serve_until(listener, app, shutdown, grace_period).await?;
+  if drained {
+    println!("👋 Server stopped");
+  } else {
+    println!("👋 Server stopped, cancelling requests still running");
+  }
 
   Ok(())
 }
+
+/// The web UI and API for `options`, to be served with connect info so
+/// the rate limit can tell clients apart
+///
+/// `addr`, `open_browser` and `grace_period` are left to [`start`].
+pub fn app(options: ServeOptions) -> Result<Router> {
+  let ServeOptions { config, auth, rate_limit, cache_ttl, static_dir, cors_origins, limits, .. } =
+    options;
+  if let Some(dir) = static_dir.as_ref().filter(|dir| !dir.is_dir()) {
+    anyhow::bail!("--static-dir {} isn't a directory", dir.display());
+  }
+  crate::metrics::install();
+  let clients = (rate_limit > 0).then(|| Arc::new(ClientLimiter::new(rate_limit)));
+  let state = api::AppState {
+    config: Arc::new(config),
+    cache: Arc::new(ResultCache::new(cache_ttl)),
+    assets: Arc::new(Assets::new(static_dir)),
+    ..Default::default()
+  };
+  Ok(router(state, Guards { clients, auth, origins: cors_origins, limits }))
+}
+
+/// Serve `app` until `shutdown` resolves, then stop accepting connections
+/// and give open requests `grace` to finish
+///
+/// Returns whether they all did. Any left run on the runtime until the
+/// process exits, which cancels their registry checks.
+async fn serve_until(
+  listener: tokio::net::TcpListener,
+  app: Router,
+  shutdown: impl Future<Output = ()> + Send + 'static,
+  grace: Duration,
+) -> Result<bool> {
+  let (signalled, mut draining) = tokio::sync::oneshot::channel();
+  let shutdown = async move {
+    shutdown.await;
+    let _ = signalled.send(());
+  };
+  let server = axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+    .with_graceful_shutdown(shutdown)
+    .into_future();
+  tokio::pin!(server);
+
+  tokio::select! {
+    result = &mut server => return Ok(result.map(|()| true)?),
+    _ = &mut draining => {}
+  }
+  match tokio::time::timeout(grace, server).await {
+    Ok(result) => Ok(result.map(|()| true)?),
+    Err(_) => Ok(false),
+  }
+}
+
+/// Ctrl+C, or SIGTERM from systemd and friends
+async fn shutdown_signal() {
+  let ctrl_c = async {
+    if tokio::signal::ctrl_c().await.is_err() {
+      std::future::pending::<()>().await;
+    }
+  };
+
+  #[cfg(unix)]
+  let terminate = async {
+    use tokio::signal::unix::{signal, SignalKind};
+    match signal(SignalKind::terminate()) {
+      Ok(mut terminate) => {
+        terminate.recv().await;
+      }
+      Err(_) => std::future::pending::<()>().await,
+    }
+  };
+  #[cfg(not(unix))]
+  let terminate = std::future::pending::<()>();
+
+  tokio::select! {
+    _ = ctrl_c => {}
+    _ = terminate => {}
+  }
+}
+
+/// Warning for listening beyond this machine without an access token,
+/// where anyone who can reach the API can use it
+fn exposure_warning(ip: IpAddr, authenticated: bool) -> Option<String> {
+  if ip.is_loopback() || authenticated {
+    return None;
+  }
+  Some(format!(
+    "⚠️  WARNING: listening on {}, which other machines can reach.\n\
+     ⚠️  The API has no authentication: anyone who can connect can run checks, change the \
+     config and create GitHub repos with this machine's token.\n\
+     ⚠️  Set --auth-token or NBI_AUTH_TOKEN to require a token.",
+    ip
+  ))
+}
+
+/// URL to open locally; an unspecified address like 0.0.0.0 also listens
+/// on loopback
+fn browser_url(addr: SocketAddr) -> String {
+  let host = match addr.ip() {
+    IpAddr::V4(ip) if ip.is_unspecified() => IpAddr::V4(Ipv4Addr::LOCALHOST),
+    IpAddr::V6(ip) if ip.is_unspecified() => IpAddr::V6(Ipv6Addr::LOCALHOST),
+    ip => ip,
+  };
+  format!("http://{}", SocketAddr::new(host, addr.port()))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::Availability;
+  use axum::http::{Method, StatusCode, Uri};
+  use axum::response::{IntoResponse, Response};
+  use axum::Json;
+  use serde_json::{json, Value};
+  use std::sync::OnceLock;
+  use std::time::Duration;
+
+  /// Canned GitHub API for the user "octocat", who already has a "taken" repo
+  async fn mock_github(method: Method, uri: Uri, body: String) -> Response {
+    match (method.as_str(), uri.path()) {
+      ("GET", "/user") => Json(json!({ "login": "octocat" })).into_response(),
+      ("POST", "/user/repos") => {
+        let request: Value = serde_json::from_str(&body).unwrap();
+        let name = request["name"].as_str().unwrap().to_string();
+        if name == "taken" {
+          return (StatusCode::UNPROCESSABLE_ENTITY, "name already exists on this account")
+            .into_response();
+        }
+        let repo = json!({
+          "id": 1,
+          "name": name,
+          "full_name": format!("octocat/{}", name),
+          "html_url": format!("https://github.com/octocat/{}", name),
+        });
+        (StatusCode::CREATED, Json(repo)).into_response()
+      }
+      ("GET", "/licenses/mit") => {
+        Json(json!({ "body": "MIT License [year] [fullname]" })).into_response()
+      }
+      ("GET", path) if path.ends_with("/contents/README.md") => {
+        Json(json!({ "sha": "readme" })).into_response()
+      }
+      ("GET", path) if path.contains("/git/refs/") => {
+        Json(json!({ "object": { "sha": "parent" } })).into_response()
+      }
+      ("GET", path) if path.contains("/git/commits/") => {
+        Json(json!({ "tree": { "sha": "tree" } })).into_response()
+      }
+      ("GET", path) if path.starts_with("/repos/") => {
+        Json(json!({ "default_branch": "main" })).into_response()
+      }
+      ("POST", path) if path.ends_with("/git/trees") || path.ends_with("/git/commits") => {
+        (StatusCode::CREATED, Json(json!({ "sha": "new" }))).into_response()
+      }
+      ("PATCH", _) | ("PUT", _) => Json(json!({})).into_response(),
+      _ => StatusCode::NOT_FOUND.into_response(),
+    }
+  }
+
+  /// Start the mock once, on its own runtime so it outlives each test's,
+  /// and point the GitHub client at it
+  fn use_mock_github() {
+    static URL: OnceLock<String> = OnceLock::new();
+    URL.get_or_init(|| {
+      let (tx, rx) = std::sync::mpsc::channel();
+      std::thread::spawn(move || {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+          let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+          tx.send(listener.local_addr().unwrap()).unwrap();
+          axum::serve(listener, Router::new().fallback(mock_github)).await.unwrap();
+        });
+      });
+      let url = format!("http://{}", rx.recv().unwrap());
+      std::env::set_var("NBI_GITHUB_API_URL", &url);
+      url
+    });
+  }
+
+  /// Server state on a config in a scratch directory, so tests never read
+  /// the real config
+  fn state() -> api::AppState {
+    let dir = std::env::temp_dir().join(format!("nbi-server-{}", std::process::id()));
+    state_at(dir.join("config.toml"))
+  }
+
+  fn state_at(path: std::path::PathBuf) -> api::AppState {
+    let config = Arc::new(ConfigSource::resolve(Some(path), None));
+    api::AppState { config, ..Default::default() }
+  }
+
+  async fn serve(limiter: api::RegisterLimiter) -> String {
+    let state = api::AppState { register: Arc::new(limiter), ..state() };
+    serve_router(router(state, Guards::default())).await + "/api/register"
+  }
+
+  async fn serve_router(app: Router) -> String {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let app = app.into_make_service_with_connect_info::<SocketAddr>();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+    format!("http://{}", addr)
+  }
+
+  async fn serve_with_token(token: &str) -> String {
+    let auth = Some(AuthToken::new(token));
+    serve_router(router(state(), Guards { auth, ..Default::default() })).await
+  }
+
+  /// Register a name no registry can take, which fails before any network
+  /// call once past authentication
+  async fn post_with_auth(base: &str, auth: Option<&str>) -> (StatusCode, Value) {
+    let mut request = reqwest::Client::new()
+      .post(format!("{}/api/register", base))
+      .json(&json!({ "name": "foo", "registry": "brew" }));
+    if let Some(auth) = auth {
+      request = request.header(header::AUTHORIZATION, auth);
+    }
+    let response = request.send().await.unwrap();
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+    (status, response.json().await.unwrap())
+  }
+
+  async fn register(url: &str, body: Value) -> (StatusCode, Value) {
+    let response = reqwest::Client::new()
+      .post(url)
+      .bearer_auth("ghp_test")
+      .json(&body)
+      .send()
+      .await
+      .unwrap();
+    let status = StatusCode::from_u16(response.status().as_u16()).unwrap();
+    (status, response.json().await.unwrap())
+  }
+
+  #[test]
+  fn test_exposure_warning_only_off_loopback() {
+    assert_eq!(exposure_warning(IpAddr::V4(Ipv4Addr::LOCALHOST), false), None);
+    assert_eq!(exposure_warning(IpAddr::V6(Ipv6Addr::LOCALHOST), false), None);
+    let warning = exposure_warning("0.0.0.0".parse().unwrap(), false).unwrap();
+    assert!(warning.contains("no authentication"));
+    assert_eq!(exposure_warning("0.0.0.0".parse().unwrap(), true), None);
+  }
+
+  #[tokio::test]
+  async fn test_auth_accepts_matching_token() {
+    let base = serve_with_token("s3cret").await;
+    let (status, body) = post_with_auth(&base, Some("Bearer s3cret")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["kind"], "invalid_registry");
+  }
+
+  #[tokio::test]
+  async fn test_auth_rejects_wrong_token() {
+    let base = serve_with_token("s3cret").await;
+    let (status, body) = post_with_auth(&base, Some("Bearer guess")).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["kind"], "unauthorized");
+    assert_eq!(body["error"], "Invalid access token");
+  }
+
+  #[tokio::test]
+  async fn test_auth_rejects_missing_header() {
+    let base = serve_with_token("s3cret").await;
+    let (status, body) = post_with_auth(&base, None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["error"], "Access token required");
+
+    // The page itself stays public so it can ask for the token
+    let page = reqwest::get(format!("{}/", base)).await.unwrap();
+    assert_eq!(page.status().as_u16(), 200);
+  }
+
+  #[test]
+  fn test_browser_url_uses_loopback_for_unspecified() {
+    assert_eq!(browser_url("0.0.0.0:3000".parse().unwrap()), "http://127.0.0.1:3000");
+    assert_eq!(browser_url("[::]:8080".parse().unwrap()), "http://[::1]:8080");
+    assert_eq!(browser_url("192.168.1.5:3000".parse().unwrap()), "http://192.168.1.5:3000");
+  }
+
+  async fn get_json(url: String) -> (u16, Value) {
+    let response = reqwest::get(url).await.unwrap();
+    (response.status().as_u16(), response.json().await.unwrap())
+  }
+
+  struct SlowServer {
+    url: String,
+    /// Notified once the handler has the request
+    started: Arc<tokio::sync::Notify>,
+    stop: tokio::sync::oneshot::Sender<()>,
+    /// Whether `serve_until` drained every request
+    drained: tokio::task::JoinHandle<bool>,
+  }
+
+  /// Serve a route that answers after `delay`, until `stop` fires
+  async fn serve_slow(delay: Duration, grace: Duration) -> SlowServer {
+    let started = Arc::new(tokio::sync::Notify::new());
+    let notify = started.clone();
+    let app = Router::new().route(
+      "/slow",
+      get(move || async move {
+        notify.notify_one();
+        tokio::time::sleep(delay).await;
+        "done"
+      }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}/slow", listener.local_addr().unwrap());
+    let (stop, stopped) = tokio::sync::oneshot::channel::<()>();
+    let shutdown = async move {
+      let _ = stopped.await;
+    };
+    let drained =
+      tokio::spawn(async move { serve_until(listener, app, shutdown, grace).await.unwrap() });
+    SlowServer { url, started, stop, drained }
+  }
+
+  #[tokio::test]
+  async fn test_shutdown_lets_in_flight_request_finish() {
+    let server = serve_slow(Duration::from_millis(300), Duration::from_secs(5)).await;
+    let request = tokio::spawn(reqwest::get(server.url));
+    server.started.notified().await;
+    server.stop.send(()).unwrap();
+
+    let response = request.await.unwrap().unwrap();
+    assert_eq!(response.text().await.unwrap(), "done");
+    assert!(server.drained.await.unwrap(), "every request should have drained");
+  }
+
+  #[tokio::test]
+  async fn test_shutdown_stops_waiting_after_grace_period() {
+    let server = serve_slow(Duration::from_secs(30), Duration::from_millis(100)).await;
+    let request = tokio::spawn(reqwest::get(server.url));
+    server.started.notified().await;
+    server.stop.send(()).unwrap();
+
+    let drained = tokio::time::timeout(Duration::from_secs(5), server.drained).await.unwrap();
+    let drained = drained.unwrap();
+    assert!(!drained);
+    assert!(!request.is_finished());
+    request.abort();
+  }
+
+  #[tokio::test]
+  async fn test_health_needs_no_token() {
+    let base = serve_with_token("s3cret").await;
+    let (status, body) = get_json(format!("{}/api/health", base)).await;
+    assert_eq!((status, body), (200, json!({ "status": "ok" })));
+  }
+
+  #[tokio::test]
+  async fn test_get_check_and_domain_reject_bad_queries() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    for path in ["/api/check", "/api/check?registries=npm", "/api/domain?tlds=com"] {
+      let (status, body) = get_json(format!("{}{}", base, path)).await;
+      assert_eq!(status, 400, "{}", path);
+      assert!(body["error"].as_str().unwrap().contains("name"), "{}: {}", path, body);
+    }
+    let (status, body) = get_json(format!("{}/api/check?name=foo&registries=nmp", base)).await;
+    assert_eq!(status, 400);
+    assert!(body["error"].as_str().unwrap().contains("valid:"));
+    let (status, _) = get_json(format!("{}/api/domain?name=%20", base)).await;
+    assert_eq!(status, 400);
+  }
+
+  /// Every `(method, path)` [`router`] registers under `/api/` besides the
+  /// spec itself, read from the `.route(...)` calls above so a new route
+  /// can't be left out
+  fn api_routes() -> Vec<(String, String)> {
+    let source = include_str!("mod.rs");
+    let router = &source[..source.find("#[cfg(test)]").unwrap()];
+    let mut routes = Vec::new();
+    for call in router.split(".route(\"").skip(1) {
+      let (path, rest) = call.split_once('"').unwrap();
+      let handler = rest.split(')').next().unwrap();
+      let method = handler.trim_start_matches([',', ' ']).split('(').next().unwrap();
+      if path.starts_with("/api/") && path != "/api/openapi.json" {
+        routes.push((method.to_string(), path.to_string()));
+      }
+    }
+    routes
+  }
+
+  /// `path` with every parameter segment alike, since the spec names the
+  /// badge file `{name}.svg` where axum can only capture `{file}`
+  fn route_shape(path: &str) -> String {
+    let segments = path.split('/').map(|s| if s.contains('{') { "{}" } else { s });
+    segments.collect::<Vec<_>>().join("/")
+  }
+
+  /// Status and `Access-Control-Allow-Origin` for `method` from `origin`
+  async fn cross_origin(
+    base: &str,
+    method: Method,
+    path: &str,
+    origin: &str,
+  ) -> (u16, Option<String>) {
+    let mut request = reqwest::Client::new()
+      .request(method.clone(), format!("{}{}", base, path))
+      .header(header::ORIGIN, origin);
+    if method == Method::OPTIONS {
+      request = request
+        .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+        .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type,authorization");
+    }
+    let response = request.send().await.unwrap();
+    let allowed = response.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN);
+    (response.status().as_u16(), allowed.map(|v| v.to_str().unwrap().to_string()))
+  }
+
+  #[tokio::test]
+  async fn test_other_origins_are_refused_by_default() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    assert_eq!(cross_origin(&base, Method::GET, "/api/version", &base).await, (200, None));
+    let evil = "https://evil.example";
+    assert_eq!(cross_origin(&base, Method::GET, "/api/version", evil).await, (403, None));
+    assert_eq!(cross_origin(&base, Method::OPTIONS, "/api/config", evil).await, (403, None));
+    assert_eq!(cross_origin(&base, Method::POST, "/api/config", evil).await, (403, None));
+
+    // Clients that aren't browsers send no Origin
+    let (status, _) = get_json(format!("{}/api/version", base)).await;
+    assert_eq!(status, 200);
+  }
+
+  #[tokio::test]
+  async fn test_configured_origins_get_cors_headers() {
+    let app = "https://app.example";
+    let origins = AllowedOrigins::parse(&[format!("{}/", app)]).unwrap();
+    let guards = Guards { origins, ..Default::default() };
+    let base = serve_router(router(state(), guards)).await;
+
+    let allowed = Some(app.to_string());
+    let preflight = cross_origin(&base, Method::OPTIONS, "/api/check", app).await;
+    assert_eq!(preflight, (200, allowed.clone()));
+    assert_eq!(cross_origin(&base, Method::GET, "/api/version", app).await, (200, allowed));
+    let other = "https://other.example";
+    assert_eq!(cross_origin(&base, Method::OPTIONS, "/api/check", other).await, (403, None));
+    assert_eq!(cross_origin(&base, Method::GET, "/api/version", other).await.0, 403);
+    assert_eq!(cross_origin(&base, Method::GET, "/api/version", &base).await.0, 200);
+  }
+
+  #[tokio::test]
+  async fn test_page_assets_are_served() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    let page = reqwest::get(format!("{}/", base)).await.unwrap().text().await.unwrap();
+    let assets = [("/static/app.jsx", "text/javascript"), ("/static/app.css", "text/css")];
+    for (path, content_type) in assets {
+      assert!(page.contains(path), "{}", path);
+      let response = reqwest::get(format!("{}{}", base, path)).await.unwrap();
+      assert_eq!(response.status().as_u16(), 200);
+      assert_eq!(response.headers()[header::CONTENT_TYPE], content_type);
+      assert!(response.headers().contains_key(header::ETAG));
+    }
+  }
+
+  #[tokio::test]
+  async fn test_responses_are_compressed_by_accept_encoding() {
+    let dir = std::env::temp_dir().join(format!("nbi-static-br-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("local.js"), "console.log(1)".repeat(10)).unwrap();
+    std::fs::write(dir.join("local.js.br"), "precompressed").unwrap();
+    let assets = Arc::new(Assets::new(Some(dir.clone())));
+    let base = serve_router(router(api::AppState { assets, ..state() }, Guards::default())).await;
+
+    let fetch = |path: &str, encoding: Option<&str>| {
+      let mut request = reqwest::Client::new().get(format!("{}{}", base, path));
+      if let Some(encoding) = encoding {
+        request = request.header(header::ACCEPT_ENCODING, encoding);
+      }
+      request.send()
+    };
+    let encoding = |response: &reqwest::Response| {
+      let value = response.headers().get(header::CONTENT_ENCODING);
+      value.map(|v| v.to_str().unwrap().to_string())
+    };
+    for path in ["/static/app.jsx", "/api/openapi.json"] {
+      for accepted in ["gzip", "br"] {
+        let response = fetch(path, Some(accepted)).await.unwrap();
+        assert_eq!(encoding(&response).as_deref(), Some(accepted), "{}", path);
+      }
+      assert_eq!(encoding(&fetch(path, None).await.unwrap()), None, "{}", path);
+    }
+
+    // A precompressed sibling is sent as is, not compressed again
+    let response = fetch("/static/local.js", Some("br")).await.unwrap();
+    assert_eq!(encoding(&response).as_deref(), Some("br"));
+    assert_eq!(response.text().await.unwrap(), "precompressed");
+    let response = fetch("/static/local.js", Some("gzip")).await.unwrap();
+    assert_eq!(encoding(&response).as_deref(), Some("gzip"));
+
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_config_schema_and_unknown_fields() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    let (status, schema) = get_json(format!("{}/api/config/schema", base)).await;
+    assert_eq!(status, 200);
+    let fields = schema["fields"].as_array().unwrap();
+    let npm = fields.iter().find(|f| f["key"] == "registries.npm").unwrap();
+    assert_eq!((&npm["type"], &npm["default"]), (&json!("boolean"), &json!(true)));
+
+    // Rejected before anything is written
+    let response = reqwest::Client::new()
+      .post(format!("{}/api/config", base))
+      .json(&json!({ "registries": { "nmp": false } }))
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("registries.nmp"));
+  }
+
+  #[tokio::test]
+  async fn test_openapi_spec_covers_every_route() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    let (status, spec) = get_json(format!("{}/api/openapi.json", base)).await;
+    assert_eq!(status, 200);
+    assert!(spec["openapi"].as_str().unwrap().starts_with("3."));
+    assert_eq!(spec["info"]["version"], env!("CARGO_PKG_VERSION"));
+
+    let paths = spec["paths"].as_object().unwrap();
+    let documented: Vec<(String, String)> = paths
+      .iter()
+      .flat_map(|(path, operations)| {
+        let methods = operations.as_object().unwrap().keys();
+        methods.map(move |method| (method.clone(), route_shape(path)))
+      })
+      .collect();
+    let routes = api_routes();
+    assert!(routes.contains(&("post".to_string(), "/api/register".to_string())));
+    for (method, path) in &routes {
+      let route = (method.clone(), route_shape(path));
+      assert!(documented.contains(&route), "{} {} is undocumented", method, path);
+    }
+
+    // And nothing documented is missing from the router: a bare request
+    // may be rejected, but never as an unknown route or method. Path
+    // parameters get a known registry and a blank name, so nothing is checked
+    let client = reqwest::Client::new();
+    for (path, operations) in paths {
+      for method in operations.as_object().unwrap().keys() {
+        let route = (method.clone(), route_shape(path));
+        let served = routes.iter().any(|(m, p)| (m.clone(), route_shape(p)) == route);
+        assert!(served, "{} {} isn't in the router", method, path);
+        let method = reqwest::Method::from_bytes(method.to_uppercase().as_bytes()).unwrap();
+        let url = format!("{}{}", base, path.replace("{registry}", "npm").replace("{name}", "%20"));
+        let response = client.request(method.clone(), url).send();
+        let status = response.await.unwrap().status().as_u16();
+        assert!(status != 404 && status != 405, "{} {} -> {}", method, path, status);
+      }
+    }
+
+    let docs = reqwest::get(format!("{}/docs", base)).await.unwrap();
+    assert!(docs.text().await.unwrap().contains("/api/openapi.json"));
+  }
+
+  #[tokio::test]
+  async fn test_badge_serves_cached_result_as_svg() {
+    use crate::registry::{AvailabilityResult, RegistryType};
+
+    let state = state();
+    let crates = AvailabilityResult {
+      registry: RegistryType::Crates,
+      name: "foo".to_string(),
+      availability: Availability::Taken,
+      error: None,
+      detail: None,
+      qualifier: None,
+    };
+    state.cache.insert("foo", &crates);
+    let base = serve_router(router(state, Guards::default())).await;
+
+    let response = reqwest::get(format!("{}/api/badge/crates/foo.svg", base)).await.unwrap();
+    assert_eq!(response.status().as_u16(), 200);
+    assert_eq!(response.headers()[header::CONTENT_TYPE], "image/svg+xml");
+    assert_eq!(response.headers()[header::CACHE_CONTROL], "public, max-age=300");
+    let svg = response.text().await.unwrap();
+    assert!(svg.contains("foo on crates.io: taken"));
+
+    for path in ["/api/badge/nope/foo.svg", "/api/badge/crates/foo.png"] {
+      let (status, body) = get_json(format!("{}{}", base, path)).await;
+      assert_eq!(status, 404, "{}", path);
+      assert!(body["kind"].is_string());
+    }
+  }
+
+  #[tokio::test]
+  async fn test_check_serves_cached_results_until_cleared() {
+    use crate::registry::{AvailabilityResult, RegistryType};
+
+    let state = state();
+    let npm = AvailabilityResult {
+      registry: RegistryType::Npm,
+      name: "foo".to_string(),
+      availability: Availability::Available,
+      error: None,
+      detail: None,
+      qualifier: None,
+    };
+    state.cache.insert("foo", &npm);
+    let base = serve_router(router(state.clone(), Guards::default())).await;
+
+    let (status, body) = get_json(format!("{}/api/check?name=foo&registries=npm", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["results"][0]["registry"], "npm");
+    assert_eq!(body["results"][0]["cached"], true);
+
+    let cleared = reqwest::Client::new()
+      .post(format!("{}/api/cache/clear", base))
+      .send()
+      .await
+      .unwrap();
+    let body: Value = cleared.json().await.unwrap();
+    assert_eq!(body["cleared"], 1);
+    assert!(state.cache.lookup("foo", &Default::default()).0.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_metrics_after_fake_checks() {
+    use crate::registry::{AvailabilityResult, RegistryType};
+
+    crate::metrics::install();
+    let taken = AvailabilityResult {
+      registry: RegistryType::Flatpak,
+      name: "foo".to_string(),
+      availability: Availability::Taken,
+      error: None,
+      detail: None,
+      qualifier: None,
+    };
+    crate::metrics::record_check(&taken, Duration::from_millis(40));
+    crate::metrics::record_check(&taken, Duration::from_millis(700));
+
+    let base = serve_router(router(state(), Guards::default())).await;
+    reqwest::get(format!("{}/api/health", base)).await.unwrap();
+    let response = reqwest::get(format!("{}/metrics", base)).await.unwrap();
+    assert!(response.headers()[header::CONTENT_TYPE].to_str().unwrap().starts_with("text/plain"));
+    let text = response.text().await.unwrap();
+
+    // Other tests share the recorder, so counts are at least what this one added
+    let value = |prefix: &str| -> u64 {
+      let line = text.lines().find(|l| l.starts_with(prefix));
+      line.unwrap_or_else(|| panic!("{}", prefix)).rsplit(' ').next().unwrap().parse().unwrap()
+    };
+    assert!(value(r#"nbi_checks_total{registry="flatpak",outcome="taken"}"#) >= 2);
+    assert!(value(r#"nbi_check_duration_seconds_bucket{registry="flatpak",le="0.05"}"#) >= 1);
+    assert!(value(r#"nbi_check_duration_seconds_count{registry="flatpak"}"#) >= 2);
+    let health = r#"nbi_http_requests_total{method="GET",route="/api/health",status="200"}"#;
+    assert!(value(health) >= 1);
+  }
+
+  #[tokio::test]
+  async fn test_domain_requests_are_capped() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    let domains: Vec<_> = (0..51).map(|i| format!("foo{}.com", i)).collect();
+    let response = reqwest::Client::new()
+      .post(format!("{}/api/domain/full", base))
+      .json(&json!({ "domains": domains }))
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(response.status().as_u16(), 400);
+    let body: Value = response.json().await.unwrap();
+    assert!(body["error"].as_str().unwrap().contains("at most 50"));
+
+    let tlds: Vec<_> = (0..51).map(|i| format!("t{}", i)).collect();
+    let url = format!("{}/api/domain?name=foo&tlds={}", base, tlds.join(","));
+    assert_eq!(get_json(url).await.0, 400);
+  }
+
+  #[tokio::test]
+  async fn test_invalid_requests_get_structured_errors() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    let client = reqwest::Client::new();
+    let post = |path: &str, body: &str, content_type: &str| {
+      client
+        .post(format!("{}{}", base, path))
+        .header(header::CONTENT_TYPE, content_type)
+        .body(body.to_string())
+        .send()
+    };
+    let error = |response: reqwest::Response| async move {
+      let status = response.status().as_u16();
+      let body: Value = response.json().await.unwrap();
+      assert!(body["error"].is_string(), "{}", body);
+      (status, body["kind"].clone(), body["field"].clone())
+    };
+    let json = "application/json";
+
+    let register = r#"{"name": "", "registry": "github"}"#;
+    let cases = [
+      ("/api/check", r#"{"name": "#, json, 400, "invalid_body", Value::Null),
+      ("/api/check", r#"{"name": 5}"#, json, 400, "invalid_body", Value::Null),
+      ("/api/check", r#"{"name": "foo"}"#, "text/plain", 415, "invalid_body", Value::Null),
+      ("/api/check", r#"{"name": " "}"#, json, 400, "invalid_name", json!("name")),
+      ("/api/domain", r#"{"name": ""}"#, json, 400, "invalid_name", json!("name")),
+      ("/api/register", register, json, 400, "invalid_name", json!("name")),
+    ];
+    for (path, body, content_type, status, kind, field) in cases {
+      let response = post(path, body, content_type).await.unwrap();
+      assert_eq!(error(response).await, (status, json!(kind), field), "{} {}", path, body);
+    }
+
+    let long = "a".repeat(215);
+    let gets = [
+      (format!("/api/check?name={}", long), "invalid_name", json!("name")),
+      ("/api/check?registries=npm".to_string(), "invalid_query", Value::Null),
+      ("/api/check/stream?name=".to_string(), "invalid_name", json!("name")),
+      ("/api/check?name=foo&exclude=nmp".to_string(), "invalid_registry", json!("exclude")),
+    ];
+    for (path, kind, field) in gets {
+      let response = reqwest::get(format!("{}{}", base, path)).await.unwrap();
+      assert_eq!(error(response).await, (400, json!(kind), field), "{}", path);
+    }
+  }
+
+  #[tokio::test]
+  async fn test_router_applies_body_limit() {
+    let limits = Limits { max_body: 16, ..Limits::default() };
+    let guards = Guards { limits, ..Default::default() };
+    let base = serve_router(router(state(), guards)).await;
+    let response = reqwest::Client::new()
+      .post(format!("{}/api/check", base))
+      .json(&json!({ "name": "a-name-long-enough-to-pass-the-limit" }))
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(response.status().as_u16(), 413);
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "payload_too_large");
+  }
+
+  #[tokio::test]
+  async fn test_version_and_registries() {
+    let base = serve_router(router(state(), Guards::default())).await;
+    let (status, body) = get_json(format!("{}/api/version", base)).await;
+    assert_eq!(status, 200);
+    assert_eq!(body["version"], env!("CARGO_PKG_VERSION"));
+
+    let (status, body) = get_json(format!("{}/api/registries", base)).await;
+    assert_eq!(status, 200);
+    let registries = body["registries"].as_array().unwrap();
+    assert_eq!(registries.len(), crate::registry::RegistryType::ALL.len());
+    assert_eq!(registries[0]["id"], "npm");
+    assert_eq!(registries[0]["name"], "npm");
+    assert!(registries[0]["enabled"].is_boolean());
+  }
+
+  #[tokio::test]
+  async fn test_broken_config_is_reported_not_defaulted() {
+    let dir = std::env::temp_dir().join(format!("nbi-server-broken-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    std::fs::write(dir.join("config.toml"), "registries = [").unwrap();
+    let base = serve_router(router(state_at(dir.join("config.toml")), Guards::default())).await;
+
+    for path in ["/api/config", "/api/registries", "/api/check?name=foo", "/api/domain?name=foo"] {
+      let (status, body) = get_json(format!("{}{}", base, path)).await;
+      assert_eq!(status, 500, "{}", path);
+      assert_eq!(body["kind"], "internal_error", "{}", path);
+      assert!(body["error"].as_str().unwrap().starts_with("Failed to load config"), "{}", path);
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_client_over_budget_gets_429() {
+    let clients = Arc::new(ClientLimiter::new(2));
+    let guards = Guards { clients: Some(clients), ..Default::default() };
+    let app = router(state(), guards);
+    let base = serve_router(app).await;
+    assert_eq!(post_with_auth(&base, None).await.0, StatusCode::BAD_REQUEST);
+    let domain = reqwest::Client::new()
+      .post(format!("{}/api/domain", base))
+      .json(&json!({ "name": "foo", "tlds": [] }))
+      .send()
+      .await
+      .unwrap();
+    assert_ne!(domain.status().as_u16(), 429);
+
+    let response = reqwest::Client::new()
+      .post(format!("{}/api/register", base))
+      .json(&json!({ "name": "foo", "registry": "brew" }))
+      .send()
+      .await
+      .unwrap();
+    assert_eq!(response.status().as_u16(), 429);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    let body: Value = response.json().await.unwrap();
+    assert_eq!(body["kind"], "rate_limited");
+
+    // The page and config reads don't count against the budget
+    let page = reqwest::get(format!("{}/", base)).await.unwrap();
+    assert_eq!(page.status().as_u16(), 200);
+    let config = reqwest::get(format!("{}/api/config", base)).await.unwrap();
+    assert_ne!(config.status().as_u16(), 429);
+  }
+
+  #[tokio::test]
+  async fn test_register_creates_repo_with_manifest() {
+    use_mock_github();
+    let url = serve(api::RegisterLimiter::default()).await;
+    let (status, body) = register(&url, json!({ "name": "foo", "registry": "crates" })).await;
+    assert_eq!(status, StatusCode::OK, "{}", body);
+    assert_eq!(body["url"], "https://github.com/octocat/foo");
+    assert_eq!(body["registry"], "crates");
+  }
+
+  #[tokio::test]
+  async fn test_register_reports_existing_repo() {
+    use_mock_github();
+    let url = serve(api::RegisterLimiter::default()).await;
+    let (status, body) = register(&url, json!({ "name": "taken", "registry": "github" })).await;
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert_eq!(body["kind"], "repo_exists");
+  }
+
+  #[tokio::test]
+  async fn test_register_rejects_unreservable_registry() {
+    let url = serve(api::RegisterLimiter::default()).await;
+    let (status, body) = register(&url, json!({ "name": "foo", "registry": "brew" })).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["kind"], "invalid_registry");
+  }
+
+  #[tokio::test]
+  async fn test_register_is_rate_limited() {
+    let url = serve(api::RegisterLimiter::new(1, Duration::from_secs(60))).await;
+    let request = json!({ "name": "foo", "registry": "brew" });
+    assert_eq!(register(&url, request.clone()).await.0, StatusCode::BAD_REQUEST);
+    let (status, body) = register(&url, request).await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+    assert_eq!(body["kind"], "rate_limited");
+  }
+}
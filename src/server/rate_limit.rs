@@ -0,0 +1,91 @@
+use axum::{
+  extract::{ConnectInfo, Request, State},
+  middleware::Next,
+  response::Response,
+};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::api::too_many_requests;
+
+/// Buckets kept before full ones are forgotten
+const MAX_TRACKED_CLIENTS: usize = 1024;
+
+/// Token bucket per client IP: `per_minute` requests in a burst, refilled
+/// evenly over a minute
+pub struct ClientLimiter {
+  per_minute: u32,
+  buckets: Mutex<HashMap<IpAddr, Bucket>>,
+}
+
+struct Bucket {
+  tokens: f64,
+  updated: Instant,
+}
+
+impl ClientLimiter {
+  pub fn new(per_minute: u32) -> Self {
+    Self { per_minute, buckets: Mutex::new(HashMap::new()) }
+  }
+
+  fn refill_per_sec(&self) -> f64 {
+    f64::from(self.per_minute) / 60.0
+  }
+
+  /// Take a token for `ip`, or say how long until one is available
+  fn acquire(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+    let capacity = f64::from(self.per_minute);
+    let rate = self.refill_per_sec();
+    let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+    if buckets.len() >= MAX_TRACKED_CLIENTS {
+      let refilled = |b: &Bucket| b.tokens + now.duration_since(b.updated).as_secs_f64() * rate;
+      buckets.retain(|_, b| refilled(b) < capacity);
+    }
+
+    let bucket = buckets.entry(ip).or_insert(Bucket { tokens: capacity, updated: now });
+    let elapsed = now.duration_since(bucket.updated).as_secs_f64();
+    bucket.tokens = (bucket.tokens + elapsed * rate).min(capacity);
+    bucket.updated = now;
+    if bucket.tokens < 1.0 {
+      return Err(Duration::from_secs_f64((1.0 - bucket.tokens) / rate));
+    }
+    bucket.tokens -= 1.0;
+    Ok(())
+  }
+}
+
+/// Answer 429 once the caller's IP has used up its budget
+pub async fn limit_clients(
+  State(limiter): State<Arc<ClientLimiter>>,
+  request: Request,
+  next: Next,
+) -> Response {
+  let ip = request
+    .extensions()
+    .get::<ConnectInfo<SocketAddr>>()
+    .map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |info| info.0.ip());
+  match limiter.acquire(ip, Instant::now()) {
+    Ok(()) => next.run(request).await,
+    Err(wait) => too_many_requests("requests", wait),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_bucket_refills_over_time_per_client() {
+    let limiter = ClientLimiter::new(2);
+    let (a, b) = ("10.0.0.1".parse().unwrap(), "10.0.0.2".parse().unwrap());
+    let start = Instant::now();
+    assert!(limiter.acquire(a, start).is_ok() && limiter.acquire(a, start).is_ok());
+    let wait = limiter.acquire(a, start).unwrap_err();
+    assert_eq!(wait, Duration::from_secs(30));
+    assert!(limiter.acquire(b, start).is_ok());
+    assert!(limiter.acquire(a, start + Duration::from_secs(30)).is_ok());
+    assert!(limiter.acquire(a, start + Duration::from_secs(30)).is_err());
+  }
+}
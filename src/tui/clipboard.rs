@@ -0,0 +1,28 @@
+//! Copying names and URLs to the system clipboard (`y`/`Y` in the TUI)
+
+use anyhow::Result;
+
+/// Put `text` on the clipboard, failing with a readable reason when there's
+/// no clipboard, e.g. over SSH without a display
+#[cfg(feature = "clipboard")]
+pub fn copy(text: &str) -> Result<()> {
+  use std::sync::Mutex;
+
+  // On X11 and Wayland the copied text is served by whoever owns the
+  // clipboard, so the handle stays open until nbi exits
+  static CLIPBOARD: Mutex<Option<arboard::Clipboard>> = Mutex::new(None);
+
+  let mut clipboard = CLIPBOARD.lock().unwrap_or_else(|e| e.into_inner());
+  if clipboard.is_none() {
+    let opened = arboard::Clipboard::new()
+      .map_err(|e| anyhow::anyhow!("No clipboard available ({})", e))?;
+    *clipboard = Some(opened);
+  }
+  let clipboard = clipboard.as_mut().expect("opened above");
+  clipboard.set_text(text).map_err(|e| anyhow::anyhow!("Copying failed ({})", e))
+}
+
+#[cfg(not(feature = "clipboard"))]
+pub fn copy(_text: &str) -> Result<()> {
+  anyhow::bail!("Not built with the clipboard feature")
+}
@@ -4,213 +4,616 @@
 //! Each handler is responsible for a specific screen and delegates business logic
 //! to appropriate services.
 
-use crate::app::{App, InputMode};
-use crate::registry::{self, RegistryType, github::{ManifestType, GitHubError}};
-use crossterm::event::KeyCode;
+use crate::app::{App, InputMode, Modal, PendingRegistration, RepoTextField, TokenCheck};
+use crate::config::TOKEN_SOURCES;
+use crate::input::TextInput;
+use crate::registry::github::{EnsuredRepo, GitHubError, OnFailure, Progress};
+use crate::registry::{self, RegistryType, TimedResult};
+use crate::tui::keys::Action;
+use crate::tui::toast::Severity;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use futures::StreamExt;
+use std::future::Future;
 use std::sync::Arc;
-use tokio::sync::Mutex;
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
 
 /// Result type for registration operations
 #[derive(Debug, Clone)]
 pub enum RegistrationResult {
-  Success(String),
+  Success { message: String, repo_url: String },
   Error(String),
 }
 
 /// Handle search screen input
 pub async fn handle_search_input(
   app: &mut App,
-  key_code: KeyCode,
+  key: KeyEvent,
   app_arc: Arc<Mutex<App>>,
 ) {
   match app.input_mode {
-    InputMode::Normal => handle_search_normal_mode(app, key_code),
-    InputMode::Editing => handle_search_editing_mode(app, key_code, app_arc).await,
+    InputMode::Normal => handle_search_normal_mode(app, key, app_arc).await,
+    InputMode::Editing => handle_search_editing_mode(app, key, app_arc).await,
   }
 }
 
-fn handle_search_normal_mode(app: &mut App, key_code: KeyCode) {
-  match key_code {
-    KeyCode::Char('i') | KeyCode::Char('e') | KeyCode::Enter => {
-      app.input_mode = InputMode::Editing;
+async fn handle_search_normal_mode(app: &mut App, key: KeyEvent, app_arc: Arc<Mutex<App>>) {
+  if app.show_result_detail {
+    // Esc is handled by the runner; Enter/d toggle the popup closed
+    match key.code {
+      KeyCode::Enter | KeyCode::Char('d') => app.show_result_detail = false,
+      KeyCode::Char('o') => app.open_selected_result(),
+      _ => {}
+    }
+    return;
+  }
+
+  // Bound keys come first, so they can take over the fixed letters
+  match key.code {
+    _ if app.keys.is(Action::SelectUp, &key) => app.select_previous_result(),
+    _ if app.keys.is(Action::SelectDown, &key) => app.select_next_result(),
+    _ if app.keys.is(Action::Edit, &key) => app.input_mode = InputMode::Editing,
+    KeyCode::Char('o') => app.open_selected_result(),
+    KeyCode::Enter | KeyCode::Char('d') if app.open_result_detail() => {}
+    KeyCode::Enter => app.input_mode = InputMode::Editing,
+    KeyCode::Char('f') => app.cycle_result_filter(),
+    KeyCode::Char('y') => {
+      if let Some(result) = app.selected_result() {
+        let url = result.page_url();
+        app.copy_to_clipboard(&url);
+      }
+    }
+    KeyCode::Char('Y') => {
+      if let Some(name) = app.selected_result().map(|r| r.name.clone()) {
+        app.copy_to_clipboard(&name);
+      }
+    }
+    KeyCode::Char('r') => recheck_selected(app, app_arc),
+    KeyCode::Char('R') if !app.is_searching => {
+      if let Some(name) = app.last_searched.clone() {
+        start_search(app, app_arc, name).await;
+      }
     }
-    KeyCode::Up => app.select_previous(),
-    KeyCode::Down => app.select_next(),
     _ => {}
   }
 }
 
 async fn handle_search_editing_mode(
   app: &mut App,
-  key_code: KeyCode,
+  key: KeyEvent,
   app_arc: Arc<Mutex<App>>,
 ) {
-  match key_code {
-    KeyCode::Enter => {
+  match key.code {
+    _ if app.keys.is(Action::Search, &key) => {
       if !app.search_input.is_empty() {
-        start_search(app, app_arc).await;
+        let name = app.search_input.as_str().to_string();
+        start_search(app, app_arc, name).await;
       }
       app.input_mode = InputMode::Normal;
     }
-    KeyCode::Char(c) => app.search_input.push(c),
-    KeyCode::Backspace => { app.search_input.pop(); }
     KeyCode::Esc => app.input_mode = InputMode::Normal,
+    _ => edit_text(&mut app.search_input, key),
+  }
+}
+
+/// Cursor movement, editing and readline-style Ctrl shortcuts for a text input
+fn edit_text(input: &mut TextInput, key: KeyEvent) {
+  if key.modifiers.contains(KeyModifiers::CONTROL) {
+    match key.code {
+      KeyCode::Char('u') => input.clear(),
+      KeyCode::Char('w') => input.delete_word_before(),
+      KeyCode::Char('a') => input.move_home(),
+      KeyCode::Char('e') => input.move_end(),
+      _ => {}
+    }
+    return;
+  }
+
+  match key.code {
+    KeyCode::Char(c) => input.insert(c),
+    KeyCode::Backspace => input.delete_before(),
+    KeyCode::Delete => input.delete_at(),
+    KeyCode::Left => input.move_left(),
+    KeyCode::Right => input.move_right(),
+    KeyCode::Home => input.move_home(),
+    KeyCode::End => input.move_end(),
     _ => {}
   }
 }
 
-async fn start_search(app: &mut App, app_arc: Arc<Mutex<App>>) {
-  let name = app.search_input.clone();
+async fn start_search(app: &mut App, app_arc: Arc<Mutex<App>>, name: String) {
   let settings = app.config.registries.clone();
-  app.is_searching = true;
+  let config = app.config.clone();
+  let generation = app.begin_search(&name);
 
   let app_clone = Arc::clone(&app_arc);
+  let task = tokio::spawn(async move {
+    let github = config.github_account(&settings).await;
+    let mut results = std::pin::pin!(registry::check_stream(&name, &settings, &github));
+    while let Some(timed) = results.next().await {
+      if !app_clone.lock().await.add_search_result(generation, timed) {
+        return;
+      }
+    }
+    let mut app = app_clone.lock().await;
+    if app.finish_search(generation) {
+      app.notify_search_verdict();
+    }
+  });
+  app.search_task = Some(task.abort_handle());
+}
+
+/// Check the highlighted result's registry again, replacing its row
+fn recheck_selected(app: &mut App, app_arc: Arc<Mutex<App>>) {
+  let Some((generation, name, registry)) = app.begin_recheck() else {
+    return;
+  };
+  let config = app.config.clone();
+  spawn_recheck(app_arc, generation, async move {
+    let github = config.github_account(&config.registries).await;
+    registry::check_timed(registry, name, github).await
+  });
+}
+
+/// Write a re-check back once `check` finishes; results from a superseded
+/// search are dropped by `App::finish_recheck`
+fn spawn_recheck(
+  app_arc: Arc<Mutex<App>>,
+  generation: u64,
+  check: impl Future<Output = TimedResult> + Send + 'static,
+) -> JoinHandle<()> {
+  tokio::spawn(async move {
+    let timed = check.await;
+    app_arc.lock().await.finish_recheck(generation, timed);
+  })
+}
+
+/// Handle domain screen input
+pub fn handle_domain_input(app: &mut App, key: KeyEvent, app_arc: Arc<Mutex<App>>) {
+  match app.input_mode {
+    InputMode::Normal => match key.code {
+      _ if app.keys.is(Action::SelectUp, &key) => app.select_previous_domain(),
+      _ if app.keys.is(Action::SelectDown, &key) => app.select_next_domain(),
+      _ if app.keys.is(Action::Edit, &key) => app.input_mode = InputMode::Editing,
+      KeyCode::Enter => app.input_mode = InputMode::Editing,
+      KeyCode::Char('o') => app.open_selected_domain(),
+      _ => {}
+    },
+    InputMode::Editing => match key.code {
+      _ if app.keys.is(Action::Search, &key) => {
+        start_domain_check(app, app_arc);
+        app.input_mode = InputMode::Normal;
+      }
+      KeyCode::Tab | KeyCode::BackTab => app.toggle_domain_field(),
+      _ => edit_text(app.domain_field_input(), key),
+    },
+  }
+}
+
+fn start_domain_check(app: &mut App, app_arc: Arc<Mutex<App>>) {
+  let Some((generation, name, tlds)) = app.begin_domain_check() else {
+    return;
+  };
+
   tokio::spawn(async move {
-    let results = registry::check_all(&name, &settings).await;
-    let mut app_guard = app_clone.lock().await;
-    app_guard.search_results = results;
-    app_guard.is_searching = false;
+    let tlds: Vec<&str> = tlds.iter().map(String::as_str).collect();
+    let results = registry::domain::check_multiple_tlds(&name, &tlds).await;
+    app_arc.lock().await.finish_domain_check(generation, results);
   });
 }
 
 /// Handle settings screen input
-pub fn handle_settings_input(app: &mut App, key_code: KeyCode) {
+pub fn handle_settings_input(app: &mut App, key: KeyEvent) {
+  if app.input_mode == InputMode::Editing {
+    handle_setting_editing(app, key.code);
+    return;
+  }
+
+  if app.keys.is(Action::SelectUp, &key) {
+    app.selected_setting = app.selected_setting.saturating_sub(1);
+  } else if app.keys.is(Action::SelectDown, &key) {
+    app.selected_setting = (app.selected_setting + 1).min(app.settings_count() - 1);
+  } else if key.code != KeyCode::Enter && !app.keys.is(Action::Toggle, &key) {
+    // Enter and the toggle keys act on the selected row
+  } else if app.tld_setting_selected() {
+    app.start_tld_edit();
+  } else if app.username_setting_selected() {
+    app.start_username_edit();
+  } else if app.desktop_setting_selected() {
+    app.toggle_desktop_notifications();
+  } else {
+    app.toggle_selected_registry();
+  }
+}
+
+/// Edit the default TLD list or GitHub username; Esc (handled by the
+/// runner) discards changes
+fn handle_setting_editing(app: &mut App, key_code: KeyCode) {
+  let username = app.username_setting_selected();
+  let input = if username { &mut app.username_input } else { &mut app.tld_input };
   match key_code {
-    KeyCode::Up => {
-      if app.selected_setting > 0 {
-        app.selected_setting -= 1;
+    KeyCode::Enter if username => app.commit_username_edit(),
+    KeyCode::Enter => app.commit_tld_edit(),
+    KeyCode::Char(c) => input.push(c),
+    KeyCode::Backspace => { input.pop(); }
+    _ => {}
+  }
+}
+
+/// Handle register screen input
+pub fn handle_register_input(app: &mut App, key: KeyEvent, app_arc: Arc<Mutex<App>>) {
+  if app.input_mode == InputMode::Editing {
+    // Esc (handled by the runner) discards the edit
+    match key.code {
+      KeyCode::Enter => {
+        app.commit_option_edit();
+        app.save_register_options();
       }
+      _ => edit_text(&mut app.option_input, key),
     }
-    KeyCode::Down => {
-      if app.selected_setting < app.registry_count() - 1 {
-        app.selected_setting += 1;
+    return;
+  }
+
+  match key.code {
+    _ if app.keys.is(Action::SelectUp, &key) => app.select_previous(),
+    _ if app.keys.is(Action::SelectDown, &key) => app.select_next(),
+    _ if app.keys.is(Action::Register, &key) => request_registration(app),
+    _ if app.keys.is(Action::Toggle, &key) => {
+      let ticked = app.toggle_register_check();
+      if !ticked {
+        app.register_status = selected_instructions(app).map(str::to_string);
       }
     }
-    KeyCode::Enter | KeyCode::Char(' ') => {
-      app.toggle_selected_registry();
+    KeyCode::Char('a') => app.toggle_all_register_checks(),
+    KeyCode::Char('t') => check_token(app, app_arc, true),
+    KeyCode::Char('p') => {
+      app.toggle_register_private();
+      app.save_register_options();
+    }
+    KeyCode::Char('i') => {
+      app.toggle_register_issues();
+      app.save_register_options();
+    }
+    KeyCode::Char('d') => app.start_option_edit(RepoTextField::Description),
+    KeyCode::Char('h') => app.start_option_edit(RepoTextField::Homepage),
+    KeyCode::Char('T') => app.start_option_edit(RepoTextField::Topics),
+    KeyCode::Char('g') => app.start_option_edit(RepoTextField::Gitignore),
+    KeyCode::Char('o') if app.cycle_register_owner() => app.save_register_options(),
+    KeyCode::Char('o') => load_orgs(app, app_arc),
+    KeyCode::Char('y') => {
+      if let Some(url) = app.registered_repo_url.clone() {
+        app.copy_to_clipboard(&url);
+      }
     }
     _ => {}
   }
 }
 
-/// Handle register screen input
-pub async fn handle_register_input(app: &mut App, key_code: KeyCode) {
+/// Validate the token in the background, caching who it belongs to
+///
+/// Without `force` this only runs the first time; the runner calls it on
+/// entering the Register screen.
+///
+/// The token itself is looked up here too, off the draw loop, since the
+/// command or keyring may be slow; it's then cached on `app.config`.
+pub fn check_token(app: &mut App, app_arc: Arc<Mutex<App>>, force: bool) {
+  if !app.begin_token_check(force) {
+    return;
+  }
+  let config = app.config.clone();
+  let source = app.source.clone();
+
+  tokio::spawn(async move {
+    let found = tokio::task::spawn_blocking(move || config.github_token_with_source())
+      .await
+      .unwrap_or_default();
+    let token = {
+      let mut app_guard = app_arc.lock().await;
+      // A profile switch meanwhile means this lookup is for another config
+      if app_guard.source != source {
+        return;
+      }
+      app_guard.config.remember_github_token(found.clone());
+      let Some((token, _)) = found else {
+        app_guard.token_check = TokenCheck::Unchecked;
+        return;
+      };
+      token
+    };
+
+    let checked = registry::github::token_info(&token).await;
+    app_arc.lock().await.token_check = match checked {
+      Ok(info) => TokenCheck::Valid(info),
+      Err(e) => TokenCheck::Invalid(format_github_error(e)),
+    };
+  });
+}
+
+/// Fetch the token's orgs, then move to the first one
+fn load_orgs(app: &mut App, app_arc: Arc<Mutex<App>>) {
+  let Some(token) = app.config.get_github_token() else {
+    app.register_status = Some(format!("Error: No GitHub token (tried {})", TOKEN_SOURCES));
+    return;
+  };
+  app.register_status = Some("Loading organizations...".to_string());
+
+  tokio::spawn(async move {
+    let orgs = registry::github::list_orgs(&token).await;
+    let mut app_guard = app_arc.lock().await;
+    match orgs {
+      Ok(orgs) => {
+        app_guard.register_status = None;
+        app_guard.register_orgs = Some(orgs);
+        app_guard.cycle_register_owner();
+        app_guard.save_register_options();
+      }
+      Err(e) => {
+        app_guard.register_status = Some(format!("Error: {}", format_github_error(e)));
+      }
+    }
+  });
+}
+
+/// Handle the registration confirmation dialog; every other key is ignored
+pub fn handle_confirm_input(app: &mut App, key_code: KeyCode, app_arc: Arc<Mutex<App>>) {
   match key_code {
-    KeyCode::Up => app.select_previous(),
-    KeyCode::Down => app.select_next(),
-    KeyCode::Enter => handle_registration(app).await,
+    KeyCode::Char('y') | KeyCode::Char('Y') | KeyCode::Enter => {
+      if let Some(Modal::Register(pending)) = app.modal.take() {
+        start_registration(app, *pending, app_arc);
+      }
+    }
+    KeyCode::Char('f') | KeyCode::Char('F') => {
+      if let Some(Modal::Register(pending)) = app.modal.as_mut() {
+        pending.on_failure = pending.on_failure.toggle();
+      }
+    }
+    KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+      app.modal = None;
+      app.register_status = Some("Registration cancelled".to_string());
+    }
     _ => {}
   }
 }
 
-async fn handle_registration(app: &mut App) {
+/// Handle the quit dialog: y quits, any other key stays
+pub fn handle_quit_confirm(app: &mut App, key_code: KeyCode) {
+  app.confirm_quit(matches!(key_code, KeyCode::Char('y') | KeyCode::Char('Y')));
+}
+
+pub fn handle_cancel_confirm(app: &mut App, key_code: KeyCode) {
+  app.confirm_cancel_registration(matches!(key_code, KeyCode::Char('y') | KeyCode::Char('Y')));
+}
+
+/// Validate the selection and open the confirmation dialog
+///
+/// Ticked registries are registered together; with none ticked the
+/// highlighted one is used. Registries that can't be reserved through
+/// GitHub just show instructions.
+fn request_registration(app: &mut App) {
   // Validate selection
-  let available_registries = app.get_available_registries();
-  if app.selected_registry >= available_registries.len() {
+  app.clamp_registry_selection();
+  let targets = app.registration_targets();
+  if targets.is_empty() {
     app.register_status = Some("No registry selected".to_string());
     return;
   }
-
-  let result = available_registries[app.selected_registry].clone();
-  if result.available != Some(true) {
-    app.register_status = Some("Name not available".to_string());
+  // Results may name more than the term (a domain row is "foo.dev"), so
+  // the repo is named after the search
+  let Some(name) = app.last_searched.clone() else {
+    app.register_status = Some("Search for a name first".to_string());
     return;
-  }
+  };
+  let registries: Vec<_> = targets.iter().map(|r| r.registry).collect();
 
-  let token = match app.config.get_github_token() {
-    Some(t) => t,
-    None => {
-      app.register_status = Some("Error: Set GITHUB_TOKEN environment variable".to_string());
+  if let [registry] = registries[..] {
+    if let Some(instructions) = registration_instructions(registry) {
+      app.register_status = Some(instructions.to_string());
       return;
     }
+  }
+
+  if app.config.get_github_token().is_none() {
+    app.register_status = Some(format!("Error: No GitHub token (tried {})", TOKEN_SOURCES));
+    return;
+  }
+  if let TokenCheck::Invalid(err) = &app.token_check {
+    app.register_status = Some(format!("Error: {} (press t to check again)", err));
+    return;
+  }
+
+  app.modal = Some(Modal::Register(Box::new(PendingRegistration {
+    name,
+    registries,
+    options: app.register_options.clone(),
+    login: app.default_owner().map(str::to_string),
+    on_failure: app.config.register.on_failure,
+  })));
+}
+
+/// Instructions for the highlighted registry, if it can't be reserved
+fn selected_instructions(app: &App) -> Option<&'static str> {
+  registration_instructions(app.highlighted_registry()?.registry)
+}
+
+/// Create the repo in a background task
+///
+/// The task only takes the lock to write back its status, so the UI keeps
+/// redrawing and Esc can abort it through `App::cancel_registration`.
+fn start_registration(app: &mut App, pending: PendingRegistration, app_arc: Arc<Mutex<App>>) {
+  let Some(token) = app.config.get_github_token() else {
+    app.register_status = Some(format!("Error: No GitHub token (tried {})", TOKEN_SOURCES));
+    return;
   };
 
   app.is_registering = true;
-  let reg_result = execute_registration(&result.name, result.registry, &token).await;
-  
-  app.register_status = Some(match reg_result {
-    RegistrationResult::Success(msg) => msg,
-    RegistrationResult::Error(msg) => format!("Error: {}", msg),
+  let owner = pending.options.org.as_ref().or(pending.login.as_ref());
+  app.registration_repo = Some(match owner {
+    Some(owner) => format!("{}/{}", owner, pending.name),
+    None => pending.name.clone(),
   });
-  app.is_registering = false;
-}
+  app.register_status = None;
+  app.register_steps.clear();
+  app.registered_repo_url = None;
 
-async fn execute_registration(
-  name: &str,
-  registry_type: RegistryType,
-  token: &str,
-) -> RegistrationResult {
-  match registry_type {
-    RegistryType::GitHub => register_github(name, token).await,
-    RegistryType::Npm => register_with_manifest(name, ManifestType::Npm, token).await,
-    RegistryType::Crates => register_with_manifest(name, ManifestType::Crates, token).await,
-    RegistryType::PyPi => register_with_manifest(name, ManifestType::PyPi, token).await,
-    RegistryType::Brew => RegistrationResult::Success(
-      "Homebrew: Create a formula and submit PR to homebrew-core".to_string()
-    ),
-    RegistryType::Flatpak => RegistrationResult::Success(
-      "Flatpak: Submit your app to flathub.org/apps/submit".to_string()
-    ),
-    RegistryType::Debian => RegistrationResult::Success(
-      "Debian: Follow ITP process at wiki.debian.org/ITP".to_string()
-    ),
-    RegistryType::DevDomain => RegistrationResult::Success(
-      "Domain registration requires a registrar (e.g., Google Domains, Namecheap)".to_string()
-    ),
-  }
+  let task = tokio::spawn(async move {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let progress = Progress::new(tx);
+    let work = async move {
+      let result = execute_registration(&pending, &token, &progress).await;
+      drop(progress);
+      result
+    };
+    let log_steps = async {
+      while let Some(step) = rx.recv().await {
+        app_arc.lock().await.record_step(step);
+      }
+    };
+    let (reg_result, ()) = tokio::join!(work, log_steps);
+
+    let mut app_guard = app_arc.lock().await;
+    let (summary, status, severity) = match reg_result {
+      RegistrationResult::Success { message, repo_url } => {
+        app_guard.registered_repo_url = Some(repo_url);
+        ("nbi: registration finished", message, Severity::Success)
+      }
+      RegistrationResult::Error(msg) => {
+        ("nbi: registration failed", format!("Error: {}", msg), Severity::Error)
+      }
+    };
+    app_guard.notify_desktop(summary, &status);
+    app_guard.toasts.push(status.clone(), severity);
+    app_guard.register_status = Some(status);
+    app_guard.is_registering = false;
+    app_guard.registration_task = None;
+  });
+  app.registration_task = Some(task.abort_handle());
 }
 
-async fn register_github(name: &str, token: &str) -> RegistrationResult {
-  match registry::github::create_repo(name, None, false, token).await {
-    Ok(repo) => RegistrationResult::Success(format!("Created: {}", repo.html_url)),
-    Err(e) => RegistrationResult::Error(format_github_error(e)),
+/// Manual steps for registries nbi can't reserve through GitHub
+fn registration_instructions(registry: RegistryType) -> Option<&'static str> {
+  match registry {
+    RegistryType::Brew => Some("Homebrew: Create a formula and submit PR to homebrew-core"),
+    RegistryType::Flatpak => Some("Flatpak: Submit your app to flathub.org/apps/submit"),
+    RegistryType::Debian => Some("Debian: Follow ITP process at wiki.debian.org/ITP"),
+    RegistryType::DevDomain => {
+      Some("Domain registration requires a registrar (e.g., Google Domains, Namecheap)")
+    }
+    RegistryType::GitHubUser => Some("GitHub user: Sign up at github.com/signup"),
+    RegistryType::NpmOrg => Some("npm org: Create it at npmjs.com/org/create"),
+    RegistryType::Bluesky => Some("Bluesky: Sign up at bsky.app"),
+    RegistryType::Mastodon => Some("Mastodon: Sign up on the instance"),
+    RegistryType::Trademark => Some("Trademark: File an application at uspto.gov/trademarks"),
+    RegistryType::Npm | RegistryType::Crates | RegistryType::PyPi | RegistryType::GitHub => None,
   }
 }
 
-async fn register_with_manifest(
-  name: &str,
-  manifest_type: ManifestType,
+async fn execute_registration(
+  pending: &PendingRegistration,
   token: &str,
+  progress: &Progress,
 ) -> RegistrationResult {
-  match registry::github::create_repo_with_manifest(name, manifest_type, token).await {
-    Ok(repo) => {
-      let publish_cmd = match manifest_type {
-        ManifestType::Npm => "npm publish",
-        ManifestType::Crates => "cargo publish",
-        ManifestType::PyPi => "twine upload",
-      };
-      RegistrationResult::Success(format!(
-        "{} - Run '{}' to claim the name",
-        repo.html_url, publish_cmd
-      ))
+  let options = pending.repo_options();
+  let login = pending.login.as_deref();
+  let ensured = registry::github::ensure_repo(&pending.name, &options, login, token, progress);
+  let repo = match ensured.await {
+    Ok(repo) => repo,
+    Err(e) => return RegistrationResult::Error(format_github_error(e)),
+  };
+
+  let manifests = pending.manifests();
+  if manifests.is_empty() && !repo.created {
+    return RegistrationResult::Error(format_github_error(GitHubError::RepoExists));
+  }
+
+  let mut added = Vec::new();
+  let mut present = Vec::new();
+  let mut failed = Vec::new();
+  if repo.created {
+    // A new repo gets every manifest plus README and LICENSE in one commit
+    let commit = async {
+      registry::github::wait_until_ready(&repo, token, progress).await?;
+      let full_name = repo.full_name();
+      registry::github::add_topics(&full_name, &manifests, &options, token, progress).await;
+      let files =
+        registry::github::reservation_files(&repo, &manifests, &pending.options, token, progress)
+          .await;
+      if files.is_empty() {
+        return Ok(());
+      }
+      let message = format!("Reserve {}", pending.name);
+      registry::github::commit_files(&repo, &files, &message, token, progress).await
+    };
+    if let Err(e) = commit.await {
+      let message = roll_back(&repo, e, pending.on_failure, token, progress).await;
+      return RegistrationResult::Error(message);
     }
-    Err(GitHubError::RepoExists) => {
-      handle_existing_repo(name, manifest_type, token).await
+    added = manifests;
+  } else {
+    for manifest in manifests {
+      let result =
+        registry::github::add_manifest(&repo, manifest, &pending.options, token, progress).await;
+      match result {
+        Ok(true) => added.push(manifest),
+        Ok(false) => present.push(manifest.filename()),
+        Err(e) => failed.push(format!("{} ({})", manifest.filename(), format_github_error(e))),
+      }
     }
-    Err(e) => RegistrationResult::Error(format_github_error(e)),
   }
+
+  let mut summary = vec![if repo.created {
+    format!("Created {}", repo.html_url)
+  } else {
+    repo.html_url.clone()
+  }];
+  if !added.is_empty() {
+    let filenames: Vec<_> = added.iter().map(|m| m.filename()).collect();
+    summary.push(format!("added {}", filenames.join(", ")));
+  }
+  if !present.is_empty() {
+    summary.push(format!("already had {}", present.join(", ")));
+  }
+
+  if !failed.is_empty() {
+    summary.push(format!("failed {}", failed.join(", ")));
+    return RegistrationResult::Error(format!(
+      "{}. Press Enter again to retry the missing manifests.",
+      summary.join(" - ")
+    ));
+  }
+
+  if !added.is_empty() {
+    let commands: Vec<_> = added.iter().map(|m| format!("'{}'", m.publish_command())).collect();
+    summary.push(format!("Run {} to claim the name", commands.join(" and ")));
+  }
+  RegistrationResult::Success { message: summary.join(" - "), repo_url: repo.html_url }
 }
 
-async fn handle_existing_repo(
-  name: &str,
-  manifest_type: ManifestType,
+/// Apply the chosen failure policy to a repo whose first commit failed and
+/// describe what happened
+async fn roll_back(
+  repo: &EnsuredRepo,
+  error: GitHubError,
+  on_failure: OnFailure,
   token: &str,
-) -> RegistrationResult {
-  let username = match registry::github::get_username(token).await {
-    Ok(u) => u,
-    Err(e) => return RegistrationResult::Error(format_github_error(e)),
-  };
-
-  match registry::github::add_manifest_if_missing(&username, name, manifest_type, token).await {
-    Ok(true) => RegistrationResult::Success(format!(
-      "Added {} to existing repo",
-      manifest_type.filename()
-    )),
-    Ok(false) => RegistrationResult::Success(format!(
-      "{} already exists in repo",
-      manifest_type.filename()
-    )),
-    Err(e) => RegistrationResult::Error(format_github_error(e)),
+  progress: &Progress,
+) -> String {
+  if on_failure == OnFailure::Keep {
+    return format_github_error(repo.manifest_failed(error));
+  }
+  let full_name = repo.full_name();
+  let deleted =
+    progress.track("Deleting repository", registry::github::delete_repo(&full_name, token)).await;
+  match deleted {
+    Ok(()) => format!(
+      "Committing the manifest failed ({}), so {} was deleted again",
+      format_github_error(error),
+      full_name
+    ),
+    Err(delete_error) => format!(
+      "{} Deleting it failed too: {}",
+      format_github_error(repo.manifest_failed(error)),
+      format_github_error(delete_error)
+    ),
   }
 }
 
@@ -219,8 +622,153 @@ fn format_github_error(error: GitHubError) -> String {
     GitHubError::AuthRequired => "Authentication required - check your token".to_string(),
     GitHubError::RepoExists => "Repository already exists".to_string(),
     GitHubError::InvalidName => "Invalid repository name".to_string(),
-    GitHubError::RateLimited => "Rate limited - try again later".to_string(),
+    GitHubError::RateLimited { reset_at: None } => "Rate limited - try again later".to_string(),
+    GitHubError::RateLimited { reset_at: Some(reset_at) } => {
+      format!("Rate limited - try again at {}", registry::github::clock_time(reset_at))
+    }
     GitHubError::ApiError(msg) => format!("API error: {}", msg),
     GitHubError::NetworkError(e) => format!("Network error: {}", e),
+    GitHubError::RepoNotReady { waited_secs } => {
+      format!("GitHub hadn't finished setting up the repo after {}s", waited_secs)
+    }
+    GitHubError::ManifestFailed { repo_url, source, .. } => format!(
+      "{} was created, but the manifest wasn't committed ({}). \
+       Press Enter again to add it to the existing repo.",
+      repo_url,
+      format_github_error(*source)
+    ),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+  use crate::registry::AvailabilityResult;
+
+  fn result(registry: RegistryType, available: Option<bool>) -> AvailabilityResult {
+    AvailabilityResult {
+      registry,
+      name: "foo".to_string(),
+      availability: available.into(),
+      error: available.is_none().then(|| "timeout".to_string()),
+      detail: None,
+      qualifier: None,
+    }
+  }
+
+  /// Rows as (registry, available, error), in display order
+  fn rows(app: &App) -> Vec<(RegistryType, Option<bool>, Option<String>)> {
+    app.search_results.iter().map(|r| (r.registry, r.available(), r.error.clone())).collect()
+  }
+
+  /// An app showing results for "foo": npm free, crates.io taken, PyPI timed out
+  fn searched_app() -> App {
+    let mut app = App::with_config(Config::default());
+    app.search_input.set("foo");
+    app.last_searched = Some("foo".to_string());
+    app.set_search_results(vec![
+      result(RegistryType::Npm, Some(true)),
+      result(RegistryType::Crates, Some(false)),
+      result(RegistryType::PyPi, None),
+    ]);
+    app.input_mode = InputMode::Normal;
+    app
+  }
+
+  #[tokio::test]
+  async fn test_recheck_replaces_only_the_selected_result() {
+    let mut app = searched_app();
+    for _ in 0..3 {
+      app.select_next_result();
+    }
+    let before = rows(&app);
+    let (generation, name, registry) = app.begin_recheck().unwrap();
+    assert_eq!((name.as_str(), registry), ("foo", RegistryType::PyPi));
+    assert!(app.begin_recheck().is_none(), "already being re-checked");
+
+    let app = Arc::new(Mutex::new(app));
+    let fresh = TimedResult { result: result(RegistryType::PyPi, Some(true)), duration_ms: 7 };
+    spawn_recheck(Arc::clone(&app), generation, async move { fresh }).await.unwrap();
+
+    let app = app.lock().await;
+    assert!(app.rechecking.is_empty());
+    assert_eq!(app.search_durations.get(&RegistryType::PyPi), Some(&7));
+    // PyPI moves up among the available rows, the others are untouched
+    let after = rows(&app);
+    let fresh_row = (RegistryType::PyPi, Some(true), None);
+    assert_eq!(after, [before[0].clone(), fresh_row, before[1].clone()]);
+    assert_eq!(app.selected_result().map(|r| r.registry), Some(RegistryType::PyPi));
+  }
+
+  #[tokio::test]
+  async fn test_recheck_from_a_superseded_search_is_dropped() {
+    let mut app = searched_app();
+    app.select_next_result();
+    let (generation, _, _) = app.begin_recheck().unwrap();
+    app.begin_search("bar");
+
+    let app = Arc::new(Mutex::new(app));
+    let late = TimedResult { result: result(RegistryType::Npm, Some(false)), duration_ms: 7 };
+    spawn_recheck(Arc::clone(&app), generation, async move { late }).await.unwrap();
+    assert!(app.lock().await.search_results.is_empty());
+  }
+
+  #[tokio::test]
+  async fn test_a_missing_token_ends_the_check() {
+    let app = App::with_config(Config::default());
+    app.config.remember_github_token(None);
+    let app = Arc::new(Mutex::new(app));
+    {
+      let mut guard = app.lock().await;
+      check_token(&mut guard, Arc::clone(&app), false);
+      assert_eq!(guard.token_check, TokenCheck::Checking);
+    }
+
+    for _ in 0..100 {
+      if app.lock().await.token_check != TokenCheck::Checking {
+        break;
+      }
+      tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+    }
+    let app = app.lock().await;
+    assert_eq!(app.token_check, TokenCheck::Unchecked);
+    assert_eq!(app.config.resolved_token_source(), Some(None));
+  }
+
+  #[tokio::test]
+  async fn test_recheck_keys_wait_for_a_running_search() {
+    let mut app = searched_app();
+    app.select_next_result();
+    app.is_searching = true;
+    let generation = app.search_generation;
+    let before = rows(&app);
+
+    let app = Arc::new(Mutex::new(app));
+    let mut guard = app.lock().await;
+    for c in ['r', 'R'] {
+      let key = KeyEvent::new(KeyCode::Char(c), KeyModifiers::NONE);
+      handle_search_input(&mut guard, key, Arc::clone(&app)).await;
+    }
+    assert!(guard.rechecking.is_empty());
+    assert_eq!(guard.search_generation, generation);
+    assert_eq!(rows(&guard), before);
+  }
+
+  #[tokio::test]
+  async fn test_copy_needs_a_highlighted_result() {
+    let app = Arc::new(Mutex::new(searched_app()));
+    let mut guard = app.lock().await;
+    let key = KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE);
+    handle_search_input(&mut guard, key, Arc::clone(&app)).await;
+    assert!(guard.toasts.history().is_empty());
+
+    guard.select_next_result();
+    handle_search_input(&mut guard, key, Arc::clone(&app)).await;
+    let toast = guard.toasts.history().back().expect("copying reports back");
+    if cfg!(not(feature = "clipboard")) {
+      assert_eq!(toast.severity, Severity::Error);
+      assert_eq!(toast.message, "Couldn't copy: Not built with the clipboard feature");
+    }
   }
 }
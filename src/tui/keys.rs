@@ -0,0 +1,297 @@
+//! Remappable TUI actions and the keys bound to them
+//!
+//! Structural keys stay fixed: Esc, Enter to confirm an edit, and the
+//! editing keys of text inputs.
+
+use crate::config::KeySettings;
+use anyhow::Result;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+
+/// Something a key can be bound to under `[keys]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+  Quit,
+  Help,
+  /// Focus the input on the Search and Domain screens
+  Edit,
+  /// Run the search (or domain check) being typed
+  Search,
+  NextScreen,
+  PreviousScreen,
+  SelectUp,
+  SelectDown,
+  /// Register the ticked or highlighted registries
+  Register,
+  /// Tick a registry to register, or flip a setting
+  Toggle,
+}
+
+impl Action {
+  pub const ALL: [Action; 10] = [
+    Action::Quit,
+    Action::Help,
+    Action::Edit,
+    Action::Search,
+    Action::NextScreen,
+    Action::PreviousScreen,
+    Action::SelectUp,
+    Action::SelectDown,
+    Action::Register,
+    Action::Toggle,
+  ];
+
+  /// Key under `[keys]`
+  pub fn id(&self) -> &'static str {
+    match self {
+      Action::Quit => "quit",
+      Action::Help => "help",
+      Action::Edit => "edit",
+      Action::Search => "search",
+      Action::NextScreen => "next_screen",
+      Action::PreviousScreen => "previous_screen",
+      Action::SelectUp => "select_up",
+      Action::SelectDown => "select_down",
+      Action::Register => "register",
+      Action::Toggle => "toggle",
+    }
+  }
+
+  /// Whether the action is read while typing rather than in Normal mode
+  fn while_editing(&self) -> bool {
+    *self == Action::Search
+  }
+}
+
+/// One key with its Ctrl and Alt modifiers, e.g. "ctrl+p", "j" or "F2"
+///
+/// Shift is part of the key itself ("K", "shift+tab"), since terminals
+/// report it inconsistently.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+  code: KeyCode,
+  modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+  const MODIFIERS: KeyModifiers = KeyModifiers::CONTROL.union(KeyModifiers::ALT);
+
+  /// Parse a spec such as "ctrl+p", "alt+enter", "shift+tab", "space" or "F2"
+  pub fn parse(spec: &str) -> Result<Self> {
+    let invalid = || anyhow::anyhow!("Invalid key '{}'", spec);
+    let mut parts: Vec<&str> = spec.trim().split('+').collect();
+    // "+" alone, or a modifier with "+" as its key
+    if spec.trim().ends_with("++") || spec.trim() == "+" {
+      parts.pop();
+      *parts.last_mut().ok_or_else(invalid)? = "+";
+    }
+    let key = parts.pop().filter(|k| !k.is_empty()).ok_or_else(invalid)?;
+    let (mut modifiers, mut shift) = (KeyModifiers::NONE, false);
+    for modifier in parts {
+      match modifier.to_lowercase().as_str() {
+        "ctrl" | "control" => modifiers |= KeyModifiers::CONTROL,
+        "alt" | "meta" => modifiers |= KeyModifiers::ALT,
+        "shift" => shift = true,
+        _ => return Err(invalid()),
+      }
+    }
+
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+      (Some(c), None) if shift => KeyCode::Char(c.to_ascii_uppercase()),
+      (Some(c), None) => KeyCode::Char(c),
+      _ => match key.to_lowercase().as_str() {
+        "tab" if shift => KeyCode::BackTab,
+        "tab" => KeyCode::Tab,
+        "backtab" => KeyCode::BackTab,
+        "enter" | "return" => KeyCode::Enter,
+        "space" => KeyCode::Char(' '),
+        "backspace" => KeyCode::Backspace,
+        "delete" | "del" => KeyCode::Delete,
+        "insert" | "ins" => KeyCode::Insert,
+        "up" => KeyCode::Up,
+        "down" => KeyCode::Down,
+        "left" => KeyCode::Left,
+        "right" => KeyCode::Right,
+        "home" => KeyCode::Home,
+        "end" => KeyCode::End,
+        "pageup" => KeyCode::PageUp,
+        "pagedown" => KeyCode::PageDown,
+        "esc" | "escape" => KeyCode::Esc,
+        f => match f.strip_prefix('f').and_then(|n| n.parse().ok()) {
+          Some(n @ 1..=24) => KeyCode::F(n),
+          _ => return Err(invalid()),
+        },
+      },
+    };
+    if matches!(code, KeyCode::Esc) {
+      anyhow::bail!("Esc can't be rebound");
+    }
+    Ok(Self { code, modifiers })
+  }
+
+  /// Whether `key` is this binding
+  pub fn matches(&self, key: &KeyEvent) -> bool {
+    key.code == self.code && key.modifiers.intersection(Self::MODIFIERS) == self.modifiers
+  }
+}
+
+impl fmt::Display for KeyBinding {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if self.modifiers.contains(KeyModifiers::CONTROL) {
+      write!(f, "Ctrl+")?;
+    }
+    if self.modifiers.contains(KeyModifiers::ALT) {
+      write!(f, "Alt+")?;
+    }
+    match self.code {
+      KeyCode::Char(' ') => write!(f, "Space"),
+      KeyCode::Char(c) => write!(f, "{}", c),
+      KeyCode::BackTab => write!(f, "Shift+Tab"),
+      KeyCode::Up => write!(f, "↑"),
+      KeyCode::Down => write!(f, "↓"),
+      KeyCode::Left => write!(f, "←"),
+      KeyCode::Right => write!(f, "→"),
+      KeyCode::F(n) => write!(f, "F{}", n),
+      code => write!(f, "{:?}", code),
+    }
+  }
+}
+
+/// Keys bound to each [`Action`], from `[keys]`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyMap {
+  bindings: Vec<(Action, Vec<KeyBinding>)>,
+}
+
+impl KeyMap {
+  /// Parse every binding, failing on the first bad spec with its action
+  pub fn from_settings(settings: &KeySettings) -> Result<Self> {
+    let bindings = Action::ALL
+      .into_iter()
+      .map(|action| {
+        let keys = settings.get(action).iter().map(|spec| {
+          KeyBinding::parse(spec).map_err(|e| anyhow::anyhow!("keys.{}: {}", action.id(), e))
+        });
+        Ok((action, keys.collect::<Result<Vec<_>>>()?))
+      })
+      .collect::<Result<_>>()?;
+    Ok(Self { bindings })
+  }
+
+  fn keys(&self, action: Action) -> &[KeyBinding] {
+    self.bindings.iter().find(|(a, _)| *a == action).map_or(&[], |(_, keys)| keys)
+  }
+
+  /// Whether `key` is bound to `action`
+  pub fn is(&self, action: Action, key: &KeyEvent) -> bool {
+    self.keys(action).iter().any(|binding| binding.matches(key))
+  }
+
+  /// Whether `key` is bound to any Normal-mode action, so fixed shortcuts
+  /// such as the screen numbers give way to it
+  pub fn is_bound(&self, key: &KeyEvent) -> bool {
+    Action::ALL.into_iter().any(|action| !action.while_editing() && self.is(action, key))
+  }
+
+  /// The keys for `action` as shown in help, e.g. "i/e"; "unbound" for none
+  pub fn label(&self, action: Action) -> String {
+    match self.keys(action) {
+      [] => "unbound".to_string(),
+      keys => keys.iter().map(KeyBinding::to_string).collect::<Vec<_>>().join("/"),
+    }
+  }
+
+  /// A warning for each key bound to two actions read in the same mode;
+  /// the first action in [`Action::ALL`] wins
+  pub fn conflicts(&self) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (i, (action, keys)) in self.bindings.iter().enumerate() {
+      for (other, other_keys) in &self.bindings[i + 1..] {
+        if action.while_editing() != other.while_editing() {
+          continue;
+        }
+        for key in keys.iter().filter(|k| other_keys.contains(k)) {
+          warnings.push(format!(
+            "'{}' is bound to both keys.{} and keys.{}",
+            key,
+            action.id(),
+            other.id()
+          ));
+        }
+      }
+    }
+    warnings
+  }
+}
+
+impl Default for KeyMap {
+  fn default() -> Self {
+    Self::from_settings(&KeySettings::default()).expect("the default bindings parse")
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn key(code: KeyCode, modifiers: KeyModifiers) -> KeyEvent {
+    KeyEvent::new(code, modifiers)
+  }
+
+  #[test]
+  fn test_parse_key_specs() {
+    let parse = |spec| KeyBinding::parse(spec).unwrap();
+    assert!(parse("ctrl+p").matches(&key(KeyCode::Char('p'), KeyModifiers::CONTROL)));
+    assert!(!parse("ctrl+p").matches(&key(KeyCode::Char('p'), KeyModifiers::NONE)));
+    assert!(parse("j").matches(&key(KeyCode::Char('j'), KeyModifiers::NONE)));
+    assert!(parse("F2").matches(&key(KeyCode::F(2), KeyModifiers::NONE)));
+    assert!(parse("Shift+Tab").matches(&key(KeyCode::BackTab, KeyModifiers::SHIFT)));
+    assert!(parse("shift+k").matches(&key(KeyCode::Char('K'), KeyModifiers::SHIFT)));
+    assert!(parse("alt+enter").matches(&key(KeyCode::Enter, KeyModifiers::ALT)));
+    assert!(parse("ctrl++").matches(&key(KeyCode::Char('+'), KeyModifiers::CONTROL)));
+    assert!(parse("space").matches(&key(KeyCode::Char(' '), KeyModifiers::NONE)));
+    assert_eq!(parse("ctrl+alt+F12").to_string(), "Ctrl+Alt+F12");
+
+    for bad in ["", "ctrl+", "hyper+x", "F25", "pgup", "esc"] {
+      assert!(KeyBinding::parse(bad).is_err(), "{}", bad);
+    }
+  }
+
+  #[test]
+  fn test_keymap_labels_and_overrides() {
+    let keys = KeyMap::default();
+    assert_eq!(keys.label(Action::Edit), "i/e");
+    assert!(keys.is(Action::SelectDown, &key(KeyCode::Down, KeyModifiers::NONE)));
+    assert!(keys.conflicts().is_empty());
+
+    let settings = KeySettings {
+      select_down: vec!["down".into(), "j".into()],
+      quit: Vec::new(),
+      ..KeySettings::default()
+    };
+    let keys = KeyMap::from_settings(&settings).unwrap();
+    assert!(keys.is(Action::SelectDown, &key(KeyCode::Char('j'), KeyModifiers::NONE)));
+    assert_eq!(keys.label(Action::SelectDown), "↓/j");
+    assert_eq!(keys.label(Action::Quit), "unbound");
+    assert!(!keys.is_bound(&key(KeyCode::Char('q'), KeyModifiers::NONE)));
+
+    let settings = KeySettings { help: vec!["F1".into(), "x".into()], ..KeySettings::default() };
+    let err = KeyMap::from_settings(&KeySettings { toggle: vec!["ctrl+".into()], ..settings })
+      .unwrap_err()
+      .to_string();
+    assert_eq!(err, "keys.toggle: Invalid key 'ctrl+'");
+  }
+
+  #[test]
+  fn test_conflicts_only_within_a_mode() {
+    let settings = KeySettings {
+      edit: vec!["j".into()],
+      select_down: vec!["down".into(), "j".into()],
+      // Search is read while typing, so Enter doesn't clash with register
+      ..KeySettings::default()
+    };
+    let keys = KeyMap::from_settings(&settings).unwrap();
+    assert_eq!(keys.conflicts(), ["'j' is bound to both keys.edit and keys.select_down"]);
+  }
+}
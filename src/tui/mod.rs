@@ -1,4 +1,8 @@
+pub mod clipboard;
 pub mod handlers;
+pub mod keys;
 pub mod runner;
+pub mod session;
+pub mod toast;
 
 pub use runner::TuiRunner;
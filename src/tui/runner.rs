@@ -1,16 +1,26 @@
 //! TUI runner with clean event loop architecture
 
-use crate::app::{App, InputMode, Screen};
-use crate::tui::handlers;
+use crate::app::{tab_at, App, InputMode, Modal, Screen};
+use crate::config::ConfigSource;
+use crate::tui::toast::Severity;
+use crate::tui::{handlers, keys::Action};
 use crate::ui;
 use anyhow::Result;
 use crossterm::{
-  event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+  event::{
+    self, DisableBracketedPaste, DisableMouseCapture, EnableBracketedPaste, EnableMouseCapture,
+    Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent,
+    MouseEventKind,
+  },
   execute,
   terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use ratatui::{backend::CrosstermBackend, Terminal};
-use std::{io, sync::Arc, time::Duration};
+use std::{
+  io,
+  sync::Arc,
+  time::{Duration, Instant},
+};
 use tokio::sync::Mutex;
 
 const POLL_TIMEOUT_MS: u64 = 100;
@@ -18,26 +28,33 @@ const POLL_TIMEOUT_MS: u64 = 100;
 pub struct TuiRunner;
 
 impl TuiRunner {
-  pub async fn run() -> Result<()> {
+  pub async fn run(source: ConfigSource) -> Result<()> {
+    // Before taking over the terminal, so the error is readable
+    let config = source.load()?;
     let mut terminal = Self::setup_terminal()?;
-    let app = Arc::new(Mutex::new(App::new()));
+    let app = Arc::new(Mutex::new(App::new(source, config)));
     
-    let res = Self::run_event_loop(&mut terminal, app).await;
+    let res = Self::run_event_loop(&mut terminal, Arc::clone(&app)).await;
     
     Self::restore_terminal()?;
+    let mut app = app.lock().await;
+    app.save_session();
+    if let Some(notice) = app.quit_notice.take() {
+      eprintln!("{}", notice);
+    }
     res
   }
 
   fn setup_terminal() -> Result<Terminal<CrosstermBackend<io::Stdout>>> {
     enable_raw_mode()?;
     let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
+    execute!(stdout, EnterAlternateScreen, EnableMouseCapture, EnableBracketedPaste)?;
     Ok(Terminal::new(CrosstermBackend::new(stdout))?)
   }
 
   fn restore_terminal() -> Result<()> {
     disable_raw_mode()?;
-    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture)?;
+    execute!(io::stdout(), LeaveAlternateScreen, DisableMouseCapture, DisableBracketedPaste)?;
     Ok(())
   }
 
@@ -48,81 +65,200 @@ impl TuiRunner {
     loop {
       // Render UI
       {
-        let app_guard = app.lock().await;
+        let mut app_guard = app.lock().await;
         if app_guard.should_quit {
           break;
         }
-        terminal.draw(|f| {
-          ui::render(f, &app_guard);
-          if app_guard.show_help {
-            ui::render_help(f);
-          }
-        })?;
+        terminal.draw(|f| ui::render(f, &mut app_guard))?;
       }
 
       // Handle events
       if event::poll(Duration::from_millis(POLL_TIMEOUT_MS))? {
-        if let Event::Key(key) = event::read()? {
-          if key.kind != KeyEventKind::Press {
-            continue;
+        match event::read()? {
+          Event::Key(key) if key.kind == KeyEventKind::Press => {
+            Self::handle_key_event(&app, key).await?;
           }
-          Self::handle_key_event(&app, key.code).await?;
+          Event::Paste(text) => Self::handle_paste(&app, &text).await,
+          Event::Mouse(mouse) => Self::handle_mouse_event(&app, mouse).await?,
+          _ => {}
         }
+      } else {
+        app.lock().await.on_tick();
       }
     }
     Ok(())
   }
 
-  async fn handle_key_event(app: &Arc<Mutex<App>>, key_code: KeyCode) -> Result<()> {
+  /// Insert a bracketed paste into the search input in one go
+  async fn handle_paste(app: &Arc<Mutex<App>>, text: &str) {
+    let mut app_guard = app.lock().await;
+    if app_guard.is_registering {
+      return;
+    }
+    match app_guard.screen {
+      Screen::Search => app_guard.search_input.insert_str(text),
+      Screen::Domain => app_guard.domain_field_input().insert_str(text),
+      Screen::Register | Screen::Settings => return,
+    }
+    app_guard.input_mode = InputMode::Editing;
+  }
+
+  /// Clicks pick tabs and list rows, a double-click acts like Enter and
+  /// the wheel moves the highlight like ↑/↓
+  ///
+  /// Popups and the confirmation dialog only take keys.
+  async fn handle_mouse_event(app: &Arc<Mutex<App>>, mouse: MouseEvent) -> Result<()> {
     let mut app_guard = app.lock().await;
+    let popup = app_guard.show_help || app_guard.show_result_detail || app_guard.show_messages;
+    if popup || app_guard.modal.is_some() {
+      return Ok(());
+    }
+    let (column, row) = (mouse.column, mouse.row);
+    let key = match mouse.kind {
+      MouseEventKind::ScrollDown if app_guard.input_mode == InputMode::Normal => KeyCode::Down,
+      MouseEventKind::ScrollUp if app_guard.input_mode == InputMode::Normal => KeyCode::Up,
+      MouseEventKind::Down(MouseButton::Left) => {
+        if let Some(screen) = tab_at(app_guard.hit_areas.tabs, column, row) {
+          app_guard.screen = screen;
+          app_guard.input_mode = InputMode::Normal;
+          if screen == Screen::Register {
+            handlers::check_token(&mut app_guard, Arc::clone(app), false);
+          }
+          return Ok(());
+        }
+        let Some(index) = app_guard.list_row_at(column, row) else {
+          return Ok(());
+        };
+        if app_guard.is_registering || !app_guard.select_row(index) {
+          return Ok(());
+        }
+        // Clicking a row leaves the input, as Esc would
+        app_guard.input_mode = InputMode::Normal;
+        if !app_guard.click(column, row, Instant::now()) {
+          return Ok(());
+        }
+        KeyCode::Enter
+      }
+      _ => return Ok(()),
+    };
+    drop(app_guard);
+    Self::handle_key_event(app, KeyEvent::new(key, KeyModifiers::NONE)).await
+  }
+
+  async fn handle_key_event(app: &Arc<Mutex<App>>, key: KeyEvent) -> Result<()> {
+    let key_code = key.code;
+    let mut app_guard = app.lock().await;
+
+    // Dialogs capture every key until answered
+    match app_guard.modal {
+      Some(Modal::Register(_)) => {
+        handlers::handle_confirm_input(&mut app_guard, key_code, Arc::clone(app));
+        return Ok(());
+      }
+      Some(Modal::Quit) => {
+        handlers::handle_quit_confirm(&mut app_guard, key_code);
+        return Ok(());
+      }
+      Some(Modal::CancelRegistration) => {
+        handlers::handle_cancel_confirm(&mut app_guard, key_code);
+        return Ok(());
+      }
+      None => {}
+    }
+
     let is_editing = app_guard.input_mode == InputMode::Editing;
-    let is_busy = app_guard.is_searching || app_guard.is_registering;
+    let is_busy = app_guard.is_registering;
+    let keys = app_guard.keys.clone();
 
-    // Allow quit even when busy
+    // The message history keeps the keys that scroll and close it
+    if app_guard.show_messages {
+      match key_code {
+        _ if keys.is(Action::SelectUp, &key) => app_guard.scroll_messages(false),
+        _ if keys.is(Action::SelectDown, &key) => app_guard.scroll_messages(true),
+        KeyCode::Esc | KeyCode::Char('m') => app_guard.show_messages = false,
+        _ => {}
+      }
+      return Ok(());
+    }
+
+    // Esc asks before cancelling an in-flight registration, and cancels a
+    // search outright
     if key_code == KeyCode::Esc && is_busy {
-      return Ok(()); // Ignore ESC during operations
+      app_guard.request_cancel_registration();
+      return Ok(());
+    }
+    if key_code == KeyCode::Esc && app_guard.cancel_search() {
+      return Ok(());
     }
 
     // Global shortcuts (available in non-editing mode)
     match key_code {
-      KeyCode::Char('q') if !is_editing => {
-        app_guard.should_quit = true;
+      _ if !is_editing && keys.is(Action::Quit, &key) => {
+        app_guard.request_quit();
         return Ok(());
       }
       KeyCode::Esc => {
         if app_guard.show_help {
           app_guard.show_help = false;
+        } else if app_guard.show_result_detail {
+          app_guard.show_result_detail = false;
         } else if is_editing {
           app_guard.input_mode = InputMode::Normal;
         } else {
-          app_guard.should_quit = true;
+          app_guard.request_quit();
         }
         return Ok(());
       }
-      KeyCode::Char('?') if !is_editing => {
+      _ if !is_editing && keys.is(Action::Help, &key) => {
         app_guard.show_help = !app_guard.show_help;
         return Ok(());
       }
-      KeyCode::Tab if !is_editing => {
-        app_guard.toggle_screen();
+      _ if !is_editing
+        && (keys.is(Action::NextScreen, &key) || keys.is(Action::PreviousScreen, &key)) =>
+      {
+        if keys.is(Action::NextScreen, &key) {
+          app_guard.next_screen();
+        } else {
+          app_guard.previous_screen();
+        }
+        if app_guard.screen == Screen::Register {
+          handlers::check_token(&mut app_guard, Arc::clone(app), false);
+        }
         return Ok(());
       }
-      KeyCode::Char('1') if !is_editing => {
-        app_guard.screen = Screen::Search;
+      // Fixed shortcuts give way to keys bound to an action
+      KeyCode::Char('P') if !is_editing && !is_busy && !keys.is_bound(&key) => {
+        match app_guard.cycle_profile() {
+          Ok(()) => {
+            let profile = app_guard.source.profile().unwrap_or("default").to_string();
+            app_guard.toasts.push(format!("Profile: {}", profile), Severity::Info);
+          }
+          Err(e) => {
+            tracing::warn!(error = %e, "failed to switch profile");
+            app_guard.toasts.push(format!("Couldn't switch profile: {:#}", e), Severity::Error);
+          }
+        }
         return Ok(());
       }
-      KeyCode::Char('2') if !is_editing => {
-        app_guard.screen = Screen::Register;
+      KeyCode::Char('m') if !is_editing && !keys.is_bound(&key) => {
+        app_guard.open_messages();
         return Ok(());
       }
-      KeyCode::Char('3') if !is_editing => {
-        app_guard.screen = Screen::Settings;
-        return Ok(());
+      KeyCode::Char(c @ '1'..='9') if !is_editing && !keys.is_bound(&key) => {
+        let index = c as usize - '1' as usize;
+        if let Some(screen) = Screen::ALL.get(index) {
+          app_guard.screen = *screen;
+          if app_guard.screen == Screen::Register {
+            handlers::check_token(&mut app_guard, Arc::clone(app), false);
+          }
+          return Ok(());
+        }
       }
       _ => {}
     }
 
-    // Block screen-specific actions when busy
+    // Block screen-specific actions while registering; a new search
+    // simply supersedes the running one
     if is_busy {
       return Ok(());
     }
@@ -134,18 +270,59 @@ impl TuiRunner {
     match current_screen {
       Screen::Search => {
         let mut guard = app.lock().await;
-        handlers::handle_search_input(&mut guard, key_code, Arc::clone(app)).await;
+        handlers::handle_search_input(&mut guard, key, Arc::clone(app)).await;
       }
       Screen::Register => {
         let mut guard = app.lock().await;
-        handlers::handle_register_input(&mut guard, key_code).await;
+        handlers::handle_register_input(&mut guard, key, Arc::clone(app));
       }
       Screen::Settings => {
         let mut guard = app.lock().await;
-        handlers::handle_settings_input(&mut guard, key_code);
+        handlers::handle_settings_input(&mut guard, key);
+      }
+      Screen::Domain => {
+        let mut guard = app.lock().await;
+        handlers::handle_domain_input(&mut guard, key, Arc::clone(app));
       }
     }
 
     Ok(())
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+
+  async fn press(app: &Arc<Mutex<App>>, key: KeyCode) {
+    TuiRunner::handle_key_event(app, KeyEvent::new(key, KeyModifiers::NONE)).await.unwrap();
+  }
+
+  #[tokio::test]
+  async fn test_esc_esc_keeps_a_registration_running() {
+    let app = Arc::new(Mutex::new(App::with_config(Config::default())));
+    let task = tokio::spawn(std::future::pending::<()>());
+    {
+      let mut app = app.lock().await;
+      app.input_mode = InputMode::Normal;
+      app.registration_task = Some(task.abort_handle());
+      app.is_registering = true;
+    }
+
+    press(&app, KeyCode::Esc).await;
+    assert!(matches!(app.lock().await.modal, Some(Modal::CancelRegistration)));
+    press(&app, KeyCode::Esc).await;
+    {
+      let app = app.lock().await;
+      assert!(app.modal.is_none() && app.is_registering && !app.should_quit);
+    }
+    assert!(!task.is_finished());
+
+    press(&app, KeyCode::Esc).await;
+    press(&app, KeyCode::Char('y')).await;
+    let app = app.lock().await;
+    assert!(!app.is_registering && !app.should_quit);
+    assert!(task.await.unwrap_err().is_cancelled());
+  }
+}
@@ -0,0 +1,121 @@
+//! The last search, saved when the TUI quits and restored on the next
+//! launch from `session.json` in the data directory
+//! (`~/.local/share/nbi/session.json`)
+//!
+//! A missing or unreadable session just means a blank start.
+
+use crate::app::Screen;
+use crate::registry::{AvailabilityResult, ResultFilter};
+use anyhow::Result;
+use directories::ProjectDirs;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const SESSION_FILE_NAME: &str = "session.json";
+
+/// What the Search screen showed when the TUI quit
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+  pub term: String,
+  pub screen: Screen,
+  #[serde(default)]
+  pub filter: ResultFilter,
+  pub results: Vec<SavedResult>,
+}
+
+/// A result with when it was checked, in seconds since the Unix epoch
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedResult {
+  #[serde(flatten)]
+  pub result: AvailabilityResult,
+  pub checked_at: u64,
+}
+
+/// Default session file path
+pub fn default_path() -> Option<PathBuf> {
+  ProjectDirs::from("", "", "nbi").map(|dirs| dirs.data_dir().join(SESSION_FILE_NAME))
+}
+
+impl Session {
+  /// Read a saved session; a missing file is a clean start and a corrupted
+  /// one is logged and ignored
+  pub fn load(path: &Path) -> Option<Self> {
+    let content = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content)
+      .inspect_err(|e| tracing::warn!(path = %path.display(), error = %e, "ignoring bad session"))
+      .ok()
+  }
+
+  pub fn save(&self, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+      std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+    Ok(())
+  }
+}
+
+/// Seconds since the Unix epoch for `time`
+pub fn unix_secs(time: SystemTime) -> u64 {
+  time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// `secs` as a time
+pub fn from_unix_secs(secs: u64) -> SystemTime {
+  UNIX_EPOCH + Duration::from_secs(secs)
+}
+
+/// A coarse age such as "45s", "12m", "2h" or "3d"
+pub fn age(elapsed: Duration) -> String {
+  match elapsed.as_secs() {
+    s @ 0..60 => format!("{}s", s),
+    s @ 60..3600 => format!("{}m", s / 60),
+    s @ 3600..86400 => format!("{}h", s / 3600),
+    s => format!("{}d", s / 86400),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::registry::{Availability, RegistryType};
+
+  fn session() -> Session {
+    let result = AvailabilityResult {
+      registry: RegistryType::Npm,
+      name: "foo".to_string(),
+      availability: Availability::Taken,
+      error: None,
+      detail: None,
+      qualifier: None,
+    };
+    Session {
+      term: "foo".to_string(),
+      screen: Screen::Register,
+      filter: ResultFilter::Taken,
+      results: vec![SavedResult { result, checked_at: 1_700_000_000 }],
+    }
+  }
+
+  #[test]
+  fn test_session_round_trips_and_ignores_corruption() {
+    let dir = std::env::temp_dir().join(format!("nbi-session-{}", std::process::id()));
+    let path = dir.join("nested").join(SESSION_FILE_NAME);
+    assert_eq!(Session::load(&path), None);
+
+    session().save(&path).unwrap();
+    assert_eq!(Session::load(&path), Some(session()));
+
+    std::fs::write(&path, "{\"term\": \"foo\", \"resu").unwrap();
+    assert_eq!(Session::load(&path), None);
+    std::fs::remove_dir_all(&dir).unwrap();
+  }
+
+  #[test]
+  fn test_age_rounds_down_to_one_unit() {
+    assert_eq!(age(Duration::from_secs(59)), "59s");
+    assert_eq!(age(Duration::from_secs(2 * 3600 + 1800)), "2h");
+    assert_eq!(age(Duration::from_secs(3 * 86400)), "3d");
+  }
+}
@@ -0,0 +1,110 @@
+//! Short-lived notices stacked in the bottom-right corner, with a history
+//! behind `m`
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// How long a toast stays up
+pub const TOAST_DURATION: Duration = Duration::from_secs(5);
+/// Toasts dim for this long before they go
+const FADE: Duration = Duration::from_secs(1);
+/// Toasts on screen at once; older ones are only in the history
+pub const MAX_VISIBLE: usize = 3;
+/// Messages kept for the history popup
+const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Info,
+  Success,
+  Error,
+}
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+  pub message: String,
+  pub severity: Severity,
+  pub created_at: Instant,
+}
+
+impl Toast {
+  /// Whether the toast is in its last second, drawn dimmed
+  pub fn is_fading(&self, now: Instant) -> bool {
+    now.saturating_duration_since(self.created_at) + FADE >= TOAST_DURATION
+  }
+}
+
+/// Every message this session, oldest first
+#[derive(Debug, Default)]
+pub struct Toasts {
+  history: VecDeque<Toast>,
+}
+
+impl Toasts {
+  pub fn push(&mut self, message: impl Into<String>, severity: Severity) {
+    self.push_at(message.into(), severity, Instant::now());
+  }
+
+  fn push_at(&mut self, message: String, severity: Severity, now: Instant) {
+    if self.history.len() == HISTORY_LIMIT {
+      self.history.pop_front();
+    }
+    self.history.push_back(Toast { message, severity, created_at: now });
+  }
+
+  /// Toasts still up at `now`, oldest first, at most [`MAX_VISIBLE`]
+  pub fn visible(&self, now: Instant) -> Vec<&Toast> {
+    let mut visible: Vec<_> = self
+      .history
+      .iter()
+      .rev()
+      .take_while(|toast| now.saturating_duration_since(toast.created_at) < TOAST_DURATION)
+      .take(MAX_VISIBLE)
+      .collect();
+    visible.reverse();
+    visible
+  }
+
+  pub fn history(&self) -> &VecDeque<Toast> {
+    &self.history
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_toasts_expire_and_stack() {
+    let start = Instant::now();
+    let mut toasts = Toasts::default();
+    toasts.push_at("first".into(), Severity::Info, start);
+    toasts.push_at("second".into(), Severity::Error, start + Duration::from_secs(2));
+
+    let messages = |at: u64| -> Vec<String> {
+      let now = start + Duration::from_secs(at);
+      toasts.visible(now).iter().map(|t| t.message.clone()).collect()
+    };
+    assert_eq!(messages(1), ["first", "second"].map(String::from));
+    assert_eq!(messages(5), ["second"]);
+    assert!(messages(7).is_empty());
+    assert_eq!(toasts.history().len(), 2);
+
+    let second = &toasts.history()[1];
+    assert!(!second.is_fading(start + Duration::from_secs(5)));
+    assert!(second.is_fading(start + Duration::from_secs(6)));
+  }
+
+  #[test]
+  fn test_only_the_newest_toasts_show() {
+    let now = Instant::now();
+    let mut toasts = Toasts::default();
+    for i in 0..HISTORY_LIMIT + 5 {
+      toasts.push_at(i.to_string(), Severity::Success, now);
+    }
+    let visible: Vec<_> = toasts.visible(now).iter().map(|t| t.message.clone()).collect();
+    assert_eq!(visible, ["102", "103", "104"].map(String::from));
+    assert_eq!(toasts.history().len(), HISTORY_LIMIT);
+    assert_eq!(toasts.history()[0].message, "5");
+  }
+}
@@ -0,0 +1,106 @@
+use crate::app::{App, DomainField, InputMode};
+use crate::input::TextInput;
+use crate::registry::Availability;
+use ratatui::{
+  layout::{Constraint, Direction, Layout, Rect},
+  style::{Modifier, Style},
+  text::{Line, Span},
+  widgets::{Block, Borders, List, ListItem, Paragraph},
+  Frame,
+};
+
+pub fn render(frame: &mut Frame, app: &mut App, area: Rect) {
+  let chunks = Layout::default()
+    .direction(Direction::Vertical)
+    .constraints([
+      Constraint::Length(3), // Name
+      Constraint::Length(3), // TLDs
+      Constraint::Min(0),    // Results
+    ])
+    .split(area);
+
+  render_input(frame, app, chunks[0], DomainField::Name);
+  render_input(frame, app, chunks[1], DomainField::Tlds);
+  render_results(frame, app, chunks[2]);
+}
+
+fn render_input(frame: &mut Frame, app: &App, area: Rect, field: DomainField) {
+  let theme = app.theme;
+  let (input, title): (&TextInput, _) = match field {
+    DomainField::Name => (&app.domain_input, " Domain Name "),
+    DomainField::Tlds => (&app.domain_tlds, " TLDs (comma-separated, @group for presets) "),
+  };
+  let focused = app.input_mode == InputMode::Editing && app.domain_field == field;
+  let style = if focused {
+    Style::default().fg(theme.warning)
+  } else {
+    Style::default().fg(theme.muted)
+  };
+
+  let paragraph = Paragraph::new(input.as_str())
+    .style(if focused { style } else { Style::default() })
+    .block(Block::default().borders(Borders::ALL).title(title).border_style(style));
+
+  frame.render_widget(paragraph, area);
+
+  if focused {
+    frame.set_cursor_position(super::input_cursor(area, input.cursor_column()));
+  }
+}
+
+fn render_results(frame: &mut Frame, app: &mut App, area: Rect) {
+  let theme = app.theme;
+  if app.domain_results.is_empty() {
+    let (message, color) = if let Some(err) = &app.domain_error {
+      (err.as_str(), theme.error)
+    } else if app.is_checking_domains {
+      ("Checking...", theme.muted)
+    } else {
+      ("Enter a name and press Enter to check domains", theme.muted)
+    };
+
+    let placeholder = Paragraph::new(message)
+      .style(Style::default().fg(color))
+      .block(Block::default().borders(Borders::ALL).title(" Domains "));
+
+    frame.render_widget(placeholder, area);
+    return;
+  }
+
+  let items: Vec<ListItem> = app
+    .domain_results
+    .iter()
+    .map(|result| {
+      let color = theme.status(result.availability);
+      let status = match result.availability {
+        Availability::Available => "Available",
+        Availability::Taken => "Taken",
+        Availability::Reserved => "Reserved",
+        Availability::Unknown => "Unknown",
+      };
+
+      let mut spans = vec![
+        Span::styled(
+          format!(" {} ", App::get_status_symbol(result)),
+          Style::default().fg(color).add_modifier(Modifier::BOLD),
+        ),
+        Span::styled(format!("{:<24}", result.name), Style::default().add_modifier(Modifier::BOLD)),
+        Span::styled(format!(" {:<10}", status), Style::default().fg(color)),
+      ];
+      if let Some(err) = &result.error {
+        spans.push(Span::styled(format!("({})", err), Style::default().fg(theme.error)));
+      }
+
+      ListItem::new(Line::from(spans))
+    })
+    .collect();
+
+  let available = app.domain_results.iter().filter(|r| r.available() == Some(true)).count();
+  let title = format!(" Domains ({}/{} available) ", available, app.domain_results.len());
+  let list = List::new(items)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .highlight_style(Style::default().bg(theme.muted));
+
+  app.hit_areas.list = area;
+  frame.render_stateful_widget(list, area, &mut app.domain_list_state);
+}
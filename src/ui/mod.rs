@@ -1,64 +1,160 @@
+pub mod domain;
 pub mod register;
 pub mod search;
 pub mod settings;
+pub mod theme;
 
-use crate::app::{App, InputMode, Screen};
+use crate::app::{App, HitAreas, InputMode, Modal, Screen};
+use crate::tui::keys::Action;
+use crate::tui::session;
+use crate::tui::toast::Severity;
 use ratatui::{
-  layout::{Constraint, Direction, Layout, Rect},
-  style::{Color, Modifier, Style},
+  layout::{Alignment, Constraint, Direction, Layout, Rect},
+  style::{Modifier, Style},
   text::{Line, Span},
-  widgets::{Block, Borders, Paragraph, Tabs},
+  widgets::{Block, Borders, Paragraph, Tabs, Wrap},
   Frame,
 };
+use std::time::Instant;
+use unicode_width::UnicodeWidthStr;
+
+/// Smallest terminal the UI is drawn in; below it only a notice is shown
+const MIN_WIDTH: u16 = 40;
+const MIN_HEIGHT: u16 = 10;
+
+/// Below this height the tab bar loses its top and bottom borders and the
+/// status bar is dropped, leaving the rows to the screen
+const COMPACT_HEIGHT: u16 = 20;
 
 /// Render the main UI
-pub fn render(frame: &mut Frame, app: &App) {
+pub fn render(frame: &mut Frame, app: &mut App) {
+  let area = frame.area();
+  if area.width < MIN_WIDTH || area.height < MIN_HEIGHT {
+    app.hit_areas = HitAreas::default();
+    render_too_small(frame, app, area);
+    return;
+  }
+
+  let compact = area.height < COMPACT_HEIGHT;
   let chunks = Layout::default()
     .direction(Direction::Vertical)
     .constraints([
-      Constraint::Length(3), // Tabs
-      Constraint::Min(0),    // Content
-      Constraint::Length(1), // Status bar
+      Constraint::Length(if compact { 1 } else { 3 }), // Tabs
+      Constraint::Min(0),                               // Content
+      Constraint::Length(if compact { 0 } else { 1 }), // Status bar
     ])
-    .split(frame.area());
+    .split(area);
+  // Screens record their list as they draw it
+  app.hit_areas = HitAreas { tabs: chunks[0], ..HitAreas::default() };
 
-  render_tabs(frame, app, chunks[0]);
+  render_tabs(frame, app, chunks[0], compact);
 
   match app.screen {
     Screen::Search => search::render(frame, app, chunks[1]),
     Screen::Register => register::render(frame, app, chunks[1]),
     Screen::Settings => settings::render(frame, app, chunks[1]),
+    Screen::Domain => domain::render(frame, app, chunks[1]),
+  }
+
+  if !compact {
+    render_status_bar(frame, app, chunks[2]);
+  }
+
+  render_toasts(frame, app, chunks[1]);
+
+  if app.screen == Screen::Search && app.show_result_detail {
+    search::render_result_detail(frame, app);
+  }
+  if app.show_messages {
+    render_messages(frame, app);
   }
+  match app.modal {
+    Some(Modal::Register(_)) => register::render_confirm(frame, app),
+    Some(Modal::Quit) => {
+      render_busy_confirm(frame, app, " Quit? ", "Quit anyway and cancel it?", " quit")
+    }
+    Some(Modal::CancelRegistration) => {
+      let question = "Cancel it? A repo may be left behind.";
+      render_busy_confirm(frame, app, " Cancel? ", question, " cancel")
+    }
+    None => {}
+  }
+  if app.show_help {
+    render_help(frame, app);
+  }
+}
 
-  render_status_bar(frame, app, chunks[2]);
+fn render_too_small(frame: &mut Frame, app: &App, area: Rect) {
+  let message = format!("Terminal too small (need ≥ {}×{})", MIN_WIDTH, MIN_HEIGHT);
+  let row = Rect { y: area.y + area.height / 2, height: area.height.min(1), ..area };
+  let notice = Paragraph::new(message)
+    .alignment(Alignment::Center)
+    .style(Style::default().fg(app.theme.warning));
+  frame.render_widget(notice, row);
 }
 
-fn render_tabs(frame: &mut Frame, app: &App, area: Rect) {
-  let titles = vec!["Search [1]", "Register [2]", "Settings [3]"];
-  let selected = match app.screen {
-    Screen::Search => 0,
-    Screen::Register => 1,
-    Screen::Settings => 2,
-  };
+/// Where the cursor goes for `column` in a bordered one-line input, kept
+/// inside the border when the text is wider than the box
+fn input_cursor(area: Rect, column: usize) -> (u16, u16) {
+  let last = area.width.saturating_sub(2).max(1) as usize;
+  (area.x + column.min(last - 1) as u16 + 1, area.y + 1)
+}
+
+/// A titled box, or just its top edge (which carries the title) on a short
+/// screen, saving two rows
+fn panel(compact: bool) -> Block<'static> {
+  Block::default().borders(if compact { Borders::TOP } else { Borders::ALL })
+}
+
+fn render_tabs(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+  let theme = app.theme;
+  let titles = Screen::ALL.iter().map(Screen::tab_title);
 
+  let title = match app.source.profile() {
+    Some(profile) => format!(" nbi [{}] ", profile),
+    None => " nbi ".to_string(),
+  };
+  // Compact tabs keep their side borders, so clicks land the same way
+  let block = if compact {
+    Block::default().borders(Borders::LEFT | Borders::RIGHT)
+  } else {
+    Block::default().borders(Borders::ALL).title(title)
+  };
   let tabs = Tabs::new(titles)
-    .block(Block::default().borders(Borders::ALL).title(" nbi "))
-    .select(selected)
-    .style(Style::default().fg(Color::White))
+    .block(block)
+    .select(app.screen.index())
     .highlight_style(
       Style::default()
-        .fg(Color::Cyan)
+        .fg(theme.accent)
         .add_modifier(Modifier::BOLD),
     );
 
   frame.render_widget(tabs, area);
 }
 
+const SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// Spinner frame for the current tick
+fn spinner(app: &App) -> &'static str {
+  SPINNER[(app.tick % SPINNER.len() as u64) as usize]
+}
+
 fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
+  let theme = app.theme;
   let (msg, style) = if app.is_searching {
-    ("Searching...".to_string(), Style::default().fg(Color::Yellow))
+    let done = app.search_results.len();
+    let total = done + app.pending_registries.len();
+    (
+      format!("{} Searching... ({}/{}) | Esc to cancel", spinner(app), done, total),
+      Style::default().fg(theme.warning),
+    )
+  } else if app.is_checking_domains && app.screen == Screen::Domain {
+    (format!("{} Checking domains...", spinner(app)), Style::default().fg(theme.warning))
   } else if app.is_registering {
-    ("Registering...".to_string(), Style::default().fg(Color::Yellow))
+    (
+      format!("{} Registering... (Esc to cancel)", spinner(app)),
+      Style::default().fg(theme.warning),
+    )
   } else {
     // Check for errors in search results
     let error_count = app
@@ -70,16 +166,46 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
     if error_count > 0 && app.screen == Screen::Search {
       (
         format!("{} error(s) occurred. Check results for details.", error_count),
-        Style::default().fg(Color::Red),
+        Style::default().fg(theme.error),
       )
     } else {
+      let key = |action| app.keys.label(action);
+      let (edit, select) = (key(Action::Edit), select_label(app));
       let mode_hint = match (app.screen, app.input_mode) {
-        (Screen::Search, InputMode::Normal) => "NORMAL | i,e to edit | Enter to focus",
-        (Screen::Search, InputMode::Editing) => "EDITING | Esc to unfocus | Enter to search",
-        (Screen::Register, _) => "↑/↓ select | Enter to register | ? help",
-        (Screen::Settings, _) => "↑/↓ select | Enter/Space toggle | ? help",
+        (Screen::Search, InputMode::Normal) => format!(
+          "NORMAL | {} edit | {} select | Enter details | o open | f filter | r/R recheck",
+          edit, select
+        ),
+        (Screen::Search, InputMode::Editing) => {
+          format!("EDITING | Esc to unfocus | {} to search", key(Action::Search))
+        }
+        (Screen::Register, InputMode::Normal) => format!(
+          "{} select | {} tick | a all | {} register | o owner | p private | d desc",
+          select,
+          key(Action::Toggle),
+          key(Action::Register)
+        ),
+        (Screen::Register, InputMode::Editing) => {
+          "EDITING repo option | Enter to save | Esc to cancel".to_string()
+        }
+        (Screen::Settings, InputMode::Normal) => format!(
+          "{} select | Enter/{} toggle | P profile | {} help",
+          select,
+          key(Action::Toggle),
+          key(Action::Help)
+        ),
+        (Screen::Settings, InputMode::Editing) => {
+          "EDITING setting | Enter to save | Esc to cancel".to_string()
+        }
+        (Screen::Domain, InputMode::Normal) => {
+          format!("NORMAL | {} to edit | {} select | o open | {} help", edit, select, key(Action::Help))
+        }
+        (Screen::Domain, InputMode::Editing) => format!(
+          "EDITING | Tab switch field | {} to check | Esc to unfocus",
+          key(Action::Search)
+        ),
       };
-      (mode_hint.to_string(), Style::default().fg(Color::DarkGray))
+      (mode_hint, Style::default().fg(theme.muted))
     }
   };
 
@@ -87,9 +213,83 @@ fn render_status_bar(frame: &mut Frame, app: &App, area: Rect) {
   frame.render_widget(status, area);
 }
 
-/// Render help popup
-pub fn render_help(frame: &mut Frame) {
-  let area = centered_rect(60, 70, frame.area());
+/// The up and down keys as one hint, e.g. "↑/↓" or "↑/k / ↓/j"
+fn select_label(app: &App) -> String {
+  let (up, down) = (app.keys.label(Action::SelectUp), app.keys.label(Action::SelectDown));
+  if up.contains('/') || down.contains('/') {
+    format!("{} / {}", up, down)
+  } else {
+    format!("{}/{}", up, down)
+  }
+}
+
+/// Stack the live toasts in the bottom-right corner of `area`, newest at
+/// the bottom; they dim in their last second
+fn render_toasts(frame: &mut Frame, app: &App, area: Rect) {
+  let theme = app.theme;
+  let now = Instant::now();
+  let toasts = app.toasts.visible(now);
+  let max_width = area.width * 2 / 3;
+  let bottom = area.bottom();
+  for (i, toast) in toasts.iter().rev().enumerate() {
+    let Some(y) = bottom.checked_sub(i as u16 + 1).filter(|y| *y >= area.y) else {
+      break;
+    };
+    let symbol = match toast.severity {
+      Severity::Info => "•",
+      Severity::Success => "✓",
+      Severity::Error => "✗",
+    };
+    let text = format!(" {} {} ", symbol, toast.message);
+    let width = (text.width() as u16).min(max_width);
+    let color = if toast.is_fading(now) { theme.muted } else { theme.severity(toast.severity) };
+    let row = Rect { x: area.right() - width, y, width, height: 1 };
+    frame.render_widget(ratatui::widgets::Clear, row);
+    frame.render_widget(Paragraph::new(text).style(Style::default().fg(color)), row);
+  }
+}
+
+/// Every message this session, newest first, scrolled by ↑/↓
+fn render_messages(frame: &mut Frame, app: &mut App) {
+  let area = centered_rect(70, 60, frame.area());
+  let theme = app.theme;
+  let now = Instant::now();
+  let lines: Vec<Line> = app
+    .toasts
+    .history()
+    .iter()
+    .rev()
+    .map(|toast| {
+      let color = theme.severity(toast.severity);
+      let age = session::age(now.saturating_duration_since(toast.created_at));
+      Line::from(vec![
+        Span::styled(format!("{:>4} ago  ", age), Style::default().fg(theme.muted)),
+        Span::styled(toast.message.clone(), Style::default().fg(color)),
+      ])
+    })
+    .collect();
+  let lines = if lines.is_empty() {
+    vec![Line::from(Span::styled("No messages yet", Style::default().fg(theme.muted)))]
+  } else {
+    lines
+  };
+  // Wrapped messages take more than one row, so this is a rough bound
+  app.messages_scroll = app.messages_scroll.min(lines.len().saturating_sub(1) as u16);
+  let messages = Paragraph::new(lines)
+    .wrap(Wrap { trim: false })
+    .scroll((app.messages_scroll, 0))
+    .block(Block::default().borders(Borders::ALL).title(" Messages (m to close) "));
+  frame.render_widget(ratatui::widgets::Clear, area);
+  frame.render_widget(messages, area);
+}
+
+/// Render help popup, listing the keys bound under `[keys]`
+fn render_help(frame: &mut Frame, app: &App) {
+  let area = centered_rect(60, 90, frame.area());
+  let theme = app.theme;
+  let key = |action| app.keys.label(action);
+  let shortcut = |keys: String, what: &str| Line::from(format!("  {:<10} - {}", keys, what));
+  let select = select_label(app);
 
   let help_text = vec![
     Line::from(Span::styled(
@@ -97,44 +297,112 @@ pub fn render_help(frame: &mut Frame) {
       Style::default().add_modifier(Modifier::BOLD),
     )),
     Line::from(""),
-    Line::from("  q          - Quit (in Normal mode)"),
+    shortcut(key(Action::Quit), "Quit (in Normal mode)"),
     Line::from("  Esc        - Unfocus input / Close popup / Quit"),
+    Line::from("  m          - Show recent messages"),
     Line::from("  1          - Go to Search screen"),
     Line::from("  2          - Go to Register screen"),
-    Line::from("  Tab        - Switch between screens"),
-    Line::from("  ?          - Toggle this help"),
+    Line::from("  3          - Go to Settings screen"),
+    Line::from("  4          - Go to Domain screen"),
+    shortcut(key(Action::NextScreen), "Next screen"),
+    shortcut(key(Action::PreviousScreen), "Previous screen"),
+    Line::from("  P          - Switch config profile"),
+    Line::from("  Mouse      - Click a tab or row, double-click for Enter, wheel for ↑/↓"),
+    shortcut(key(Action::Help), "Toggle this help"),
     Line::from(""),
     Line::from(Span::styled(
       "Search Screen",
       Style::default().add_modifier(Modifier::BOLD),
     )),
-    Line::from("  i, e       - Enter edit mode (focus input)"),
-    Line::from("  Enter      - Focus input / Execute search"),
+    shortcut(key(Action::Edit), "Enter edit mode (focus input)"),
+    Line::from("  Enter      - Focus input"),
+    shortcut(key(Action::Search), "Execute search (while editing)"),
     Line::from("  Esc        - Exit edit mode (unfocus input)"),
+    Line::from("  ←/→        - Move cursor (Home/End to jump)"),
+    Line::from("  Ctrl+A/E   - Jump to start / end of input"),
+    Line::from("  Ctrl+U/W   - Clear input / Delete previous word"),
+    Line::from("  f          - Cycle filter (all/available/taken/problems)"),
+    Line::from("  Esc        - Cancel a running search"),
+    shortcut(select.clone(), "Highlight a result"),
+    Line::from("  Enter, d   - Show result details"),
+    Line::from("  o          - Open result page in browser"),
+    Line::from("  r / R      - Check the highlighted result again / Refresh all"),
+    Line::from("  y / Y      - Copy the result's URL / name"),
     Line::from(""),
     Line::from(Span::styled(
       "Register Screen",
       Style::default().add_modifier(Modifier::BOLD),
     )),
-    Line::from("  ↑/↓        - Navigate available registries"),
-    Line::from("  Enter      - Register selected"),
+    shortcut(select, "Navigate available registries"),
+    shortcut(
+      format!("{} / a", key(Action::Toggle)),
+      "Tick registry / Tick all (one repo, every manifest)",
+    ),
+    shortcut(key(Action::Register), "Register ticked or selected (asks to confirm)"),
+    Line::from("  y / n      - Confirm / cancel registration"),
+    Line::from("  f          - Keep or delete the repo if the commit fails"),
+    Line::from("  o / p / i  - Cycle owner / Toggle private / Toggle issues"),
+    Line::from("  d / h      - Edit description / homepage"),
+    Line::from("  T / g      - Edit topics / .gitignore template"),
+    Line::from("  t          - Validate the GitHub token again"),
+    Line::from("  y          - Copy the registered repo's URL"),
+    Line::from(""),
+    Line::from(Span::styled(
+      "Domain Screen",
+      Style::default().add_modifier(Modifier::BOLD),
+    )),
+    Line::from("  Tab        - Switch between name and TLDs (editing)"),
+    shortcut(key(Action::Search), "Check domains (@group expands presets)"),
+    Line::from("  o          - Open registrar (free) or site (taken)"),
     Line::from(""),
     Line::from(Span::styled(
       "Note",
-      Style::default().fg(Color::Yellow),
+      Style::default().fg(theme.warning),
     )),
     Line::from("  GitHub token required for registration"),
-    Line::from("  Set GITHUB_TOKEN env or add to config"),
+    Line::from("  Tried in order: GITHUB_TOKEN, GH_TOKEN,"),
+    Line::from("  github_token_command (config), keyring"),
   ];
 
   let help = Paragraph::new(help_text)
     .block(Block::default().borders(Borders::ALL).title(" Help "))
-    .style(Style::default().bg(Color::DarkGray));
+    .style(Style::default().bg(theme.muted));
 
   frame.render_widget(ratatui::widgets::Clear, area);
   frame.render_widget(help, area);
 }
 
+/// Ask before quitting or Esc aborts a running registration; `yes` names
+/// what answering y does
+fn render_busy_confirm(frame: &mut Frame, app: &App, title: &str, question: &str, yes: &str) {
+  let theme = app.theme;
+  let screen = frame.area();
+  let (width, height) = (screen.width.min(40), screen.height.min(6));
+  let area = Rect {
+    x: screen.x + (screen.width - width) / 2,
+    y: screen.y + (screen.height - height) / 2,
+    width,
+    height,
+  };
+  let bold = |color| Style::default().fg(color).add_modifier(Modifier::BOLD);
+  let lines = vec![
+    Line::from(Span::styled("Registration in progress", bold(theme.warning))),
+    Line::from(question),
+    Line::from(""),
+    Line::from(vec![
+      Span::styled("[y]", bold(theme.error)),
+      Span::raw(format!("{}    ", yes)),
+      Span::styled("[N]", bold(theme.success)),
+      Span::raw(" keep it running"),
+    ]),
+  ];
+  let dialog = Paragraph::new(lines)
+    .alignment(Alignment::Center)
+    .block(Block::default().borders(Borders::ALL).title(title));
+  frame.render_widget(ratatui::widgets::Clear, area);
+  frame.render_widget(dialog, area);
+}
+
 /// Helper function to create a centered rect
 fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
   let popup_layout = Layout::default()
@@ -155,3 +423,166 @@ fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     ])
     .split(popup_layout[1])[1]
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::config::Config;
+  use crate::registry::{Availability, AvailabilityResult, RegistryType};
+  use ratatui::{backend::TestBackend, Terminal};
+
+  /// The Search screen with a free (highlighted), a taken, a reserved and a
+  /// failed result in `preset`, as text followed by its styles
+  fn draw_search(preset: &str) -> String {
+    let mut config = Config::default();
+    config.theme.preset = preset.to_string();
+    let mut app = App::with_config(config);
+    let result = |registry, availability, error: Option<&str>| AvailabilityResult {
+      registry,
+      name: "foo".to_string(),
+      availability,
+      error: error.map(String::from),
+      detail: None,
+      qualifier: None,
+    };
+    app.search_input.set("foo");
+    app.last_searched = Some("foo".to_string());
+    app.set_search_results(vec![
+      result(RegistryType::Npm, Availability::Available, None),
+      result(RegistryType::Crates, Availability::Taken, None),
+      result(RegistryType::Brew, Availability::Reserved, None),
+      result(RegistryType::PyPi, Availability::Unknown, Some("timeout")),
+    ]);
+    app.input_mode = InputMode::Normal;
+    app.select_next_result();
+
+    let mut terminal = Terminal::new(TestBackend::new(72, 12)).unwrap();
+    terminal.draw(|frame| render(frame, &mut app)).unwrap();
+    format!("{:?}", terminal.backend().buffer())
+  }
+
+  /// Every screen drawn at `width`×`height`, as text
+  fn draw_screens(width: u16, height: u16) -> String {
+    let mut app = App::with_config(Config::default());
+    app.config.remember_github_token(None);
+    app.search_input.set("foo");
+    app.input_mode = InputMode::Normal;
+    let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+    let mut screens = Vec::new();
+    for screen in Screen::ALL {
+      app.screen = screen;
+      terminal.draw(|frame| render(frame, &mut app)).unwrap();
+      let buffer = terminal.backend().buffer();
+      let rows = buffer.content.chunks(width as usize).map(|row| {
+        row.iter().map(|cell| cell.symbol()).collect::<String>().trim_end().to_string()
+      });
+      screens.push(format!("{:?}:\n{}", screen, rows.collect::<Vec<_>>().join("\n")));
+    }
+    screens.join("\n\n")
+  }
+
+  #[test]
+  fn test_drawing_never_looks_up_the_token() {
+    let mut app = App::with_config(Config::default());
+    app.screen = Screen::Register;
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal.draw(|frame| render(frame, &mut app)).unwrap();
+    let text: String = terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect();
+    assert!(text.contains("Looking for a GitHub token..."));
+    assert_eq!(app.config.resolved_token_source(), None);
+  }
+
+  #[test]
+  fn test_too_small_terminal_shows_a_notice() {
+    insta::assert_snapshot!(draw_screens(39, 12));
+    insta::assert_snapshot!(draw_screens(80, 9));
+  }
+
+  #[test]
+  fn test_short_terminal_drops_the_chrome() {
+    insta::assert_snapshot!(draw_screens(40, 10));
+    insta::assert_snapshot!(draw_screens(60, 16));
+  }
+
+  #[test]
+  fn test_full_layout() {
+    insta::assert_snapshot!(draw_screens(80, 24));
+  }
+
+  #[test]
+  fn test_desktop_notifications_row() {
+    let mut app = App::with_config(Config::default());
+    app.screen = Screen::Settings;
+    app.config.notifications.desktop = true;
+    app.selected_setting = app.settings_count() - 1;
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal.draw(|frame| render(frame, &mut app)).unwrap();
+    let text: String = terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect();
+    let row = "▶ [✓] Desktop notifications - when a search or registration finishes";
+    assert!(text.contains(row));
+  }
+
+  #[test]
+  fn test_cursor_stays_inside_the_input() {
+    let mut app = App::with_config(Config::default());
+    app.search_input.set("x".repeat(100));
+    let mut terminal = Terminal::new(TestBackend::new(40, 10)).unwrap();
+    terminal.draw(|frame| render(frame, &mut app)).unwrap();
+    assert_eq!(terminal.get_cursor_position().unwrap(), (38, 2).into());
+  }
+
+  #[test]
+  fn test_quit_dialog_fits_the_smallest_terminal() {
+    let mut app = App::with_config(Config::default());
+    app.modal = Some(Modal::Quit);
+    let mut terminal = Terminal::new(TestBackend::new(MIN_WIDTH, MIN_HEIGHT)).unwrap();
+    terminal.draw(|frame| render(frame, &mut app)).unwrap();
+    let text: String = terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect();
+    assert!(text.contains("Quit anyway and cancel it?"));
+    assert!(text.contains("[y] quit    [N] keep it running"));
+
+    app.modal = Some(Modal::CancelRegistration);
+    terminal.draw(|frame| render(frame, &mut app)).unwrap();
+    let text: String = terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect();
+    assert!(text.contains("Cancel it? A repo may be left behind."));
+    assert!(text.contains("[y] cancel    [N] keep it running"));
+  }
+
+  #[test]
+  fn test_register_list_labels_every_registry() {
+    let mut app = App::with_config(Config::default());
+    app.last_searched = Some("foo".to_string());
+    let free = |registry, name: &str| AvailabilityResult {
+      registry,
+      name: name.to_string(),
+      availability: Availability::Available,
+      error: None,
+      detail: None,
+      qualifier: None,
+    };
+    app.set_search_results(vec![
+      free(RegistryType::Brew, "foo"),
+      free(RegistryType::Debian, "foo"),
+      free(RegistryType::DevDomain, "foo.dev"),
+    ]);
+    app.screen = Screen::Register;
+    app.input_mode = InputMode::Normal;
+
+    let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+    terminal.draw(|frame| render(frame, &mut app)).unwrap();
+    let text: String = terminal.backend().buffer().content.iter().map(|c| c.symbol()).collect();
+    for action in ["Submit formula PR", "Submit package", "Check registrar"] {
+      assert!(text.contains(action), "missing {:?}", action);
+    }
+  }
+
+  #[test]
+  fn test_dark_theme() {
+    insta::assert_snapshot!(draw_search("dark"));
+  }
+
+  #[test]
+  fn test_light_theme() {
+    insta::assert_snapshot!(draw_search("light"));
+  }
+}
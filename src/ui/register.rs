@@ -1,57 +1,173 @@
-use crate::app::App;
+use super::theme::Theme;
+use crate::app::{is_reservable, App, InputMode, TokenCheck};
+use crate::config::TOKEN_SOURCES;
+use crate::registry::github::{repo_topics, Step, StepStatus, TokenInfo};
 use crate::registry::RegistryType;
 use ratatui::{
   layout::{Constraint, Direction, Layout, Rect},
-  style::{Color, Modifier, Style},
+  style::{Modifier, Style},
   text::{Line, Span},
-  widgets::{Block, Borders, List, ListItem, Paragraph},
+  widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
   Frame,
 };
 
-pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+/// Below this height the boxes around the list keep only their top edge
+const COMPACT_HEIGHT: u16 = 16;
+
+pub fn render(frame: &mut Frame, app: &mut App, area: Rect) {
+  let compact = area.height < COMPACT_HEIGHT;
+  let border = if compact { 1 } else { 2 };
+  // Step log lines, the final status (which may wrap) and borders
+  let status_height = match app.register_steps.len() {
+    0 => 1 + border,
+    steps => (steps as u16 + 2 + border).min(12),
+  };
   let chunks = Layout::default()
     .direction(Direction::Vertical)
     .constraints([
-      Constraint::Length(3), // Info
-      Constraint::Min(0),    // Registry list
-      Constraint::Length(3), // Status
+      Constraint::Length(1 + border), // Info
+      Constraint::Length(2 + border), // Repo options
+      Constraint::Min(3),             // Registry list
+      Constraint::Length(status_height), // Status
     ])
     .split(area);
 
-  render_info(frame, app, chunks[0]);
-  render_registry_list(frame, app, chunks[1]);
-  render_status(frame, app, chunks[2]);
+  render_info(frame, app, chunks[0], compact);
+  render_options(frame, app, chunks[1], compact);
+  render_registry_list(frame, app, chunks[2]);
+  render_status(frame, app, chunks[3], compact);
 }
 
-fn render_info(frame: &mut Frame, app: &App, area: Rect) {
-  let has_token = app.config.get_github_token().is_some();
-  let token_status = if has_token {
-    Span::styled("✓ GitHub token configured", Style::default().fg(Color::Green))
-  } else {
-    Span::styled(
-      "✗ GitHub token not set (export GITHUB_TOKEN or add to config)",
-      Style::default().fg(Color::Red),
-    )
+/// How to get a token when none is found
+const LOGIN_HINT: &str = if cfg!(feature = "keyring") {
+  " - run `nbi auth login --device`"
+} else {
+  ""
+};
+
+fn render_info(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+  let theme = app.theme;
+  // Only the cached lookup: check_token resolves the token off the draw loop
+  let token_status = match (app.config.resolved_token_source(), &app.token_check) {
+    (None, _) => Span::styled(
+      format!("{} Looking for a GitHub token...", super::spinner(app)),
+      Style::default().fg(theme.muted),
+    ),
+    (Some(None), _) => Span::styled(
+      format!("✗ No GitHub token (tried {}){}", TOKEN_SOURCES, LOGIN_HINT),
+      Style::default().fg(theme.error),
+    ),
+    (Some(Some(source)), TokenCheck::Unchecked) => Span::styled(
+      format!("✓ GitHub token from {} (t to validate)", source.label()),
+      Style::default().fg(theme.success),
+    ),
+    (Some(Some(_)), TokenCheck::Checking) => Span::styled(
+      format!("{} Checking GitHub token...", super::spinner(app)),
+      Style::default().fg(theme.muted),
+    ),
+    (Some(Some(_)), TokenCheck::Valid(info)) => match app.username_mismatch() {
+      Some(username) => Span::styled(
+        format!("⚠ Authenticated as {}, but github_username is {}", info.login, username),
+        Style::default().fg(theme.warning),
+      ),
+      None => token_line(&theme, info, app.register_options.private),
+    },
+    (Some(Some(_)), TokenCheck::Invalid(err)) => Span::styled(
+      format!("✗ GitHub token rejected: {} (t to retry)", err),
+      Style::default().fg(theme.error),
+    ),
   };
 
   let info = Paragraph::new(Line::from(vec![Span::raw("  "), token_status]))
-    .block(Block::default().borders(Borders::ALL).title(" Configuration "));
+    .block(super::panel(compact).title(" Configuration "));
 
   frame.render_widget(info, area);
 }
 
-fn render_registry_list(frame: &mut Frame, app: &App, area: Rect) {
+/// "Authenticated as <login> (scopes: ...)", or a warning about scopes
+fn token_line(theme: &Theme, info: &TokenInfo, private: bool) -> Span<'static> {
+  if let Some(warning) = info.scope_warning(private) {
+    return Span::styled(
+      format!("⚠ Authenticated as {}, but {}", info.login, warning),
+      Style::default().fg(theme.warning),
+    );
+  }
+  Span::styled(
+    format!("✓ Authenticated as {} ({})", info.login, info.scopes_label()),
+    Style::default().fg(theme.success),
+  )
+}
+
+fn render_options(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+  let theme = app.theme;
+  if app.input_mode == InputMode::Editing {
+    let title = format!(" {} (Enter to save, Esc to cancel) ", app.editing_option.label());
+    let input = Paragraph::new(app.option_input.as_str())
+      .style(Style::default().fg(theme.warning))
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .title(title)
+          .border_style(Style::default().fg(theme.warning)),
+      );
+    frame.render_widget(input, area);
+    frame.set_cursor_position(super::input_cursor(area, app.option_input.cursor_column()));
+    return;
+  }
+
+  let options = &app.register_options;
+  let key = |k: &str| Span::styled(format!("[{}] ", k), Style::default().fg(theme.muted));
+  let issues = options.has_issues.unwrap_or(true);
+  let topics = match options.topics.join(", ") {
+    topics if topics.is_empty() => "none".to_string(),
+    topics => topics,
+  };
+  let lines = vec![
+    Line::from(vec![
+      Span::raw("  "),
+      key("o"),
+      Span::raw(format!("Owner: {}   ", options.org.as_deref().unwrap_or("personal"))),
+      key("p"),
+      Span::raw(if options.private { "Private   " } else { "Public   " }),
+      key("i"),
+      Span::raw(if issues { "Issues on   " } else { "Issues off   " }),
+      key("d"),
+      Span::raw(format!(
+        "Description: {}",
+        options.description.as_deref().unwrap_or("(placeholder)")
+      )),
+    ]),
+    Line::from(vec![
+      Span::raw("  "),
+      key("h"),
+      Span::raw(format!("Homepage: {}   ", options.homepage.as_deref().unwrap_or("none"))),
+      key("T"),
+      Span::raw(format!("Topics: {}   ", topics)),
+      key("g"),
+      Span::raw(format!(
+        ".gitignore: {}",
+        options.gitignore_template.as_deref().unwrap_or("none")
+      )),
+    ]),
+  ];
+
+  let block = super::panel(compact).title(" Repository Options ");
+  frame.render_widget(Paragraph::new(lines).block(block), area);
+}
+
+fn render_registry_list(frame: &mut Frame, app: &mut App, area: Rect) {
+  let theme = app.theme;
   let available = app.get_available_registries();
 
   if available.is_empty() {
     let message = if app.search_results.is_empty() {
-      "Search for a package name first (Tab to switch to Search)"
+      "Search for a package name first (Shift+Tab to go back to Search)"
     } else {
       "No available registries found for this name"
     };
 
     let placeholder = Paragraph::new(message)
-      .style(Style::default().fg(Color::DarkGray))
+      .style(Style::default().fg(theme.muted))
       .block(
         Block::default()
           .borders(Borders::ALL)
@@ -62,15 +178,16 @@ fn render_registry_list(frame: &mut Frame, app: &App, area: Rect) {
     return;
   }
 
+  let selected = app.selected_registry_index();
   let items: Vec<ListItem> = available
     .iter()
     .enumerate()
     .map(|(i, result)| {
-      let is_selected = i == app.selected_registry;
+      let is_selected = i == selected;
       let prefix = if is_selected { "▶ " } else { "  " };
 
       let style = if is_selected {
-        Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)
+        Style::default().fg(theme.accent).add_modifier(Modifier::BOLD)
       } else {
         Style::default()
       };
@@ -84,12 +201,24 @@ fn render_registry_list(frame: &mut Frame, app: &App, area: Rect) {
         RegistryType::Flatpak => "Submit to Flathub",
         RegistryType::Debian => "Submit package",
         RegistryType::DevDomain => "Check registrar",
+        RegistryType::NpmOrg => "Create organization",
+        RegistryType::GitHubUser | RegistryType::Bluesky | RegistryType::Mastodon => "Sign up",
+        RegistryType::Trademark => "File application",
+      };
+
+      let checkbox = if !is_reservable(result.registry) {
+        "    "
+      } else if app.register_checked.contains(&result.registry) {
+        "[x] "
+      } else {
+        "[ ] "
       };
 
       let line = Line::from(vec![
         Span::styled(prefix, style),
+        Span::styled(checkbox, style),
         Span::styled(format!("{:<12}", result.registry), style),
-        Span::styled(format!(" - {}", action), Style::default().fg(Color::DarkGray)),
+        Span::styled(format!(" - {}", action), Style::default().fg(theme.muted)),
       ]);
 
       ListItem::new(line)
@@ -99,32 +228,153 @@ fn render_registry_list(frame: &mut Frame, app: &App, area: Rect) {
   let list = List::new(items).block(
     Block::default()
       .borders(Borders::ALL)
-      .title(" Available Registries (↑/↓ to select, Enter to register) "),
+      .title(format!(
+        " Available Registries for '{}' (↑/↓ select, Space tick, a all, Enter register) ",
+        app.last_searched.as_deref().unwrap_or_default()
+      )),
   );
 
+  app.hit_areas.list = area;
   frame.render_widget(list, area);
 }
 
-fn render_status(frame: &mut Frame, app: &App, area: Rect) {
+fn render_status(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+  let theme = app.theme;
   let status_text = if let Some(ref status) = app.register_status {
     status.as_str()
   } else if app.is_registering {
     "Registering..."
   } else {
-    "Select a registry and press Enter to register"
+    "Select or tick registries and press Enter to register"
   };
 
   let style = if app.register_status.as_ref().is_some_and(|s| s.contains("Error")) {
-    Style::default().fg(Color::Red)
+    Style::default().fg(theme.error)
   } else if app.register_status.as_ref().is_some_and(|s| s.contains("Success")) {
-    Style::default().fg(Color::Green)
+    Style::default().fg(theme.success)
   } else {
-    Style::default().fg(Color::DarkGray)
+    Style::default().fg(theme.muted)
   };
 
-  let status = Paragraph::new(status_text)
-    .style(style)
-    .block(Block::default().borders(Borders::ALL).title(" Status "));
+  let mut lines: Vec<Line> =
+    app.register_steps.iter().map(|step| step_line(&theme, step)).collect();
+  lines.push(Line::from(Span::styled(status_text, style)));
+
+  let status = Paragraph::new(lines)
+    .wrap(Wrap { trim: false })
+    .block(super::panel(compact).title(" Status "));
 
   frame.render_widget(status, area);
 }
+
+/// One registration step, e.g. "Creating repository… ✓"
+fn step_line<'a>(theme: &Theme, step: &'a Step) -> Line<'a> {
+  let (mark, color, detail) = match &step.status {
+    StepStatus::Running => ("…", theme.warning, None),
+    StepStatus::Done => ("✓", theme.success, None),
+    StepStatus::Failed(err) => ("✗", theme.error, Some(err.as_str())),
+  };
+  let mut spans = vec![
+    Span::raw(format!("  {}… ", step.label)),
+    Span::styled(mark, Style::default().fg(color).add_modifier(Modifier::BOLD)),
+  ];
+  if let Some(detail) = detail {
+    spans.push(Span::styled(format!(" {}", detail), Style::default().fg(theme.error)));
+  }
+  Line::from(spans)
+}
+
+/// Render the confirmation dialog for a pending registration
+pub fn render_confirm(frame: &mut Frame, app: &App) {
+  let theme = app.theme;
+  let Some(pending) = app.pending_registration() else {
+    return;
+  };
+  let area = super::centered_rect(70, 80, frame.area());
+  let label = |text: &str| {
+    Span::styled(format!("{:<13}", text), Style::default().add_modifier(Modifier::BOLD))
+  };
+  let visibility = if pending.options.private { "private" } else { "public" };
+  let manifests: Vec<_> = pending.manifests().iter().map(|m| m.filename()).collect();
+  let manifest = if manifests.is_empty() {
+    "none (README only)".to_string()
+  } else {
+    manifests.join(", ")
+  };
+  let description = pending.repo_options().description.unwrap_or_default();
+
+  let mut lines = vec![
+    Line::from(Span::styled(
+      "Create a GitHub repository?",
+      Style::default().fg(theme.warning).add_modifier(Modifier::BOLD),
+    )),
+    Line::from(""),
+    Line::from(vec![
+      label("Repository"),
+      Span::raw(format!("{}/{}", pending.owner(), pending.name)),
+    ]),
+    Line::from(vec![label("Visibility"), Span::raw(visibility)]),
+    Line::from(vec![label("Description"), Span::raw(description)]),
+    Line::from(vec![
+      label(if manifests.len() > 1 { "Manifests" } else { "Manifest" }),
+      Span::raw(manifest),
+    ]),
+  ];
+  let fields = &pending.options.manifest;
+  let extras: Vec<_> = [
+    fields.readme.then(|| "README.md".to_string()),
+    fields.license_file.then(|| format!("LICENSE ({})", fields.license)),
+  ]
+  .into_iter()
+  .flatten()
+  .collect();
+  if !extras.is_empty() {
+    lines.push(Line::from(vec![label("Also adds"), Span::raw(extras.join(", "))]));
+  }
+  let topics = repo_topics(&pending.options, &pending.manifests());
+  if !topics.is_empty() {
+    lines.push(Line::from(vec![label("Topics"), Span::raw(topics.join(", "))]));
+  }
+  if let Some(homepage) = &pending.options.homepage {
+    lines.push(Line::from(vec![label("Homepage"), Span::raw(homepage.clone())]));
+  }
+  if let Some(gitignore) = &pending.options.gitignore_template {
+    lines.push(Line::from(vec![label(".gitignore"), Span::raw(gitignore.clone())]));
+  }
+  if pending.options.has_issues == Some(false) {
+    lines.push(Line::from(vec![label("Issues"), Span::raw("off")]));
+  }
+  lines.push(Line::from(vec![
+    label("On failure"),
+    Span::raw(pending.on_failure.label()),
+    Span::styled("  (f to change)", Style::default().fg(theme.muted)),
+  ]));
+  let dim = Style::default().fg(theme.muted);
+  for (manifest, preview) in pending.manifest_previews() {
+    lines.push(Line::from(""));
+    // Label each preview when there is more than one
+    if manifests.len() > 1 {
+      let heading = dim.add_modifier(Modifier::BOLD);
+      lines.push(Line::from(Span::styled(format!("  {}", manifest.filename()), heading)));
+    }
+    lines.extend(preview.lines().map(|line| Line::from(Span::styled(format!("  {}", line), dim))));
+  }
+  lines.push(Line::from(""));
+  lines.push(Line::from(vec![
+    Span::styled("[Y]es", Style::default().fg(theme.success).add_modifier(Modifier::BOLD)),
+    Span::raw(" / Enter to create    "),
+    Span::styled("[N]o", Style::default().fg(theme.error).add_modifier(Modifier::BOLD)),
+    Span::raw(" / Esc to cancel"),
+  ]));
+
+  let dialog = Paragraph::new(lines)
+    .wrap(Wrap { trim: false })
+    .block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title(" Confirm Registration "),
+    );
+
+  frame.render_widget(Clear, area);
+  frame.render_widget(dialog, area);
+}
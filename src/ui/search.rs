@@ -1,13 +1,15 @@
 use crate::app::{App, InputMode};
+use crate::registry::{Availability, AvailabilityResult, RegistryType, ResultFilter};
+use crate::tui::session;
 use ratatui::{
   layout::{Constraint, Direction, Layout, Rect},
-  style::{Color, Modifier, Style},
+  style::{Modifier, Style},
   text::{Line, Span},
-  widgets::{Block, Borders, List, ListItem, Paragraph},
+  widgets::{Block, Borders, Clear, List, ListItem, Paragraph, Wrap},
   Frame,
 };
 
-pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+pub fn render(frame: &mut Frame, app: &mut App, area: Rect) {
   let chunks = Layout::default()
     .direction(Direction::Vertical)
     .constraints([
@@ -21,49 +23,60 @@ pub fn render(frame: &mut Frame, app: &App, area: Rect) {
 }
 
 fn render_search_input(frame: &mut Frame, app: &App, area: Rect) {
-  let (border_style, title, text_style) = if app.is_searching {
+  let theme = app.theme;
+  let (border_style, title, text_style) = if app.is_searching && app.input_mode == InputMode::Normal {
     (
-      Style::default().fg(Color::DarkGray),
-      " Searching... ",
-      Style::default().fg(Color::DarkGray),
+      Style::default().fg(theme.muted),
+      " Searching... (Esc to cancel) ",
+      Style::default().fg(theme.muted),
     )
   } else {
     match app.input_mode {
       InputMode::Normal => (
-        Style::default().fg(Color::DarkGray),
+        Style::default().fg(theme.muted),
         " Package Name (i/e to edit) ",
         Style::default(),
       ),
       InputMode::Editing => (
-        Style::default().fg(Color::Yellow),
+        Style::default().fg(theme.warning),
         " Package Name (Enter to search) ",
-        Style::default().fg(Color::Yellow),
+        Style::default().fg(theme.warning),
       ),
     }
   };
 
+  let mut block = Block::default()
+    .borders(Borders::ALL)
+    .title(title)
+    .border_style(border_style);
+  if let Some(term) = app.stale_search_term() {
+    block = block.title_bottom(
+      Line::from(Span::styled(
+        format!(" (results are for '{}') ", term),
+        Style::default().fg(theme.muted),
+      ))
+      .right_aligned(),
+    );
+  }
+
   let input = Paragraph::new(app.search_input.as_str())
     .style(text_style)
-    .block(
-      Block::default()
-        .borders(Borders::ALL)
-        .title(title)
-        .border_style(border_style),
-    );
+    .block(block);
 
   frame.render_widget(input, area);
 
-  // Show cursor when editing (but not when searching)
-  if app.input_mode == InputMode::Editing && !app.is_searching {
-    frame.set_cursor_position((
-      area.x + app.search_input.len() as u16 + 1,
-      area.y + 1,
-    ));
+  // Show cursor when editing
+  if app.input_mode == InputMode::Editing {
+    frame.set_cursor_position(super::input_cursor(area, app.search_input.cursor_column()));
   }
 }
 
-fn render_results(frame: &mut Frame, app: &App, area: Rect) {
-  if app.search_results.is_empty() {
+/// Pending rows show their elapsed time once a check takes this long
+const SLOW_CHECK_SECS: u64 = 3;
+
+fn render_results(frame: &mut Frame, app: &mut App, area: Rect) {
+  let theme = app.theme;
+  if app.search_results.is_empty() && app.pending_registries.is_empty() {
     let message = if app.is_searching {
       "Searching..."
     } else if app.search_input.is_empty() {
@@ -73,59 +86,51 @@ fn render_results(frame: &mut Frame, app: &App, area: Rect) {
     };
 
     let placeholder = Paragraph::new(message)
-      .style(Style::default().fg(Color::DarkGray))
+      .style(Style::default().fg(theme.muted))
       .block(Block::default().borders(Borders::ALL).title(" Results "));
 
     frame.render_widget(placeholder, area);
     return;
   }
 
-  let items: Vec<ListItem> = app
-    .search_results
-    .iter()
+  let mut items: Vec<ListItem> = app
+    .filtered_results()
+    .into_iter()
     .map(|result| {
-      let symbol = App::get_status_symbol(result);
-      let color = App::get_status_color(result);
-
-      let (status_text, error_text) = match (result.available, &result.error) {
-        (Some(true), _) => ("Available", None),
-        (Some(false), _) => ("Taken", None),
-        (None, Some(err)) => {
-          let short_err = if err.contains("timeout") || err.contains("Timeout") {
-            "Timeout"
-          } else if err.contains("rate") || err.contains("429") {
-            "Rate Limited"
-          } else if err.contains("403") || err.contains("Forbidden") {
-            "Access Denied"
-          } else if err.contains("connect") || err.contains("network") {
-            "Network Error"
-          } else if err.len() > 30 {
-            "Error"
-          } else {
-            "Error"
-          };
-          (short_err, Some(err.as_str()))
-        }
-        (None, None) => ("Unknown", None),
+      // A row being re-checked keeps its old result behind a spinner
+      let (symbol, symbol_color) = if app.rechecking.contains(&result.registry) {
+        (super::spinner(app), theme.warning)
+      } else {
+        (App::get_status_symbol(result), theme.status(result.availability))
       };
+      let color = theme.status(result.availability);
+
+      let status_text = status_label(result);
+      let error_text = result.error.as_deref().filter(|_| result.available().is_none());
 
       let line = Line::from(vec![
         Span::styled(
           format!(" {} ", symbol),
-          Style::default().fg(color).add_modifier(Modifier::BOLD),
+          Style::default().fg(symbol_color).add_modifier(Modifier::BOLD),
         ),
         Span::styled(
           format!("{:<12}", result.registry),
           Style::default().add_modifier(Modifier::BOLD),
         ),
         Span::styled(format!(" {:<14}", status_text), Style::default().fg(color)),
+        match app.search_durations.get(&result.registry) {
+          Some(ms) => Span::styled(format!("{:>6}ms ", ms), Style::default().fg(theme.muted)),
+          None => Span::raw(""),
+        },
         if let Some(err) = error_text {
-          let truncated = if err.len() > 40 {
-            format!("{}...", &err[..40])
+          let truncated = if err.chars().count() > 40 {
+            format!("{}...", err.chars().take(40).collect::<String>())
           } else {
             err.to_string()
           };
-          Span::styled(format!("({})", truncated), Style::default().fg(Color::Red))
+          Span::styled(format!("({})", truncated), Style::default().fg(theme.error))
+        } else if let Some(detail) = &result.detail {
+          Span::styled(detail.clone(), Style::default().fg(theme.muted))
         } else {
           Span::raw("")
         },
@@ -134,12 +139,144 @@ fn render_results(frame: &mut Frame, app: &App, area: Rect) {
       ListItem::new(line)
     })
     .collect();
+  // Pending rows go last so the highlight indexes stay aligned with results
+  items.extend(app.pending_registries.iter().map(|registry| pending_item(app, *registry)));
+
+  let term = app.last_searched.as_deref().unwrap_or_default();
+  let incomplete = if app.search_incomplete { " (incomplete)" } else { "" };
+  let title = match app.result_filter {
+    ResultFilter::All => format!(" Results for '{}'{} ", term, incomplete),
+    filter => format!(
+      " Results for '{}'{} [{} only, f to cycle] ",
+      term,
+      incomplete,
+      filter.label()
+    ),
+  };
+  let mut block = Block::default().borders(Borders::ALL).title(title);
+  let summary = summary(&app.search_results);
+  if !summary.is_empty() {
+    block = block.title(Line::from(format!(" {} ", summary)).right_aligned());
+  }
+  if let Some(age) = app.restored_age() {
+    let note = format!(" from {} ago, R to refresh ", session::age(age));
+    block = block.title_bottom(Span::styled(note, Style::default().fg(theme.warning)));
+  }
+  let results_list = List::new(items)
+    .block(block)
+    .highlight_style(Style::default().bg(theme.muted));
+
+  app.hit_areas.list = area;
+  frame.render_stateful_widget(results_list, area, &mut app.results_list_state);
+}
+
+/// Counts for the results header, e.g. "4 available · 9 taken · 2 errors";
+/// empty groups are left out
+fn summary(results: &[AvailabilityResult]) -> String {
+  let count = |availability| results.iter().filter(|r| r.availability == availability).count();
+  let errors = count(Availability::Unknown);
+  let groups = [
+    (count(Availability::Available), "available"),
+    (count(Availability::Taken), "taken"),
+    (count(Availability::Reserved), "reserved"),
+    (errors, if errors == 1 { "error" } else { "errors" }),
+  ];
+  let parts: Vec<_> = groups
+    .iter()
+    .filter(|(n, _)| *n > 0)
+    .map(|(n, label)| format!("{} {}", n, label))
+    .collect();
+  parts.join(" · ")
+}
+
+/// Placeholder row for a registry whose check is still running
+fn pending_item(app: &App, registry: RegistryType) -> ListItem<'static> {
+  let theme = app.theme;
+  let elapsed = app.search_elapsed().map_or(0, |d| d.as_secs());
+  let status = if elapsed >= SLOW_CHECK_SECS {
+    format!("…pending {}s", elapsed)
+  } else {
+    "…pending".to_string()
+  };
+  let style = Style::default().fg(theme.muted);
+
+  ListItem::new(Line::from(vec![
+    Span::styled(format!(" {} ", super::spinner(app)), style),
+    Span::styled(format!("{:<12}", registry), style.add_modifier(Modifier::BOLD)),
+    Span::styled(format!(" {}", status), style),
+  ]))
+}
+
+/// Short status for a result, classifying common error causes
+fn status_label(result: &AvailabilityResult) -> &'static str {
+  match (result.availability, &result.error) {
+    (Availability::Available, _) => "Available",
+    (Availability::Taken, _) => "Taken",
+    (Availability::Reserved, _) => "Reserved",
+    (Availability::Unknown, Some(err)) => {
+      if err.contains("timeout") || err.contains("Timeout") {
+        "Timeout"
+      } else if err.contains("rate") || err.contains("429") {
+        "Rate Limited"
+      } else if err.contains("403") || err.contains("Forbidden") {
+        "Access Denied"
+      } else if err.contains("connect") || err.contains("network") {
+        "Network Error"
+      } else {
+        "Error"
+      }
+    }
+    (Availability::Unknown, None) if result.detail.is_some() => "Review",
+    (Availability::Unknown, None) => "Unknown",
+  }
+}
+
+/// Render the full details of the highlighted result in a popup
+pub fn render_result_detail(frame: &mut Frame, app: &App) {
+  let theme = app.theme;
+  let Some(result) = app.selected_result() else {
+    return;
+  };
+  let area = super::centered_rect(60, 50, frame.area());
+  let label = |text: &str| {
+    Span::styled(format!("{:<10}", text), Style::default().add_modifier(Modifier::BOLD))
+  };
 
-  let results_list = List::new(items).block(
-    Block::default()
-      .borders(Borders::ALL)
-      .title(format!(" Results for '{}' ", app.search_input)),
-  );
+  let mut lines = vec![
+    Line::from(vec![
+      label("Registry"),
+      Span::raw(format!("{} ({})", result.registry, result.registry.id())),
+    ]),
+    Line::from(vec![label("Name"), Span::raw(result.qualified_name())]),
+    Line::from(vec![
+      label("URL"),
+      Span::styled(result.page_url(), Style::default().fg(theme.accent)),
+    ]),
+    Line::from(vec![
+      label("Status"),
+      Span::styled(
+        format!("{} {}", App::get_status_symbol(result), status_label(result)),
+        Style::default().fg(theme.status(result.availability)),
+      ),
+    ]),
+  ];
+  if let Some(detail) = &result.detail {
+    lines.push(Line::from(vec![label("Detail"), Span::raw(detail.clone())]));
+  }
+  if let Some(err) = &result.error {
+    lines.push(Line::from(""));
+    lines.push(Line::from(label("Error")));
+    lines.push(Line::from(Span::styled(err.clone(), Style::default().fg(theme.error))));
+  }
+
+  let detail = Paragraph::new(lines)
+    .wrap(Wrap { trim: false })
+    .block(
+      Block::default()
+        .borders(Borders::ALL)
+        .title(" Result Details (o to open, Esc to close) "),
+    );
 
-  frame.render_widget(results_list, area);
+  frame.render_widget(Clear, area);
+  frame.render_widget(detail, area);
 }
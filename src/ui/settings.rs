@@ -1,56 +1,72 @@
-use crate::app::App;
+use crate::app::{App, InputMode, RegistryRow};
+use crate::registry::RegistryType;
+use crate::tui::keys::Action;
 use ratatui::{
   layout::{Constraint, Direction, Layout, Rect},
-  style::{Color, Modifier, Style},
+  style::{Modifier, Style},
   text::{Line, Span},
   widgets::{Block, Borders, List, ListItem, Paragraph},
   Frame,
 };
 
-pub fn render(frame: &mut Frame, app: &App, area: Rect) {
+/// Below this height the title and help boxes make way for the list, and
+/// the other boxes keep only their top edge
+const COMPACT_HEIGHT: u16 = 20;
+
+pub fn render(frame: &mut Frame, app: &mut App, area: Rect) {
+  let compact = area.height < COMPACT_HEIGHT;
+  let (chrome, row) = if compact { (0, 2) } else { (3, 3) };
   let chunks = Layout::default()
     .direction(Direction::Vertical)
     .constraints([
-      Constraint::Length(3), // Title
-      Constraint::Min(0),    // Registry list
-      Constraint::Length(3), // Help
+      Constraint::Length(chrome), // Title
+      Constraint::Min(3),         // Registry list
+      Constraint::Length(row),    // Default TLDs
+      Constraint::Length(row),    // GitHub username
+      Constraint::Length(row),    // Desktop notifications
+      Constraint::Length(chrome), // Help
     ])
     .split(area);
 
-  render_title(frame, chunks[0]);
-  render_registry_list(frame, app, chunks[1]);
-  render_help(frame, chunks[2]);
+  if !compact {
+    render_title(frame, app, chunks[0]);
+    render_help(frame, app, chunks[5]);
+  }
+  render_registry_list(frame, app, chunks[1], compact);
+  render_tlds(frame, app, chunks[2], compact);
+  render_username(frame, app, chunks[3], compact);
+  render_desktop_notifications(frame, app, chunks[4], compact);
 }
 
-fn render_title(frame: &mut Frame, area: Rect) {
+fn render_title(frame: &mut Frame, app: &App, area: Rect) {
+  let mut title = vec![Span::raw(" Settings ")];
+  title.extend(unsaved_marker(app));
   let title = Paragraph::new("Toggle registries to include in search")
-    .style(Style::default().fg(Color::Cyan))
-    .block(Block::default().borders(Borders::ALL).title(" Settings "));
+    .style(Style::default().fg(app.theme.accent))
+    .block(Block::default().borders(Borders::ALL).title(Line::from(title)));
 
   frame.render_widget(title, area);
 }
 
-fn render_registry_list(frame: &mut Frame, app: &App, area: Rect) {
-  let registries = [
-    ("npm", app.config.registries.npm, "npmjs.com"),
-    ("crates.io", app.config.registries.crates, "crates.io"),
-    ("PyPI", app.config.registries.pypi, "pypi.org"),
-    ("GitHub", app.config.registries.github, "github.com/user"),
-    ("Homebrew", app.config.registries.brew, "brew.sh"),
-    ("Flatpak", app.config.registries.flatpak, "flathub.org"),
-    ("Debian", app.config.registries.debian, "debian.org"),
-    (".dev Domain", app.config.registries.dev_domain, "DNS lookup"),
-  ];
-
-  let items: Vec<ListItem> = registries
-    .iter()
+/// Title addition while saving the settings fails
+fn unsaved_marker(app: &App) -> Option<Span<'static>> {
+  app
+    .settings_unsaved
+    .then(|| Span::styled("settings not persisted ", Style::default().fg(app.theme.error)))
+}
+
+fn render_registry_list(frame: &mut Frame, app: &mut App, area: Rect, compact: bool) {
+  let theme = app.theme;
+  let items: Vec<ListItem> = app
+    .registry_rows()
     .enumerate()
-    .map(|(i, (name, enabled, desc))| {
+    .map(|(i, (registry, enabled))| {
       let is_selected = i == app.selected_setting;
       let prefix = if is_selected { "▶ " } else { "  " };
+      let (name, desc) = registry_label(registry);
 
-      let checkbox = if *enabled { "[✓]" } else { "[ ]" };
-      let checkbox_color = if *enabled { Color::Green } else { Color::DarkGray };
+      let checkbox = if enabled { "[✓]" } else { "[ ]" };
+      let checkbox_color = if enabled { theme.success } else { theme.muted };
 
       let style = if is_selected {
         Style::default().add_modifier(Modifier::BOLD)
@@ -62,26 +78,141 @@ fn render_registry_list(frame: &mut Frame, app: &App, area: Rect) {
         Span::styled(prefix, style),
         Span::styled(checkbox, Style::default().fg(checkbox_color)),
         Span::styled(format!(" {:<12}", name), style),
-        Span::styled(format!(" - {}", desc), Style::default().fg(Color::DarkGray)),
+        Span::styled(format!(" - {}", desc), Style::default().fg(theme.muted)),
       ]);
 
       ListItem::new(line)
     })
     .collect();
 
+  // Compact layouts have no title box, so the list carries the marker
+  let mut title = vec![Span::raw(" Registries ")];
+  title.extend(unsaved_marker(app).filter(|_| compact));
   let list = List::new(items).block(
     Block::default()
       .borders(Borders::ALL)
-      .title(" Registries "),
+      .title(Line::from(title)),
   );
 
+  app.hit_areas.list = area;
   frame.render_widget(list, area);
 }
 
-fn render_help(frame: &mut Frame, area: Rect) {
-  let help = Paragraph::new("↑/↓ Navigate | Enter/Space Toggle | Tab Switch screen")
-    .style(Style::default().fg(Color::DarkGray))
-    .block(Block::default().borders(Borders::ALL));
+/// Settings label and description for a registry row
+fn registry_label(row: RegistryRow) -> (&'static str, &'static str) {
+  let RegistryRow::Registry(registry) = row else {
+    return ("Handles", "GitHub user, npm org, Bluesky, Mastodon");
+  };
+  match registry {
+    RegistryType::Npm => ("npm", "npmjs.com"),
+    RegistryType::Crates => ("crates.io", "crates.io"),
+    RegistryType::PyPi => ("PyPI", "pypi.org"),
+    RegistryType::GitHub => ("GitHub", "github.com/user"),
+    RegistryType::Brew => ("Homebrew", "brew.sh"),
+    RegistryType::Flatpak => ("Flatpak", "flathub.org"),
+    RegistryType::Debian => ("Debian", "debian.org"),
+    RegistryType::DevDomain => (".dev Domain", "DNS lookup"),
+    RegistryType::GitHubUser => ("GitHub user", "github.com"),
+    RegistryType::NpmOrg => ("npm org", "npmjs.com/org"),
+    RegistryType::Bluesky => ("Bluesky", "bsky.social"),
+    RegistryType::Mastodon => ("Mastodon", "configured instance"),
+    RegistryType::Trademark => ("Trademark", "tmsearch.uspto.gov"),
+  }
+}
+
+fn render_tlds(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+  let theme = app.theme;
+  let is_selected = app.tld_setting_selected();
+  let is_editing = is_selected && app.input_mode == InputMode::Editing;
+  let prefix = if is_selected { "▶ " } else { "  " };
+
+  let value = if is_editing {
+    Span::styled(format!("{}▏", app.tld_input), Style::default().fg(theme.warning))
+  } else {
+    Span::raw(app.config.default_tlds.join(", "))
+  };
+  let style = if is_selected {
+    Style::default().add_modifier(Modifier::BOLD)
+  } else {
+    Style::default()
+  };
+
+  let line = Line::from(vec![Span::styled(prefix, style), value]);
+  let title = if is_editing {
+    " Default TLDs (Enter save, Esc cancel) "
+  } else {
+    " Default TLDs "
+  };
+  let paragraph = Paragraph::new(line).block(super::panel(compact).title(title));
+
+  frame.render_widget(paragraph, area);
+}
+
+fn render_username(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+  let theme = app.theme;
+  let is_selected = app.username_setting_selected();
+  let is_editing = is_selected && app.input_mode == InputMode::Editing;
+  let prefix = if is_selected { "▶ " } else { "  " };
+
+  let value = match app.config.configured_username() {
+    _ if is_editing => {
+      Span::styled(format!("{}▏", app.username_input), Style::default().fg(theme.warning))
+    }
+    Some(username) => Span::raw(username.to_string()),
+    None => Span::styled("(the token's user)", Style::default().fg(theme.muted)),
+  };
+  let style = if is_selected {
+    Style::default().add_modifier(Modifier::BOLD)
+  } else {
+    Style::default()
+  };
+
+  let line = Line::from(vec![Span::styled(prefix, style), value]);
+  let title = if is_editing {
+    " GitHub username (Enter save, Esc cancel) "
+  } else {
+    " GitHub username "
+  };
+  let paragraph = Paragraph::new(line).block(super::panel(compact).title(title));
+
+  frame.render_widget(paragraph, area);
+}
+
+fn render_desktop_notifications(frame: &mut Frame, app: &App, area: Rect, compact: bool) {
+  let theme = app.theme;
+  let is_selected = app.desktop_setting_selected();
+  let enabled = app.config.notifications.desktop;
+  let prefix = if is_selected { "▶ " } else { "  " };
+  let checkbox = if enabled { "[✓]" } else { "[ ]" };
+  let checkbox_color = if enabled { theme.success } else { theme.muted };
+  let style = if is_selected {
+    Style::default().add_modifier(Modifier::BOLD)
+  } else {
+    Style::default()
+  };
+  let line = Line::from(vec![
+    Span::styled(prefix, style),
+    Span::styled(checkbox, Style::default().fg(checkbox_color)),
+    Span::styled(" Desktop notifications", style),
+    Span::styled(" - when a search or registration finishes", Style::default().fg(theme.muted)),
+  ]);
+  let paragraph =
+    Paragraph::new(line).block(super::panel(compact).title(" Notifications "));
+
+  frame.render_widget(paragraph, area);
+}
+
+fn render_help(frame: &mut Frame, app: &App, area: Rect) {
+  let key = |action| app.keys.label(action);
+  let help = Paragraph::new(format!(
+    "{} Navigate | Enter/{} Toggle or edit | {}/{} Switch screen",
+    super::select_label(app),
+    key(Action::Toggle),
+    key(Action::NextScreen),
+    key(Action::PreviousScreen)
+  ))
+  .style(Style::default().fg(app.theme.muted))
+  .block(Block::default().borders(Borders::ALL));
 
   frame.render_widget(help, area);
 }
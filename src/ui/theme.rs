@@ -0,0 +1,131 @@
+use crate::config::ThemeSettings;
+use crate::registry::Availability;
+use crate::tui::toast::Severity;
+use anyhow::Result;
+use ratatui::style::Color;
+
+/// Colors the TUI draws with, by role
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Theme {
+  /// Titles, links and the selected tab
+  pub accent: Color,
+  /// Available names, enabled toggles, finished steps
+  pub success: Color,
+  /// Taken names and failures
+  pub error: Color,
+  /// Work in progress, unknown results and inputs being edited
+  pub warning: Color,
+  /// Names nobody has but the registry won't hand out
+  pub reserved: Color,
+  /// Hints, timings and the highlighted row's background
+  pub muted: Color,
+}
+
+impl Theme {
+  /// For dark terminal backgrounds
+  pub const DARK: Theme = Theme {
+    accent: Color::Cyan,
+    success: Color::Green,
+    error: Color::Red,
+    warning: Color::Yellow,
+    reserved: Color::Blue,
+    muted: Color::DarkGray,
+  };
+
+  /// For light terminal backgrounds, from the 256-color palette since the
+  /// bright ANSI colors wash out on white
+  pub const LIGHT: Theme = Theme {
+    accent: Color::Indexed(25),
+    success: Color::Indexed(28),
+    error: Color::Indexed(124),
+    warning: Color::Indexed(130),
+    reserved: Color::Indexed(61),
+    muted: Color::Indexed(244),
+  };
+
+  /// Built-in presets by name
+  pub const PRESETS: [(&'static str, Theme); 2] = [("dark", Theme::DARK), ("light", Theme::LIGHT)];
+
+  /// The preset named in `settings` with its colors overridden, failing on
+  /// an unknown preset or color
+  pub fn resolve(settings: &ThemeSettings) -> Result<Theme> {
+    let preset = settings.preset.trim();
+    let mut theme = match preset {
+      "" => Theme::DARK,
+      name => Self::PRESETS.iter().find(|(n, _)| *n == name).map(|(_, t)| *t).ok_or_else(|| {
+        let valid: Vec<_> = Self::PRESETS.iter().map(|(n, _)| *n).collect();
+        anyhow::anyhow!("Unknown theme '{}' (valid: {})", name, valid.join(", "))
+      })?,
+    };
+    let overrides = [
+      ("accent", &settings.accent, &mut theme.accent),
+      ("success", &settings.success, &mut theme.success),
+      ("error", &settings.error, &mut theme.error),
+      ("warning", &settings.warning, &mut theme.warning),
+      ("reserved", &settings.reserved, &mut theme.reserved),
+      ("muted", &settings.muted, &mut theme.muted),
+    ];
+    for (key, value, color) in overrides {
+      if value.trim().is_empty() {
+        continue;
+      }
+      *color = value.trim().parse().map_err(|_| {
+        anyhow::anyhow!("Invalid color for theme.{}: '{}' (use a name or #rrggbb)", key, value)
+      })?;
+    }
+    Ok(theme)
+  }
+
+  /// Color for a result: available, taken, reserved or unknown
+  pub fn status(&self, availability: Availability) -> Color {
+    match availability {
+      Availability::Available => self.success,
+      Availability::Taken => self.error,
+      Availability::Reserved => self.reserved,
+      Availability::Unknown => self.warning,
+    }
+  }
+
+  /// Color for a toast
+  pub fn severity(&self, severity: Severity) -> Color {
+    match severity {
+      Severity::Info => self.accent,
+      Severity::Success => self.success,
+      Severity::Error => self.error,
+    }
+  }
+}
+
+impl Default for Theme {
+  fn default() -> Self {
+    Theme::DARK
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn settings(preset: &str, accent: &str) -> ThemeSettings {
+    ThemeSettings { preset: preset.to_string(), accent: accent.to_string(), ..Default::default() }
+  }
+
+  #[test]
+  fn test_presets_and_overrides() {
+    assert_eq!(Theme::resolve(&ThemeSettings::default()).unwrap(), Theme::DARK);
+    assert_eq!(Theme::resolve(&settings("light", "")).unwrap(), Theme::LIGHT);
+
+    let theme = Theme::resolve(&settings("light", "#112233")).unwrap();
+    assert_eq!(theme.accent, Color::Rgb(0x11, 0x22, 0x33));
+    assert_eq!(theme.error, Theme::LIGHT.error);
+    assert_eq!(Theme::resolve(&settings("", "light blue")).unwrap().accent, Color::LightBlue);
+  }
+
+  #[test]
+  fn test_bad_presets_and_colors_name_the_value() {
+    let err = Theme::resolve(&settings("solarized", "")).unwrap_err().to_string();
+    assert_eq!(err, "Unknown theme 'solarized' (valid: dark, light)");
+    let err = Theme::resolve(&settings("", "puce")).unwrap_err().to_string();
+    assert!(err.starts_with("Invalid color for theme.accent: 'puce'"), "{}", err);
+  }
+}
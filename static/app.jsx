@@ -0,0 +1,367 @@
+const { useState, useEffect, useRef } = React;
+
+const TOKEN_KEY = 'nbi-auth-token';
+
+// Sends the saved access token; on 401 asks for one and retries
+async function apiFetch(url, options = {}) {
+  const send = () => {
+    const token = sessionStorage.getItem(TOKEN_KEY);
+    const auth = token ? { Authorization: `Bearer ${token}` } : {};
+    return fetch(url, { ...options, headers: { ...options.headers, ...auth } });
+  };
+  const res = await send();
+  if (res.status !== 401) return res;
+  const token = window.prompt('This server requires an access token:');
+  if (!token) return res;
+  sessionStorage.setItem(TOKEN_KEY, token.trim());
+  return send();
+}
+
+// EventSource can't send headers, so parse the event stream by hand
+async function readEvents(res, onEvent) {
+  const reader = res.body.pipeThrough(new TextDecoderStream()).getReader();
+  let buffer = '';
+  for (;;) {
+    const { value, done } = await reader.read();
+    if (done) return;
+    buffer += value.replace(/\r\n/g, '\n');
+    let end;
+    while ((end = buffer.indexOf('\n\n')) !== -1) {
+      const block = buffer.slice(0, end);
+      buffer = buffer.slice(end + 2);
+      let event = 'message';
+      const data = [];
+      for (const line of block.split('\n')) {
+        if (line.startsWith('event:')) event = line.slice(6).trim();
+        else if (line.startsWith('data:')) data.push(line.slice(5).replace(/^ /, ''));
+      }
+      if (data.length) onEvent(event, data.join('\n'));
+    }
+  }
+}
+
+const DEFAULT_TLDS = ['com', 'net', 'org', 'io', 'dev', 'app', 'co', 'ai', 'wiki', 'xyz', 'me', 'tv', 'gg'];
+
+function App() {
+  const [tab, setTab] = useState('packages');
+  const [name, setName] = useState('');
+  const [results, setResults] = useState([]);
+  const [domainResults, setDomainResults] = useState([]);
+  const [loading, setLoading] = useState(false);
+  const [registries, setRegistries] = useState([]);
+  const [settings, setSettings] = useState({});
+  const [selectedTlds, setSelectedTlds] = useState(DEFAULT_TLDS);
+  const [customTld, setCustomTld] = useState('');
+  const [customDomains, setCustomDomains] = useState([]);
+
+  const checkStream = useRef(null);
+
+  // The server lists what it supports and which registries config enables
+  useEffect(() => {
+    apiFetch('/api/registries')
+      .then(res => res.json())
+      .then(data => {
+        const list = data.registries || [];
+        setRegistries(list);
+        setSettings(Object.fromEntries(list.map(r => [r.id, r.enabled])));
+      })
+      .catch(console.error);
+  }, []);
+
+  const registryName = (id) => registries.find(r => r.id === id)?.name || id;
+
+  // Results arrive one event per registry; aborting the request cancels the rest
+  const checkPackages = async () => {
+    if (!name.trim()) return;
+    if (checkStream.current) checkStream.current.abort();
+    const controller = new AbortController();
+    checkStream.current = controller;
+    setLoading(true);
+    setResults([]);
+    const selected = registries.map(r => r.id).filter(id => settings[id]);
+    const params = new URLSearchParams({ name: name.trim(), registries: selected.join(',') });
+    try {
+      const res = await apiFetch(`/api/check/stream?${params}`, { signal: controller.signal });
+      if (!res.ok) throw new Error(`Check failed: HTTP ${res.status}`);
+      await readEvents(res, (event, data) => {
+        if (event === 'result') setResults(prev => [...prev, JSON.parse(data)]);
+      });
+    } catch (e) {
+      if (e.name !== 'AbortError') console.error(e);
+    }
+    if (checkStream.current === controller) setLoading(false);
+  };
+
+  const checkDomains = async () => {
+    const input = name.trim();
+    if (!input) return;
+    setLoading(true);
+    setDomainResults([]);
+    
+    try {
+      // Check if input contains a dot (full domain like banana.wiki)
+      if (input.includes('.')) {
+        // Single full domain check
+        const res = await apiFetch('/api/domain/full', {
+          method: 'POST',
+          headers: { 'Content-Type': 'application/json' },
+          body: JSON.stringify({ domains: [input, ...customDomains] })
+        });
+        const data = await res.json();
+        setDomainResults(data.results || []);
+      } else {
+        // Name + TLDs check
+        const allTlds = [...selectedTlds, ...customDomains.map(d => d.split('.').pop())].filter(Boolean);
+        const res = await apiFetch('/api/domain', {
+          method: 'POST',
+          headers: { 'Content-Type': 'application/json' },
+          body: JSON.stringify({ name: input, tlds: [...new Set(allTlds)] })
+        });
+        const data = await res.json();
+        setDomainResults(data.results || []);
+      }
+    } catch (e) {
+      console.error(e);
+    }
+    setLoading(false);
+  };
+
+  const addCustomDomain = () => {
+    const tld = customTld.trim().replace(/^\./, '');
+    if (tld && !selectedTlds.includes(tld) && !customDomains.includes(tld)) {
+      setCustomDomains(prev => [...prev, tld]);
+      setCustomTld('');
+    }
+  };
+
+  const removeCustomDomain = (tld) => {
+    setCustomDomains(prev => prev.filter(t => t !== tld));
+  };
+
+  const handleSearch = () => {
+    if (tab === 'packages') checkPackages();
+    else checkDomains();
+  };
+
+  const toggleRegistry = (key) => {
+    setSettings(prev => ({ ...prev, [key]: !prev[key] }));
+  };
+
+  const toggleTld = (tld) => {
+    setSelectedTlds(prev => 
+      prev.includes(tld) ? prev.filter(t => t !== tld) : [...prev, tld]
+    );
+  };
+
+  const STATUS = {
+    available: { icon: '✓', label: 'Available', iconColor: 'text-green-500', color: 'text-green-400' },
+    taken: { icon: '✗', label: 'Taken', iconColor: 'text-red-500', color: 'text-red-400' },
+    reserved: { icon: '⊘', label: 'Reserved', iconColor: 'text-blue-500', color: 'text-blue-400' },
+    unknown: { icon: '?', label: 'Unknown', iconColor: 'text-yellow-500', color: 'text-yellow-400' },
+  };
+
+  const statusOf = (r) => STATUS[r.availability] || STATUS.unknown;
+
+  const getStatusIcon = (r) => {
+    const status = statusOf(r);
+    return <span className={status.iconColor}>{status.icon}</span>;
+  };
+
+  return (
+    <div className="container mx-auto px-4 py-8 max-w-4xl">
+      <h1 className="text-4xl font-bold text-center mb-2 text-cyan-400">nbi</h1>
+      <p className="text-center text-gray-400 mb-8">Package Name Availability Checker</p>
+
+      {/* Tabs */}
+      <div className="flex justify-center gap-4 mb-6">
+        <button
+          onClick={() => setTab('packages')}
+          className={`px-6 py-2 rounded-lg font-medium transition ${
+            tab === 'packages' 
+              ? 'bg-cyan-600 text-white' 
+              : 'bg-gray-700 text-gray-300 hover:bg-gray-600'
+          }`}
+        >
+          Packages
+        </button>
+        <button
+          onClick={() => setTab('domains')}
+          className={`px-6 py-2 rounded-lg font-medium transition ${
+            tab === 'domains' 
+              ? 'bg-cyan-600 text-white' 
+              : 'bg-gray-700 text-gray-300 hover:bg-gray-600'
+          }`}
+        >
+          Domains
+        </button>
+        <button
+          onClick={() => setTab('settings')}
+          className={`px-6 py-2 rounded-lg font-medium transition ${
+            tab === 'settings' 
+              ? 'bg-cyan-600 text-white' 
+              : 'bg-gray-700 text-gray-300 hover:bg-gray-600'
+          }`}
+        >
+          Settings
+        </button>
+      </div>
+
+      {/* Search Input */}
+      {tab !== 'settings' && (
+        <div className="flex gap-2 mb-6">
+          <input
+            type="text"
+            value={name}
+            onChange={(e) => setName(e.target.value)}
+            onKeyDown={(e) => e.key === 'Enter' && handleSearch()}
+            placeholder={tab === 'packages' ? 'Enter package name...' : 'Enter domain name...'}
+            className="flex-1 px-4 py-3 bg-gray-800 border border-gray-600 rounded-lg focus:outline-none focus:border-cyan-500"
+          />
+          <button
+            onClick={handleSearch}
+            disabled={loading || !name.trim()}
+            className="px-6 py-3 bg-cyan-600 hover:bg-cyan-700 disabled:bg-gray-600 rounded-lg font-medium transition"
+          >
+            {loading ? <span className="spinner"></span> : 'Search'}
+          </button>
+        </div>
+      )}
+
+      {/* Packages Tab */}
+      {tab === 'packages' && (
+        <div className="bg-gray-800 rounded-lg p-4">
+          <h2 className="text-lg font-semibold mb-4 text-gray-300">Results</h2>
+          {results.length === 0 && !loading && (
+            <p className="text-gray-500">Enter a package name to check availability</p>
+          )}
+          {loading && <p className="text-gray-400">Checking...</p>}
+          <div className="space-y-2">
+            {results.map((r, i) => (
+              <div key={i} className="flex items-center gap-3 p-3 bg-gray-700 rounded">
+                <span className="text-xl">{getStatusIcon(r)}</span>
+                <span className="font-medium w-32">{registryName(r.registry)}</span>
+                <span className={statusOf(r).color}>
+                  {statusOf(r).label}
+                </span>
+                {r.cached && <span className="text-gray-500 text-xs">cached</span>}
+                {r.error && <span className="text-red-400 text-sm ml-auto">{r.error}</span>}
+              </div>
+            ))}
+          </div>
+        </div>
+      )}
+
+      {/* Domains Tab */}
+      {tab === 'domains' && (
+        <div>
+          {/* Hint */}
+          <div className="bg-gray-800 rounded-lg p-3 mb-4 text-sm text-gray-400">
+            <span className="text-cyan-400">Tip:</span> Enter just a name (e.g., <code className="bg-gray-700 px-1 rounded">banana</code>) to check multiple TLDs, 
+            or a full domain (e.g., <code className="bg-gray-700 px-1 rounded">banana.wiki</code>) for a specific check.
+          </div>
+
+          {/* TLD Selection */}
+          <div className="bg-gray-800 rounded-lg p-4 mb-4">
+            <h3 className="text-sm font-semibold mb-3 text-gray-400">Select TLDs</h3>
+            <div className="flex flex-wrap gap-2 mb-3">
+              {DEFAULT_TLDS.map(tld => (
+                <button
+                  key={tld}
+                  onClick={() => toggleTld(tld)}
+                  className={`px-3 py-1 rounded text-sm transition ${
+                    selectedTlds.includes(tld)
+                      ? 'bg-cyan-600 text-white'
+                      : 'bg-gray-700 text-gray-400'
+                  }`}
+                >
+                  .{tld}
+                </button>
+              ))}
+            </div>
+
+            {/* Custom TLDs */}
+            {customDomains.length > 0 && (
+              <div className="flex flex-wrap gap-2 mb-3">
+                {customDomains.map(tld => (
+                  <button
+                    key={tld}
+                    onClick={() => removeCustomDomain(tld)}
+                    className="px-3 py-1 rounded text-sm bg-purple-600 text-white hover:bg-purple-700 transition"
+                  >
+                    .{tld} ✕
+                  </button>
+                ))}
+              </div>
+            )}
+
+            {/* Add Custom TLD */}
+            <div className="flex gap-2 mt-3">
+              <input
+                type="text"
+                value={customTld}
+                onChange={(e) => setCustomTld(e.target.value)}
+                onKeyDown={(e) => e.key === 'Enter' && addCustomDomain()}
+                placeholder="Add custom TLD (e.g., wiki, gg, sh)"
+                className="flex-1 px-3 py-2 bg-gray-700 border border-gray-600 rounded text-sm focus:outline-none focus:border-cyan-500"
+              />
+              <button
+                onClick={addCustomDomain}
+                className="px-4 py-2 bg-purple-600 hover:bg-purple-700 rounded text-sm transition"
+              >
+                Add
+              </button>
+            </div>
+          </div>
+
+          {/* Domain Results */}
+          <div className="bg-gray-800 rounded-lg p-4">
+            <h2 className="text-lg font-semibold mb-4 text-gray-300">Domain Results</h2>
+            {domainResults.length === 0 && !loading && (
+              <p className="text-gray-500">Enter a domain name to check availability</p>
+            )}
+            {loading && <p className="text-gray-400">Checking DNS...</p>}
+            <div className="grid grid-cols-2 md:grid-cols-3 gap-2">
+              {domainResults.map((r, i) => (
+                <div key={i} className="flex items-center gap-2 p-3 bg-gray-700 rounded">
+                  <span className="text-lg">{getStatusIcon(r)}</span>
+                  <span className="font-mono text-sm">{r.domain}</span>
+                </div>
+              ))}
+            </div>
+          </div>
+        </div>
+      )}
+
+      {/* Settings Tab */}
+      {tab === 'settings' && (
+        <div className="bg-gray-800 rounded-lg p-4">
+          <h2 className="text-lg font-semibold mb-4 text-gray-300">Registry Settings</h2>
+          <p className="text-gray-500 text-sm mb-4">Toggle registries to include in package search</p>
+          <div className="space-y-2">
+            {registries.map(reg => (
+              <div 
+                key={reg.id}
+                onClick={() => toggleRegistry(reg.id)}
+                className="flex items-center gap-3 p-3 bg-gray-700 rounded cursor-pointer hover:bg-gray-600 transition"
+              >
+                <span className={`w-5 h-5 rounded border-2 flex items-center justify-center ${
+                  settings[reg.id] ? 'bg-cyan-600 border-cyan-600' : 'border-gray-500'
+                }`}>
+                  {settings[reg.id] && <span className="text-white text-sm">✓</span>}
+                </span>
+                <span className="font-medium w-32">{reg.name}</span>
+                <span className="text-gray-500 text-sm">{reg.id}</span>
+              </div>
+            ))}
+          </div>
+        </div>
+      )}
+
+      <footer className="text-center text-gray-600 mt-8 text-sm">
+        nbi - Package Name Availability Checker | <a href="https://github.com" className="text-cyan-600 hover:underline">GitHub</a>
+      </footer>
+    </div>
+  );
+}
+
+ReactDOM.createRoot(document.getElementById('root')).render(<App />);
@@ -0,0 +1,222 @@
+//! End-to-end tests for argument handling, run against the built binary
+//!
+//! These avoid the network by stopping at argument validation or help output,
+//! or by answering checks from the bundled offline fixtures.
+
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A config directory of its own for each run, so tests never read or
+/// migrate the developer's config
+fn config_dir() -> PathBuf {
+  static RUNS: AtomicUsize = AtomicUsize::new(0);
+  let run = RUNS.fetch_add(1, Ordering::SeqCst);
+  std::env::temp_dir().join(format!("nbi-cli-{}-{}", std::process::id(), run))
+}
+
+/// `nbi` in offline mode on an empty config, so checks never leave the
+/// machine
+fn command() -> Command {
+  let mut command = Command::new(env!("CARGO_BIN_EXE_nbi"));
+  command.env("NBI_OFFLINE", "1").env_remove("NBI_FIXTURES").env_remove("NBI_LOG");
+  command.env("NBI_CONFIG_DIR", config_dir()).env_remove("NBI_PROFILE");
+  command
+}
+
+fn nbi(args: &[&str]) -> Output {
+  command().args(args).output().expect("failed to run nbi")
+}
+
+fn stdout(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stdout).into_owned()
+}
+
+fn stderr(output: &Output) -> String {
+  String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn shorthand_routes_name_to_check() {
+  // An invalid registry proves the args reached `check` without any lookups
+  let output = nbi(&["coolname", "--json", "-r", "nope"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("invalid value 'nope' for '--registries"));
+}
+
+#[test]
+fn subcommand_names_are_not_shorthand() {
+  let output = nbi(&["serve", "--help"]);
+  assert!(output.status.success());
+  assert!(String::from_utf8_lossy(&output.stdout).contains("Port to listen on"));
+}
+
+#[test]
+fn explicit_check_accepts_subcommand_names() {
+  let output = nbi(&["check", "serve", "-r", "nope"]);
+  assert!(stderr(&output).contains("invalid value 'nope' for '--registries"));
+}
+
+#[test]
+fn shorthand_keeps_global_flags_first() {
+  let output = nbi(&["-v", "coolname", "-r", "nope"]);
+  assert!(stderr(&output).contains("invalid value 'nope' for '--registries"));
+}
+
+#[test]
+fn gh_actions_writes_commands_and_step_summary() {
+  let summary = std::env::temp_dir().join(format!("nbi-step-summary-{}.md", std::process::id()));
+  let output = command()
+    .args(["check", "ferrite", "-r", "npm,crates", "--format", "gh-actions"])
+    .env("GITHUB_STEP_SUMMARY", &summary)
+    .output()
+    .expect("failed to run nbi");
+  let written = std::fs::read_to_string(&summary).expect("step summary written");
+  std::fs::remove_file(&summary).unwrap();
+
+  // The bundled fixtures have ferrite taken on npm only
+  assert_eq!(stdout(&output), "::error::ferrite is taken on npm\n");
+  assert!(written.starts_with("### Name availability: "), "{}", written);
+  assert!(written.contains("| ferrite | npm | ✗ Taken |"), "{}", written);
+  assert!(written.contains("| ferrite | crates.io | ✓ Available |"), "{}", written);
+}
+
+#[test]
+fn jsonl_streams_results_then_summary() {
+  use std::io::{BufRead, BufReader};
+  use std::process::Stdio;
+
+  let mut child = command()
+    .args(["check", "nbi-jsonl-test", "-r", "npm,crates", "--format", "jsonl"])
+    .stdout(Stdio::piped())
+    .spawn()
+    .expect("failed to run nbi");
+  let stdout = BufReader::new(child.stdout.take().unwrap());
+
+  // Read line by line as the process writes them
+  let mut records = Vec::new();
+  for line in stdout.lines() {
+    let record: serde_json::Value = serde_json::from_str(&line.unwrap()).expect("valid JSON line");
+    records.push(record);
+  }
+  child.wait().unwrap();
+
+  let (summary, results) = records.split_last().expect("at least a summary line");
+  assert_eq!(summary["type"], "summary");
+  assert_eq!(results.len(), 2);
+  for result in results {
+    assert_eq!(result["type"], "result");
+    assert_eq!(result["name"], "nbi-jsonl-test");
+    assert!(result["duration_ms"].is_u64());
+  }
+  assert_eq!(summary["available"], 2);
+  assert_eq!(summary["taken"], 0);
+}
+
+#[test]
+fn offline_mode_answers_from_fixtures() {
+  let output = nbi(&["check", "serde", "nbi-unlisted", "-r", "crates", "--format", "json"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  let results: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+  assert_eq!(results[0]["results"][0]["availability"], "taken");
+  assert_eq!(results[1]["results"][0]["availability"], "available");
+  // The old boolean is still there for scripts that read it
+  assert_eq!(results[0]["results"][0]["available"], false);
+  assert_eq!(results[1]["results"][0]["available"], true);
+
+  let output = nbi(&["domain", "example", "--tlds", "com,org", "--format", "json"]);
+  let results: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+  assert_eq!(results[0]["available"], false);
+  assert_eq!(results[1]["available"], true);
+}
+
+#[test]
+fn fixtures_file_replaces_bundled_table() {
+  let path = std::env::temp_dir().join(format!("nbi-fixtures-{}.json", std::process::id()));
+  std::fs::write(&path, r#"{"serde": {"npm": false}, "flaky": null}"#).unwrap();
+  let output = command()
+    .args(["check", "serde", "flaky", "-r", "npm,crates", "--format", "jsonl"])
+    .env("NBI_FIXTURES", &path)
+    .output()
+    .expect("failed to run nbi");
+  std::fs::remove_file(&path).unwrap();
+
+  let summary = stdout(&output).lines().last().map(str::to_string).unwrap();
+  let summary: serde_json::Value = serde_json::from_str(&summary).unwrap();
+  // serde is taken on npm only; every flaky check fails
+  assert_eq!(summary["available"], 1);
+  assert_eq!(summary["taken"], 1);
+  assert_eq!(summary["unknown"], 2);
+
+  let output = nbi(&["--fixtures", "/nonexistent/nbi.json", "check", "foo"]);
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("/nonexistent/nbi.json"));
+}
+
+#[test]
+fn server_answers_from_fixtures() {
+  use std::io::{BufRead, BufReader, Read, Write};
+  use std::process::Stdio;
+
+  let mut child = command()
+    .args(["serve", "--port", "0"])
+    .stdout(Stdio::piped())
+    .spawn()
+    .expect("failed to run nbi");
+  let mut banner = String::new();
+  BufReader::new(child.stdout.take().unwrap()).read_line(&mut banner).unwrap();
+  let addr = banner.trim().rsplit("http://").next().unwrap().to_string();
+
+  let mut stream = std::net::TcpStream::connect(&addr).unwrap();
+  let request = "GET /api/check?name=serde&registries=crates,npm HTTP/1.1\r\n";
+  write!(stream, "{}Host: {}\r\nConnection: close\r\n\r\n", request, addr).unwrap();
+  let mut response = String::new();
+  stream.read_to_string(&mut response).unwrap();
+  child.kill().unwrap();
+  child.wait().unwrap();
+
+  let (head, body) = response.split_once("\r\n\r\n").unwrap();
+  assert!(head.starts_with("HTTP/1.1 200"), "{}", head);
+  let body: serde_json::Value = serde_json::from_str(body).unwrap();
+  for result in body["results"].as_array().unwrap() {
+    assert_eq!(result["available"], false, "{}", result);
+  }
+}
+
+#[test]
+fn registry_enable_and_disable_persist() {
+  let dir = std::env::temp_dir().join(format!("nbi-registry-cli-{}", std::process::id()));
+  let config = dir.join("config.toml");
+  let config = config.to_str().unwrap();
+
+  let output = nbi(&["--config", config, "registry", "disable", "brew", "npm"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+  assert_eq!(stdout(&output), "brew disabled\nnpm disabled\n");
+  let output = nbi(&["--config", config, "registry", "enable", "npm"]);
+  assert!(output.status.success(), "{}", stderr(&output));
+
+  let output = nbi(&["--config", config, "registry", "list", "--json"]);
+  let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+  let enabled = |id: &str| {
+    let registries = list.as_array().unwrap();
+    registries.iter().find(|r| r["id"] == id).unwrap()["enabled"].as_bool().unwrap()
+  };
+  assert!(!enabled("brew"));
+  assert!(enabled("npm") && enabled("crates"));
+  let _ = std::fs::remove_dir_all(dir);
+}
+
+#[test]
+fn tui_refuses_a_broken_config() {
+  let dir = config_dir();
+  std::fs::create_dir_all(&dir).unwrap();
+  let path = dir.join("config.toml");
+  std::fs::write(&path, "registries = [").unwrap();
+
+  let output = command().args(["--config", path.to_str().unwrap(), "tui"]).output().unwrap();
+  assert!(!output.status.success());
+  assert!(stderr(&output).contains("Failed to parse"), "{}", stderr(&output));
+  // Left for the user to fix rather than overwritten with defaults
+  assert_eq!(std::fs::read_to_string(&path).unwrap(), "registries = [");
+  std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,77 @@
+//! The typed client against a real `nbi serve` running in-process, with
+//! checks answered by the bundled offline fixtures
+
+use nbi::client::{ClientError, NbiClient};
+use nbi::config::ConfigSource;
+use nbi::registry::{self, Availability, RegistryType};
+use nbi::server::{self, AllowedOrigins, AuthToken, Limits, ServeOptions};
+use std::net::SocketAddr;
+use std::time::Duration;
+
+/// Start a server on a free port, returning its base URL
+async fn serve(auth: Option<&str>) -> String {
+  registry::offline::enable(registry::offline::Fixtures::bundled());
+  let dir = std::env::temp_dir().join(format!("nbi-client-{}", std::process::id()));
+  let options = ServeOptions {
+    config: ConfigSource::resolve(Some(dir.join("config.toml")), None),
+    addr: SocketAddr::from(([127, 0, 0, 1], 0)),
+    open_browser: false,
+    auth: auth.map(AuthToken::new),
+    rate_limit: 0,
+    grace_period: Duration::from_secs(1),
+    cache_ttl: Duration::ZERO,
+    static_dir: None,
+    cors_origins: AllowedOrigins::default(),
+    limits: Limits::default(),
+  };
+  let app = server::app(options).unwrap();
+  let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+  let url = format!("http://{}", listener.local_addr().unwrap());
+  tokio::spawn(async move {
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>()).await
+  });
+  url
+}
+
+#[tokio::test]
+async fn check_returns_typed_results() {
+  let client = NbiClient::new(serve(None).await, None);
+  let registries = [RegistryType::Npm, RegistryType::Crates];
+  let response = client.check("ferrite", &registries).await.unwrap();
+
+  assert_eq!(response.name, "ferrite");
+  let results: Vec<_> =
+    response.results.iter().map(|r| (r.result.registry, r.result.availability)).collect();
+  let expected = [
+    (RegistryType::Npm, Availability::Taken),
+    (RegistryType::Crates, Availability::Available),
+  ];
+  assert_eq!(results, expected);
+  assert!(response.results.iter().all(|r| !r.cached));
+}
+
+#[tokio::test]
+async fn check_domains_and_config() {
+  let client = NbiClient::new(serve(None).await, None);
+  let response = client.check_domains("ferrite", &["com", "io"]).await.unwrap();
+  let domains: Vec<_> =
+    response.results.iter().map(|r| (r.domain.as_str(), r.available)).collect();
+  assert_eq!(domains, [("ferrite.com", Some(false)), ("ferrite.io", Some(true))]);
+
+  let config = client.get_config().await.unwrap();
+  assert_eq!(config.default_tlds, nbi::config::Config::default().default_tlds);
+}
+
+#[tokio::test]
+async fn errors_carry_the_server_body() {
+  let url = serve(Some("s3cret")).await;
+  let err = NbiClient::new(url.as_str(), None).check("foo", &[]).await.unwrap_err();
+  let ClientError::Api { status, body } = err else { panic!("expected an API error") };
+  assert_eq!((status, body.kind.as_str()), (401, "unauthorized"));
+
+  let client = NbiClient::new(format!("{}/", url), Some("s3cret".to_string()));
+  let err = client.check(" ", &[]).await.unwrap_err();
+  let ClientError::Api { status, body } = err else { panic!("expected an API error") };
+  assert_eq!((status, body.kind.as_str()), (400, "invalid_name"));
+  assert_eq!(body.field.as_deref(), Some("name"));
+}
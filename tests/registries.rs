@@ -0,0 +1,318 @@
+//! Every registry check against a local wiremock server standing in for the
+//! registry, so the suite runs offline: a taken and a free name, rate
+//! limiting, a server error and a registry that never answers, each with
+//! the exact result it produces
+//!
+//! The checks that query the real registries are `#[ignore]`d next to each
+//! checker; run them with `cargo test -- --ignored`.
+
+use nbi::registry::{
+  self, brew, crates, debian, flatpak, github, handles, npm, pypi, trademark, Availability,
+  AvailabilityResult, RegistryType,
+};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{body_string_contains, method, path, query_param};
+use wiremock::{Mock, MockBuilder, MockServer, ResponseTemplate};
+
+/// How long checks wait for an answer in this suite
+const TIMEOUT: Duration = Duration::from_millis(500);
+
+/// A fresh stand-in registry, with checks timing out after [`TIMEOUT`]
+async fn server() -> MockServer {
+  registry::set_request_timeout(TIMEOUT);
+  MockServer::start().await
+}
+
+fn result(
+  registry: RegistryType,
+  name: &str,
+  availability: Availability,
+  error: Option<&str>,
+) -> AvailabilityResult {
+  AvailabilityResult {
+    registry,
+    name: name.to_string(),
+    availability,
+    error: error.map(String::from),
+    detail: None,
+    qualifier: None,
+  }
+}
+
+/// Answer `request` with `response` on `server`
+async fn answer(server: &MockServer, request: MockBuilder, response: ResponseTemplate) {
+  request.respond_with(response).mount(server).await;
+}
+
+/// Answer the requests `request` builds for the names "limited", "broken"
+/// and "slow" with a 429, a 500 and no answer in time, returning each name
+/// with the error its check should report
+///
+/// `status` is how the checker words an unexpected status.
+async fn mount_failures(
+  server: &MockServer,
+  request: impl Fn(&str) -> MockBuilder,
+  status: &str,
+) -> Vec<(&'static str, String)> {
+  let never = ResponseTemplate::new(200).set_delay(TIMEOUT * 4);
+  let failures = [
+    ("limited", ResponseTemplate::new(429), format!("{}: 429 Too Many Requests", status)),
+    ("broken", ResponseTemplate::new(500), format!("{}: 500 Internal Server Error", status)),
+    ("slow", never, format!("Timeout after {:?}", TIMEOUT)),
+  ];
+  let mut expected = Vec::new();
+  for (name, response, error) in failures {
+    answer(server, request(name), response).await;
+    expected.push((name, error));
+  }
+  expected
+}
+
+#[tokio::test]
+async fn npm_results() {
+  let server = server().await;
+  let base = server.uri();
+  answer(&server, Mock::given(path("/taken")), ResponseTemplate::new(200)).await;
+  answer(&server, Mock::given(path("/package/held")), ResponseTemplate::new(200)).await;
+  let check = |name| npm::check(name, &base, Some(&base));
+  let registry = RegistryType::Npm;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+  let held = result(registry, "held", Availability::Reserved, None);
+  assert_eq!(check("held").await, AvailabilityResult { detail: Some(npm::HELD.into()), ..held });
+
+  let request = |n: &str| Mock::given(path(format!("/{}", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn crates_results() {
+  let server = server().await;
+  let base = server.uri();
+  answer(&server, Mock::given(path("/taken")), ResponseTemplate::new(200)).await;
+  let check = |name| crates::check(name, &base);
+  let registry = RegistryType::Crates;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  let request = |n: &str| Mock::given(path(format!("/{}", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn pypi_results() {
+  let server = server().await;
+  let base = format!("{}/simple", server.uri());
+  answer(&server, Mock::given(path("/simple/taken/")), ResponseTemplate::new(200)).await;
+  answer(&server, Mock::given(path("/project/unreleased/")), ResponseTemplate::new(200)).await;
+  let check = |name| pypi::check(name, &base);
+  let registry = RegistryType::PyPi;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+  let unreleased = AvailabilityResult {
+    detail: Some(pypi::UNRELEASED.into()),
+    ..result(registry, "unreleased", Availability::Reserved, None)
+  };
+  assert_eq!(check("unreleased").await, unreleased);
+
+  let request = |n: &str| Mock::given(path(format!("/simple/{}/", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn brew_results() {
+  let server = server().await;
+  let base = format!("{}/api/formula", server.uri());
+  answer(&server, Mock::given(path("/api/formula/taken.json")), ResponseTemplate::new(200)).await;
+  // Free names are also looked up among the aliases
+  let formulae = ResponseTemplate::new(200).set_body_json(json!([]));
+  answer(&server, Mock::given(path("/api/formula.json")), formulae).await;
+  let api = server.uri();
+  let check = |name| brew::check(name, &base, &[], &api, None);
+  let registry = RegistryType::Brew;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  let request = |n: &str| Mock::given(path(format!("/api/formula/{}.json", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn flatpak_results() {
+  let server = server().await;
+  let base = server.uri();
+  let apps = json!([{ "flatpakAppId": "org.example.taken", "name": "Taken" }]);
+  let found = ResponseTemplate::new(200).set_body_json(apps);
+  answer(&server, Mock::given(path("/search/taken")), found).await;
+  let nothing = ResponseTemplate::new(200).set_body_json(json!([]));
+  answer(&server, Mock::given(path("/search/free")), nothing).await;
+  let check = |name| flatpak::check(name, &base);
+  let registry = RegistryType::Flatpak;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  let request = |n: &str| Mock::given(path(format!("/search/{}", n)));
+  for (name, error) in mount_failures(&server, request, "Status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn debian_results() {
+  let server = server().await;
+  let base = server.uri();
+  let versions = json!({ "package": "taken", "versions": [{ "version": "1.0-1" }] });
+  let found = ResponseTemplate::new(200).set_body_json(versions);
+  answer(&server, Mock::given(path("/taken/")), found).await;
+  let check = |name| debian::check(name, &base);
+  let registry = RegistryType::Debian;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  let request = |n: &str| Mock::given(path(format!("/{}/", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn github_repo_results() {
+  let server = server().await;
+  let base = server.uri();
+  answer(&server, Mock::given(path("/repos/octocat/taken")), ResponseTemplate::new(200)).await;
+  let check = |name| github::check_repo("octocat", name, &base, None);
+  let expected = |name, availability, error: Option<&str>| AvailabilityResult {
+    qualifier: Some("octocat".to_string()),
+    ..result(RegistryType::GitHub, name, availability, error)
+  };
+
+  assert_eq!(check("taken").await, expected("taken", Availability::Taken, None));
+  assert_eq!(check("free").await, expected("free", Availability::Available, None));
+
+  let request = |n: &str| Mock::given(path(format!("/repos/octocat/{}", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, expected(name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn github_user_results() {
+  let server = server().await;
+  let base = server.uri();
+  answer(&server, Mock::given(path("/users/taken")), ResponseTemplate::new(200)).await;
+  let check = |name| handles::check_github_user(name, &base, None);
+  let registry = RegistryType::GitHubUser;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  let request = |n: &str| Mock::given(path(format!("/users/{}", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn npm_org_results() {
+  let server = server().await;
+  let base = server.uri();
+  answer(&server, Mock::given(path("/-/org/taken/user")), ResponseTemplate::new(200)).await;
+  let check = |name| handles::check_npm_org(name, &base);
+  let registry = RegistryType::NpmOrg;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  let request = |n: &str| Mock::given(path(format!("/-/org/{}/user", n)));
+  for (name, error) in mount_failures(&server, request, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn bluesky_results() {
+  let server = server().await;
+  let base = server.uri();
+  let resolve = |n: &str| {
+    Mock::given(path("/xrpc/com.atproto.identity.resolveHandle"))
+      .and(query_param("handle", format!("{}.bsky.social", n)))
+  };
+  answer(&server, resolve("taken"), ResponseTemplate::new(200)).await;
+  // Unknown handles are a bad request rather than not found
+  answer(&server, resolve("free"), ResponseTemplate::new(400)).await;
+  let check = |name| handles::check_bluesky(name, &base);
+  let registry = RegistryType::Bluesky;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  for (name, error) in mount_failures(&server, resolve, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn mastodon_results() {
+  let server = server().await;
+  let base = server.uri();
+  let lookup =
+    |n: &str| Mock::given(path("/api/v1/accounts/lookup")).and(query_param("acct", n));
+  answer(&server, lookup("taken"), ResponseTemplate::new(200)).await;
+  let check = |name| handles::check_mastodon(name, &base);
+  let registry = RegistryType::Mastodon;
+
+  assert_eq!(check("taken").await, result(registry, "taken", Availability::Taken, None));
+  assert_eq!(check("free").await, result(registry, "free", Availability::Available, None));
+
+  for (name, error) in mount_failures(&server, lookup, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}
+
+#[tokio::test]
+async fn trademark_results() {
+  let server = server().await;
+  let base = format!("{}/tmsearch", server.uri());
+  let search = |n: &str| {
+    Mock::given(method("POST"))
+      .and(path("/tmsearch"))
+      .and(body_string_contains(format!("\"wordmark\":\"{}\"", n)))
+  };
+  let mark = json!({ "wordmark": "TAKEN", "alive": true, "internationalClass": ["IC 009"] });
+  let hits = json!({ "hits": { "hits": [{ "source": mark }] } });
+  let found = ResponseTemplate::new(200).set_body_json(hits);
+  answer(&server, search("taken"), found).await;
+  let nothing = ResponseTemplate::new(200).set_body_json(json!({ "hits": { "hits": [] } }));
+  answer(&server, search("free"), nothing).await;
+  let check = |name| trademark::check(name, &base, &trademark::DEFAULT_CLASSES);
+  let registry = RegistryType::Trademark;
+
+  // A live mark is only a potential conflict, never a definite answer
+  let expected = |name, availability, detail: &str| AvailabilityResult {
+    detail: Some(detail.to_string()),
+    ..result(registry, name, availability, None)
+  };
+  let taken = expected("taken", Availability::Unknown, "potential conflicts: 1");
+  assert_eq!(check("taken").await, taken);
+  let free = expected("free", Availability::Available, "no live marks in classes 9, 42");
+  assert_eq!(check("free").await, free);
+
+  for (name, error) in mount_failures(&server, search, "Unexpected status").await {
+    assert_eq!(check(name).await, result(registry, name, Availability::Unknown, Some(&error)));
+  }
+}